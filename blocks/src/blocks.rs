@@ -5122,7 +5122,7 @@ impl Block {
                 should_cull_against: false,
                 never_cull: false,
                 force_shade: false,
-                transparent: false,
+                transparent: true,
                 absorbed_light: 1,
                 emitted_light: 11,
                 collidable: false,
@@ -13618,181 +13618,36 @@ impl Block {
             ),
             Block::Water { level } => level as usize,
             Block::Lava { level } => level as usize,
-            Block::OakLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::SpruceLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::BirchLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::JungleLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::AcaciaLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::DarkOakLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::MangroveLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::OakLog { axis } => axis.offset(),
+            Block::SpruceLog { axis } => axis.offset(),
+            Block::BirchLog { axis } => axis.offset(),
+            Block::JungleLog { axis } => axis.offset(),
+            Block::AcaciaLog { axis } => axis.offset(),
+            Block::DarkOakLog { axis } => axis.offset(),
+            Block::MangroveLog { axis } => axis.offset(),
             Block::MangroveRoots { waterlogged } => if waterlogged { 0 } else { 1 },
-            Block::MuddyMangroveRoots { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedSpruceLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedBirchLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedJungleLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedAcaciaLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedDarkOakLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedOakLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedMangroveLog { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::OakWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::SpruceWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::BirchWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::JungleWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::AcaciaWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::DarkOakWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::MangroveWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedOakWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedSpruceWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedBirchWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedJungleWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedAcaciaWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedDarkOakWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedMangroveWood { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::MuddyMangroveRoots { axis } => axis.offset(),
+            Block::StrippedSpruceLog { axis } => axis.offset(),
+            Block::StrippedBirchLog { axis } => axis.offset(),
+            Block::StrippedJungleLog { axis } => axis.offset(),
+            Block::StrippedAcaciaLog { axis } => axis.offset(),
+            Block::StrippedDarkOakLog { axis } => axis.offset(),
+            Block::StrippedOakLog { axis } => axis.offset(),
+            Block::StrippedMangroveLog { axis } => axis.offset(),
+            Block::OakWood { axis } => axis.offset(),
+            Block::SpruceWood { axis } => axis.offset(),
+            Block::BirchWood { axis } => axis.offset(),
+            Block::JungleWood { axis } => axis.offset(),
+            Block::AcaciaWood { axis } => axis.offset(),
+            Block::DarkOakWood { axis } => axis.offset(),
+            Block::MangroveWood { axis } => axis.offset(),
+            Block::StrippedOakWood { axis } => axis.offset(),
+            Block::StrippedSpruceWood { axis } => axis.offset(),
+            Block::StrippedBirchWood { axis } => axis.offset(),
+            Block::StrippedJungleWood { axis } => axis.offset(),
+            Block::StrippedAcaciaWood { axis } => axis.offset(),
+            Block::StrippedDarkOakWood { axis } => axis.offset(),
+            Block::StrippedMangroveWood { axis } => axis.offset(),
             Block::OakLeaves { distance, persistent, waterlogged } => (
                 if waterlogged { 0 } else { 1 } +
                 if persistent { 0 } else { 2 } +
@@ -14514,18 +14369,8 @@ impl Block {
                 if north { 0 } else { 8 } +
                 if east { 0 } else { 16 }
             ),
-            Block::Basalt { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::PolishedBasalt { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::Basalt { axis } => axis.offset(),
+            Block::PolishedBasalt { axis } => axis.offset(),
             Block::SoulWallTorch { facing } => match facing {
                 Direction::North => 0,
                 Direction::South => 1,
@@ -14717,12 +14562,7 @@ impl Block {
             ),
             Block::Chain { axis, waterlogged } => (
                 if waterlogged { 0 } else { 1 } +
-                match axis {
-                    Axis::X => 0,
-                    Axis::Y => 2,
-                    Axis::Z => 4,
-                    _ => unreachable!(),
-                }
+                axis.offset() * 2
             ),
             Block::GlassPane { east, north, south, waterlogged, west } => (
                 if west { 0 } else { 1 } +
@@ -15300,12 +15140,7 @@ impl Block {
                 } +
                 if enabled { 0 } else { 5 }
             ),
-            Block::QuartzPillar { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::QuartzPillar { axis } => axis.offset(),
             Block::QuartzStairs { facing, half, shape, waterlogged } => (
                 if waterlogged { 0 } else { 1 } +
                 match shape {
@@ -15645,12 +15480,7 @@ impl Block {
                     _ => unreachable!(),
                 }
             ),
-            Block::HayBlock { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::HayBlock { axis } => axis.offset(),
             Block::Sunflower { half } => match half {
                 BlockHalf::Upper => 0,
                 BlockHalf::Lower => 1,
@@ -16266,12 +16096,7 @@ impl Block {
                 if down { 0 } else { 32 }
             ),
             Block::ChorusFlower { age } => age as usize,
-            Block::PurpurPillar { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::PurpurPillar { axis } => axis.offset(),
             Block::PurpurStairs { facing, half, shape, waterlogged } => (
                 if waterlogged { 0 } else { 1 } +
                 match shape {
@@ -16320,12 +16145,7 @@ impl Block {
                 if conditional { 0 } else { 6 }
             ),
             Block::FrostedIce { age } => age as usize,
-            Block::BoneBlock { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::BoneBlock { axis } => axis.offset(),
             Block::Observer { facing, powered } => (
                 if powered { 0 } else { 1 } +
                 match facing {
@@ -17605,54 +17425,14 @@ impl Block {
                 }
             ),
             Block::SweetBerryBush { age } => age as usize,
-            Block::WarpedStem { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedWarpedStem { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::WarpedHyphae { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedWarpedHyphae { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::CrimsonStem { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedCrimsonStem { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::CrimsonHyphae { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::StrippedCrimsonHyphae { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::WarpedStem { axis } => axis.offset(),
+            Block::StrippedWarpedStem { axis } => axis.offset(),
+            Block::WarpedHyphae { axis } => axis.offset(),
+            Block::StrippedWarpedHyphae { axis } => axis.offset(),
+            Block::CrimsonStem { axis } => axis.offset(),
+            Block::StrippedCrimsonStem { axis } => axis.offset(),
+            Block::CrimsonHyphae { axis } => axis.offset(),
+            Block::StrippedCrimsonHyphae { axis } => axis.offset(),
             Block::WeepingVines { age } => age as usize,
             Block::TwistingVines { age } => age as usize,
             Block::CrimsonSlab { type_, waterlogged } => (
@@ -18608,12 +18388,7 @@ impl Block {
                 }
             ),
             Block::HangingRoots { waterlogged } => if waterlogged { 0 } else { 1 },
-            Block::Deepslate { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::Deepslate { axis } => axis.offset(),
             Block::CobbledDeepslateStairs { facing, half, shape, waterlogged } => (
                 if waterlogged { 0 } else { 1 } +
                 match shape {
@@ -18834,30 +18609,10 @@ impl Block {
                     WallSide::Tall => 216,
                 }
             ),
-            Block::InfestedDeepslate { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::OchreFroglight { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::VerdantFroglight { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
-            Block::PearlescentFroglight { axis } => match axis {
-                Axis::X => 0,
-                Axis::Y => 1,
-                Axis::Z => 2,
-                _ => unreachable!(),
-            },
+            Block::InfestedDeepslate { axis } => axis.offset(),
+            Block::OchreFroglight { axis } => axis.offset(),
+            Block::VerdantFroglight { axis } => axis.offset(),
+            Block::PearlescentFroglight { axis } => axis.offset(),
             _ => 0,
         }
     }
@@ -88150,6 +87905,11 @@ impl Block {
                 shape: update_stair_shape(world, pos, facing),
                 waterlogged,
             },
+            Block::Chest { facing, type_, waterlogged } => Chest {
+                facing,
+                type_: update_chest_type(world, pos, facing),
+                waterlogged,
+            },
             Block::RedstoneWire { east, north, power, south, west } => update_redstone_state(world, pos, power),
             Block::OakDoor { facing, half, hinge, open, powered } => {
                 let (facing, hinge, open, powered) = update_door_state(world, pos, half, facing, hinge, open, powered);
@@ -88347,6 +88107,11 @@ impl Block {
                 let (up, north, south, west, east) = update_wall_state(world, pos);
                 MossyCobblestoneWall { up, north, south, west, east, waterlogged }
             },
+            Block::TrappedChest { facing, type_, waterlogged } => TrappedChest {
+                facing,
+                type_: update_chest_type(world, pos, facing),
+                waterlogged,
+            },
             Block::QuartzStairs { facing, half, shape, waterlogged } => QuartzStairs {
                 facing,
                 half,
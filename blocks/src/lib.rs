@@ -11,6 +11,8 @@ use std::collections::HashMap;
 
 pub mod material;
 pub use self::material::Material;
+mod state;
+pub use self::state::StateId;
 #[rustfmt::skip] mod blocks;
 #[rustfmt::skip] mod versions;
 
@@ -22,7 +24,7 @@ pub trait WorldAccess {
 }
 
 enum IDMapKind {
-    Flat(&'static [Block]),
+    Flat(&'static [Block], Vec<StateId>),
     Hierarchical,
 }
 
@@ -35,7 +37,8 @@ impl VanillaIDMap {
     pub fn new(protocol_version: i32) -> VanillaIDMap {
         let version = Version::from_id(protocol_version as u32);
         let mapping = if version >= Version::V1_13 {
-            IDMapKind::Flat(versions::get_block_mapping(version))
+            let blocks = versions::get_block_mapping(version);
+            IDMapKind::Flat(blocks, blocks.iter().map(|b| StateId::of(*b)).collect())
         } else {
             IDMapKind::Hierarchical
         };
@@ -52,7 +55,7 @@ impl VanillaIDMap {
         modded_block_ids: &HashMap<usize, String>, // TODO: remove and add to constructor, but have to mutate in Server
     ) -> Block {
         match &self.mapping {
-            IDMapKind::Flat(blocks) => {
+            IDMapKind::Flat(blocks, _) => {
                 blocks.get(id).copied().unwrap_or(Block::Missing {})
                 // TODO: support modded 1.13.2+ blocks after https://github.com/iceiix/stevenarella/pull/145
             }
@@ -75,6 +78,80 @@ impl VanillaIDMap {
             }
         }
     }
+
+    /// Like [`by_vanilla_id`](Self::by_vanilla_id), but returns the interned
+    /// state. Flattened ids are interned up front so decoding a chunk doesn't
+    /// hash any blocks.
+    pub fn state_by_vanilla_id(
+        &self,
+        id: usize,
+        modded_block_ids: &HashMap<usize, String>,
+    ) -> StateId {
+        match &self.mapping {
+            IDMapKind::Flat(_, states) => states
+                .get(id)
+                .copied()
+                .unwrap_or_else(|| StateId::of(Block::Missing {})),
+            IDMapKind::Hierarchical => StateId::of(self.by_vanilla_id(id, modded_block_ids)),
+        }
+    }
+}
+
+/// Parses a block state string like
+/// `minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]`.
+///
+/// The namespace defaults to `minecraft`. Properties which are left out take
+/// their first value and unknown ones are ignored.
+pub fn parse_blockstate(state: &str) -> Option<Block> {
+    let (name, properties) = match state.find('[') {
+        Some(start) => (&state[..start], state[start + 1..].strip_suffix(']')?),
+        None => (state, ""),
+    };
+    let name = if name.contains(':') {
+        name.to_owned()
+    } else {
+        format!("minecraft:{}", name)
+    };
+    let properties = properties
+        .split(',')
+        .filter(|property| !property.is_empty())
+        .map(|property| property.split_once('='))
+        .collect::<Option<Vec<_>>>()?;
+
+    let matches = |block: &&Block| {
+        block
+            .get_model_variant()
+            .split(',')
+            .filter_map(|property| property.split_once('='))
+            .all(|(key, value)| properties.iter().all(|&(k, v)| k != key || v == value))
+    };
+    // Waterlogging is left out of the model variant, so it's checked separately
+    let waterlogged = properties
+        .iter()
+        .find(|(key, _)| *key == "waterlogged")
+        .map(|(_, value)| *value == "true");
+    let candidates = blocks_by_name().get(&name)?;
+    candidates
+        .iter()
+        .filter(matches)
+        .find(|block| block.is_waterlogged() == waterlogged.unwrap_or(false))
+        .or_else(|| candidates.iter().find(matches))
+        .copied()
+}
+
+fn blocks_by_name() -> &'static HashMap<String, Vec<Block>> {
+    static BLOCKS: std::sync::OnceLock<HashMap<String, Vec<Block>>> = std::sync::OnceLock::new();
+    BLOCKS.get_or_init(|| {
+        let mut blocks: HashMap<String, Vec<Block>> = HashMap::new();
+        for block in versions::get_block_mapping(Version::V1_19) {
+            let (namespace, name) = block.get_model();
+            let states = blocks.entry(format!("{}:{}", namespace, name)).or_default();
+            if !states.contains(block) {
+                states.push(*block);
+            }
+        }
+        blocks
+    })
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -95,6 +172,47 @@ mod tests {
     // Spot check a few blocks across different versions, including the correctly recognized last supported block
     // TODO: comprehensive testing against https://github.com/PrismarineJS/minecraft-data/tree/master/data/pc
 
+    #[test]
+    fn parse_blockstate_round_trip() {
+        let stairs = OakStairs {
+            facing: Direction::North,
+            half: BlockHalf::Bottom,
+            shape: StairShape::Straight,
+            waterlogged: false,
+        };
+        assert_eq!(
+            parse_blockstate(
+                "minecraft:oak_stairs[facing=north,half=bottom,shape=straight,waterlogged=false]"
+            ),
+            Some(stairs)
+        );
+        let (namespace, name) = stairs.get_model();
+        let state = format!("{}:{}[{}]", namespace, name, stairs.get_model_variant());
+        assert_eq!(parse_blockstate(&state), Some(stairs));
+
+        assert_eq!(parse_blockstate("stone"), Some(Stone {}));
+        assert_eq!(parse_blockstate("minecraft:not_a_block"), None);
+        assert_eq!(parse_blockstate("minecraft:stone[broken"), None);
+    }
+
+    #[test]
+    fn render_shapes() {
+        assert_eq!(Air {}.render_shape(), RenderShape::Invisible);
+        assert_eq!(Stone {}.render_shape(), RenderShape::Cube);
+        assert_eq!(Dandelion {}.render_shape(), RenderShape::Cross);
+        assert_eq!(
+            Peony {
+                half: BlockHalf::Upper
+            }
+            .render_shape(),
+            RenderShape::Cross
+        );
+        assert_eq!(WeepingVines { age: 0 }.render_shape(), RenderShape::Cross);
+        assert_eq!(Water { level: 0 }.render_shape(), RenderShape::Fluid);
+        assert_eq!(Lava { level: 0 }.render_shape(), RenderShape::Fluid);
+        assert_eq!(Torch {}.render_shape(), RenderShape::Custom);
+    }
+
     #[test]
     fn hier_1_12_2() {
         let id_map = VanillaIDMap::new(340);
@@ -181,6 +299,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn flat_states() {
+        let id_map = VanillaIDMap::new(751);
+        let modded = HashMap::new();
+        let state = id_map.state_by_vanilla_id(1048, &modded);
+        assert_eq!(state, id_map.state_by_vanilla_id(1048, &modded));
+        assert_ne!(state, id_map.state_by_vanilla_id(1049, &modded));
+        assert_eq!(state.block(), id_map.by_vanilla_id(1048, &modded));
+        assert_eq!(state, StateId::of(state.block()));
+    }
+
     #[test]
     fn verify_blocks() {
         let dirt = Block::Dirt {};
@@ -380,7 +509,12 @@ mod tests {
             //(vine, Some(Tool::Shears), Some(0.3)),
         ];
         for (block, tool, time) in data {
-            let result = block.get_mining_time(&tool).map(|d| d.as_secs_f64());
+            let result = match block.get_mining_time(&tool) {
+                // The expected times list instant breaking as a single tick
+                MiningTime::Instant => Some(0.05),
+                MiningTime::Time(duration) => Some(duration.as_secs_f64()),
+                MiningTime::Never => None,
+            };
             match (time, result) {
                 (Some(time), Some(result)) => assert_eq!(result, time,
                     "Expected to mine block {:?} with {:?} in {} seconds, but it took {} seconds",
@@ -397,6 +531,100 @@ mod tests {
     }
 }
 
+impl Block {
+    /// Whether entities can climb up and down the block while inside it.
+    pub fn is_climbable(&self) -> bool {
+        matches!(
+            self,
+            Block::Ladder { .. }
+                | Block::Vine { .. }
+                | Block::Scaffolding { .. }
+                | Block::WeepingVines { .. }
+                | Block::WeepingVinesPlant {}
+                | Block::TwistingVines { .. }
+                | Block::TwistingVinesPlant {}
+                | Block::CaveVines { .. }
+                | Block::CaveVinesPlant { .. }
+        )
+    }
+
+    /// How the chunk mesher should draw the block.
+    pub fn render_shape(&self) -> RenderShape {
+        let material = self.get_material();
+        if !material.renderable {
+            return RenderShape::Invisible;
+        }
+        match self {
+            Block::Water { .. } | Block::Lava { .. } => RenderShape::Fluid,
+            // Blocks whose models are children of block/cross or block/tinted_cross
+            Block::OakSapling { .. }
+            | Block::SpruceSapling { .. }
+            | Block::BirchSapling { .. }
+            | Block::JungleSapling { .. }
+            | Block::AcaciaSapling { .. }
+            | Block::DarkOakSapling { .. }
+            | Block::BambooSapling { .. }
+            | Block::Grass { .. }
+            | Block::Fern { .. }
+            | Block::TallGrass { .. }
+            | Block::LargeFern { .. }
+            | Block::Lilac { .. }
+            | Block::RoseBush { .. }
+            | Block::Peony { .. }
+            | Block::DeadBush { .. }
+            | Block::Dandelion { .. }
+            | Block::Poppy { .. }
+            | Block::BlueOrchid { .. }
+            | Block::Allium { .. }
+            | Block::AzureBluet { .. }
+            | Block::RedTulip { .. }
+            | Block::OrangeTulip { .. }
+            | Block::WhiteTulip { .. }
+            | Block::PinkTulip { .. }
+            | Block::OxeyeDaisy { .. }
+            | Block::Cornflower { .. }
+            | Block::LilyOfTheValley { .. }
+            | Block::WitherRose { .. }
+            | Block::BrownMushroom { .. }
+            | Block::RedMushroom { .. }
+            | Block::CrimsonFungus { .. }
+            | Block::WarpedFungus { .. }
+            | Block::CrimsonRoots { .. }
+            | Block::WarpedRoots { .. }
+            | Block::NetherSprouts { .. }
+            | Block::WeepingVines { .. }
+            | Block::WeepingVinesPlant { .. }
+            | Block::TwistingVines { .. }
+            | Block::TwistingVinesPlant { .. }
+            | Block::CaveVines { .. }
+            | Block::CaveVinesPlant { .. }
+            | Block::HangingRoots { .. }
+            | Block::SweetBerryBush { .. }
+            | Block::SugarCane { .. }
+            | Block::Kelp { .. }
+            | Block::KelpPlant { .. }
+            | Block::Cobweb { .. } => RenderShape::Cross,
+            _ if material.should_cull_against && !material.transparent => RenderShape::Cube,
+            _ => RenderShape::Custom,
+        }
+    }
+}
+
+/// The kind of geometry a block is drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderShape {
+    /// Not drawn at all, like air.
+    Invisible,
+    /// A full, opaque block which hides the faces of its neighbours.
+    Cube,
+    /// Two planes crossing diagonally, used by most plants.
+    Cross,
+    /// A fluid surface that depends on the surrounding fluid levels.
+    Fluid,
+    /// Any other block model.
+    Custom,
+}
+
 pub enum MiningTime {
     Instant,
     Time(std::time::Duration),
@@ -576,19 +804,46 @@ fn is_snowy<W: WorldAccess>(world: &W, pos: Position) -> bool {
     )
 }
 
+/// Pairs a chest up with a neighbouring chest of the same kind and facing,
+/// `Left` means the other half is clockwise of the facing.
+fn update_chest_type<W: WorldAccess>(world: &W, pos: Position, facing: Direction) -> ChestType {
+    let block = world.get_block(pos);
+    let same_chest = |dir: Direction| match (block, world.get_block(pos.shift(dir))) {
+        (Block::Chest { .. }, Block::Chest { facing: f, .. })
+        | (Block::TrappedChest { .. }, Block::TrappedChest { facing: f, .. }) => f == facing,
+        _ => false,
+    };
+
+    if same_chest(facing.clockwise()) {
+        ChestType::Left
+    } else if same_chest(facing.counter_clockwise()) {
+        ChestType::Right
+    } else {
+        ChestType::Single
+    }
+}
+
 fn can_connect_sides<F: Fn(Block) -> bool, W: WorldAccess>(
     world: &W,
     pos: Position,
     f: &F,
 ) -> (bool, bool, bool, bool) {
+    let [_, _, north, south, west, east] = pos.neighbors();
     (
-        can_connect(world, pos.shift(Direction::North), f),
-        can_connect(world, pos.shift(Direction::South), f),
-        can_connect(world, pos.shift(Direction::West), f),
-        can_connect(world, pos.shift(Direction::East), f),
+        can_connect(world, north, f),
+        can_connect(world, south, f),
+        can_connect(world, west, f),
+        can_connect(world, east, f),
     )
 }
 
+/// Works out a value for each horizontal side, returned as north, south,
+/// west and east.
+fn map_horizontal<T, F: FnMut(Direction) -> T>(f: F) -> (T, T, T, T) {
+    let [north, south, west, east] = Direction::horizontal().map(f);
+    (north, south, west, east)
+}
+
 fn can_connect<F: Fn(Block) -> bool, W: WorldAccess>(world: &W, pos: Position, f: &F) -> bool {
     let block = world.get_block(pos);
     f(block) || (block.get_material().renderable && block.get_material().should_cull_against)
@@ -702,12 +957,8 @@ fn fence_gate_update_state<W: WorldAccess>(world: &W, pos: Position, facing: Dir
 }
 
 fn update_redstone_state<W: WorldAccess>(world: &W, pos: Position, power: u8) -> Block {
-    let (mut north, mut south, mut west, mut east) = (
-        can_connect_redstone(world, pos, Direction::North),
-        can_connect_redstone(world, pos, Direction::South),
-        can_connect_redstone(world, pos, Direction::West),
-        can_connect_redstone(world, pos, Direction::East),
-    );
+    let (mut north, mut south, mut west, mut east) =
+        map_horizontal(|dir| can_connect_redstone(world, pos, dir));
 
     if north == RedstoneSide::None && south == RedstoneSide::None {
         match (west, east) {
@@ -737,7 +988,8 @@ fn update_redstone_state<W: WorldAccess>(world: &W, pos: Position, power: u8) ->
 }
 
 fn update_fire_state<W: WorldAccess>(world: &W, pos: Position, age: u8) -> Block {
-    match world.get_block(pos.shift(Direction::Down)) {
+    let [down, up, north, south, west, east] = pos.neighbors();
+    match world.get_block(down) {
         Air {} => Fire {
             age,
             up: false,
@@ -748,11 +1000,11 @@ fn update_fire_state<W: WorldAccess>(world: &W, pos: Position, age: u8) -> Block
         },
         _ => Fire {
             age,
-            up: can_burn(world, pos.shift(Direction::Up)),
-            north: can_burn(world, pos.shift(Direction::North)),
-            south: can_burn(world, pos.shift(Direction::South)),
-            west: can_burn(world, pos.shift(Direction::West)),
-            east: can_burn(world, pos.shift(Direction::East)),
+            up: can_burn(world, up),
+            north: can_burn(world, north),
+            south: can_burn(world, south),
+            west: can_burn(world, west),
+            east: can_burn(world, east),
         },
     }
 }
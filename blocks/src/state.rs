@@ -0,0 +1,60 @@
+use crate::Block;
+use lazy_static::lazy_static;
+use parking_lot::RwLock;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// More states than any supported version has, legacy ones included.
+const MAX_STATES: usize = 1 << 16;
+
+/// A block state interned to a number.
+///
+/// Ids are handed out the first time a state is seen and stay the same for
+/// the rest of the process, so comparing or hashing one is as cheap as it
+/// is for a `u32` instead of walking the whole `Block` enum. They differ
+/// between runs and must never be written to disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StateId(u32);
+
+struct States {
+    ids: RwLock<HashMap<Block, StateId>>,
+    // Filled in order and never changed afterwards, so looking a block up
+    // doesn't have to take the lock.
+    blocks: Box<[OnceLock<Block>]>,
+}
+
+lazy_static! {
+    static ref STATES: States = States {
+        ids: RwLock::new(HashMap::new()),
+        blocks: (0..MAX_STATES).map(|_| OnceLock::new()).collect(),
+    };
+}
+
+impl StateId {
+    /// Returns the id of the state, interning it if it hasn't been seen yet.
+    pub fn of(block: Block) -> StateId {
+        if let Some(id) = STATES.ids.read().get(&block) {
+            return *id;
+        }
+        let mut ids = STATES.ids.write();
+        let next = ids.len();
+        *ids.entry(block).or_insert_with(|| {
+            assert!(next < MAX_STATES, "ran out of block state ids");
+            STATES.blocks[next]
+                .set(block)
+                .expect("block state id handed out twice");
+            StateId(next as u32)
+        })
+    }
+
+    pub fn block(self) -> Block {
+        *STATES.blocks[self.0 as usize]
+            .get()
+            .expect("block state id was never handed out")
+    }
+
+    /// The id as an index into tables that cover every state.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
@@ -207,6 +207,13 @@ impl BlockProp {
                     multiplier
                 )
             }
+            // Pillar axes are ordered like `Axis::offset`
+            block::StateType::Enum if self.name == "axis" && self.values == ["x", "y", "z"] => {
+                match multiplier {
+                    1 => format!("{}.offset()", self.get_safe_name()),
+                    _ => format!("{}.offset() * {}", self.get_safe_name(), multiplier),
+                }
+            }
             block::StateType::Enum => {
                 let mut result = format!("match {} {{\n", self.get_safe_name());
                 for (i, state) in self
@@ -711,6 +718,11 @@ impl BlockMetaInfo {
             transparent = false;
         }
 
+        // Drawn in the translucent pass so its texture blends like vanilla
+        if block.name == "nether_portal" {
+            transparent = true;
+        }
+
         if block.transparent {
             absorbed_light = 1;
             should_cull_against = false;
@@ -862,6 +874,13 @@ impl BlockMetaInfo {
             "RedstoneWire" => {
                 Some("update_redstone_state(world, pos, power)".into())
             }
+            "Chest" | "TrappedChest" => {
+                Some(format!("{} {{
+                    facing,
+                    type_: update_chest_type(world, pos, facing),
+                    waterlogged,
+                }}", name))
+            }
             "OakDoor" | "IronDoor" | "SpruceDoor" | "BirchDoor" | "JungleDoor" |
             "AcaciaDoor" | "DarkOakDoor" => {
                 Some(format!("{{
@@ -1457,7 +1476,7 @@ fn main() -> std::io::Result<()> {
         writeln!(blocks_file, "        }}")?;
         writeln!(blocks_file, "    }}")?;
         writeln!(blocks_file, "")?;
-        writeln!(blocks_file, "    pub fn get_mining_time(&self, tool: &Option<Tool>) -> Option<std::time::Duration> {{")?;
+        writeln!(blocks_file, "    pub fn get_mining_time(&self, tool: &Option<Tool>) -> MiningTime {{")?;
         writeln!(blocks_file, "        get_mining_time(self, tool)")?;
         writeln!(blocks_file, "    }}")?;
         writeln!(blocks_file, "")?;
@@ -194,42 +194,12 @@ impl Component {
                 .collect(),
 
             (Some(translate), None, None) => {
-                let mut list = chat
+                let args = chat
                     .with
                     .iter()
                     .map(|inner_chat| Component::get_text(inner_chat, &modifier))
                     .collect::<Vec<Component>>();
-
-                let mut iter_component = list.iter_mut();
-
-                // FIXME: this is just a band-aid solution, parse the translate string properly as defined in https://wiki.vg/Text_formatting#Text_components
-                if translate == "%s" {
-                    return list.into_iter().next().unwrap();
-                }
-
-                let mut components = Vec::new();
-                let translated = translate::translate(translate).replace("%%", "%");
-                let mut index = 0;
-                for (i, char) in translated.char_indices() {
-                    match char {
-                        '{' => {
-                            components.push(ComponentType::Text {
-                                text: translated[index..i].to_string(),
-                                modifier: modifier.clone(),
-                            });
-                            if let Some(component) = iter_component.next() {
-                                components.append(&mut component.list);
-                            }
-                        }
-                        '}' => index = i + 1,
-                        _ => {}
-                    }
-                }
-                components.push(ComponentType::Text {
-                    text: translated[index..].to_string(),
-                    modifier: modifier.clone(),
-                });
-                components
+                Component::translate(translate, &args, &modifier).list
             }
             (Some(translate), Some(text), None) => {
                 format!("ERR trans: {}, text: {}", translate, text);
@@ -258,11 +228,105 @@ impl Component {
             (None, Some(text), None) => Component::from_legacy_str(text, &modifier).list,
         };
         // chat.build_component_from_string(return_type);
+        let text_components = match &chat.click_event {
+            // Parts with their own click event keep it
+            Some(click_event) => text_components
+                .into_iter()
+                .map(|component| match component {
+                    ComponentType::Text { text, modifier } => ComponentType::Click {
+                        text,
+                        modifier,
+                        click_event: click_event.clone(),
+                    },
+                    ComponentType::Hover { text, modifier } => ComponentType::ClickAndHover {
+                        text,
+                        modifier,
+                        click_event: click_event.clone(),
+                    },
+                    component => component,
+                })
+                .collect(),
+            None => text_components,
+        };
         Component {
             list: text_components,
         }
     }
 
+    /// Translates `key` and substitutes the arguments into its placeholders.
+    ///
+    /// Both the `{}` placeholders of the built-in table and the `%s`/`%1$s`
+    /// forms used by the game are understood. Arguments are inserted as-is,
+    /// so any nested formatting or events on them are kept. Placeholders
+    /// without a matching argument are left in the text.
+    pub fn translate(key: &str, args: &[Component], modifier: &Modifier) -> Self {
+        let translated: Vec<char> = translate::translate(key).chars().collect();
+        let mut list = Vec::new();
+        let mut text = String::new();
+        let mut next_arg = 0;
+        let mut i = 0;
+        while i < translated.len() {
+            // The argument a placeholder at `i` refers to and its length
+            let placeholder = match (translated[i], translated.get(i + 1)) {
+                ('{', Some('}')) | ('%', Some('s')) | ('%', Some('d')) => {
+                    next_arg += 1;
+                    Some((next_arg - 1, 2))
+                }
+                ('%', Some(c)) if c.is_ascii_digit() => {
+                    let digits = translated[i + 1..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    let end = i + 1 + digits;
+                    match translated.get(end..end + 2) {
+                        Some(['$', 's']) | Some(['$', 'd']) => {
+                            let position: String = translated[i + 1..end].iter().collect();
+                            // Positions are one based
+                            position
+                                .parse::<usize>()
+                                .ok()
+                                .map(|position| (position.wrapping_sub(1), digits + 3))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            match placeholder {
+                Some((index, len)) => {
+                    match args.get(index) {
+                        Some(arg) => {
+                            if !text.is_empty() {
+                                list.push(ComponentType::Text {
+                                    text: std::mem::take(&mut text),
+                                    modifier: modifier.clone(),
+                                });
+                            }
+                            list.extend(arg.list.iter().cloned());
+                        }
+                        None => text.extend(&translated[i..i + len]),
+                    }
+                    i += len;
+                }
+                None if translated[i] == '%' && translated.get(i + 1) == Some(&'%') => {
+                    text.push('%');
+                    i += 2;
+                }
+                None => {
+                    text.push(translated[i]);
+                    i += 1;
+                }
+            }
+        }
+        if !text.is_empty() || list.is_empty() {
+            list.push(ComponentType::Text {
+                text,
+                modifier: modifier.clone(),
+            });
+        }
+        Self { list }
+    }
+
     pub fn from_json(v: &serde_json::Value) -> Result<Self, Error> {
         match serde_json::from_value::<ChatSections>(v.clone()) {
             Ok(sections) => Ok(Component::from_chat_sections(
@@ -398,11 +462,25 @@ impl fmt::Display for ComponentType {
 
 #[derive(Debug, Clone)]
 pub enum ComponentType {
-    Text { text: String, modifier: Modifier },
+    Text {
+        text: String,
+        modifier: Modifier,
+    },
     // TODO: Implment the rest!
-    Hover { text: String, modifier: Modifier },
-    Click { text: String, modifier: Modifier },
-    ClickAndHover { text: String, modifier: Modifier },
+    Hover {
+        text: String,
+        modifier: Modifier,
+    },
+    Click {
+        text: String,
+        modifier: Modifier,
+        click_event: ClickEvent,
+    },
+    ClickAndHover {
+        text: String,
+        modifier: Modifier,
+        click_event: ClickEvent,
+    },
 }
 
 impl ComponentType {
@@ -760,6 +838,43 @@ mod test {
         );
     }
 
+    #[test]
+    fn translate_with_args() {
+        let args = [
+            Component::from_str("Steve"),
+            Component::from_str("Alex"),
+            Component::new(ComponentType::Hover {
+                text: "[Sword]".into(),
+                modifier: Modifier::default(),
+            }),
+        ];
+        let message = Component::translate("death.attack.player.item", &args, &Modifier::default());
+        assert_eq!(message.to_string(), "Steve was slain by Alex using [Sword]");
+        assert!(matches!(
+            message.list.last(),
+            Some(ComponentType::Hover { text, .. }) if text == "[Sword]"
+        ));
+
+        let message = Component::translate("%2$s before %1$s, 100%%", &args, &Modifier::default());
+        assert_eq!(message.to_string(), "Alex before Steve, 100%");
+
+        let message = Component::translate("%s and %s", &args[..1], &Modifier::default());
+        assert_eq!(message.to_string(), "Steve and %s");
+    }
+
+    #[test]
+    fn click_event() {
+        let message = Component::from_str(
+            r#"{"text":"Visit ","extra":[{"text":"here","clickEvent":{"action":"open_url","value":"https://example.com"}}]}"#,
+        );
+        assert!(matches!(message.list[0], ComponentType::Text { .. }));
+        assert!(matches!(
+            &message.list[1],
+            ComponentType::Click { text, click_event, .. }
+                if text == "here" && click_event.action == "open_url" && click_event.value == "https://example.com"
+        ));
+    }
+
     #[test]
     fn test_color_from() {
         match Color::from_str("FF0000").expect("could not parse FF0000") {
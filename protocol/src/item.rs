@@ -158,6 +158,39 @@ impl ItemMeta {
             .unwrap_or_default()
     }
 
+    /// Items stored in the block entity of this item, like the contents
+    /// of a shulker box picked up with its items inside.
+    pub fn block_entity_items(&self) -> Vec<StoredItem> {
+        let items = match self.0.as_ref() {
+            Some(tag) => tag
+                .1
+                .as_compound()
+                .and_then(|comp| comp.get("BlockEntityTag"))
+                .and_then(|tag| tag.as_compound())
+                .and_then(|tag| tag.get("Items"))
+                .and_then(|items| items.as_list()),
+            None => None,
+        };
+        items
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| {
+                        let item = item.as_compound()?;
+                        Some(StoredItem {
+                            slot: item.get("Slot")?.as_byte()? as u8,
+                            id: item.get("id")?.as_str()?.to_owned(),
+                            count: item
+                                .get("Count")
+                                .and_then(|count| count.as_byte())
+                                .unwrap_or(1),
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default()
+    }
+
     pub fn repair_cost(&self) -> Option<i32> {
         match self.0.as_ref() {
             Some(tag) => tag
@@ -200,6 +233,14 @@ impl ItemMeta {
     }
 }
 
+/// An item inside another item's block entity data.
+pub struct StoredItem {
+    pub slot: u8,
+    /// Namespaced name of the item, e.g. `minecraft:stone`
+    pub id: String,
+    pub count: i8,
+}
+
 pub struct Enchantment {
     pub ty: EnchantmentTy,
     pub level: i16,
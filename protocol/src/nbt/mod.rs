@@ -19,6 +19,7 @@ use std::io::Read;
 use super::protocol;
 use super::protocol::Serializable;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use flate2::read::{GzDecoder, ZlibDecoder};
 
 #[derive(Debug, Clone)]
 pub enum Tag {
@@ -304,3 +305,69 @@ pub fn read_string<R: io::Read>(buf: &mut R) -> Result<String, protocol::Error>
     let ret = String::from_utf8(bytes).unwrap();
     Ok(ret)
 }
+
+/// Reads a named tag which may be gzip compressed, like `.dat` files,
+/// zlib compressed, like chunks in region files, or not compressed at
+/// all, like NBT sent over the network.
+pub fn read_auto<R: io::Read>(buf: &mut R) -> Result<Option<NamedTag>, protocol::Error> {
+    let mut data = Vec::new();
+    buf.read_to_end(&mut data)?;
+    match data[..] {
+        [0x1f, 0x8b, ..] => Serializable::read_from(&mut GzDecoder::new(&data[..])),
+        // A zlib header starts with the deflate method and its two bytes
+        // are always a multiple of 31
+        [cmf, flg, ..] if cmf & 0x0f == 8 && u16::from_be_bytes([cmf, flg]) % 31 == 0 => {
+            Serializable::read_from(&mut ZlibDecoder::new(&data[..]))
+        }
+        _ => Serializable::read_from(&mut &data[..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::{GzEncoder, ZlibEncoder};
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn level_dat() -> Vec<u8> {
+        let mut data = Tag::new_compound();
+        data.put("LevelName", Tag::String("world".to_owned()));
+        data.put("SpawnY", Tag::Int(64));
+        let mut root = Tag::new_compound();
+        root.put("Data", data);
+        let mut buf = vec![];
+        Some(NamedTag("".to_owned(), root))
+            .write_to(&mut buf)
+            .unwrap();
+        buf
+    }
+
+    fn assert_level_dat(tag: Option<NamedTag>) {
+        let data = tag.unwrap().1;
+        let data = data.get("Data").unwrap();
+        assert_eq!(data.get("LevelName").and_then(Tag::as_str), Some("world"));
+        assert_eq!(data.get("SpawnY").and_then(Tag::as_int), Some(64));
+    }
+
+    #[test]
+    fn read_auto_raw() {
+        assert_level_dat(read_auto(&mut &level_dat()[..]).unwrap());
+    }
+
+    #[test]
+    fn read_auto_gzip() {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&level_dat()).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_level_dat(read_auto(&mut &data[..]).unwrap());
+    }
+
+    #[test]
+    fn read_auto_zlib() {
+        let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+        encoder.write_all(&level_dat()).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_level_dat(read_auto(&mut &data[..]).unwrap());
+    }
+}
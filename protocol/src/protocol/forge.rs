@@ -281,7 +281,7 @@ pub mod fml2 {
                     filename: Serializable::read_from(buf)?,
                     contents: Serializable::read_from(buf)?,
                 },
-                _ => unimplemented!(),
+                _ => return Err(Error::UnknownForgePacket(id)),
             })
         }
     }
@@ -0,0 +1,102 @@
+//! Lets the client stand in for a proxy when connecting straight to a
+//! server set up to sit behind BungeeCord or Velocity, which expects the
+//! proxy to forward who the player is.
+
+use super::{Error, Serializable, VarInt, UUID};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::str::FromStr;
+
+/// The channel Velocity's modern forwarding asks for the player's details on.
+pub const VELOCITY_CHANNEL: &str = "velocity:player_info";
+/// The oldest modern forwarding version, which every Velocity backend
+/// accepts.
+const VELOCITY_FORWARDING_VERSION: i32 = 1;
+/// The address sent as the player's, as a proxy running on the same machine
+/// would.
+const CLIENT_ADDRESS: &str = "127.0.0.1";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Forwarding {
+    #[default]
+    None,
+    /// BungeeCord's ip forwarding, which appends the player to the host in
+    /// the handshake.
+    BungeeCord,
+    /// Velocity's modern forwarding, signed with the secret shared with the
+    /// server.
+    Velocity { secret: String },
+}
+
+impl Forwarding {
+    /// Adds the player's details to the host sent in the handshake if the
+    /// server expects them there.
+    pub fn handshake_host(&self, host: &str, uuid: &str) -> Option<String> {
+        match self {
+            Forwarding::BungeeCord => Some(format!(
+                "{}\0{}\0{}",
+                host,
+                CLIENT_ADDRESS,
+                uuid.replace('-', "")
+            )),
+            _ => None,
+        }
+    }
+
+    /// The answer to a Velocity server asking for the player's details, if
+    /// modern forwarding is set up.
+    pub fn velocity_player_info(&self, name: &str, uuid: &str) -> Result<Option<Vec<u8>>, Error> {
+        let secret = match self {
+            Forwarding::Velocity { secret } => secret,
+            _ => return Ok(None),
+        };
+        let uuid = UUID::from_str(uuid)
+            .map_err(|_| Error::Err(format!("invalid player UUID {:?}", uuid)))?;
+        let mut payload = vec![];
+        VarInt(VELOCITY_FORWARDING_VERSION).write_to(&mut payload)?;
+        CLIENT_ADDRESS.to_owned().write_to(&mut payload)?;
+        uuid.write_to(&mut payload)?;
+        name.to_owned().write_to(&mut payload)?;
+        // No profile properties, so the player shows up without a skin
+        VarInt(0).write_to(&mut payload)?;
+
+        let mut data = hmac_sha256(secret.as_bytes(), &payload).to_vec();
+        data.extend_from_slice(&payload);
+        Ok(Some(data))
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hmac_matches_reference() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            hex::encode(hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn invalid_uuid_is_an_error() {
+        let forwarding = Forwarding::Velocity {
+            secret: "secret".into(),
+        };
+        assert!(forwarding.velocity_player_info("Steve", "1234").is_err());
+        assert!(forwarding
+            .velocity_player_info("Steve", "zz0e0000000040000000000000000000")
+            .is_err());
+        assert!(Forwarding::None
+            .velocity_player_info("Steve", "1234")
+            .unwrap()
+            .is_none());
+    }
+}
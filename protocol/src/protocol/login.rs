@@ -26,6 +26,9 @@ pub struct Account {
     pub name: String,
     pub uuid: Option<String>,
     pub verification_tokens: Vec<String>, // this represents the verification tokens used to verify the account, such as hashed passwords, actual tokens, etc
+    /// The account's skin as png, the launcher cuts the head out of it.
+    /// Heads are cached separately, so this isn't saved with the account.
+    #[serde(skip)]
     pub head_img_data: Option<Vec<u8>>,
     pub account_type: AccountType,
 }
@@ -42,8 +45,6 @@ impl Account {
     }
 
     /// Whether the profile is complete(not head-wise)
-    /// Will return false if the account is an offline account,
-    /// due to the uuid field being set to None
     pub fn is_complete(&self) -> bool {
         !self.name.is_empty() && self.uuid.is_some() && !self.verification_tokens.is_empty()
     }
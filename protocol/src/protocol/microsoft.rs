@@ -13,10 +13,13 @@
 // limitations under the License.
 
 use crate::protocol::login::{Account, AccountImpl};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use serde_json::json;
 use sha1::Digest;
 
 const JOIN_URL: &str = "https://sessionserver.mojang.com/session/minecraft/join";
+const PROFILE_URL: &str = "https://sessionserver.mojang.com/session/minecraft/profile";
 
 pub struct MicrosoftAccount {}
 
@@ -77,11 +80,47 @@ impl AccountImpl for MicrosoftAccount {
         if res.status() == reqwest::StatusCode::NO_CONTENT {
             Ok(())
         } else {
-            Err(super::Error::Err("Failed to auth with server".to_owned()))
+            Err(super::Error::Auth(format!(
+                "session server responded with {}",
+                res.status()
+            )))
         }
     }
 
-    fn append_head_img_data(&self, _account: &mut Account) -> Result<(), super::Error> {
+    fn append_head_img_data(&self, account: &mut Account) -> Result<(), super::Error> {
+        let Some(uuid) = account.uuid.as_ref() else {
+            return Ok(());
+        };
+        let client = reqwest::blocking::Client::new();
+        let profile: serde_json::Value = serde_json::from_str(
+            &client
+                .get(format!("{}/{}", PROFILE_URL, uuid))
+                .send()?
+                .text()?,
+        )?;
+        let textures = profile
+            .get("properties")
+            .and_then(|v| v.as_array())
+            .and_then(|props| {
+                props
+                    .iter()
+                    .find(|prop| prop.get("name").and_then(|v| v.as_str()) == Some("textures"))
+            })
+            .and_then(|prop| prop.get("value"))
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| super::Error::Err("profile has no textures".to_owned()))?;
+        let textures = STANDARD
+            .decode(textures)
+            .map_err(|err| super::Error::Err(format!("invalid textures property: {}", err)))?;
+        let textures: serde_json::Value = serde_json::from_slice(&textures)?;
+        // Players without a skin use the default one
+        if let Some(url) = textures
+            .pointer("/textures/SKIN/url")
+            .and_then(|v| v.as_str())
+        {
+            let skin = client.get(url).send()?.bytes()?;
+            account.head_img_data = Some(skin.to_vec());
+        }
         Ok(())
     }
 }
@@ -26,6 +26,7 @@ use std::io::{Read, Write};
 use std::net::{Shutdown, TcpStream};
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use aes::Aes128;
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
@@ -35,7 +36,7 @@ use flate2::read::{ZlibDecoder, ZlibEncoder};
 use flate2::Compression;
 use instant::{Duration, Instant};
 use lazy_static::lazy_static;
-use log::{debug, warn};
+use log::{debug, info, warn};
 use num_traits::cast::{cast, NumCast};
 use serde::{Deserialize, Serialize};
 use trust_dns_resolver::config::ResolverConfig;
@@ -45,11 +46,15 @@ use trust_dns_resolver::Resolver;
 use crate::format;
 use crate::nbt;
 use crate::shared::{Position, Version};
+use crate::translate;
 
 pub mod forge;
+pub mod forwarding;
 pub mod login;
 pub mod microsoft;
 pub mod offline_acc;
+pub mod packet_log;
+pub mod unhandled;
 
 pub const SUPPORTED_PROTOCOLS: [i32; 21] = [
     754, 753, 751, 736, 735, 578, 575, 498, 490, 485, 480, 477, 404, 340, 316, 315, 210, 109, 107,
@@ -58,6 +63,9 @@ pub const SUPPORTED_PROTOCOLS: [i32; 21] = [
 
 static CURRENT_PROTOCOL_VERSION: AtomicI32 = AtomicI32::new(SUPPORTED_PROTOCOLS[0]);
 static NETWORK_DEBUG: AtomicBool = AtomicBool::new(false);
+/// Threshold used when compressing packets sent by the client instead of
+/// the one the server asked for, `i32::MIN` when not overridden.
+static COMPRESSION_OVERRIDE: AtomicI32 = AtomicI32::new(i32::MIN);
 
 pub fn current_protocol_version() -> i32 {
     CURRENT_PROTOCOL_VERSION.load(Ordering::Relaxed)
@@ -71,6 +79,24 @@ pub fn is_network_debug() -> bool {
     NETWORK_DEBUG.load(Ordering::Relaxed)
 }
 
+/// Overrides the threshold above which packets sent to the server are
+/// compressed, a negative value sends every packet uncompressed.
+///
+/// Once the server enables compression the packet framing has to follow
+/// it, so this only changes which packets the client actually compresses.
+/// Servers reject compressed packets below their own threshold, so lower
+/// thresholds are raised to the server's.
+pub fn set_compression_override(threshold: i32) {
+    COMPRESSION_OVERRIDE.store(threshold, Ordering::Relaxed);
+}
+
+pub fn compression_override() -> Option<i32> {
+    match COMPRESSION_OVERRIDE.load(Ordering::Relaxed) {
+        i32::MIN => None,
+        threshold => Some(threshold),
+    }
+}
+
 /// Helper macro for defining packets
 #[macro_export]
 macro_rules! state_packets {
@@ -206,6 +232,21 @@ macro_rules! state_mapped_packets {
         )+
         }
 
+        impl MappedPacket {
+            /// The name of the packet, used when logging it.
+            pub fn name(&self) -> &'static str {
+                match self {
+                $(
+                    $(
+                        $(
+                MappedPacket::$name(_) => stringify!($name),
+                        )*
+                    )+
+                )+
+                }
+            }
+        }
+
         $(
         pub mod $state {
 
@@ -498,21 +539,16 @@ impl fmt::Display for UUIDParseError {
 impl std::str::FromStr for UUID {
     type Err = UUIDParseError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut parts = hex::decode(&s[..8]).unwrap();
-        if s.len() != 36 {
-            if s.len() != 32 {
-                return Err(UUIDParseError {});
+        let digits: Vec<u8> = match s.len() {
+            32 => s.bytes().collect(),
+            36 if [8, 13, 18, 23].iter().all(|&i| s.as_bytes()[i] == b'-') => {
+                s.bytes().filter(|&b| b != b'-').collect()
             }
-            // TODO: Verify that this parses uuids correctly (although it should).
-            parts.extend_from_slice(&hex::decode(&s[8..12]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[12..16]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[16..20]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[20..32]).unwrap());
-        } else {
-            parts.extend_from_slice(&hex::decode(&s[9..13]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[14..18]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[19..23]).unwrap());
-            parts.extend_from_slice(&hex::decode(&s[24..36]).unwrap());
+            _ => return Err(UUIDParseError {}),
+        };
+        let parts = hex::decode(digits).map_err(|_| UUIDParseError {})?;
+        if parts.len() != 16 {
+            return Err(UUIDParseError {});
         }
         let mut high = 0u64;
         let mut low = 0u64;
@@ -560,10 +596,10 @@ impl Default for Biomes3D {
 
 impl Serializable for Biomes3D {
     fn read_from<R: io::Read>(buf: &mut R) -> Result<Biomes3D, Error> {
-        let data: [i32; 1024] = [0; 1024];
+        let mut data: [i32; 1024] = [0; 1024];
 
         // Non-length-prefixed three-dimensional biome data
-        for item in &mut data.to_vec() {
+        for item in data.iter_mut() {
             let b: i32 = Serializable::read_from(buf)?;
             *item = b;
         }
@@ -1050,14 +1086,62 @@ pub enum State {
 pub enum Error {
     Err(String),
     Disconnect(format::Component),
-    IOError(io::Error),
+    Io(io::Error),
     Json(serde_json::Error),
     Reqwest(reqwest::Error),
+    UnsupportedVersion(i32),
+    /// The server runs Forge with an FML network version the client can't
+    /// handshake with.
+    UnsupportedForgeVersion(i64),
+    Auth(String),
+    Timeout,
+    /// The server sent a packet that doesn't belong at this point of the
+    /// login or status exchange.
+    UnexpectedPacket(String),
+    /// The server sent an `fml:handshake` packet with an id the client
+    /// doesn't know.
+    UnknownForgePacket(i32),
+}
+
+/// What servers that predate the translated disconnect reasons, and the
+/// proxies in front of them, say when the client runs another version.
+const VERSION_MISMATCH_MESSAGES: [&str; 3] = [
+    "outdated client",
+    "outdated server",
+    "unsupported client version",
+];
+const VERSION_MISMATCH_KEYS: [&str; 3] = [
+    "multiplayer.disconnect.outdated_client",
+    "multiplayer.disconnect.outdated_server",
+    "multiplayer.disconnect.incompatible",
+];
+
+impl Error {
+    /// Whether the server turned the login down because it runs another
+    /// version than the one the client connected with.
+    pub fn is_version_mismatch(&self) -> bool {
+        let reason = match self {
+            Error::Disconnect(reason) => reason.to_string().to_lowercase(),
+            _ => return false,
+        };
+        VERSION_MISMATCH_MESSAGES
+            .iter()
+            .any(|message| reason.contains(message))
+            || VERSION_MISMATCH_KEYS.iter().any(|key| {
+                // Only the text before the version the server asks for
+                let translated = translate::translate(key).to_lowercase();
+                let prefix = translated.split(['{', '%']).next().unwrap_or_default();
+                !prefix.trim().is_empty() && reason.contains(prefix.trim())
+            })
+    }
 }
 
 impl convert::From<io::Error> for Error {
     fn from(e: io::Error) -> Error {
-        Error::IOError(e)
+        match e.kind() {
+            io::ErrorKind::TimedOut => Error::Timeout,
+            _ => Error::Io(e),
+        }
     }
 }
 
@@ -1073,24 +1157,114 @@ impl convert::From<reqwest::Error> for Error {
     }
 }
 
-impl ::std::error::Error for Error {}
+impl ::std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+        match *self {
+            Error::Io(ref e) => Some(e),
+            Error::Json(ref e) => Some(e),
+            Error::Reqwest(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
 
 impl ::std::fmt::Display for Error {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
         match *self {
             Error::Err(ref val) => write!(f, "protocol error: {}", val),
             Error::Disconnect(ref val) => write!(f, "{}", val),
-            Error::IOError(ref e) => e.fmt(f),
+            Error::Io(ref e) => e.fmt(f),
             Error::Json(ref e) => e.fmt(f),
             Error::Reqwest(ref e) => e.fmt(f),
+            Error::UnsupportedVersion(version) => {
+                match versions::protocol_version_to_release_name(version) {
+                    Some(name) => write!(
+                        f,
+                        "The server runs Minecraft {}, which isn't supported!",
+                        name
+                    )?,
+                    None => write!(
+                        f,
+                        "The server runs an unknown version (protocol version: {}), \
+                         which isn't supported!",
+                        version
+                    )?,
+                }
+                write!(
+                    f,
+                    "\nSupported versions: {}",
+                    versions::supported_release_names().join(", ")
+                )
+            }
+            Error::UnsupportedForgeVersion(version) => write!(
+                f,
+                "The server runs a Forge version which isn't supported (FML network version: {})",
+                version
+            ),
+            Error::Auth(ref val) => write!(f, "authentication error: {}", val),
+            Error::Timeout => write!(f, "connection timed out"),
+            Error::UnexpectedPacket(ref val) => write!(f, "unexpected packet: {}", val),
+            Error::UnknownForgePacket(id) => write!(f, "unknown fml:handshake packet id {}", id),
         }
     }
 }
 
 type Aes128Cfb = Cfb8<Aes128>;
 
+/// The transport a `Conn` reads from and writes to.
+enum Stream {
+    Tcp(TcpStream),
+    /// Replays a packet log, anything written is discarded. The flag is
+    /// the reader's close flag.
+    Replay(Arc<Mutex<packet_log::ReplayReader>>, Arc<AtomicBool>),
+}
+
+impl Stream {
+    fn try_clone(&self) -> io::Result<Stream> {
+        Ok(match self {
+            Stream::Tcp(stream) => Stream::Tcp(stream.try_clone()?),
+            Stream::Replay(reader, closed) => Stream::Replay(reader.clone(), closed.clone()),
+        })
+    }
+
+    fn shutdown(&self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.shutdown(Shutdown::Both),
+            Stream::Replay(_, closed) => {
+                closed.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.read(buf),
+            Stream::Replay(reader, _) => reader.lock().unwrap().read(buf),
+        }
+    }
+}
+
+impl Write for Stream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Tcp(stream) => stream.write(buf),
+            Stream::Replay(..) => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Tcp(stream) => stream.flush(),
+            Stream::Replay(..) => Ok(()),
+        }
+    }
+}
+
 pub struct Conn {
-    stream: TcpStream,
+    stream: Stream,
     pub host: String,
     pub port: u16,
     direction: Direction,
@@ -1102,6 +1276,8 @@ pub struct Conn {
 
     pub compression_threshold: i32,
     pub send: Arc<Mutex<Option<bool>>>,
+    /// The id and length of the last packet read.
+    last_read: (i32, usize),
 }
 
 lazy_static! {
@@ -1156,7 +1332,7 @@ impl Conn {
     fn try_stream(address: &str, port: u16, protocol_version: i32) -> Result<Conn, Error> {
         let stream = TcpStream::connect(format!("{}:{}", address, port))?;
         Ok(Conn {
-            stream,
+            stream: Stream::Tcp(stream),
             host: address.to_string(),
             port,
             direction: Direction::Serverbound,
@@ -1166,20 +1342,73 @@ impl Conn {
             write_cipher: Arc::new(Mutex::new(None)),
             compression_threshold: -1,
             send: Arc::new(Mutex::new(None)),
+            last_read: (0, 0),
         })
     }
 
+    /// Creates a connection that plays back the clientbound packets of a
+    /// packet log instead of talking to a server.
+    pub fn replay(path: &str) -> Result<Conn, Error> {
+        let protocol_version = packet_log::ReplayReader::protocol_version(path)?;
+        CURRENT_PROTOCOL_VERSION.store(protocol_version, Ordering::Relaxed);
+        let reader = packet_log::ReplayReader::new(path)?;
+        let closed = reader.close_flag();
+        Ok(Conn {
+            stream: Stream::Replay(Arc::new(Mutex::new(reader)), closed),
+            host: path.to_string(),
+            port: 0,
+            direction: Direction::Serverbound,
+            state: State::Play,
+            protocol_version,
+            read_cipher: Arc::new(Mutex::new(None)),
+            write_cipher: Arc::new(Mutex::new(None)),
+            compression_threshold: -1,
+            send: Arc::new(Mutex::new(None)),
+            last_read: (0, 0),
+        })
+    }
+
+    /// The id and payload length of the last packet read, for logging.
+    pub fn last_read(&self) -> (i32, usize) {
+        self.last_read
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.stream, Stream::Replay(..))
+    }
+
     pub fn write_packet<T: PacketType>(&mut self, packet: T) -> Result<(), Error> {
         let mut buf = Vec::new();
-        VarInt(packet.packet_id(self.protocol_version)).write_to(&mut buf)?;
+        let id = packet.packet_id(self.protocol_version);
+        VarInt(id).write_to(&mut buf)?;
+        let id_len = buf.len();
         packet.write(&mut buf)?;
 
+        if packet_log::is_packet_log() {
+            packet_log::log_packet(
+                self.direction,
+                self.state,
+                self.protocol_version,
+                id,
+                &buf[id_len..],
+                &packet,
+            );
+        }
+
         let mut extra = if self.compression_threshold >= 0 {
             1
         } else {
             0
         };
-        if self.compression_threshold >= 0 && buf.len() as i32 > self.compression_threshold {
+        let send_threshold = match compression_override() {
+            Some(threshold) if threshold >= 0 => threshold.max(self.compression_threshold),
+            Some(threshold) => threshold,
+            None => self.compression_threshold,
+        };
+        if self.compression_threshold >= 0
+            && send_threshold >= 0
+            && buf.len() as i32 > send_threshold
+        {
             extra = 0;
             let uncompressed_size = buf.len();
             let mut new = Vec::new();
@@ -1191,7 +1420,7 @@ impl Conn {
                     "Compressed for sending {} bytes to {} since > threshold {}, new={:?}",
                     uncompressed_size,
                     new.len(),
-                    self.compression_threshold,
+                    send_threshold,
                     new
                 );
             }
@@ -1273,7 +1502,7 @@ impl Conn {
 
             self.write_login_plugin_response(message_id, true, &outer_buf)
         } else {
-            unimplemented!() // successful: false, no payload
+            self.write_login_plugin_response(message_id, false, &[])
         }
     }
 
@@ -1319,6 +1548,8 @@ impl Conn {
     pub fn read_packet(&mut self) -> Result<packet::Packet, Error> {
         let compression_threshold = self.compression_threshold;
         let (id, mut buf) = Conn::read_raw_packet_from(self, compression_threshold)?;
+        let len = buf.get_ref().len() - buf.position() as usize;
+        self.last_read = (id, len);
 
         let dir = match self.direction {
             Direction::Clientbound => Direction::Serverbound,
@@ -1333,8 +1564,18 @@ impl Conn {
             fs::File::create("last-packet")?.write_all(buf.get_ref())?;
         }
 
+        let logged_data = if packet_log::is_packet_log() {
+            Some(buf.get_ref()[buf.position() as usize..].to_vec())
+        } else {
+            None
+        };
+
         let packet = packet::packet_by_id(self.protocol_version, self.state, dir, id, &mut buf)?;
 
+        if let (Some(data), Some(logged)) = (logged_data, &packet) {
+            packet_log::log_packet(dir, self.state, self.protocol_version, id, &data, logged);
+        }
+
         if is_network_debug() {
             debug!("packet = {:?}", packet);
         }
@@ -1355,7 +1596,10 @@ impl Conn {
                 }
                 Ok(val)
             }
-            None => Err(Error::Err("missing packet".to_owned())),
+            None => {
+                unhandled::note_unknown(self.state, id, len);
+                Err(Error::Err("missing packet".to_owned()))
+            }
         }
     }
 
@@ -1367,14 +1611,46 @@ impl Conn {
     }
 
     pub fn set_compression(&mut self, threshold: i32) {
+        match compression_override() {
+            Some(send_threshold) if send_threshold < 0 => info!(
+                "Server set compression threshold {}, sending packets uncompressed",
+                threshold
+            ),
+            Some(send_threshold) => info!(
+                "Server set compression threshold {}, compressing sent packets above {}",
+                threshold, send_threshold
+            ),
+            None => info!("Server set compression threshold {}", threshold),
+        }
         self.compression_threshold = threshold;
     }
 
     pub fn close(&self) {
-        self.stream.shutdown(Shutdown::Both).unwrap();
+        // The server may have closed the connection already
+        if let Err(err) = self.stream.shutdown() {
+            debug!("Failed to close connection: {}", err);
+        }
+    }
+
+    /// Sends a timestamped status ping and returns the round-trip time
+    /// once the matching pong arrives, or `None` if the server doesn't
+    /// reply with one.
+    fn measure_ping(&mut self) -> Option<Duration> {
+        use self::packet::status::serverbound::StatusPing;
+        use self::packet::Packet;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or(0);
+        let start = Instant::now();
+        self.write_packet(StatusPing { ping: timestamp }).ok()?;
+        match self.read_packet().ok()? {
+            Packet::StatusPong(pong) if pong.ping == timestamp => Some(start.elapsed()),
+            _ => None,
+        }
     }
 
-    pub fn do_status(mut self) -> Result<(Status, Duration), Error> {
+    pub fn do_status(mut self) -> Result<(Status, Option<Duration>), Error> {
         use self::packet::handshake::serverbound::Handshake;
         use self::packet::status::serverbound::*;
         use self::packet::Packet;
@@ -1391,21 +1667,14 @@ impl Conn {
 
         self.write_packet(StatusRequest { empty: () })?;
 
-        let status = if let Packet::StatusResponse(res) = self.read_packet()? {
-            res.status
-        } else {
-            return Err(Error::Err("Wrong packet".to_owned()));
+        let status = match self.read_packet()? {
+            Packet::StatusResponse(res) => res.status,
+            val => return Err(Error::UnexpectedPacket(format!("{:?}", val))),
         };
 
-        let start = Instant::now();
-        self.write_packet(StatusPing { ping: 42 })?;
-
-        if let Packet::StatusPong(_) = self.read_packet()? {
-        } else {
-            return Err(Error::Err("Wrong packet".to_owned()));
-        };
-
-        let ping = start.elapsed();
+        // Some servers close the connection after the status response
+        // instead of answering the ping, that shouldn't hide the status
+        let ping = self.measure_ping();
 
         let val: Value = match serde_json::from_str(&status) {
             Ok(val) => val,
@@ -1444,26 +1713,28 @@ impl Conn {
                 }
             }
         }
-        // Forge 1.13+ TODO: update for 1.14+ and test
+        // Forge 1.13+, newer versions may leave out the mods or the marker
+        // of mods that don't need to match
         if let Some(forge_data) = val.get("forgeData") {
             if let Some(Value::Array(items)) = forge_data.get("mods") {
                 for item in items {
-                    if let Value::Object(obj) = item {
-                        let modid = obj.get("modId").unwrap().as_str().unwrap().to_string();
-                        let modmarker = obj.get("modmarker").unwrap().as_str().unwrap().to_string();
-
-                        let version = modmarker;
-
-                        forge_mods.push(crate::protocol::forge::ForgeMod { modid, version });
+                    if let Some(modid) = item.get("modId").and_then(Value::as_str) {
+                        let version = item
+                            .get("modmarker")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default();
+                        forge_mods.push(crate::protocol::forge::ForgeMod {
+                            modid: modid.to_string(),
+                            version: version.to_string(),
+                        });
                     }
                 }
             }
             fml_network_version = Some(
                 forge_data
                     .get("fmlNetworkVersion")
-                    .unwrap()
-                    .as_i64()
-                    .unwrap(),
+                    .and_then(Value::as_i64)
+                    .unwrap_or(2),
             );
         }
 
@@ -1625,11 +1896,12 @@ impl Clone for Conn {
             write_cipher: self.write_cipher.clone(),
             compression_threshold: self.compression_threshold,
             send: self.send.clone(),
+            last_read: self.last_read,
         }
     }
 }
 
-pub trait PacketType {
+pub trait PacketType: fmt::Debug {
     fn packet_id(&self, protocol_version: i32) -> i32;
 
     fn write<W: io::Write>(&self, buf: &mut W) -> Result<(), Error>;
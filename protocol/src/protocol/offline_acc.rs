@@ -1,18 +1,24 @@
 use crate::protocol::login::{Account, AccountImpl, AccountType};
+use md5::{Digest, Md5};
+
 pub struct OfflineAccount {}
 
 impl AccountImpl for OfflineAccount {
     fn login(&self, name: &str, _password: &str, _token: &str) -> Result<Account, super::Error> {
         Ok(Account {
             name: name.to_string(),
-            uuid: None,
+            uuid: Some(offline_uuid(name)),
             verification_tokens: vec![name.to_string(), "".to_string(), "".to_string()],
             head_img_data: None,
             account_type: AccountType::None,
         })
     }
 
-    fn refresh(&self, account: Account, _token: &str) -> Result<Account, super::Error> {
+    fn refresh(&self, mut account: Account, _token: &str) -> Result<Account, super::Error> {
+        // Accounts saved by older versions have no uuid yet
+        if account.uuid.is_none() {
+            account.uuid = Some(offline_uuid(&account.name));
+        }
         Ok(account)
     }
 
@@ -30,3 +36,22 @@ impl AccountImpl for OfflineAccount {
         Ok(())
     }
 }
+
+/// Derives the uuid an offline mode server assigns to a player, a version 3
+/// uuid of "OfflinePlayer:<name>".
+pub fn offline_uuid(name: &str) -> String {
+    let mut hash = Md5::digest(format!("OfflinePlayer:{}", name).as_bytes());
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+    hex::encode(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuid_matches_server() {
+        assert_eq!(offline_uuid("Notch"), "b50ad385829d3141a2167e7d7539ba7f");
+    }
+}
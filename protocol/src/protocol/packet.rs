@@ -3331,7 +3331,7 @@ impl From<i32> for DigType {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Copy, Debug, Default)]
 pub enum Hand {
     #[default]
     MainHand,
@@ -3476,6 +3476,45 @@ pub fn send_arm_swing(conn: &mut Conn, hand: Hand) -> Result<(), Error> {
     }
 }
 
+pub fn send_attack_entity(conn: &mut Conn, target_id: i32, sneaking: bool) -> Result<(), Error> {
+    // Attacking is the second action of the use entity packet
+    const ATTACK: i32 = 1;
+    let version = conn.get_version();
+    if version < Version::V1_8 {
+        conn.write_packet(packet::play::serverbound::UseEntity_Handsfree_i32 {
+            target_id,
+            ty: ATTACK as u8,
+        })
+    } else if version < Version::V1_9 {
+        conn.write_packet(packet::play::serverbound::UseEntity_Handsfree {
+            target_id: VarInt(target_id),
+            ty: VarInt(ATTACK),
+            target_x: 0.0,
+            target_y: 0.0,
+            target_z: 0.0,
+        })
+    } else if version < Version::V1_16 {
+        conn.write_packet(packet::play::serverbound::UseEntity_Hand {
+            target_id: VarInt(target_id),
+            ty: VarInt(ATTACK),
+            target_x: 0.0,
+            target_y: 0.0,
+            target_z: 0.0,
+            hand: VarInt(Hand::MainHand.ordinal()),
+        })
+    } else {
+        conn.write_packet(packet::play::serverbound::UseEntity_Sneakflag {
+            target_id: VarInt(target_id),
+            ty: VarInt(ATTACK),
+            target_x: 0.0,
+            target_y: 0.0,
+            target_z: 0.0,
+            hand: VarInt(Hand::MainHand.ordinal()),
+            sneaking,
+        })
+    }
+}
+
 pub fn send_digging(
     conn: &mut Conn,
     status: DigType,
@@ -3649,6 +3688,33 @@ pub fn send_client_settings(
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum ResourcePackResult {
+    Loaded = 0,
+    Declined = 1,
+    FailedDownload = 2,
+    Accepted = 3,
+}
+
+pub fn send_resource_pack_status(
+    conn: &mut Conn,
+    hash: String,
+    result: ResourcePackResult,
+) -> Result<(), Error> {
+    let version = conn.get_version();
+    if version < Version::V1_10 {
+        conn.write_packet(packet::play::serverbound::ResourcePackStatus_hash {
+            hash,
+            result: VarInt(result as i32),
+        })
+    } else {
+        conn.write_packet(packet::play::serverbound::ResourcePackStatus {
+            result: VarInt(result as i32),
+        })
+    }
+}
+
 pub fn send_keep_alive(conn: &mut Conn, id: i64) -> Result<(), Error> {
     let version = conn.get_version();
     if version < Version::V1_8 {
@@ -3662,6 +3728,7 @@ pub fn send_keep_alive(conn: &mut Conn, id: i64) -> Result<(), Error> {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum InventoryOperation {
     LeftClick,
     RightClick,
@@ -3745,6 +3812,48 @@ pub fn send_click_container(
     }
 }
 
+pub fn send_creative_inventory_action(
+    conn: &mut Conn,
+    slot: i16,
+    clicked_item: Option<item::Stack>,
+) -> Result<(), Error> {
+    conn.write_packet(packet::play::serverbound::CreativeInventoryAction { slot, clicked_item })
+}
+
 pub fn send_close_window(conn: &mut Conn, id: u8) -> Result<(), Error> {
     conn.write_packet(packet::play::serverbound::CloseWindow { id })
 }
+
+pub fn send_set_sign(conn: &mut Conn, location: Position, lines: [String; 4]) -> Result<(), Error> {
+    let version = conn.get_version();
+    let [line1, line2, line3, line4] = lines;
+    if version < Version::V1_8 {
+        conn.write_packet(packet::play::serverbound::SetSign_i16y {
+            x: location.x,
+            y: location.y as i16,
+            z: location.z,
+            line1,
+            line2,
+            line3,
+            line4,
+        })
+    } else if version < Version::V1_9 {
+        // 1.8 sends the lines as chat components
+        let component = |line: String| serde_json::json!({ "text": line }).to_string();
+        conn.write_packet(packet::play::serverbound::SetSign {
+            location,
+            line1: component(line1),
+            line2: component(line2),
+            line3: component(line3),
+            line4: component(line4),
+        })
+    } else {
+        conn.write_packet(packet::play::serverbound::SetSign {
+            location,
+            line1,
+            line2,
+            line3,
+            line4,
+        })
+    }
+}
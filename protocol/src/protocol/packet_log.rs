@@ -0,0 +1,195 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use instant::{Duration, Instant};
+use lazy_static::lazy_static;
+use log::{info, warn};
+use serde_json::Value;
+
+use super::{Direction, Error, Serializable, State, VarInt};
+
+static PACKET_LOG: AtomicBool = AtomicBool::new(false);
+/// Whether replays feed packets as fast as they're read instead of with
+/// the pacing they were recorded with.
+static REPLAY_UNPACED: AtomicBool = AtomicBool::new(false);
+static REPLAY_FINISHED: AtomicBool = AtomicBool::new(false);
+static REPLAY_FAILED: AtomicBool = AtomicBool::new(false);
+
+struct PacketLog {
+    file: fs::File,
+    start: Instant,
+}
+
+lazy_static! {
+    static ref PACKET_LOG_FILE: Mutex<Option<PacketLog>> = Mutex::new(None);
+}
+
+pub fn enable_packet_log(path: &str) -> Result<(), Error> {
+    let file = fs::File::create(path)?;
+    PACKET_LOG_FILE.lock().unwrap().replace(PacketLog {
+        file,
+        start: Instant::now(),
+    });
+    PACKET_LOG.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn is_packet_log() -> bool {
+    PACKET_LOG.load(Ordering::Relaxed)
+}
+
+/// Makes replays play back as fast as possible, for reproducible timings.
+pub fn disable_replay_pacing() {
+    REPLAY_UNPACED.store(true, Ordering::Relaxed);
+}
+
+/// Whether a replay has fed every packet of its log.
+pub fn is_replay_finished() -> bool {
+    REPLAY_FINISHED.load(Ordering::Relaxed)
+}
+
+/// Whether a replay stopped early because its log couldn't be read.
+pub fn is_replay_failed() -> bool {
+    REPLAY_FAILED.load(Ordering::Relaxed)
+}
+
+/// Appends a packet to the packet log as a line of JSON. `data` is the raw
+/// payload (without the packet id) so the trace can be replayed later.
+pub fn log_packet(
+    dir: Direction,
+    state: State,
+    protocol_version: i32,
+    id: i32,
+    data: &[u8],
+    packet: &dyn fmt::Debug,
+) {
+    let mut log = PACKET_LOG_FILE.lock().unwrap();
+    let log = match log.as_mut() {
+        Some(log) => log,
+        None => return,
+    };
+    let entry = serde_json::json!({
+        "time": log.start.elapsed().as_millis() as u64,
+        "direction": format!("{:?}", dir),
+        "state": format!("{:?}", state),
+        "protocol_version": protocol_version,
+        "id": id,
+        "data": hex::encode(data),
+        "packet": format!("{:?}", packet),
+    });
+    if let Err(err) = writeln!(log.file, "{}", entry) {
+        warn!("Failed to write to packet log: {}", err);
+    }
+}
+
+/// Reads a packet log and re-frames its clientbound play packets as an
+/// uncompressed packet stream, so it can stand in for a server connection.
+pub struct ReplayReader {
+    lines: io::Lines<io::BufReader<fs::File>>,
+    frame: io::Cursor<Vec<u8>>,
+    start: Instant,
+    /// Set when the connection is closed, to stop waiting for more packets
+    /// once the log is exhausted.
+    closed: Arc<AtomicBool>,
+}
+
+impl ReplayReader {
+    pub fn new(path: &str) -> Result<ReplayReader, Error> {
+        let file = fs::File::open(path)?;
+        Ok(ReplayReader {
+            lines: io::BufReader::new(file).lines(),
+            frame: io::Cursor::new(vec![]),
+            start: Instant::now(),
+            closed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// A flag which ends the replay when set, without needing the reader
+    /// which may be blocked waiting in `read`.
+    pub fn close_flag(&self) -> Arc<AtomicBool> {
+        self.closed.clone()
+    }
+
+    /// Returns the protocol version the log was recorded with.
+    pub fn protocol_version(path: &str) -> Result<i32, Error> {
+        let file = fs::File::open(path)?;
+        for line in io::BufReader::new(file).lines() {
+            let entry: Value = serde_json::from_str(&line?)?;
+            if let Some(version) = entry.get("protocol_version").and_then(Value::as_i64) {
+                return Ok(version as i32);
+            }
+        }
+        Err(Error::Err("Packet log contains no packets".to_owned()))
+    }
+
+    fn next_frame(&mut self) -> Result<Option<Vec<u8>>, Error> {
+        for line in &mut self.lines {
+            let entry: Value = serde_json::from_str(&line?)?;
+            if entry.get("direction").and_then(Value::as_str) != Some("Clientbound")
+                || entry.get("state").and_then(Value::as_str) != Some("Play")
+            {
+                continue;
+            }
+            let invalid_entry = || Error::Err("Invalid packet log entry".to_owned());
+            let id = entry
+                .get("id")
+                .and_then(Value::as_i64)
+                .ok_or_else(invalid_entry)? as i32;
+            let data = entry
+                .get("data")
+                .and_then(Value::as_str)
+                .and_then(|data| hex::decode(data).ok())
+                .ok_or_else(invalid_entry)?;
+
+            // Keep the original pacing between packets
+            let paced = !REPLAY_UNPACED.load(Ordering::Relaxed);
+            if let Some(time) = entry.get("time").and_then(Value::as_u64).filter(|_| paced) {
+                let time = Duration::from_millis(time);
+                let elapsed = self.start.elapsed();
+                if time > elapsed {
+                    thread::sleep(time - elapsed);
+                }
+            }
+
+            let mut packet = vec![];
+            VarInt(id).write_to(&mut packet)?;
+            packet.extend_from_slice(&data);
+            let mut frame = vec![];
+            VarInt(packet.len() as i32).write_to(&mut frame)?;
+            frame.extend_from_slice(&packet);
+            return Ok(Some(frame));
+        }
+        Ok(None)
+    }
+}
+
+impl io::Read for ReplayReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.frame.position() as usize >= self.frame.get_ref().len() {
+            match self.next_frame() {
+                Ok(Some(frame)) => self.frame = io::Cursor::new(frame),
+                Ok(None) => {
+                    // Like an idle connection, block once the log is exhausted
+                    // so the replayed world stays loaded, until closed.
+                    if !REPLAY_FINISHED.swap(true, Ordering::Relaxed) {
+                        info!("Packet replay finished");
+                    }
+                    while !self.closed.load(Ordering::Relaxed) {
+                        thread::sleep(Duration::from_millis(100));
+                    }
+                    return Ok(0);
+                }
+                Err(err) => {
+                    REPLAY_FAILED.store(true, Ordering::Relaxed);
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, err.to_string()));
+                }
+            }
+        }
+        self.frame.read(buf)
+    }
+}
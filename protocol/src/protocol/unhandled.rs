@@ -0,0 +1,61 @@
+//! Keeps track of packets the client couldn't decode or doesn't act on
+//! when `--network-debug` is enabled, so gaps in the protocol support show
+//! up in the log instead of being dropped silently.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use log::{debug, info, warn};
+
+use super::{is_network_debug, State};
+
+lazy_static! {
+    static ref COUNTS: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+}
+
+/// Notes a packet id that has no packet in the current protocol version.
+pub fn note_unknown(state: State, id: i32, len: usize) {
+    if !is_network_debug() {
+        return;
+    }
+    warn!(
+        "Unknown packet id 0x{:02X} in state {:?} ({} bytes)",
+        id, state, len
+    );
+    count(format!("unknown 0x{:02X} ({:?})", id, state));
+}
+
+/// Notes a packet that was decoded but isn't handled by the client.
+pub fn note_unhandled(name: &str, state: State, id: i32, len: usize) {
+    if !is_network_debug() {
+        return;
+    }
+    debug!(
+        "Unhandled packet {} (id 0x{:02X}) in state {:?} ({} bytes)",
+        name, id, state, len
+    );
+    count(name.to_owned());
+}
+
+fn count(key: String) {
+    *COUNTS.lock().unwrap().entry(key).or_insert(0) += 1;
+}
+
+/// Logs how often each unknown or unhandled packet came in since the last
+/// summary, most frequent first, and resets the counts.
+pub fn log_summary() {
+    let mut counts = COUNTS
+        .lock()
+        .unwrap()
+        .drain()
+        .collect::<Vec<(String, u32)>>();
+    if counts.is_empty() {
+        return;
+    }
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    info!("Unknown or unhandled packets received:");
+    for (name, count) in counts {
+        info!("  {}: {}", name, count);
+    }
+}
@@ -55,6 +55,70 @@ pub fn protocol_name_to_protocol_version(s: String) -> i32 {
     }
 }
 
+/// The Minecraft releases that speak `version`, for telling the user which
+/// version a server runs, whether or not it's supported.
+pub fn protocol_version_to_release_name(version: i32) -> Option<&'static str> {
+    Some(match version {
+        4 => "1.7.2-1.7.5",
+        5 => "1.7.6-1.7.10",
+        47 => "1.8-1.8.9",
+        107 => "1.9",
+        108 => "1.9.1",
+        109 => "1.9.2",
+        110 => "1.9.3-1.9.4",
+        210 => "1.10-1.10.2",
+        315 => "1.11",
+        316 => "1.11.1-1.11.2",
+        335 => "1.12",
+        338 => "1.12.1",
+        340 => "1.12.2",
+        393 => "1.13",
+        401 => "1.13.1",
+        404 => "1.13.2",
+        477 => "1.14",
+        480 => "1.14.1",
+        485 => "1.14.2",
+        490 => "1.14.3",
+        498 => "1.14.4",
+        573 => "1.15",
+        575 => "1.15.1",
+        578 => "1.15.2",
+        735 => "1.16",
+        736 => "1.16.1",
+        751 => "1.16.2",
+        753 => "1.16.3",
+        754 => "1.16.4-1.16.5",
+        755 => "1.17",
+        756 => "1.17.1",
+        757 => "1.18-1.18.1",
+        758 => "1.18.2",
+        759 => "1.19",
+        760 => "1.19.1-1.19.2",
+        761 => "1.19.3",
+        762 => "1.19.4",
+        763 => "1.20-1.20.1",
+        764 => "1.20.2",
+        765 => "1.20.3-1.20.4",
+        766 => "1.20.5-1.20.6",
+        767 => "1.21-1.21.1",
+        768 => "1.21.2-1.21.3",
+        769 => "1.21.4",
+        770 => "1.21.5",
+        771 => "1.21.6",
+        772 => "1.21.7-1.21.8",
+        _ => return None,
+    })
+}
+
+/// The releases Leafish can connect to, oldest first.
+pub fn supported_release_names() -> Vec<&'static str> {
+    SUPPORTED_PROTOCOLS
+        .iter()
+        .rev()
+        .filter_map(|&version| protocol_version_to_release_name(version))
+        .collect()
+}
+
 pub fn translate_internal_packet_id_for_version(
     version: i32,
     state: State,
@@ -3,7 +3,10 @@ use serde::{
     de::{MapAccess, SeqAccess, Visitor},
     Deserialize, Deserializer,
 };
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fmt;
+use std::sync::{OnceLock, RwLock};
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 #[serde(untagged)]
@@ -201,8 +204,30 @@ impl std::fmt::Debug for Chat {
     }
 }
 
-// TODO: Add multiple language support.
-pub fn translate(str: &str) -> &str {
+fn language() -> &'static RwLock<HashMap<String, String>> {
+    static LANGUAGE: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+    LANGUAGE.get_or_init(Default::default)
+}
+
+/// Replaces the translations of the selected language. Keys it doesn't
+/// translate fall back to English.
+pub fn set_language(translations: HashMap<String, String>) {
+    *language().write().unwrap() = translations;
+}
+
+/// The translation of `key` in the selected language, if it has one.
+pub fn translate_selected(key: &str) -> Option<String> {
+    language().read().unwrap().get(key).cloned()
+}
+
+pub fn translate(str: &str) -> Cow<'_, str> {
+    match translate_selected(str) {
+        Some(translated) => Cow::Owned(translated),
+        None => Cow::Borrowed(translate_english(str)),
+    }
+}
+
+fn translate_english(str: &str) -> &str {
     match str {
             "commands.message.sameTarget" => "You can't send a private message to yourself!",
             "commands.op.success" => "Made {} a server operator",                       // minecraft 1.12.2 - "Opped {}"
@@ -402,6 +427,9 @@ pub fn translate(str: &str) -> &str {
             "multiplayer.player.left" => "{} left the game",
             "multiplayer.disconnect.kicked" => "Kicked by an operator.",
             "multiplayer.disconnect.server_shutdown" => "Server closed",
+            "multiplayer.disconnect.outdated_client" => "Incompatible client! Please use {}",
+            "multiplayer.disconnect.outdated_server" => "Incompatible client! Please use {}",
+            "multiplayer.disconnect.incompatible" => "Incompatible client! Please use {}",
 
             "chat.type.admin" => "[{}: {}]",
             "chat.type.text" => "<{}> {}",
@@ -425,6 +453,9 @@ pub fn translate(str: &str) -> &str {
             "death.attack.mob" => "{} was slain by {}",
             "death.attack.arrow" => "{} was shot by {}",
             "death.attack.player" => "{} was slain by {}",
+            "death.attack.player.item" => "{} was slain by {} using {}",
+            "death.attack.mob.item" => "{} was slain by {} using {}",
+            "death.attack.arrow.item" => "{} was shot by {} using {}",
             "death.attack.explosion.player" => "{} was blown up by {}",
             "death.attack.lava" => "{} tried to swim in lava",
             "death.attack.drowned" => "{} drowned",
@@ -58,6 +58,12 @@ impl Metadata {
         self.map.insert(key.index, val.wrap());
     }
 
+    /// Get the value at an index without knowing its type up front, for
+    /// indices that differ between versions.
+    pub fn get_raw(&self, index: i32) -> Option<&Value> {
+        self.map.get(&index)
+    }
+
     fn put_raw<T: MetaValue>(&mut self, index: i32, val: T) {
         self.map.insert(index, val.wrap());
     }
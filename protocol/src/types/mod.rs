@@ -54,6 +54,27 @@ impl GameMode {
         matches!(*self, GameMode::Spectator)
     }
 
+    pub fn breaks_instantly(&self) -> bool {
+        matches!(*self, GameMode::Creative)
+    }
+
+    /// How far away blocks can be targeted, as the vanilla client allows.
+    pub fn reach_distance(&self) -> f64 {
+        match *self {
+            GameMode::Creative => 5.0,
+            _ => 4.5,
+        }
+    }
+
+    /// How far away entities can be attacked, which is shorter than the block
+    /// reach outside of creative mode.
+    pub fn attack_reach_distance(&self) -> f64 {
+        match *self {
+            GameMode::Creative => 6.0,
+            _ => 3.0,
+        }
+    }
+
     pub fn can_interact_with_world(&self) -> bool {
         matches!(
             *self,
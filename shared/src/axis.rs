@@ -24,4 +24,14 @@ impl Axis {
             Axis::None => 3,
         }
     }
+
+    /// The position of the axis in the `axis` block state property (x, y, z).
+    pub fn offset(&self) -> usize {
+        match *self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+            Axis::None => unreachable!(),
+        }
+    }
 }
@@ -11,16 +11,36 @@ pub enum Direction {
     East,
 }
 
+const VALUES: [Direction; 6] = [
+    Direction::Down,
+    Direction::Up,
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
+const HORIZONTAL: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::West,
+    Direction::East,
+];
+
 impl Direction {
-    pub fn all() -> Vec<Direction> {
-        vec![
-            Direction::Down,
-            Direction::Up,
-            Direction::North,
-            Direction::South,
-            Direction::West,
-            Direction::East,
-        ]
+    /// Every valid direction by value, to loop over without allocating.
+    pub fn all() -> [Direction; 6] {
+        VALUES
+    }
+
+    /// Every valid direction, down, up, north, south, west then east.
+    pub fn values() -> &'static [Direction; 6] {
+        &VALUES
+    }
+
+    /// The directions along the ground, north, south, west then east.
+    pub fn horizontal() -> &'static [Direction; 4] {
+        &HORIZONTAL
     }
 
     pub fn from_string(val: &str) -> Direction {
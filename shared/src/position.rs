@@ -24,6 +24,28 @@ impl Position {
         let (ox, oy, oz) = dir.get_offset();
         self + (ox * by, oy * by, oz * by)
     }
+
+    /// The six positions sharing a face with this one, in the same order
+    /// as `Direction::all`: down, up, north, south, west and east.
+    pub fn neighbors(self) -> [Position; 6] {
+        [
+            self.shift(Direction::Down),
+            self.shift(Direction::Up),
+            self.shift(Direction::North),
+            self.shift(Direction::South),
+            self.shift(Direction::West),
+            self.shift(Direction::East),
+        ]
+    }
+
+    /// Iterates over every position in the box from `min` to `max`, both
+    /// inclusive, with x changing fastest and y slowest. Nothing is
+    /// returned if `min` is past `max` on any axis.
+    pub fn iter_box(min: Position, max: Position) -> impl Iterator<Item = Position> {
+        (min.y..=max.y).flat_map(move |y| {
+            (min.z..=max.z).flat_map(move |z| (min.x..=max.x).map(move |x| Position::new(x, y, z)))
+        })
+    }
 }
 
 impl ops::Add<Position> for Position {
@@ -85,3 +107,43 @@ impl fmt::Debug for Position {
         write!(f, "<{},{},{}>", self.x, self.y, self.z)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn iter_box_empty() {
+        let min = Position::new(0, 0, 0);
+        assert_eq!(Position::iter_box(min, Position::new(-1, 0, 0)).count(), 0);
+        assert_eq!(Position::iter_box(min, Position::new(0, -1, 0)).count(), 0);
+        assert_eq!(Position::iter_box(min, Position::new(5, 5, -1)).count(), 0);
+    }
+
+    #[test]
+    fn iter_box_single() {
+        let pos = Position::new(3, -64, 7);
+        assert_eq!(Position::iter_box(pos, pos).collect::<Vec<_>>(), vec![pos]);
+    }
+
+    #[test]
+    fn iter_box_order() {
+        let positions =
+            Position::iter_box(Position::new(0, 0, 0), Position::new(1, 1, 1)).collect::<Vec<_>>();
+        assert_eq!(positions.len(), 8);
+        assert_eq!(positions[0], Position::new(0, 0, 0));
+        assert_eq!(positions[1], Position::new(1, 0, 0));
+        assert_eq!(positions[2], Position::new(0, 0, 1));
+        assert_eq!(positions[4], Position::new(0, 1, 0));
+        assert_eq!(positions[7], Position::new(1, 1, 1));
+    }
+
+    #[test]
+    fn neighbors() {
+        let pos = Position::new(1, 2, 3);
+        for (neighbor, dir) in pos.neighbors().iter().zip(Direction::all()) {
+            assert_eq!(*neighbor, pos.shift(dir));
+            assert_eq!(neighbor.shift(dir.opposite()), pos);
+        }
+    }
+}
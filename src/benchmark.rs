@@ -0,0 +1,158 @@
+//! Times a replayed session for `--benchmark`: how long the world takes to
+//! load and mesh, and how long frames take once it has.
+//!
+//! Meshing settings are read from the config as usual, so their cost can be
+//! measured by benchmarking the same packet log with them on and off, for
+//! example with `block_model_cache false` in `conf.cfg`.
+
+use crate::protocol::packet_log;
+use crate::settings::BoolSetting;
+use crate::Game;
+use log::info;
+use std::time::{Duration, Instant};
+
+/// How many frames in a row nothing may be left to build before the
+/// world counts as meshed, as the render list is worked out on another
+/// thread and can lag behind.
+const SETTLE_FRAMES: usize = 10;
+
+enum Phase {
+    /// Waiting for the replay to feed every packet.
+    Loading,
+    /// Waiting for the chunk builder to catch up with the loaded world.
+    Building { start: Instant, settled: usize },
+    /// Timing frames.
+    Rendering {
+        build_time: Duration,
+        frame_times: Vec<Duration>,
+    },
+}
+
+pub struct Benchmark {
+    frames: usize,
+    start: Instant,
+    load_time: Duration,
+    phase: Phase,
+}
+
+impl Benchmark {
+    /// Prepares a benchmark rendering `frames` frames once the world has
+    /// loaded.
+    pub fn new(frames: usize) -> Self {
+        packet_log::disable_replay_pacing();
+        Self {
+            frames: frames.max(1),
+            start: Instant::now(),
+            load_time: Duration::default(),
+            phase: Phase::Loading,
+        }
+    }
+
+    /// Records a frame that took `frame_time`, returning whether the
+    /// benchmark is done and has reported its results, or why it can't
+    /// finish when the replay broke off.
+    pub fn frame(&mut self, game: &Game, frame_time: Duration) -> Result<bool, String> {
+        if packet_log::is_replay_failed() {
+            return Err("the packet log couldn't be read".to_owned());
+        }
+        if !game
+            .server
+            .load()
+            .as_ref()
+            .is_some_and(|server| server.is_connected())
+        {
+            return Err("the replay disconnected".to_owned());
+        }
+        match &mut self.phase {
+            Phase::Loading => {
+                if packet_log::is_replay_finished() {
+                    self.load_time = self.start.elapsed();
+                    info!("Benchmark: replay loaded in {:?}", self.load_time);
+                    self.phase = Phase::Building {
+                        start: Instant::now(),
+                        settled: 0,
+                    };
+                }
+            }
+            Phase::Building { start, settled } => {
+                if is_meshed(game) {
+                    *settled += 1;
+                } else {
+                    *settled = 0;
+                }
+                if *settled >= SETTLE_FRAMES {
+                    let build_time = start.elapsed();
+                    info!("Benchmark: chunks built in {:?}", build_time);
+                    self.phase = Phase::Rendering {
+                        build_time,
+                        frame_times: Vec::with_capacity(self.frames),
+                    };
+                }
+            }
+            Phase::Rendering {
+                build_time,
+                frame_times,
+            } => {
+                frame_times.push(frame_time);
+                if frame_times.len() >= self.frames {
+                    let sections = game.chunk_builder.lock().sections_built();
+                    let block_model_cache = game.settings.get_bool(BoolSetting::BlockModelCache);
+                    report(
+                        self.load_time,
+                        *build_time,
+                        sections,
+                        block_model_cache,
+                        frame_times,
+                    );
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Whether every section in view has an up to date mesh.
+fn is_meshed(game: &Game) -> bool {
+    let server = game.server.load();
+    let server = match server.as_ref() {
+        Some(server) => server,
+        None => return false,
+    };
+    game.chunk_builder.lock().is_idle()
+        && !server
+            .world
+            .get_render_list()
+            .iter()
+            .any(|(pos, _)| server.world.is_section_dirty(*pos))
+}
+
+fn report(
+    load_time: Duration,
+    build_time: Duration,
+    sections: usize,
+    block_model_cache: bool,
+    frame_times: &mut [Duration],
+) {
+    frame_times.sort();
+    let total: Duration = frame_times.iter().sum();
+    let average = total / frame_times.len() as u32;
+    let percentile = |p: usize| frame_times[(frame_times.len() - 1) * p / 100];
+    info!("Benchmark results:");
+    info!("  Replay load time: {:?}", load_time);
+    info!(
+        "  Chunk build time: {:?} ({} sections built, block model cache {})",
+        build_time,
+        sections,
+        if block_model_cache { "on" } else { "off" }
+    );
+    info!(
+        "  Frame time over {} frames: average {:?} ({:.1} FPS), median {:?}, 99th percentile {:?}, worst {:?}",
+        frame_times.len(),
+        average,
+        1.0 / average.as_secs_f64(),
+        percentile(50),
+        percentile(99),
+        frame_times[frame_times.len() - 1],
+    );
+}
@@ -1,18 +1,40 @@
 use crate::model;
+use crate::model::greedy::GreedyMesher;
+use crate::paths;
 use crate::render;
 use crate::resources;
+use crate::settings::{BoolSetting, IntSetting, SettingStore};
 use crate::shared::Direction;
 use crate::types::bit::Set;
+use crate::types::hash::FNVHash;
 use crate::world;
+use crate::world::block::RenderShape;
 use crate::world::{block, CPos, ChunkSectionSnapshotGroup, World};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use crossbeam_channel::unbounded;
 use crossbeam_channel::{Receiver, Sender};
+use log::warn;
 use parking_lot::RwLock;
 use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::SystemTime;
 
 const NUM_WORKERS: usize = 8;
+/// The furthest biome tints are blended, which keeps every block the blend
+/// reaches within the neighbouring chunks of a section snapshot.
+const MAX_BIOME_BLEND: i32 = 7;
+/// The most space cached sections may take up on disk before the least
+/// recently used ones are removed.
+const MAX_CACHE_SIZE: u64 = 512 * 1024 * 1024;
+/// How many sections are built between checks of the cache size.
+const CACHE_PRUNE_INTERVAL: usize = 4096;
 
 pub struct ChunkBuilder {
     threads: Vec<(Sender<BuildReq>, thread::JoinHandle<()>)>,
@@ -20,7 +42,18 @@ pub struct ChunkBuilder {
     built_recv: Receiver<(usize, BuildReply)>,
 
     models: Arc<RwLock<model::Factory>>,
+    resources: Arc<RwLock<resources::Manager>>,
     resource_version: usize,
+    protocol_version: i32,
+    /// Where built sections are stored on disk, if caching is enabled.
+    cache_dir: Option<PathBuf>,
+    greedy_meshing: bool,
+    biome_blend: i32,
+    block_model_cache: bool,
+    /// How many sections have been built so far.
+    sections_built: usize,
+    /// Set while the cache is being trimmed down to `MAX_CACHE_SIZE`.
+    pruning_cache: Arc<AtomicBool>,
 }
 
 impl ChunkBuilder {
@@ -28,7 +61,10 @@ impl ChunkBuilder {
         resources: Arc<RwLock<resources::Manager>>,
         textures: Arc<RwLock<render::TextureManager>>,
     ) -> Self {
-        let models = Arc::new(RwLock::new(model::Factory::new(resources, textures)));
+        let models = Arc::new(RwLock::new(model::Factory::new(
+            resources.clone(),
+            textures,
+        )));
 
         let mut threads = vec![];
         let mut free = vec![];
@@ -49,23 +85,90 @@ impl ChunkBuilder {
             free_builders: free,
             built_recv,
             models,
+            resources,
             resource_version: 0xFFFF,
+            protocol_version: 0,
+            cache_dir: None,
+            greedy_meshing: false,
+            biome_blend: 1,
+            block_model_cache: true,
+            sections_built: 0,
+            pruning_cache: Arc::new(AtomicBool::new(false)),
         }
     }
 
-    pub fn tick(&mut self, world: Arc<World>, renderer: Arc<render::Renderer>, version: usize) {
+    /// Whether no sections are being built right now.
+    pub fn is_idle(&self) -> bool {
+        self.free_builders.len() == self.threads.len()
+    }
+
+    pub fn sections_built(&self) -> usize {
+        self.sections_built
+    }
+
+    pub fn tick(
+        &mut self,
+        world: Arc<World>,
+        renderer: Arc<render::Renderer>,
+        version: usize,
+        settings: &SettingStore,
+    ) {
         if version != self.resource_version {
             self.resource_version = version;
             self.models.write().version_change();
+            self.cache_dir = None;
+        }
+        if world.protocol_version() != self.protocol_version {
+            self.protocol_version = world.protocol_version();
+            self.cache_dir = None;
+        }
+        let greedy_meshing = settings.get_bool(BoolSetting::GreedyMeshing);
+        if greedy_meshing != self.greedy_meshing {
+            self.greedy_meshing = greedy_meshing;
+            world.flag_dirty_all();
+        }
+        let biome_blend = if settings.get_bool(BoolSetting::BiomeBlend) {
+            settings
+                .get_int(IntSetting::BiomeBlendRadius)
+                .clamp(1, MAX_BIOME_BLEND)
+        } else {
+            0
+        };
+        if biome_blend != self.biome_blend {
+            self.biome_blend = biome_blend;
+            self.models.write().set_biome_blend(biome_blend);
+            world.flag_dirty_all();
+        }
+        let block_model_cache = settings.get_bool(BoolSetting::BlockModelCache);
+        if block_model_cache != self.block_model_cache {
+            self.block_model_cache = block_model_cache;
+            self.models.write().set_block_model_cache(block_model_cache);
+            world.flag_dirty_all();
+        }
+        let disk_cache = settings.get_bool(BoolSetting::ChunkMeshCache);
+        if disk_cache != self.cache_dir.is_some() {
+            self.cache_dir = if disk_cache {
+                cache_dir(self.resources.read().pack_hash(), self.protocol_version)
+            } else {
+                None
+            };
+            if self.cache_dir.is_some() {
+                self.prune_cache();
+            }
         }
 
         while let Ok((id, mut val)) = self.built_recv.try_recv() {
             world.clone().reset_building_flag(val.position);
+            self.sections_built += 1;
+            if self.cache_dir.is_some() && self.sections_built.is_multiple_of(CACHE_PRUNE_INTERVAL)
+            {
+                self.prune_cache();
+            }
 
             let mut chunks = world.chunks.write();
             let chunk = chunks.get_mut(&CPos(val.position.0, val.position.2));
             if let Some(chunk) = chunk {
-                let section = chunk.sections[val.position.1 as usize].as_mut();
+                let section = chunk.section_mut(val.position.1);
 
                 if let Some(sec) = section {
                     sec.cull_info = val.cull_info;
@@ -108,6 +211,8 @@ impl ChunkBuilder {
                     position: (x, y, z),
                     solid_buffer: t_id.1,
                     trans_buffer: t_id.2,
+                    cache_dir: self.cache_dir.clone(),
+                    greedy_meshing: self.greedy_meshing,
                 })
                 .unwrap();
             if self.free_builders.is_empty() {
@@ -116,6 +221,22 @@ impl ChunkBuilder {
         }
     }
 
+    /// Trims the cache on a background thread, unless that's already
+    /// happening.
+    fn prune_cache(&self) {
+        if self.pruning_cache.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let pruning_cache = self.pruning_cache.clone();
+        thread::spawn(move || {
+            let root = paths::get_cache_dir().join("chunk-cache");
+            if let Err(err) = prune_cache(&root, MAX_CACHE_SIZE) {
+                warn!("Failed to prune chunk cache: {}", err);
+            }
+            pruning_cache.store(false, Ordering::Release);
+        });
+    }
+
     pub fn reset(&mut self) {
         // TODO: Find a safer solution!
         // Drain the built chunk data
@@ -138,6 +259,8 @@ struct BuildReq {
     position: (i32, i32, i32),
     solid_buffer: Vec<u8>,
     trans_buffer: Vec<u8>,
+    cache_dir: Option<PathBuf>,
+    greedy_meshing: bool,
 }
 
 struct BuildReply {
@@ -173,8 +296,46 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
         position,
         mut solid_buffer,
         mut trans_buffer,
+        cache_dir,
+        greedy_meshing,
     } = work;
     let snapshot = ChunkSectionSnapshotGroup::new(world, position.0, position.2, position.1, 2);
+    let biome_blend = models.read().biome_blend();
+
+    let snapshot_hash = cache_dir
+        .as_ref()
+        .map(|_| hash_snapshot(&snapshot, biome_blend));
+    let cache_path = |dir: &Path, snapshot_hash: u64| {
+        dir.join(format!(
+            "{}_{}_{}_{:016x}_blend{}{}.bin",
+            position.0,
+            position.1,
+            position.2,
+            snapshot_hash,
+            biome_blend,
+            if greedy_meshing { "_greedy" } else { "" }
+        ))
+    };
+    if let (Some(dir), Some(snapshot_hash)) = (&cache_dir, snapshot_hash) {
+        let textures = models.read().textures.clone();
+        if let Ok((solid_count, trans_count, cull_info)) = read_cached(
+            &cache_path(dir, snapshot_hash),
+            &textures,
+            &mut solid_buffer,
+            &mut trans_buffer,
+        ) {
+            return BuildReply {
+                position,
+                solid_buffer,
+                solid_count,
+                trans_buffer,
+                trans_count,
+                cull_info,
+            };
+        }
+        solid_buffer.clear();
+        trans_buffer.clear();
+    }
 
     let mut rng = rand_pcg::Pcg32::from_seed([
         ((position.0 as u32) & 0xff) as u8,
@@ -197,39 +358,58 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
 
     let mut solid_count = 0;
     let mut trans_count = 0;
+    let mut greedy = greedy_meshing.then(GreedyMesher::new);
+    let mut block_buffer = vec![];
 
     for y in 0..16 {
         for x in 0..16 {
             for z in 0..16 {
-                let block = snapshot.get_block(x, y, z);
-                let mat = block.get_material();
-                if !mat.renderable {
-                    // Use one step of the rng so that
-                    // if a block is placed in an empty
-                    // location is variant doesn't change
-                    let _: u32 = rng.gen();
-                    continue;
-                }
-
-                match block {
-                    block::Block::Water { .. } => {
+                let state = snapshot.get_state(x, y, z);
+                let block = state.block();
+                let shape = block.render_shape();
+                match shape {
+                    RenderShape::Invisible => {
+                        // Use one step of the rng so that
+                        // if a block is placed in an empty
+                        // location is variant doesn't change
+                        let _: u32 = rng.gen();
+                        continue;
+                    }
+                    RenderShape::Fluid => {
                         let tex = models.read().textures.clone();
-                        trans_count += model::liquid::render_liquid(
-                            tex,
-                            false,
-                            &snapshot,
-                            x,
-                            y,
-                            z,
-                            &mut trans_buffer,
-                        );
+                        if let block::Block::Lava { .. } = block {
+                            solid_count += model::liquid::render_liquid(
+                                tex,
+                                true,
+                                biome_blend,
+                                &snapshot,
+                                x,
+                                y,
+                                z,
+                                &mut solid_buffer,
+                            );
+                        } else {
+                            trans_count += model::liquid::render_liquid(
+                                tex,
+                                false,
+                                biome_blend,
+                                &snapshot,
+                                x,
+                                y,
+                                z,
+                                &mut trans_buffer,
+                            );
+                        }
                         continue;
                     }
-                    block::Block::Lava { .. } => {
+                    _ => {}
+                }
+
+                match block {
+                    block::Block::EndPortal {} => {
                         let tex = models.read().textures.clone();
-                        solid_count += model::liquid::render_liquid(
+                        solid_count += model::portal::render_end_portal(
                             tex,
-                            true,
                             &snapshot,
                             x,
                             y,
@@ -243,6 +423,7 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
                         trans_count += model::liquid::render_liquid(
                             tex,
                             false,
+                            biome_blend,
                             &snapshot,
                             x,
                             y,
@@ -253,10 +434,23 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
                     _ => {}
                 }
 
-                if mat.transparent {
+                if shape == RenderShape::Cross {
+                    // Plants are cut out rather than blended, and their
+                    // diagonal planes are never merged by the greedy mesher
+                    solid_count += model::Factory::get_state_model(
+                        &models,
+                        state,
+                        &mut rng,
+                        &snapshot,
+                        x,
+                        y,
+                        z,
+                        &mut solid_buffer,
+                    );
+                } else if block.get_material().transparent {
                     trans_count += model::Factory::get_state_model(
                         &models,
-                        block,
+                        state,
                         &mut rng,
                         &snapshot,
                         x,
@@ -264,10 +458,23 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
                         z,
                         &mut trans_buffer,
                     );
+                } else if let (RenderShape::Cube, Some(greedy)) = (shape, greedy.as_mut()) {
+                    block_buffer.clear();
+                    model::Factory::get_state_model(
+                        &models,
+                        state,
+                        &mut rng,
+                        &snapshot,
+                        x,
+                        y,
+                        z,
+                        &mut block_buffer,
+                    );
+                    solid_count += greedy.add(x, y, z, &block_buffer, &mut solid_buffer);
                 } else {
                     solid_count += model::Factory::get_state_model(
                         &models,
-                        block,
+                        state,
                         &mut rng,
                         &snapshot,
                         x,
@@ -280,8 +487,30 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
         }
     }
 
+    if let Some(mut greedy) = greedy {
+        solid_count += greedy.finish(&mut solid_buffer);
+    }
+
     let cull_info = build_cull_info(&snapshot);
 
+    if let (Some(dir), Some(snapshot_hash)) = (&cache_dir, snapshot_hash) {
+        let path = cache_path(dir, snapshot_hash);
+        let mut data = vec![];
+        let textures = models.read().textures.clone();
+        let written = write_cached(
+            &mut data,
+            &textures.read(),
+            &solid_buffer,
+            solid_count,
+            &trans_buffer,
+            trans_count,
+            cull_info,
+        );
+        if let Err(err) = written.and_then(|_| paths::write_atomic(&path, &data)) {
+            warn!("Failed to cache chunk section at {:?}: {}", position, err);
+        }
+    }
+
     BuildReply {
         position,
         solid_buffer,
@@ -292,6 +521,211 @@ fn build_func_1(models: Arc<RwLock<model::Factory>>, work: BuildReq) -> BuildRep
     }
 }
 
+/// Returns the directory built sections are cached in for the given resource
+/// packs and protocol version, which decide the models blocks get. The crate
+/// version is part of the name as well, so meshes built by older versions of
+/// the builder are never reused.
+fn cache_dir(pack_hash: u64, protocol_version: i32) -> Option<PathBuf> {
+    let mut hasher = FNVHash::default();
+    pack_hash.hash(&mut hasher);
+    protocol_version.hash(&mut hasher);
+    let dir = paths::get_cache_dir().join(format!(
+        "chunk-cache/{}-{:016x}",
+        env!("CARGO_PKG_VERSION"),
+        hasher.finish()
+    ));
+    if let Err(err) = fs::create_dir_all(&dir) {
+        warn!("Failed to create chunk cache directory: {}", err);
+        return None;
+    }
+    Some(dir)
+}
+
+/// Hashes everything a section's mesh depends on: the blocks, light and
+/// biomes of the section and the blocks directly around it, as well as the
+/// biomes within the blend radius.
+fn hash_snapshot(snapshot: &ChunkSectionSnapshotGroup, biome_blend: i32) -> u64 {
+    let mut hasher = FNVHash::default();
+    for y in -1..17 {
+        for z in -1..17 {
+            for x in -1..17 {
+                snapshot.get_block(x, y, z).hash(&mut hasher);
+                snapshot.get_block_light(x, y, z).hash(&mut hasher);
+                snapshot.get_sky_light(x, y, z).hash(&mut hasher);
+            }
+        }
+    }
+    // The biomes out to the blend radius tint the blocks in the section
+    let reach = biome_blend.max(1);
+    for z in -reach..16 + reach {
+        for x in -reach..16 + reach {
+            snapshot.get_biome(x, z).id.hash(&mut hasher);
+        }
+    }
+    hasher.finish()
+}
+
+fn read_cached(
+    path: &Path,
+    textures: &RwLock<render::TextureManager>,
+    solid_buffer: &mut Vec<u8>,
+    trans_buffer: &mut Vec<u8>,
+) -> io::Result<(usize, usize, CullInfo)> {
+    // Opened for writing too, which some platforms need to set the
+    // modification time below
+    let file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+    let mut file = io::BufReader::new(file);
+    let solid_count = file.read_u32::<LittleEndian>()? as usize;
+    let trans_count = file.read_u32::<LittleEndian>()? as usize;
+    let cull_info = CullInfo(file.read_u64::<LittleEndian>()?);
+    let mut names = vec![];
+    for _ in 0..file.read_u16::<LittleEndian>()? {
+        let mut name = vec![0; file.read_u16::<LittleEndian>()? as usize];
+        file.read_exact(&mut name)?;
+        names.push(String::from_utf8(name).map_err(|_| io::ErrorKind::InvalidData)?);
+    }
+    let solid_len = file.read_u32::<LittleEndian>()? as u64;
+    (&mut file).take(solid_len).read_to_end(solid_buffer)?;
+    let trans_len = file.read_u32::<LittleEndian>()? as u64;
+    (&mut file).take(trans_len).read_to_end(trans_buffer)?;
+    if solid_buffer.len() as u64 != solid_len || trans_buffer.len() as u64 != trans_len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let textures = names
+        .iter()
+        .map(|name| render::Renderer::get_texture(textures, name))
+        .collect::<Vec<_>>();
+    bind_textures(solid_buffer, &textures)?;
+    bind_textures(trans_buffer, &textures)?;
+    // Mark the section as recently used so pruning keeps it around
+    file.get_ref().set_modified(SystemTime::now())?;
+    Ok((solid_count, trans_count, cull_info))
+}
+
+/// Removes the least recently used sections from every cache directory
+/// under `root` until they take up at most `max_size` bytes together.
+fn prune_cache(root: &Path, max_size: u64) -> io::Result<()> {
+    let mut files = vec![];
+    for dir in fs::read_dir(root)? {
+        let dir = dir?;
+        if !dir.file_type()?.is_dir() {
+            continue;
+        }
+        for file in fs::read_dir(dir.path())? {
+            let file = file?;
+            let metadata = file.metadata()?;
+            if metadata.is_file() {
+                files.push((metadata.modified()?, metadata.len(), file.path()));
+            }
+        }
+    }
+    let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+    if size <= max_size {
+        return Ok(());
+    }
+    files.sort_unstable_by_key(|(modified, _, _)| *modified);
+    for (_, len, path) in files {
+        if size <= max_size {
+            break;
+        }
+        fs::remove_file(&path)?;
+        size -= len;
+    }
+    Ok(())
+}
+
+fn write_cached(
+    data: &mut Vec<u8>,
+    textures: &render::TextureManager,
+    solid_buffer: &[u8],
+    solid_count: usize,
+    trans_buffer: &[u8],
+    trans_count: usize,
+    cull_info: CullInfo,
+) -> io::Result<()> {
+    let mut names = vec![];
+    let solid_buffer = unbind_textures(solid_buffer, textures, &mut names)?;
+    let trans_buffer = unbind_textures(trans_buffer, textures, &mut names)?;
+    data.write_u32::<LittleEndian>(solid_count as u32)?;
+    data.write_u32::<LittleEndian>(trans_count as u32)?;
+    data.write_u64::<LittleEndian>(cull_info.0)?;
+    data.write_u16::<LittleEndian>(names.len() as u16)?;
+    for name in &names {
+        data.write_u16::<LittleEndian>(name.len() as u16)?;
+        data.extend_from_slice(name.as_bytes());
+    }
+    data.write_u32::<LittleEndian>(solid_buffer.len() as u32)?;
+    data.extend_from_slice(&solid_buffer);
+    data.write_u32::<LittleEndian>(trans_buffer.len() as u32)?;
+    data.extend_from_slice(&trans_buffer);
+    Ok(())
+}
+
+/// Points the vertices in `buffer` at their texture's index in `names`
+/// instead of its place in the atlases. The atlases are packed in the order
+/// textures are first used, so those places change from run to run.
+fn unbind_textures(
+    buffer: &[u8],
+    textures: &render::TextureManager,
+    names: &mut Vec<String>,
+) -> io::Result<Vec<u8>> {
+    // Where each atlas position is found in `names`, and the offset of the
+    // position into its texture
+    let mut found: HashMap<(i16, u16, u16), (usize, u16, u16)> = HashMap::new();
+    let mut unbound = Vec::with_capacity(buffer.len());
+    let mut data = buffer;
+    while !data.is_empty() {
+        let mut vert = model::BlockVertex::read(&mut data)?;
+        let key = (vert.tatlas, vert.tx, vert.ty);
+        let (index, x, y) = match found.get(&key) {
+            Some(found) => *found,
+            None => {
+                let tex = textures
+                    .texture_at(vert.tatlas as i32, vert.tx as usize, vert.ty as usize)
+                    .ok_or(io::ErrorKind::InvalidData)?;
+                let index = match names.iter().position(|name| *name == tex.name) {
+                    Some(index) => index,
+                    None => {
+                        names.push(tex.name.clone());
+                        names.len() - 1
+                    }
+                };
+                let offset = (
+                    index,
+                    vert.tx - tex.get_x() as u16,
+                    vert.ty - tex.get_y() as u16,
+                );
+                found.insert(key, offset);
+                offset
+            }
+        };
+        vert.tatlas = index as i16;
+        vert.tx = x;
+        vert.ty = y;
+        vert.write(&mut unbound);
+    }
+    Ok(unbound)
+}
+
+/// Points the vertices of a cached mesh back at the atlases, where the
+/// textures `unbind_textures` named sit now.
+fn bind_textures(buffer: &mut Vec<u8>, textures: &[render::Texture]) -> io::Result<()> {
+    let mut bound = Vec::with_capacity(buffer.len());
+    let mut data = &buffer[..];
+    while !data.is_empty() {
+        let mut vert = model::BlockVertex::read(&mut data)?;
+        let tex = textures
+            .get(vert.tatlas as usize)
+            .ok_or(io::ErrorKind::InvalidData)?;
+        vert.tatlas = tex.atlas as i16;
+        vert.tx += tex.get_x() as u16;
+        vert.ty += tex.get_y() as u16;
+        vert.write(&mut bound);
+    }
+    *buffer = bound;
+    Ok(())
+}
+
 fn build_cull_info(snapshot: &world::ChunkSectionSnapshotGroup) -> CullInfo {
     let mut visited = Set::new(16 * 16 * 16);
     let mut info = CullInfo::new();
@@ -1,15 +1,25 @@
 use log::Level;
 use parking_lot::Mutex;
 
+use crate::entity::Position;
 use crate::format::{Color, Component, ComponentType};
+use crate::screen::{self, chat};
 use crate::settings::SettingStore;
-use crate::{paths, ui};
+use crate::world::{anvil, CPos};
+use crate::{paths, ui, Game};
 use crate::{render, StringSetting};
+use log::{error, info, warn};
+
+use winit::keyboard::{Key, NamedKey};
 
 use std::fs;
 use std::io::Write;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Space left below the log for the command line.
+const INPUT_HEIGHT: f64 = 18.0;
 
 pub struct Console {
     history: Vec<Component>,
@@ -21,11 +31,14 @@ pub struct Console {
     elements: Option<ConsoleElements>,
     active: bool,
     position: f64,
+    /// The command being typed in.
+    input: String,
 }
 
 struct ConsoleElements {
     background: ui::ImageRef,
     lines: Vec<ui::FormattedRef>,
+    input: Option<ui::TextRef>,
 }
 
 impl Default for Console {
@@ -47,6 +60,7 @@ impl Console {
             elements: None,
             active: false,
             position: -220.0,
+            input: String::new(),
         }
     }
 
@@ -73,7 +87,7 @@ impl Console {
         }
     }
 
-    pub fn _is_active(&self) -> bool {
+    pub fn is_active(&self) -> bool {
         self.active
     }
 
@@ -123,6 +137,7 @@ impl Console {
             self.elements = Some(ConsoleElements {
                 background,
                 lines: vec![],
+                input: None,
             });
             self.dirty = true;
         }
@@ -134,8 +149,15 @@ impl Console {
         if self.dirty {
             self.dirty = false;
             elements.lines.clear();
-
-            let mut offset = 0.0;
+            elements.input = Some(
+                ui::TextBuilder::new()
+                    .text(format!("> {}_", self.input))
+                    .position(5.0, 5.0)
+                    .alignment(ui::VAttach::Bottom, ui::HAttach::Left)
+                    .create(&mut *background),
+            );
+
+            let mut offset = INPUT_HEIGHT;
             for line in self.history.iter().rev() {
                 if offset >= 210.0 {
                     break;
@@ -155,6 +177,42 @@ impl Console {
         }
     }
 
+    /// Types into the command line while the console is open, returning
+    /// the command once enter is pressed.
+    pub fn key_press(&mut self, key: &Key) -> Option<String> {
+        match key {
+            Key::Named(NamedKey::Enter) => {
+                let command = std::mem::take(&mut self.input);
+                self.dirty = true;
+                let command = command.trim();
+                if command.is_empty() {
+                    return None;
+                }
+                self.history.remove(0);
+                self.history.push(Component::new(ComponentType::new(
+                    &format!("> {}", command),
+                    Some(Color::Gray),
+                )));
+                Some(command.to_owned())
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.active = false;
+                None
+            }
+            Key::Named(NamedKey::Backspace) => {
+                self.dirty |= self.input.pop().is_some();
+                None
+            }
+            key => {
+                if let Some(text) = key.to_text() {
+                    self.input.extend(text.chars().filter(|c| !c.is_control()));
+                    self.dirty = true;
+                }
+                None
+            }
+        }
+    }
+
     fn log(&mut self, record: &log::Record) {
         for filtered in FILTERED_CRATES {
             if record.module_path().unwrap_or("").starts_with(filtered) {
@@ -215,6 +273,222 @@ impl Console {
     }
 }
 
+/// A command that can be typed into the console.
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    description: &'static str,
+    run: fn(&Game, &[&str]),
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        usage: "/help",
+        description: "Lists the console commands",
+        run: help,
+    },
+    Command {
+        name: "connect",
+        usage: "/connect <address>",
+        description: "Joins a server",
+        run: connect,
+    },
+    Command {
+        name: "disconnect",
+        usage: "/disconnect",
+        description: "Leaves the current server",
+        run: disconnect,
+    },
+    Command {
+        name: "fps",
+        usage: "/fps",
+        description: "Shows the frame rate",
+        run: fps,
+    },
+    Command {
+        name: "pos",
+        usage: "/pos",
+        description: "Shows where the player is",
+        run: pos,
+    },
+    Command {
+        name: "set",
+        usage: "/set <setting> [value]",
+        description: "Shows or changes a setting",
+        run: set,
+    },
+    Command {
+        name: "ignore",
+        usage: "/ignore <name>",
+        description: "Hides chat from a player",
+        run: ignore,
+    },
+    Command {
+        name: "unignore",
+        usage: "/unignore <name>",
+        description: "Shows chat from an ignored player again",
+        run: unignore,
+    },
+    Command {
+        name: "dumpworld",
+        usage: "/dumpworld <radius>",
+        description: "Saves the chunks around the player as Anvil region files",
+        run: dump_world,
+    },
+];
+
+/// Runs a command typed into the console. Commands the console doesn't
+/// know are sent on to the server while connected.
+pub fn execute(game: &Game, input: &str) {
+    let mut args = input.trim_start_matches('/').split_whitespace();
+    let name = match args.next() {
+        Some(name) => name,
+        None => return,
+    };
+    let args = args.collect::<Vec<_>>();
+    if let Some(command) = COMMANDS.iter().find(|command| command.name == name) {
+        (command.run)(game, &args);
+    } else if input.starts_with('/') && game.server.load().is_some() {
+        chat::send_message(game, input.to_owned());
+    } else {
+        warn!("Unknown command: {}, see /help", name);
+    }
+}
+
+fn help(_game: &Game, _args: &[&str]) {
+    for command in COMMANDS {
+        info!("{} - {}", command.usage, command.description);
+    }
+}
+
+fn connect(game: &Game, args: &[&str]) {
+    let address = match args {
+        [address] => *address,
+        _ => {
+            warn!("Usage: /connect <address>");
+            return;
+        }
+    };
+    if game.server.load().is_some() {
+        warn!("Already connected, use /disconnect first");
+        return;
+    }
+    if game.current_account.lock().is_none() {
+        warn!("Log in before connecting to a server");
+        return;
+    }
+    screen::connect_to_server(game, address);
+}
+
+fn disconnect(game: &Game, _args: &[&str]) {
+    match game.server.load().as_ref() {
+        Some(server) => server.disconnect(None),
+        None => warn!("Not connected to a server"),
+    }
+}
+
+fn fps(game: &Game, _args: &[&str]) {
+    match game.server.load().as_ref() {
+        Some(server) => info!("{} fps", server.hud_context.read().fps()),
+        None => warn!("The frame rate is only counted while playing"),
+    }
+}
+
+fn pos(game: &Game, _args: &[&str]) {
+    let server = game.server.load();
+    let position = server.as_ref().and_then(|server| {
+        let player = server.player.load();
+        let entities = server.entities.read();
+        entities
+            .world
+            .get::<Position>(player.as_ref()?.1)
+            .map(|position| position.position)
+    });
+    match position {
+        Some(pos) => info!(
+            "Position: {:.3} {:.3} {:.3} (chunk {} {})",
+            pos.x,
+            pos.y,
+            pos.z,
+            (pos.x.floor() as i32) >> 4,
+            (pos.z.floor() as i32) >> 4
+        ),
+        None => warn!("Not in a world"),
+    }
+}
+
+fn set(game: &Game, args: &[&str]) {
+    match args {
+        [name] => match game.settings.get_by_name(name) {
+            Some(value) => info!("{} is {}", name, value),
+            None => warn!("Unknown setting: {}", name),
+        },
+        [name, value @ ..] if !value.is_empty() => {
+            let value = value.join(" ");
+            if game.settings.set_by_name(name, &value) {
+                info!("Set {} to {}", name, value);
+            } else {
+                warn!("Couldn't set {} to {}", name, value);
+            }
+        }
+        _ => warn!("Usage: /set <setting> [value]"),
+    }
+}
+
+fn ignore(_game: &Game, args: &[&str]) {
+    match args {
+        [name] if chat::ignore_player(name) => info!("Ignoring chat from {}", name),
+        [name] => warn!("{} is already ignored", name),
+        _ => warn!("Usage: /ignore <name>"),
+    }
+}
+
+fn unignore(_game: &Game, args: &[&str]) {
+    match args {
+        [name] if chat::unignore_player(name) => info!("No longer ignoring {}", name),
+        [name] => warn!("{} isn't ignored", name),
+        _ => warn!("Usage: /unignore <name>"),
+    }
+}
+
+/// Saves the chunks around the player as Anvil region files, so they can
+/// be opened in world editors.
+fn dump_world(game: &Game, args: &[&str]) {
+    let radius = match args {
+        [radius] => radius.parse::<i32>().ok().filter(|radius| *radius >= 0),
+        _ => None,
+    };
+    let radius = match radius {
+        Some(radius) => radius,
+        None => {
+            warn!("Usage: /dumpworld <radius>");
+            return;
+        }
+    };
+    let server = game.server.load();
+    let server = match server.as_ref() {
+        Some(server) => server,
+        None => {
+            warn!("Not connected to a server");
+            return;
+        }
+    };
+    let pos = game.renderer.camera.lock().pos;
+    let center = CPos((pos.x.floor() as i32) >> 4, (pos.z.floor() as i32) >> 4);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let dir = paths::get_data_dir()
+        .join("dumps")
+        .join(format!("world-{}", timestamp))
+        .join("region");
+    match anvil::export_region_files(&server.world, center, radius, &dir) {
+        Ok(count) => info!("Wrote {} chunks to {}", count, dir.display()),
+        Err(err) => error!("Failed to dump the world: {}", err),
+    }
+}
+
 fn _log_level_from_str(s: &str) -> Option<log::Level> {
     // TODO: no opposite of FromStr in log crate?
     use log::Level::*;
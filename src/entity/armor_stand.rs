@@ -0,0 +1,438 @@
+use super::{Bounds, Light, Position, Rotation};
+use crate::entity::{armor_texture_name, resolve_textures};
+use crate::inventory::material::versions::to_material;
+use crate::inventory::Material;
+use crate::render::model::{self, Vertex};
+use crate::render::{Renderer, Texture};
+use crate::server::RendererResource;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use collision::Aabb3;
+use leafish_protocol::item::Stack;
+use leafish_protocol::types::{self, Value};
+use shared::Version;
+use std::sync::Arc;
+
+const FLAG_SMALL: i8 = 0x01;
+const FLAG_ARMS: i8 = 0x04;
+const FLAG_NO_BASE_PLATE: i8 = 0x08;
+const FLAG_INVISIBLE: i8 = 0x20;
+
+/// Rotations of the stand's limbs in degrees, as sent by the server.
+#[derive(Clone, Copy, PartialEq)]
+struct Pose {
+    head: [f32; 3],
+    body: [f32; 3],
+    left_arm: [f32; 3],
+    right_arm: [f32; 3],
+    left_leg: [f32; 3],
+    right_leg: [f32; 3],
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Self {
+            head: [0.0, 0.0, 0.0],
+            body: [0.0, 0.0, 0.0],
+            left_arm: [-10.0, 0.0, -10.0],
+            right_arm: [-15.0, 0.0, 10.0],
+            left_leg: [-1.0, 0.0, -1.0],
+            right_leg: [1.0, 0.0, 1.0],
+        }
+    }
+}
+
+#[derive(Component, Default)]
+pub struct ArmorStandModel {
+    model: Option<model::ModelHandle>,
+    dirty: bool,
+
+    flags: i8,
+    invisible: bool,
+    pose: Pose,
+    /// Worn armor from the boots up to the helmet.
+    armor: [Option<Material>; 4],
+}
+
+impl ArmorStandModel {
+    pub fn apply_metadata(&mut self, metadata: &types::Metadata, version: Version) {
+        // The stand's fields come straight after those of living entities
+        let flags_index = match version {
+            Version::V1_7 | Version::V1_8 | Version::V1_9 => 10,
+            Version::V1_10
+            | Version::V1_11
+            | Version::V1_12
+            | Version::V1_13
+            | Version::V1_13_2 => 11,
+            Version::V1_14 => 13,
+            Version::V1_15 | Version::V1_16 | Version::V1_16_2 => 14,
+            _ => 15,
+        };
+        if let Some(Value::Byte(flags)) = metadata.get_raw(0) {
+            let invisible = flags & FLAG_INVISIBLE != 0;
+            if invisible != self.invisible {
+                self.invisible = invisible;
+                self.dirty = true;
+            }
+        }
+        if let Some(Value::Byte(flags)) = metadata.get_raw(flags_index) {
+            if *flags != self.flags {
+                self.flags = *flags;
+                self.dirty = true;
+            }
+        }
+        let pose = &mut self.pose;
+        for (offset, rotation) in [
+            &mut pose.head,
+            &mut pose.body,
+            &mut pose.left_arm,
+            &mut pose.right_arm,
+            &mut pose.left_leg,
+            &mut pose.right_leg,
+        ]
+        .iter_mut()
+        .enumerate()
+        {
+            if let Some(Value::Vector(value)) = metadata.get_raw(flags_index + 1 + offset as i32) {
+                **rotation = *value;
+            }
+        }
+    }
+
+    /// Handles an equipment update, ignoring held items as they aren't
+    /// rendered yet.
+    pub fn set_equipment(&mut self, slot: i32, item: Option<&Stack>, version: Version) {
+        // Before 1.9 there was no offhand slot before the armor
+        let first_armor_slot = if version <= Version::V1_8 { 1 } else { 2 };
+        let index = slot - first_armor_slot;
+        if !(0..4).contains(&index) {
+            return;
+        }
+        let material = item.map(|stack| {
+            to_material(
+                stack.id as u16,
+                Some(stack.damage.unwrap_or(0)),
+                None,
+                version,
+            )
+        });
+        if self.armor[index as usize] != material {
+            self.armor[index as usize] = material;
+            self.dirty = true;
+        }
+    }
+
+    fn is_small(&self) -> bool {
+        self.flags & FLAG_SMALL != 0
+    }
+}
+
+pub fn added_armor_stand(
+    renderer: Res<RendererResource>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ArmorStandModel), Added<ArmorStandModel>>,
+) {
+    let renderer = &renderer.0;
+    for (entity, mut stand_model) in query.iter_mut() {
+        commands
+            .entity(entity)
+            .insert(Bounds::new(stand_bounds(false)));
+        create_armor_stand_model(renderer, &mut stand_model);
+    }
+}
+
+pub fn update_armor_stand(
+    renderer: Res<RendererResource>,
+    mut query: Query<(
+        &mut ArmorStandModel,
+        &Position,
+        &Rotation,
+        &Light,
+        Option<&mut Bounds>,
+    )>,
+) {
+    use std::f32::consts::PI;
+
+    let renderer = &renderer.0;
+    for (mut stand_model, position, rotation, light, bounds) in query.iter_mut() {
+        if stand_model.dirty {
+            if let Some(mut bounds) = bounds {
+                bounds.bounds = stand_bounds(stand_model.is_small());
+            }
+            create_armor_stand_model(renderer, &mut stand_model);
+        }
+
+        if let Some(smodel) = &stand_model.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(smodel).unwrap();
+
+            mdl.block_light = light.block_light;
+            mdl.sky_light = light.sky_light;
+
+            let offset_matrix = Matrix4::from(Decomposed {
+                scale: if stand_model.is_small() { 0.5 } else { 1.0 },
+                rot: Quaternion::from_angle_y(Rad(PI + rotation.yaw as f32)),
+                disp: Vector3::new(
+                    position.position.x as f32,
+                    -position.position.y as f32,
+                    position.position.z as f32,
+                ),
+            });
+            let part = |pivot: [f32; 3], rotation: [f32; 3]| {
+                offset_matrix
+                    * Matrix4::from(Decomposed {
+                        scale: 1.0,
+                        rot: Quaternion::from_angle_z(Rad(rotation[2].to_radians()))
+                            * Quaternion::from_angle_y(Rad(rotation[1].to_radians()))
+                            * Quaternion::from_angle_x(Rad(rotation[0].to_radians())),
+                        disp: Vector3::new(
+                            pivot[0] / 16.0,
+                            (pivot[1] - 24.0) / 16.0,
+                            pivot[2] / 16.0,
+                        ),
+                    })
+            };
+
+            let pose = stand_model.pose;
+            mdl.matrix[ArmorStandModelPart::Head as usize] = part([0.0, 1.0, 0.0], pose.head);
+            mdl.matrix[ArmorStandModelPart::Body as usize] = part([0.0, 0.0, 0.0], pose.body);
+            mdl.matrix[ArmorStandModelPart::ArmLeft as usize] =
+                part([5.0, 2.0, 0.0], pose.left_arm);
+            mdl.matrix[ArmorStandModelPart::ArmRight as usize] =
+                part([-5.0, 2.0, 0.0], pose.right_arm);
+            mdl.matrix[ArmorStandModelPart::LegLeft as usize] =
+                part([1.9, 12.0, 0.0], pose.left_leg);
+            mdl.matrix[ArmorStandModelPart::LegRight as usize] =
+                part([-1.9, 12.0, 0.0], pose.right_leg);
+            mdl.matrix[ArmorStandModelPart::BasePlate as usize] =
+                part([0.0, 24.0, 0.0], [0.0, 0.0, 0.0]);
+        }
+    }
+}
+
+fn stand_bounds(small: bool) -> Aabb3<f64> {
+    let scale = if small { 0.5 } else { 1.0 };
+    Aabb3::new(
+        Point3::new(-0.25 * scale, 0.0, -0.25 * scale),
+        Point3::new(0.25 * scale, 1.975 * scale, 0.25 * scale),
+    )
+}
+
+/// Appends a box given in the vanilla model's coordinates, in pixels
+/// relative to the part's pivot with y pointing down.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn append_part_box(
+    verts: &mut Vec<Vertex>,
+    tex: &Texture,
+    (x, y, z): (f32, f32, f32),
+    (w, h, d): (f32, f32, f32),
+    inflate: f32,
+    (u, v): (f32, f32),
+) {
+    model::append_box(
+        verts,
+        (x - inflate) / 16.0,
+        -(y + h + inflate) / 16.0,
+        (z - inflate) / 16.0,
+        (w + inflate * 2.0) / 16.0,
+        (h + inflate * 2.0) / 16.0,
+        (d + inflate * 2.0) / 16.0,
+        resolve_textures(tex, w, h, d, u, v),
+    );
+}
+
+fn create_armor_stand_model(renderer: &Arc<Renderer>, stand_model: &mut ArmorStandModel) {
+    stand_model.dirty = false;
+
+    let textures = renderer.get_textures_ref();
+    let mut parts: Vec<Vec<Vertex>> = vec![vec![]; 7];
+
+    if !stand_model.invisible {
+        use ArmorStandModelPart::*;
+        let tex = Renderer::get_texture(textures, "minecraft:entity/armorstand/wood");
+        append_part_box(
+            &mut parts[Head as usize],
+            &tex,
+            (-1.0, -7.0, -1.0),
+            (2.0, 7.0, 2.0),
+            0.0,
+            (0.0, 0.0),
+        );
+        append_part_box(
+            &mut parts[Body as usize],
+            &tex,
+            (-6.0, 0.0, -1.5),
+            (12.0, 3.0, 3.0),
+            0.0,
+            (0.0, 26.0),
+        );
+        append_part_box(
+            &mut parts[Body as usize],
+            &tex,
+            (-3.0, 3.0, -1.0),
+            (2.0, 7.0, 2.0),
+            0.0,
+            (16.0, 0.0),
+        );
+        append_part_box(
+            &mut parts[Body as usize],
+            &tex,
+            (1.0, 3.0, -1.0),
+            (2.0, 7.0, 2.0),
+            0.0,
+            (48.0, 16.0),
+        );
+        append_part_box(
+            &mut parts[Body as usize],
+            &tex,
+            (-4.0, 10.0, -1.0),
+            (8.0, 2.0, 2.0),
+            0.0,
+            (0.0, 48.0),
+        );
+        if stand_model.flags & FLAG_ARMS != 0 {
+            let size = (2.0, 12.0, 2.0);
+            append_part_box(
+                &mut parts[ArmLeft as usize],
+                &tex,
+                (0.0, -2.0, -1.0),
+                size,
+                0.0,
+                (32.0, 16.0),
+            );
+            append_part_box(
+                &mut parts[ArmRight as usize],
+                &tex,
+                (-2.0, -2.0, -1.0),
+                size,
+                0.0,
+                (24.0, 0.0),
+            );
+        }
+        let size = (2.0, 11.0, 2.0);
+        append_part_box(
+            &mut parts[LegLeft as usize],
+            &tex,
+            (-1.0, 0.0, -1.0),
+            size,
+            0.0,
+            (40.0, 16.0),
+        );
+        append_part_box(
+            &mut parts[LegRight as usize],
+            &tex,
+            (-1.0, 0.0, -1.0),
+            size,
+            0.0,
+            (8.0, 0.0),
+        );
+        if stand_model.flags & FLAG_NO_BASE_PLATE == 0 {
+            append_part_box(
+                &mut parts[BasePlate as usize],
+                &tex,
+                (-6.0, -1.0, -6.0),
+                (12.0, 1.0, 12.0),
+                0.0,
+                (0.0, 32.0),
+            );
+        }
+    }
+
+    // Armor uses the player's proportions around the same pivots
+    for (slot, material) in stand_model.armor.iter().enumerate() {
+        let name = match material.and_then(armor_texture_name) {
+            Some(name) => name,
+            None => continue,
+        };
+        use ArmorStandModelPart::*;
+        // Leggings are drawn from the second, slightly tighter layer
+        let (layer, inflate) = if slot == 1 { (2, 0.5) } else { (1, 1.0) };
+        let tex = Renderer::get_texture(
+            textures,
+            &format!("minecraft:models/armor/{}_layer_{}", name, layer),
+        );
+        let limb = (4.0, 12.0, 4.0);
+        match slot {
+            0 | 1 => {
+                for leg in [LegLeft, LegRight] {
+                    append_part_box(
+                        &mut parts[leg as usize],
+                        &tex,
+                        (-2.0, 0.0, -2.0),
+                        limb,
+                        inflate,
+                        (0.0, 16.0),
+                    );
+                }
+                if slot == 1 {
+                    let body = (8.0, 12.0, 4.0);
+                    append_part_box(
+                        &mut parts[Body as usize],
+                        &tex,
+                        (-4.0, 0.0, -2.0),
+                        body,
+                        inflate,
+                        (16.0, 16.0),
+                    );
+                }
+            }
+            2 => {
+                let body = (8.0, 12.0, 4.0);
+                append_part_box(
+                    &mut parts[Body as usize],
+                    &tex,
+                    (-4.0, 0.0, -2.0),
+                    body,
+                    inflate,
+                    (16.0, 16.0),
+                );
+                append_part_box(
+                    &mut parts[ArmLeft as usize],
+                    &tex,
+                    (-1.0, -2.0, -2.0),
+                    limb,
+                    inflate,
+                    (40.0, 16.0),
+                );
+                append_part_box(
+                    &mut parts[ArmRight as usize],
+                    &tex,
+                    (-3.0, -2.0, -2.0),
+                    limb,
+                    inflate,
+                    (40.0, 16.0),
+                );
+            }
+            _ => {
+                let head = (8.0, 8.0, 8.0);
+                append_part_box(
+                    &mut parts[Head as usize],
+                    &tex,
+                    (-4.0, -8.0, -4.0),
+                    head,
+                    inflate,
+                    (0.0, 0.0),
+                );
+            }
+        }
+    }
+
+    stand_model
+        .model
+        .replace(renderer.clone().models.lock().create_model(
+            model::DEFAULT,
+            parts,
+            renderer.clone(),
+        ));
+}
+
+#[derive(Clone, Copy)]
+enum ArmorStandModelPart {
+    Head = 0,
+    Body = 1,
+    ArmLeft = 2,
+    ArmRight = 3,
+    LegLeft = 4,
+    LegRight = 5,
+    BasePlate = 6,
+}
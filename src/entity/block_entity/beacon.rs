@@ -0,0 +1,286 @@
+use crate::ecs::SystemExecStage;
+use crate::entity::GameInfo;
+use crate::render;
+use crate::render::model;
+use crate::render::Renderer;
+use crate::server::{RendererResource, WorldResource};
+use crate::shared::Position;
+use crate::world::block::Block;
+use crate::world::World;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Quaternion, Rad, Rotation3, Vector3};
+use std::sync::Arc;
+
+/// How often the pyramid below the beacon is checked again, like vanilla.
+const LEVEL_CHECK_TICKS: f64 = 80.0;
+
+pub fn add_systems(sched: &mut Schedule) {
+    sched /*sync*/
+        .add_systems(
+            update_beacon
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        );
+}
+
+pub fn init_entity(cmds: &mut Commands, e: Entity) {
+    cmds.get_entity(e).unwrap().insert(BeaconInfo {
+        model: None,
+        levels: 0,
+        primary: 0,
+        secondary: 0,
+        dirty: true,
+        level_check: LEVEL_CHECK_TICKS,
+        rotation: 0.0,
+    });
+}
+
+#[derive(Component)]
+pub struct BeaconInfo {
+    model: Option<model::ModelHandle>,
+
+    pub levels: i32,
+    pub primary: i32,
+    pub secondary: i32,
+    pub dirty: bool,
+
+    /// Ticks until the pyramid and the beam are checked again
+    level_check: f64,
+    rotation: f32,
+}
+
+struct BeamSegment {
+    color: [f32; 3],
+    height: i32,
+}
+
+pub fn update_beacon(
+    renderer: Res<RendererResource>,
+    world: Res<WorldResource>,
+    game_info: Res<GameInfo>,
+    mut query: Query<(&mut BeaconInfo, &Position)>,
+) {
+    let renderer = &renderer.0;
+    let world = &world.0;
+    // Frame deltas are measured in thirds of a game tick
+    let ticks = game_info.delta / 3.0;
+    for (mut info, position) in query.iter_mut() {
+        info.level_check -= ticks;
+        if info.level_check <= 0.0 {
+            info.level_check = LEVEL_CHECK_TICKS;
+            info.levels = count_levels(world, *position);
+            // Glass above the beacon may have changed as well
+            info.dirty = true;
+        }
+        if info.dirty {
+            build_beam(renderer, world, &mut info, *position);
+        }
+        info.rotation += ticks as f32 * 0.04;
+
+        if let Some(model) = &info.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(model).unwrap();
+            let disp = Vector3::new(
+                position.x as f32 + 0.5,
+                -position.y as f32 - 1.0,
+                position.z as f32 + 0.5,
+            );
+            mdl.matrix[BeaconModelPart::Beam as usize] = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: Quaternion::from_angle_y(Rad(info.rotation)),
+                disp,
+            });
+            mdl.matrix[BeaconModelPart::Glow as usize] = Matrix4::from_translation(disp);
+        }
+    }
+}
+
+fn build_beam(renderer: &Arc<Renderer>, world: &Arc<World>, info: &mut BeaconInfo, pos: Position) {
+    info.dirty = false;
+    info.model = None;
+    if info.levels <= 0 {
+        return;
+    }
+    let segments = beam_segments(world, pos);
+    if segments.is_empty() {
+        return;
+    }
+
+    let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "entity/beacon_beam");
+    let sides = |tex: &render::Texture| {
+        [
+            None,
+            None,
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+        ]
+    };
+    let total: i32 = segments.iter().map(|segment| segment.height).sum();
+
+    let mut beam = vec![];
+    let mut glow = vec![];
+    let mut y = 0;
+    for segment in &segments {
+        let (r, g, b) = (
+            (segment.color[0] * 255.0) as u8,
+            (segment.color[1] * 255.0) as u8,
+            (segment.color[2] * 255.0) as u8,
+        );
+        let height = segment.height as f32;
+        let start = beam.len();
+        model::append_box(
+            &mut beam,
+            -0.2,
+            y as f32,
+            -0.2,
+            0.4,
+            height,
+            0.4,
+            sides(&tex),
+        );
+        let glow_start = glow.len();
+        model::append_box(
+            &mut glow,
+            -0.25,
+            y as f32,
+            -0.25,
+            0.5,
+            height,
+            0.5,
+            sides(&tex),
+        );
+        for vert in beam[start..].iter_mut() {
+            vert.r = r;
+            vert.g = g;
+            vert.b = b;
+        }
+        for vert in glow[glow_start..].iter_mut() {
+            vert.r = r;
+            vert.g = g;
+            vert.b = b;
+            vert.a = 32;
+        }
+        y += segment.height;
+    }
+    // Let the beam fade out as it reaches the top of the world
+    for vert in beam.iter_mut().chain(glow.iter_mut()) {
+        if vert.y >= total as f32 {
+            vert.a = 0;
+        }
+    }
+
+    let mut models = renderer.models.lock();
+    let model = models.create_model(model::DEFAULT, vec![beam, glow], renderer.clone());
+    let mdl = models.get_model(&model).unwrap();
+    // The beam glows regardless of the light around it
+    mdl.block_light = 15.0;
+    mdl.sky_light = 15.0;
+    mdl.radius = total as f32 / 2.0;
+    mdl.x = pos.x as f32 + 0.5;
+    mdl.y = pos.y as f32 + 1.0 + total as f32 / 2.0;
+    mdl.z = pos.z as f32 + 0.5;
+    drop(models);
+
+    info.model.replace(model);
+}
+
+/// Counts the complete layers of the pyramid below the beacon.
+fn count_levels(world: &World, pos: Position) -> i32 {
+    let min_y = world.dimension.load().min_y();
+    for level in 1..=4 {
+        let y = pos.y - level;
+        if y < min_y {
+            return level - 1;
+        }
+        for x in pos.x - level..=pos.x + level {
+            for z in pos.z - level..=pos.z + level {
+                let block = world.get_block(Position::new(x, y, z));
+                if !matches!(
+                    block,
+                    Block::IronBlock {}
+                        | Block::GoldBlock {}
+                        | Block::EmeraldBlock {}
+                        | Block::DiamondBlock {}
+                        | Block::NetheriteBlock {}
+                ) {
+                    return level - 1;
+                }
+            }
+        }
+    }
+    4
+}
+
+/// Walks up from the beacon, starting a new colored segment whenever the
+/// beam passes through stained glass. An opaque block stops the beam.
+fn beam_segments(world: &World, pos: Position) -> Vec<BeamSegment> {
+    let dimension = world.dimension.load();
+    let top = dimension.min_y() + dimension.height();
+    let mut segments = vec![BeamSegment {
+        color: [1.0, 1.0, 1.0],
+        height: 0,
+    }];
+    for y in pos.y + 1..top {
+        let block = world.get_block(Position::new(pos.x, y, pos.z));
+        if let Some(glass) = glass_color(block) {
+            let only_white = segments.len() <= 1;
+            let last = segments.last_mut().unwrap();
+            // The first pane of glass replaces the white entirely, later
+            // ones mix with the color so far
+            let color = if only_white {
+                glass
+            } else {
+                [
+                    (last.color[0] + glass[0]) / 2.0,
+                    (last.color[1] + glass[1]) / 2.0,
+                    (last.color[2] + glass[2]) / 2.0,
+                ]
+            };
+            if color == last.color {
+                last.height += 1;
+            } else {
+                segments.push(BeamSegment { color, height: 1 });
+            }
+        } else if block.get_material().absorbed_light >= 15 && !matches!(block, Block::Bedrock {}) {
+            return vec![];
+        } else {
+            segments.last_mut().unwrap().height += 1;
+        }
+    }
+    segments.retain(|segment| segment.height > 0);
+    segments
+}
+
+fn glass_color(block: Block) -> Option<[f32; 3]> {
+    let rgb: u32 = match block {
+        Block::WhiteStainedGlass {} | Block::WhiteStainedGlassPane { .. } => 0xF9FFFE,
+        Block::OrangeStainedGlass {} | Block::OrangeStainedGlassPane { .. } => 0xF9801D,
+        Block::MagentaStainedGlass {} | Block::MagentaStainedGlassPane { .. } => 0xC74EBD,
+        Block::LightBlueStainedGlass {} | Block::LightBlueStainedGlassPane { .. } => 0x3AB3DA,
+        Block::YellowStainedGlass {} | Block::YellowStainedGlassPane { .. } => 0xFED83D,
+        Block::LimeStainedGlass {} | Block::LimeStainedGlassPane { .. } => 0x80C71F,
+        Block::PinkStainedGlass {} | Block::PinkStainedGlassPane { .. } => 0xF38BAA,
+        Block::GrayStainedGlass {} | Block::GrayStainedGlassPane { .. } => 0x474F52,
+        Block::LightGrayStainedGlass {} | Block::LightGrayStainedGlassPane { .. } => 0x9D9D97,
+        Block::CyanStainedGlass {} | Block::CyanStainedGlassPane { .. } => 0x169C9C,
+        Block::PurpleStainedGlass {} | Block::PurpleStainedGlassPane { .. } => 0x8932B8,
+        Block::BlueStainedGlass {} | Block::BlueStainedGlassPane { .. } => 0x3C44AA,
+        Block::BrownStainedGlass {} | Block::BrownStainedGlassPane { .. } => 0x835432,
+        Block::GreenStainedGlass {} | Block::GreenStainedGlassPane { .. } => 0x5E7C16,
+        Block::RedStainedGlass {} | Block::RedStainedGlassPane { .. } => 0xB02E26,
+        Block::BlackStainedGlass {} | Block::BlackStainedGlassPane { .. } => 0x1D1D21,
+        _ => return None,
+    };
+    Some([
+        ((rgb >> 16) & 0xFF) as f32 / 255.0,
+        ((rgb >> 8) & 0xFF) as f32 / 255.0,
+        (rgb & 0xFF) as f32 / 255.0,
+    ])
+}
+
+enum BeaconModelPart {
+    Beam = 0,
+    Glow = 1,
+}
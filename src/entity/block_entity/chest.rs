@@ -0,0 +1,166 @@
+use crate::ecs::SystemExecStage;
+use crate::render;
+use crate::render::model::{self, Vertex};
+use crate::render::{Renderer, Texture};
+use crate::server::{RendererResource, WorldResource};
+use crate::shared::{Direction, Position};
+use crate::world::block::{Block, ChestType};
+use crate::world::World;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Quaternion, Rad, Rotation3, Vector3};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+pub fn add_systems(sched: &mut Schedule) {
+    sched /*sync*/
+        .add_systems(
+            render_chest
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        );
+}
+
+pub fn init_entity(cmds: &mut Commands, e: Entity) {
+    cmds.get_entity(e)
+        .unwrap()
+        .insert(ChestInfo { model: None });
+}
+
+/// Chests have no block model, they are drawn like vanilla's block entity
+/// renderer. Each half of a double chest draws its own side of the pair.
+#[derive(Component)]
+pub struct ChestInfo {
+    model: Option<model::ModelHandle>,
+}
+
+pub fn render_chest(
+    renderer: Res<RendererResource>,
+    world: Res<WorldResource>,
+    mut query: Query<(&mut ChestInfo, &Position)>,
+) {
+    let renderer = &renderer.0;
+    let world = &world.0;
+    for (mut info, position) in query.iter_mut() {
+        if info.model.is_none() {
+            create_chest_model(renderer, world, &mut info, *position);
+        }
+        if let Some(model) = &info.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(model).unwrap();
+            mdl.block_light = world.get_block_light(*position) as f32;
+            mdl.sky_light = world.get_sky_light(*position) as f32;
+        }
+    }
+}
+
+fn create_chest_model(
+    renderer: &Arc<Renderer>,
+    world: &Arc<World>,
+    info: &mut ChestInfo,
+    pos: Position,
+) {
+    let (facing, type_, name) = match world.get_block(pos) {
+        Block::Chest { facing, type_, .. } => (facing, type_, "normal"),
+        Block::TrappedChest { facing, type_, .. } => (facing, type_, "trapped"),
+        Block::EnderChest { facing, .. } => (facing, ChestType::Single, "ender"),
+        _ => return,
+    };
+    let path = match type_ {
+        ChestType::Single => format!("entity/chest/{name}"),
+        ChestType::Left => format!("entity/chest/{name}_left"),
+        ChestType::Right => format!("entity/chest/{name}_right"),
+    };
+    let tex = render::Renderer::get_texture(renderer.get_textures_ref(), &path);
+
+    // The model faces north, halves of a double chest are a pixel wider and
+    // reach into the block of the other half.
+    let (x, width, lock_x, lock_width) = match type_ {
+        ChestType::Single => (-7.0, 14.0, -1.0, 2.0),
+        // The other half is clockwise of the facing, east for this model
+        ChestType::Left => (-7.0, 15.0, 7.0, 1.0),
+        ChestType::Right => (-8.0, 15.0, -8.0, 1.0),
+    };
+
+    let mut verts = vec![];
+    // Base
+    append_chest_box(
+        &mut verts,
+        &tex,
+        (x, -8.0, -7.0),
+        (width, 10.0, 14.0),
+        (0.0, 19.0),
+    );
+    // Lid
+    append_chest_box(
+        &mut verts,
+        &tex,
+        (x, 1.0, -7.0),
+        (width, 5.0, 14.0),
+        (0.0, 0.0),
+    );
+    // Lock
+    append_chest_box(
+        &mut verts,
+        &tex,
+        (lock_x, -1.0, -8.0),
+        (lock_width, 4.0, 1.0),
+        (0.0, 0.0),
+    );
+
+    let rotation = match facing {
+        Direction::South => PI,
+        Direction::West => PI / 2.0,
+        Direction::East => -PI / 2.0,
+        _ => 0.0,
+    };
+
+    let mut models = renderer.models.lock();
+    let model = models.create_model(model::DEFAULT, vec![verts], renderer.clone());
+    let mdl = models.get_model(&model).unwrap();
+    mdl.radius = 1.0;
+    mdl.x = pos.x as f32 + 0.5;
+    mdl.y = pos.y as f32 + 0.5;
+    mdl.z = pos.z as f32 + 0.5;
+    mdl.matrix[0] = Matrix4::from(Decomposed {
+        scale: 1.0,
+        rot: Quaternion::from_angle_y(Rad(rotation)),
+        disp: Vector3::new(pos.x as f32 + 0.5, -pos.y as f32 - 0.5, pos.z as f32 + 0.5),
+    });
+    drop(models);
+
+    info.model.replace(model);
+}
+
+/// Appends a box given in pixels around the block center, textured with
+/// vanilla's cuboid layout starting at `uv`. Chest textures are stored
+/// upside down, so the sides are flipped and the top and bottom swapped.
+fn append_chest_box(
+    verts: &mut Vec<Vertex>,
+    tex: &Texture,
+    (x, y, z): (f32, f32, f32),
+    (w, h, d): (f32, f32, f32),
+    (u, v): (f32, f32),
+) {
+    let size = (tex.get_width() as f32, tex.get_height() as f32);
+    let rel = |tx: f32, ty: f32, tw: f32, th: f32| {
+        Some(tex.relative(tx / size.0, ty / size.1, tw / size.0, th / size.1))
+    };
+    let side = |tx: f32, tw: f32| rel(tx, v + d + h, tw, -h);
+    model::append_box(
+        verts,
+        x / 16.0,
+        y / 16.0,
+        z / 16.0,
+        w / 16.0,
+        h / 16.0,
+        d / 16.0,
+        [
+            rel(u + d, v, w, d),     // Down
+            rel(u + d + w, v, w, d), // Up
+            side(u + d + w + d, w),  // North
+            side(u + d, w),          // South
+            side(u + d + w, d),      // West
+            side(u, d),              // East
+        ],
+    );
+}
@@ -0,0 +1,241 @@
+use crate::ecs::SystemExecStage;
+use crate::entity::{resolve_textures, GameInfo};
+use crate::render;
+use crate::render::model::{self, Vertex};
+use crate::render::{Renderer, Texture};
+use crate::server::{RendererResource, WorldResource};
+use crate::shared::Position;
+use crate::world::block::Block;
+use crate::world::World;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, InnerSpace, Matrix4, Quaternion, Rad, Rotation3, Vector3};
+use std::sync::Arc;
+
+/// How often the frame around the conduit is checked, like vanilla.
+const FRAME_CHECK_TICKS: f64 = 40.0;
+/// Frame blocks needed to activate the conduit.
+const ACTIVE_FRAME_SIZE: usize = 16;
+/// A complete frame, which lets the conduit attack mobs.
+const FULL_FRAME_SIZE: usize = 42;
+
+pub fn add_systems(sched: &mut Schedule) {
+    sched /*sync*/
+        .add_systems(
+            update_conduit
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        );
+}
+
+pub fn init_entity(cmds: &mut Commands, e: Entity) {
+    cmds.get_entity(e).unwrap().insert(ConduitInfo {
+        model: None,
+        frame_size: 0,
+        dirty: true,
+        frame_check: 0.0,
+        age: 0.0,
+    });
+}
+
+#[derive(Component)]
+pub struct ConduitInfo {
+    model: Option<model::ModelHandle>,
+
+    frame_size: usize,
+    dirty: bool,
+    /// Ticks until the frame is checked again
+    frame_check: f64,
+    age: f64,
+}
+
+impl ConduitInfo {
+    fn is_active(&self) -> bool {
+        self.frame_size >= ACTIVE_FRAME_SIZE
+    }
+}
+
+pub fn update_conduit(
+    renderer: Res<RendererResource>,
+    world: Res<WorldResource>,
+    game_info: Res<GameInfo>,
+    mut query: Query<(&mut ConduitInfo, &Position)>,
+) {
+    let renderer = &renderer.0;
+    let world = &world.0;
+    // Frame deltas are measured in thirds of a game tick
+    let ticks = game_info.delta / 3.0;
+    for (mut info, position) in query.iter_mut() {
+        info.frame_check -= ticks;
+        if info.frame_check <= 0.0 {
+            info.frame_check = FRAME_CHECK_TICKS;
+            let frame_size = frame_size(world, *position);
+            // Only the activation and the eye opening change the model
+            if (frame_size >= ACTIVE_FRAME_SIZE) != info.is_active()
+                || (frame_size >= FULL_FRAME_SIZE) != (info.frame_size >= FULL_FRAME_SIZE)
+            {
+                info.dirty = true;
+            }
+            info.frame_size = frame_size;
+        }
+        if info.dirty {
+            create_conduit_model(renderer, world, &mut info, *position);
+        }
+        if info.is_active() {
+            info.age += ticks;
+        }
+
+        if let Some(model) = &info.model {
+            let (cam_x, cam_z) = {
+                let camera = renderer.camera.lock();
+                (camera.pos.x, camera.pos.z)
+            };
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(model).unwrap();
+            let center = Vector3::new(
+                position.x as f32 + 0.5,
+                -position.y as f32 - 0.5,
+                position.z as f32 + 0.5,
+            );
+            if !info.is_active() {
+                mdl.matrix[ConduitModelPart::Shell as usize] = Matrix4::from_translation(center);
+                continue;
+            }
+
+            let age = info.age as f32;
+            let bob = (age * 0.1).sin() / 2.0 + 0.5;
+            let bob = (bob * bob + bob) * 0.1;
+            let lifted = center - Vector3::new(0.0, bob, 0.0);
+            mdl.matrix[ConduitModelPart::Shell as usize] = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: Quaternion::from_axis_angle(
+                    Vector3::new(0.5, 1.0, 0.5).normalize(),
+                    Rad(age * -0.0375),
+                ),
+                disp: lifted,
+            });
+            // The eye always looks at the camera
+            let ang = (position.x as f64 + 0.5 - cam_x).atan2(position.z as f64 + 0.5 - cam_z);
+            mdl.matrix[ConduitModelPart::Eye as usize] = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: Quaternion::from_angle_y(Rad(ang as f32)),
+                disp: lifted,
+            });
+        }
+    }
+}
+
+fn create_conduit_model(
+    renderer: &Arc<Renderer>,
+    world: &Arc<World>,
+    info: &mut ConduitInfo,
+    pos: Position,
+) {
+    info.dirty = false;
+    let textures = renderer.get_textures_ref();
+
+    let mut shell = vec![];
+    let mut eye = vec![];
+    if info.is_active() {
+        let cage = render::Renderer::get_texture(textures, "entity/conduit/cage");
+        append_centered_box(&mut shell, &cage, 8.0);
+        let eye_tex = if info.frame_size >= FULL_FRAME_SIZE {
+            render::Renderer::get_texture(textures, "entity/conduit/open_eye")
+        } else {
+            render::Renderer::get_texture(textures, "entity/conduit/closed_eye")
+        };
+        let face = eye_tex.relative(0.0, 0.0, 0.5, 0.5);
+        model::append_box(
+            &mut eye,
+            -4.0 / 16.0,
+            -4.0 / 16.0,
+            0.0,
+            8.0 / 16.0,
+            8.0 / 16.0,
+            0.0,
+            [None, None, Some(face.clone()), Some(face), None, None],
+        );
+    } else {
+        let base = render::Renderer::get_texture(textures, "entity/conduit/base");
+        append_centered_box(&mut shell, &base, 6.0);
+    }
+
+    let mut models = renderer.models.lock();
+    let model = models.create_model(model::DEFAULT, vec![shell, eye], renderer.clone());
+    let mdl = models.get_model(&model).unwrap();
+    mdl.radius = 1.0;
+    mdl.x = pos.x as f32 + 0.5;
+    mdl.y = pos.y as f32 + 0.5;
+    mdl.z = pos.z as f32 + 0.5;
+    mdl.block_light = world.get_block_light(pos) as f32;
+    mdl.sky_light = world.get_sky_light(pos) as f32;
+    drop(models);
+
+    info.model.replace(model);
+}
+
+/// Appends a cube of `size` pixels centered on the origin, textured from
+/// the top left of `tex` like vanilla's conduit parts.
+fn append_centered_box(verts: &mut Vec<Vertex>, tex: &Texture, size: f32) {
+    let half = size / 2.0 / 16.0;
+    model::append_box(
+        verts,
+        -half,
+        -half,
+        -half,
+        half * 2.0,
+        half * 2.0,
+        half * 2.0,
+        resolve_textures(tex, size, size, size, 0.0, 0.0),
+    );
+}
+
+/// Counts the prismarine blocks making up the frame around the conduit,
+/// or returns 0 if the conduit isn't surrounded by water.
+fn frame_size(world: &World, pos: Position) -> usize {
+    for dx in -1..=1 {
+        for dy in -1..=1 {
+            for dz in -1..=1 {
+                if (dx, dy, dz) == (0, 0, 0) {
+                    continue;
+                }
+                let block = world.get_block(Position::new(pos.x + dx, pos.y + dy, pos.z + dz));
+                if !matches!(block, Block::Water { .. }) && !block.is_waterlogged() {
+                    return 0;
+                }
+            }
+        }
+    }
+
+    let mut count = 0;
+    for dx in -2i32..=2 {
+        for dy in -2i32..=2 {
+            for dz in -2i32..=2 {
+                let (ax, ay, az) = (dx.abs(), dy.abs(), dz.abs());
+                // Three rings of blocks, one around each axis
+                let in_frame = (ax > 1 || ay > 1 || az > 1)
+                    && ((dx == 0 && (ay == 2 || az == 2))
+                        || (dy == 0 && (ax == 2 || az == 2))
+                        || (dz == 0 && (ax == 2 || ay == 2)));
+                if !in_frame {
+                    continue;
+                }
+                let block = world.get_block(Position::new(pos.x + dx, pos.y + dy, pos.z + dz));
+                if matches!(
+                    block,
+                    Block::Prismarine {}
+                        | Block::PrismarineBricks {}
+                        | Block::DarkPrismarine {}
+                        | Block::SeaLantern {}
+                ) {
+                    count += 1;
+                }
+            }
+        }
+    }
+    count
+}
+
+enum ConduitModelPart {
+    Shell = 0,
+    Eye = 1,
+}
@@ -0,0 +1,104 @@
+use crate::ecs::SystemExecStage;
+use crate::render;
+use crate::render::model;
+use crate::server::{RendererResource, WorldResource};
+use crate::shared::{Direction, Position};
+use crate::world::block::Block;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Quaternion, Rad, Rotation3, Vector3};
+use std::f32::consts::PI;
+
+pub fn add_systems(sched: &mut Schedule) {
+    sched /*sync*/
+        .add_systems(
+            update_lightning_rod
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        );
+}
+
+/// Powered lightning rods get a spark at their tip, the server powers
+/// them for a few ticks after they are struck.
+pub fn init_entity(cmds: &mut Commands, e: Entity) {
+    cmds.get_entity(e)
+        .unwrap()
+        .insert(LightningRodSpark { model: None });
+}
+
+#[derive(Component)]
+pub struct LightningRodSpark {
+    model: Option<model::ModelHandle>,
+}
+
+pub fn update_lightning_rod(
+    renderer: Res<RendererResource>,
+    world: Res<WorldResource>,
+    mut query: Query<(&mut LightningRodSpark, &Position)>,
+) {
+    let renderer = &renderer.0;
+    let world = &world.0;
+    for (mut spark, position) in query.iter_mut() {
+        if spark.model.is_none() {
+            let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "particle/glow");
+            let mut verts = vec![];
+            // Two crossed quads so the spark is visible from every side
+            model::append_box(
+                &mut verts,
+                -3.0 / 16.0,
+                -3.0 / 16.0,
+                0.0,
+                6.0 / 16.0,
+                6.0 / 16.0,
+                0.0,
+                [None, None, Some(tex.clone()), Some(tex.clone()), None, None],
+            );
+            model::append_box(
+                &mut verts,
+                0.0,
+                -3.0 / 16.0,
+                -3.0 / 16.0,
+                0.0,
+                6.0 / 16.0,
+                6.0 / 16.0,
+                [None, None, None, None, Some(tex.clone()), Some(tex)],
+            );
+            for vert in &mut verts {
+                vert.r = 200;
+                vert.g = 230;
+                vert.b = 255;
+            }
+            let mut models = renderer.models.lock();
+            let model = models.create_model(model::DEFAULT, vec![verts], renderer.clone());
+            let mdl = models.get_model(&model).unwrap();
+            mdl.radius = 0.5;
+            mdl.x = position.x as f32 + 0.5;
+            mdl.y = position.y as f32 + 0.5;
+            mdl.z = position.z as f32 + 0.5;
+            mdl.block_light = 15.0;
+            mdl.sky_light = 15.0;
+            drop(models);
+            spark.model.replace(model);
+        }
+
+        let facing = match world.get_block(*position) {
+            Block::LightningRod { facing, .. } => facing,
+            _ => Direction::Up,
+        };
+        if let Some(model) = &spark.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(model).unwrap();
+            let (ox, oy, oz) = facing.get_offset();
+            // Flicker like the electric spark particles do
+            let scale = 0.5 + rand::random::<f32>() * 0.5;
+            mdl.matrix[0] = Matrix4::from(Decomposed {
+                scale,
+                rot: Quaternion::from_angle_y(Rad(rand::random::<f32>() * PI)),
+                disp: Vector3::new(
+                    position.x as f32 + 0.5 + ox as f32 * 0.5,
+                    -position.y as f32 - 0.5 - oy as f32 * 0.5,
+                    position.z as f32 + 0.5 + oz as f32 * 0.5,
+                ),
+            });
+        }
+    }
+}
@@ -1,3 +1,7 @@
+pub mod beacon;
+pub mod chest;
+pub mod conduit;
+pub mod lightning_rod;
 pub mod sign;
 
 use crate::shared::Position;
@@ -5,10 +9,18 @@ use crate::world::block::Block;
 use bevy_ecs::prelude::*;
 
 pub fn add_systems(sched: &mut Schedule) {
+    beacon::add_systems(sched);
+    chest::add_systems(sched);
+    conduit::add_systems(sched);
+    lightning_rod::add_systems(sched);
     sign::add_systems(sched);
 }
 
 pub enum BlockEntityType {
+    Beacon,
+    Chest,
+    Conduit,
+    LightningRod,
     Sign,
 }
 
@@ -33,6 +45,12 @@ impl BlockEntityType {
             | Block::MangroveWallSign { .. }
             | Block::CrimsonWallSign { .. }
             | Block::WarpedWallSign { .. } => Some(BlockEntityType::Sign),
+            Block::Beacon {} => Some(BlockEntityType::Beacon),
+            Block::Chest { .. } | Block::TrappedChest { .. } | Block::EnderChest { .. } => {
+                Some(BlockEntityType::Chest)
+            }
+            Block::Conduit { .. } => Some(BlockEntityType::Conduit),
+            Block::LightningRod { powered: true, .. } => Some(BlockEntityType::LightningRod),
             _ => None,
         }
     }
@@ -42,6 +60,10 @@ impl BlockEntityType {
         e.insert(pos);
         let e = e.id();
         match *self {
+            BlockEntityType::Beacon => beacon::init_entity(cmds, e),
+            BlockEntityType::Chest => chest::init_entity(cmds, e),
+            BlockEntityType::Conduit => conduit::init_entity(cmds, e),
+            BlockEntityType::LightningRod => lightning_rod::init_entity(cmds, e),
             BlockEntityType::Sign => sign::init_entity(cmds, e),
         }
         e
@@ -0,0 +1,143 @@
+use super::{item_texture, Bounds, GameInfo, Light, Position};
+use crate::inventory::material::versions::to_material;
+use crate::inventory::Material;
+use crate::render::model;
+use crate::render::Renderer;
+use crate::server::RendererResource;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use collision::Aabb3;
+use leafish_protocol::types::{self, Value};
+use shared::Version;
+use std::sync::Arc;
+
+#[derive(Component)]
+pub struct DroppedItemModel {
+    model: Option<model::ModelHandle>,
+    dirty: bool,
+
+    item: Option<Material>,
+    /// Ticks since the item was spawned, drives the bobbing and spinning.
+    age: f64,
+    /// Random phase so a pile of items doesn't move in lockstep.
+    hover_start: f64,
+}
+
+impl Default for DroppedItemModel {
+    fn default() -> Self {
+        Self {
+            model: None,
+            dirty: false,
+            item: None,
+            age: 0.0,
+            hover_start: rand::random::<f64>() * std::f64::consts::PI * 2.0,
+        }
+    }
+}
+
+impl DroppedItemModel {
+    pub fn apply_metadata(&mut self, metadata: &types::Metadata, version: Version) {
+        let item_index = match version {
+            Version::V1_7 | Version::V1_8 => 10,
+            Version::V1_9 => 5,
+            Version::V1_10
+            | Version::V1_11
+            | Version::V1_12
+            | Version::V1_13
+            | Version::V1_13_2 => 6,
+            Version::V1_14 | Version::V1_15 | Version::V1_16 | Version::V1_16_2 => 7,
+            _ => 8,
+        };
+        if let Some(Value::OptionalItemStack(stack)) = metadata.get_raw(item_index) {
+            let item = stack.as_ref().map(|stack| {
+                to_material(
+                    stack.id as u16,
+                    Some(stack.damage.unwrap_or(0)),
+                    None,
+                    version,
+                )
+            });
+            if item != self.item {
+                self.item = item;
+                self.dirty = true;
+            }
+        }
+    }
+}
+
+pub fn added_dropped_item(mut commands: Commands, query: Query<Entity, Added<DroppedItemModel>>) {
+    for entity in query.iter() {
+        commands.entity(entity).insert(Bounds::new(Aabb3::new(
+            Point3::new(-0.125, 0.0, -0.125),
+            Point3::new(0.125, 0.25, 0.125),
+        )));
+    }
+}
+
+pub fn update_dropped_item(
+    game_info: Res<GameInfo>,
+    renderer: Res<RendererResource>,
+    mut query: Query<(&mut DroppedItemModel, &Position, &Light)>,
+) {
+    let renderer = &renderer.0;
+    for (mut item_model, position, light) in query.iter_mut() {
+        // The stack only arrives with the metadata following the spawn
+        if item_model.dirty {
+            create_dropped_item_model(renderer, &mut item_model);
+        }
+        // Frame deltas are measured in thirds of a game tick
+        item_model.age += game_info.delta / 3.0;
+
+        if let Some(imodel) = &item_model.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(imodel).unwrap();
+
+            mdl.block_light = light.block_light;
+            mdl.sky_light = light.sky_light;
+
+            let bob = ((item_model.age / 10.0 + item_model.hover_start).sin() * 0.1 + 0.1) as f32;
+            let spin = (item_model.age / 20.0 + item_model.hover_start) as f32;
+            mdl.matrix[0] = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: Quaternion::from_angle_y(Rad(spin)),
+                disp: Vector3::new(
+                    position.position.x as f32,
+                    -position.position.y as f32 - bob,
+                    position.position.z as f32,
+                ),
+            });
+        }
+    }
+}
+
+fn create_dropped_item_model(renderer: &Arc<Renderer>, item_model: &mut DroppedItemModel) {
+    item_model.dirty = false;
+
+    let item = match item_model.item {
+        Some(item) => item,
+        None => {
+            item_model.model = None;
+            return;
+        }
+    };
+    let tex = item_texture(renderer, item);
+    let mut verts = vec![];
+    model::append_box(
+        &mut verts,
+        -4.0 / 16.0,
+        0.0,
+        -0.05 / 16.0,
+        8.0 / 16.0,
+        8.0 / 16.0,
+        0.1 / 16.0,
+        [None, None, Some(tex.clone()), Some(tex), None, None],
+    );
+
+    item_model
+        .model
+        .replace(renderer.clone().models.lock().create_model(
+            model::DEFAULT,
+            vec![verts],
+            renderer.clone(),
+        ));
+}
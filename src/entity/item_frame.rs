@@ -0,0 +1,231 @@
+use super::{item_texture, Bounds, Light, Position};
+use crate::inventory::material::versions::to_material;
+use crate::inventory::Material;
+use crate::render::model;
+use crate::render::Renderer;
+use crate::server::RendererResource;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use collision::Aabb3;
+use leafish_protocol::protocol;
+use leafish_protocol::types::{self, Value};
+use shared::{Axis, Direction, Version};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+#[derive(Component)]
+pub struct ItemFrameModel {
+    model: Option<model::ModelHandle>,
+    dirty: bool,
+
+    facing: Direction,
+    item: Option<Material>,
+    rotation: f32,
+}
+
+impl ItemFrameModel {
+    pub fn new(facing: Direction) -> Self {
+        Self {
+            model: None,
+            dirty: false,
+            facing,
+            item: None,
+            rotation: 0.0,
+        }
+    }
+
+    /// Reads the facing the server sends as object data when spawning
+    /// the frame. Before 1.13 frames could only hang on walls.
+    pub fn facing_from_data(data: i32, version: Version) -> Direction {
+        if version < Version::V1_13 {
+            match data & 0x3 {
+                0 => Direction::South,
+                1 => Direction::West,
+                2 => Direction::North,
+                _ => Direction::East,
+            }
+        } else {
+            match data {
+                0 => Direction::Down,
+                1 => Direction::Up,
+                2 => Direction::North,
+                3 => Direction::South,
+                4 => Direction::West,
+                _ => Direction::East,
+            }
+        }
+    }
+
+    /// The center of a frame hanging in the block at `(x, y, z)`, which is
+    /// what the server sends as the spawn position.
+    pub fn center(x: f64, y: f64, z: f64, facing: Direction) -> (f64, f64, f64) {
+        // Frames are one pixel thick and sit flush against the block behind them
+        const INSET: f64 = 0.5 - 1.0 / 32.0;
+        let (ox, oy, oz) = facing.get_offset();
+        (
+            x.floor() + 0.5 - ox as f64 * INSET,
+            y.floor() + 0.5 - oy as f64 * INSET,
+            z.floor() + 0.5 - oz as f64 * INSET,
+        )
+    }
+
+    pub fn apply_metadata(&mut self, metadata: &types::Metadata, version: Version) {
+        let (item_index, rotation_index) = match version {
+            Version::V1_7 | Version::V1_8 => (8, 9),
+            Version::V1_9 => (5, 6),
+            Version::V1_10
+            | Version::V1_11
+            | Version::V1_12
+            | Version::V1_13
+            | Version::V1_13_2 => (6, 7),
+            Version::V1_14 | Version::V1_15 | Version::V1_16 | Version::V1_16_2 => (7, 8),
+            _ => (8, 9),
+        };
+        if let Some(Value::OptionalItemStack(stack)) = metadata.get_raw(item_index) {
+            let item = stack.as_ref().map(|stack| {
+                to_material(
+                    stack.id as u16,
+                    Some(stack.damage.unwrap_or(0)),
+                    None,
+                    version,
+                )
+            });
+            if item != self.item {
+                self.item = item;
+                self.dirty = true;
+            }
+        }
+        // 1.8 only had four rotation steps
+        match metadata.get_raw(rotation_index) {
+            Some(Value::Byte(rotation)) => self.rotation = *rotation as f32 * (PI / 2.0),
+            Some(Value::Direction(protocol::VarInt(rotation))) => {
+                self.rotation = *rotation as f32 * (PI / 4.0)
+            }
+            _ => {}
+        }
+    }
+
+    fn orientation(&self) -> Quaternion<f32> {
+        // The model is built facing south
+        match self.facing {
+            Direction::Up => Quaternion::from_angle_x(Rad(PI / 2.0)),
+            Direction::Down => Quaternion::from_angle_x(Rad(-PI / 2.0)),
+            Direction::North => Quaternion::from_angle_y(Rad(PI)),
+            Direction::East => Quaternion::from_angle_y(Rad(PI / 2.0)),
+            Direction::West => Quaternion::from_angle_y(Rad(-PI / 2.0)),
+            _ => Quaternion::from_angle_y(Rad(0.0)),
+        }
+    }
+}
+
+pub fn added_item_frame(
+    renderer: Res<RendererResource>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut ItemFrameModel), Added<ItemFrameModel>>,
+) {
+    let renderer = &renderer.0;
+    for (entity, mut frame_model) in query.iter_mut() {
+        let half = |axis| {
+            if frame_model.facing.axis() == axis {
+                0.5 / 16.0
+            } else {
+                6.0 / 16.0
+            }
+        };
+        let (x, y, z) = (half(Axis::X), half(Axis::Y), half(Axis::Z));
+        commands.entity(entity).insert(Bounds::new(Aabb3::new(
+            Point3::new(-x, -y, -z),
+            Point3::new(x, y, z),
+        )));
+        create_item_frame_model(renderer, &mut frame_model);
+    }
+}
+
+pub fn update_item_frame(
+    renderer: Res<RendererResource>,
+    mut query: Query<(&mut ItemFrameModel, &Position, &Light)>,
+) {
+    let renderer = &renderer.0;
+    for (mut frame_model, position, light) in query.iter_mut() {
+        if frame_model.dirty {
+            create_item_frame_model(renderer, &mut frame_model);
+        }
+
+        if let Some(fmodel) = &frame_model.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(fmodel).unwrap();
+
+            mdl.block_light = light.block_light;
+            mdl.sky_light = light.sky_light;
+
+            let offset_matrix = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: frame_model.orientation(),
+                disp: Vector3::new(
+                    position.position.x as f32,
+                    -position.position.y as f32,
+                    position.position.z as f32,
+                ),
+            });
+
+            mdl.matrix[ItemFrameModelPart::Frame as usize] = offset_matrix;
+            mdl.matrix[ItemFrameModelPart::Item as usize] =
+                offset_matrix * Matrix4::from(Quaternion::from_angle_z(Rad(frame_model.rotation)));
+        }
+    }
+}
+
+fn create_item_frame_model(renderer: &Arc<Renderer>, frame_model: &mut ItemFrameModel) {
+    frame_model.dirty = false;
+
+    let textures = renderer.get_textures_ref();
+    let background = Renderer::get_texture(textures, "minecraft:block/item_frame");
+    let planks = Renderer::get_texture(textures, "minecraft:block/birch_planks");
+    let mut frame_verts = vec![];
+    model::append_box(
+        &mut frame_verts,
+        -6.0 / 16.0,
+        -6.0 / 16.0,
+        -0.5 / 16.0,
+        12.0 / 16.0,
+        12.0 / 16.0,
+        1.0 / 16.0,
+        [
+            Some(planks.relative(2.0 / 16.0, 0.0, 12.0 / 16.0, 1.0 / 16.0)),
+            Some(planks.relative(2.0 / 16.0, 0.0, 12.0 / 16.0, 1.0 / 16.0)),
+            Some(background.relative(2.0 / 16.0, 2.0 / 16.0, 12.0 / 16.0, 12.0 / 16.0)),
+            Some(background.relative(2.0 / 16.0, 2.0 / 16.0, 12.0 / 16.0, 12.0 / 16.0)),
+            Some(planks.relative(0.0, 2.0 / 16.0, 1.0 / 16.0, 12.0 / 16.0)),
+            Some(planks.relative(0.0, 2.0 / 16.0, 1.0 / 16.0, 12.0 / 16.0)),
+        ],
+    );
+
+    let mut item_verts = vec![];
+    if let Some(item) = frame_model.item {
+        let tex = item_texture(renderer, item);
+        // Flat billboard just in front of the frame's background
+        model::append_box(
+            &mut item_verts,
+            -4.0 / 16.0,
+            -4.0 / 16.0,
+            0.5 / 16.0,
+            8.0 / 16.0,
+            8.0 / 16.0,
+            0.1 / 16.0,
+            [None, None, Some(tex.clone()), Some(tex), None, None],
+        );
+    }
+
+    frame_model
+        .model
+        .replace(renderer.clone().models.lock().create_model(
+            model::DEFAULT,
+            vec![frame_verts, item_verts],
+            renderer.clone(),
+        ));
+}
+
+enum ItemFrameModelPart {
+    Frame = 0,
+    Item = 1,
+}
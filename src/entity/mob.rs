@@ -0,0 +1,531 @@
+use super::armor_stand::append_part_box;
+use super::{
+    BabyHead, Bounds, EntityType, GameInfo, HeadRotation, Light, Position, Rotation, Scale,
+};
+use crate::render::model::{self, Vertex};
+use crate::render::Renderer;
+use crate::server::RendererResource;
+use bevy_ecs::prelude::*;
+use cgmath::{Decomposed, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use collision::Aabb3;
+use std::f32::consts::FRAC_PI_2;
+
+/// How a part moves while the mob is animated.
+#[derive(Clone, Copy)]
+enum Motion {
+    Still,
+    /// Follows where the mob is looking.
+    Head,
+    /// Swings back and forth while walking, the factor picks the direction
+    /// and strength of the swing.
+    Swing(f32),
+}
+
+struct Cuboid {
+    /// Index into the textures of the mob's definition.
+    texture: usize,
+    origin: (f32, f32, f32),
+    size: (f32, f32, f32),
+    inflate: f32,
+    uv: (f32, f32),
+}
+
+/// Where a part of a mob's model sits and how it moves, positioned like the
+/// vanilla model in pixels with y pointing down from 24 pixels above the
+/// ground.
+#[derive(Clone, Copy)]
+struct Joint {
+    pivot: [f32; 3],
+    rotation: [f32; 3],
+    motion: Motion,
+}
+
+struct Part {
+    joint: Joint,
+    cuboids: Vec<Cuboid>,
+}
+
+struct MobDefinition {
+    textures: &'static [&'static str],
+    /// Width and height of the mob's bounding box.
+    size: (f64, f64),
+    parts: Vec<Part>,
+    /// How the head is drawn on babies, for mobs that have them.
+    baby_head: Option<BabyHead>,
+}
+
+fn cuboid(origin: (f32, f32, f32), size: (f32, f32, f32), uv: (f32, f32)) -> Cuboid {
+    Cuboid {
+        texture: 0,
+        origin,
+        size,
+        inflate: 0.0,
+        uv,
+    }
+}
+
+fn part(pivot: [f32; 3], motion: Motion, cuboids: Vec<Cuboid>) -> Part {
+    Part {
+        joint: Joint {
+            pivot,
+            rotation: [0.0; 3],
+            motion,
+        },
+        cuboids,
+    }
+}
+
+/// A body lying on its side, as four legged mobs have.
+fn body(pivot: [f32; 3], cuboids: Vec<Cuboid>) -> Part {
+    let mut body = part(pivot, Motion::Still, cuboids);
+    body.joint.rotation = [FRAC_PI_2, 0.0, 0.0];
+    body
+}
+
+/// Four legs of the given height, `x` to the sides of the body and at `back`
+/// and `front` along it. Diagonally opposite legs move together.
+fn legs(
+    height: f32,
+    x: f32,
+    (back, front): (f32, f32),
+    leg: impl Fn() -> Vec<Cuboid>,
+) -> Vec<Part> {
+    let y = 24.0 - height;
+    vec![
+        part([-x, y, back], Motion::Swing(1.0), leg()),
+        part([x, y, back], Motion::Swing(-1.0), leg()),
+        part([-x, y, front], Motion::Swing(-1.0), leg()),
+        part([x, y, front], Motion::Swing(1.0), leg()),
+    ]
+}
+
+/// Four legged mobs keep the size of their head while growing up, it only
+/// moves down and back onto the smaller body.
+fn quadruped_head(y: f32, z: f32) -> Option<BabyHead> {
+    Some(BabyHead {
+        scale: 1.0,
+        offset: [0.0, y, z],
+    })
+}
+
+fn definition(kind: EntityType) -> Option<MobDefinition> {
+    Some(match kind {
+        EntityType::Skeleton => {
+            let limb = || vec![cuboid((-1.0, -2.0, -1.0), (2.0, 12.0, 2.0), (40.0, 16.0))];
+            let leg = || vec![cuboid((-1.0, 0.0, -1.0), (2.0, 12.0, 2.0), (0.0, 16.0))];
+            MobDefinition {
+                textures: &["minecraft:entity/skeleton/skeleton"],
+                size: (0.6, 1.99),
+                parts: vec![
+                    part(
+                        [0.0, 0.0, 0.0],
+                        Motion::Head,
+                        vec![cuboid((-4.0, -8.0, -4.0), (8.0, 8.0, 8.0), (0.0, 0.0))],
+                    ),
+                    part(
+                        [0.0, 0.0, 0.0],
+                        Motion::Still,
+                        vec![cuboid((-4.0, 0.0, -2.0), (8.0, 12.0, 4.0), (16.0, 16.0))],
+                    ),
+                    part([-5.0, 2.0, 0.0], Motion::Swing(-0.75), limb()),
+                    part([5.0, 2.0, 0.0], Motion::Swing(0.75), limb()),
+                    part([-2.0, 12.0, 0.0], Motion::Swing(1.0), leg()),
+                    part([2.0, 12.0, 0.0], Motion::Swing(-1.0), leg()),
+                ],
+                baby_head: None,
+            }
+        }
+        EntityType::Creeper => {
+            let mut parts = vec![
+                part(
+                    [0.0, 6.0, 0.0],
+                    Motion::Head,
+                    vec![cuboid((-4.0, -8.0, -4.0), (8.0, 8.0, 8.0), (0.0, 0.0))],
+                ),
+                part(
+                    [0.0, 6.0, 0.0],
+                    Motion::Still,
+                    vec![cuboid((-4.0, 0.0, -2.0), (8.0, 12.0, 4.0), (16.0, 16.0))],
+                ),
+            ];
+            parts.extend(legs(6.0, 2.0, (4.0, -4.0), || {
+                vec![cuboid((-2.0, 0.0, -2.0), (4.0, 6.0, 4.0), (0.0, 16.0))]
+            }));
+            MobDefinition {
+                textures: &["minecraft:entity/creeper/creeper"],
+                size: (0.6, 1.7),
+                parts,
+                baby_head: None,
+            }
+        }
+        EntityType::Cow => {
+            let mut parts = vec![
+                part(
+                    [0.0, 4.0, -8.0],
+                    Motion::Head,
+                    vec![
+                        cuboid((-4.0, -4.0, -6.0), (8.0, 8.0, 6.0), (0.0, 0.0)),
+                        cuboid((-5.0, -5.0, -4.0), (1.0, 3.0, 1.0), (22.0, 0.0)),
+                        cuboid((4.0, -5.0, -4.0), (1.0, 3.0, 1.0), (22.0, 0.0)),
+                    ],
+                ),
+                body(
+                    [0.0, 5.0, 2.0],
+                    vec![
+                        cuboid((-6.0, -10.0, -7.0), (12.0, 18.0, 10.0), (18.0, 4.0)),
+                        cuboid((-2.0, 2.0, -8.0), (4.0, 6.0, 1.0), (52.0, 0.0)),
+                    ],
+                ),
+            ];
+            parts.extend(legs(12.0, 4.0, (7.0, -6.0), || {
+                vec![cuboid((-2.0, 0.0, -2.0), (4.0, 12.0, 4.0), (0.0, 16.0))]
+            }));
+            MobDefinition {
+                textures: &["minecraft:entity/cow/cow"],
+                size: (0.9, 1.4),
+                parts,
+                baby_head: quadruped_head(10.0, 4.0),
+            }
+        }
+        EntityType::Pig => {
+            let mut parts = vec![
+                part(
+                    [0.0, 12.0, -6.0],
+                    Motion::Head,
+                    vec![
+                        cuboid((-4.0, -4.0, -8.0), (8.0, 8.0, 8.0), (0.0, 0.0)),
+                        cuboid((-2.0, 0.0, -9.0), (4.0, 3.0, 1.0), (16.0, 16.0)),
+                    ],
+                ),
+                body(
+                    [0.0, 11.0, 2.0],
+                    vec![cuboid((-5.0, -10.0, -7.0), (10.0, 16.0, 8.0), (28.0, 8.0))],
+                ),
+            ];
+            parts.extend(legs(6.0, 3.0, (7.0, -5.0), || {
+                vec![cuboid((-2.0, 0.0, -2.0), (4.0, 6.0, 4.0), (0.0, 16.0))]
+            }));
+            MobDefinition {
+                textures: &["minecraft:entity/pig/pig"],
+                size: (0.9, 0.9),
+                parts,
+                baby_head: quadruped_head(4.0, 4.0),
+            }
+        }
+        EntityType::Sheep => {
+            // The wool is a second, slightly larger layer around the body
+            let wool = |origin, size, inflate, uv| Cuboid {
+                texture: 1,
+                inflate,
+                ..cuboid(origin, size, uv)
+            };
+            let mut parts = vec![
+                part(
+                    [0.0, 6.0, -8.0],
+                    Motion::Head,
+                    vec![
+                        cuboid((-3.0, -4.0, -6.0), (6.0, 6.0, 8.0), (0.0, 0.0)),
+                        wool((-3.0, -4.0, -4.0), (6.0, 6.0, 6.0), 0.6, (0.0, 0.0)),
+                    ],
+                ),
+                body(
+                    [0.0, 5.0, 2.0],
+                    vec![
+                        cuboid((-4.0, -10.0, -7.0), (8.0, 16.0, 6.0), (28.0, 8.0)),
+                        wool((-4.0, -10.0, -7.0), (8.0, 16.0, 6.0), 1.75, (28.0, 8.0)),
+                    ],
+                ),
+            ];
+            parts.extend(legs(12.0, 3.0, (7.0, -5.0), || {
+                vec![
+                    cuboid((-2.0, 0.0, -2.0), (4.0, 12.0, 4.0), (0.0, 16.0)),
+                    wool((-2.0, 0.0, -2.0), (4.0, 6.0, 4.0), 0.5, (0.0, 16.0)),
+                ]
+            }));
+            MobDefinition {
+                textures: &[
+                    "minecraft:entity/sheep/sheep",
+                    "minecraft:entity/sheep/sheep_fur",
+                ],
+                size: (0.9, 1.3),
+                parts,
+                baby_head: quadruped_head(8.0, 4.0),
+            }
+        }
+        EntityType::Chicken => {
+            let leg = || vec![cuboid((-1.0, 0.0, -3.0), (3.0, 5.0, 3.0), (26.0, 0.0))];
+            MobDefinition {
+                textures: &["minecraft:entity/chicken"],
+                size: (0.4, 0.7),
+                parts: vec![
+                    part(
+                        [0.0, 15.0, -4.0],
+                        Motion::Head,
+                        vec![
+                            cuboid((-2.0, -6.0, -2.0), (4.0, 6.0, 3.0), (0.0, 0.0)),
+                            cuboid((-2.0, -4.0, -4.0), (4.0, 2.0, 2.0), (14.0, 0.0)),
+                            cuboid((-1.0, -2.0, -3.0), (2.0, 2.0, 2.0), (14.0, 4.0)),
+                        ],
+                    ),
+                    body(
+                        [0.0, 16.0, 0.0],
+                        vec![cuboid((-3.0, -4.0, -3.0), (6.0, 8.0, 6.0), (0.0, 9.0))],
+                    ),
+                    part([-2.0, 19.0, 1.0], Motion::Swing(1.0), leg()),
+                    part([1.0, 19.0, 1.0], Motion::Swing(-1.0), leg()),
+                    part(
+                        [-4.0, 13.0, 0.0],
+                        Motion::Still,
+                        vec![cuboid((0.0, 0.0, -3.0), (1.0, 4.0, 6.0), (24.0, 13.0))],
+                    ),
+                    part(
+                        [4.0, 13.0, 0.0],
+                        Motion::Still,
+                        vec![cuboid((-1.0, 0.0, -3.0), (1.0, 4.0, 6.0), (24.0, 13.0))],
+                    ),
+                ],
+                baby_head: quadruped_head(5.0, 2.0),
+            }
+        }
+        EntityType::Villager => {
+            let leg = || vec![cuboid((-2.0, 0.0, -2.0), (4.0, 12.0, 4.0), (0.0, 22.0))];
+            let mut arms = part(
+                [0.0, 3.0, -1.0],
+                Motion::Still,
+                vec![
+                    cuboid((-8.0, -2.0, -2.0), (4.0, 8.0, 4.0), (44.0, 22.0)),
+                    cuboid((4.0, -2.0, -2.0), (4.0, 8.0, 4.0), (44.0, 22.0)),
+                    cuboid((-4.0, 2.0, -2.0), (8.0, 4.0, 4.0), (40.0, 38.0)),
+                ],
+            );
+            arms.joint.rotation = [-0.75, 0.0, 0.0];
+            MobDefinition {
+                textures: &["minecraft:entity/villager/villager"],
+                size: (0.6, 1.95),
+                parts: vec![
+                    part(
+                        [0.0, 0.0, 0.0],
+                        Motion::Head,
+                        vec![
+                            cuboid((-4.0, -10.0, -4.0), (8.0, 10.0, 8.0), (0.0, 0.0)),
+                            cuboid((-1.0, -3.0, -6.0), (2.0, 4.0, 2.0), (24.0, 0.0)),
+                        ],
+                    ),
+                    part(
+                        [0.0, 0.0, 0.0],
+                        Motion::Still,
+                        vec![
+                            cuboid((-4.0, 0.0, -3.0), (8.0, 12.0, 6.0), (16.0, 20.0)),
+                            Cuboid {
+                                inflate: 0.5,
+                                ..cuboid((-4.0, 0.0, -3.0), (8.0, 18.0, 6.0), (0.0, 38.0))
+                            },
+                        ],
+                    ),
+                    arms,
+                    part([-2.0, 12.0, 0.0], Motion::Swing(1.0), leg()),
+                    part([2.0, 12.0, 0.0], Motion::Swing(-1.0), leg()),
+                ],
+                baby_head: Some(BabyHead::HUMANOID),
+            }
+        }
+        _ => return None,
+    })
+}
+
+/// Model of one of the common mobs that are described by a list of parts.
+#[derive(Component)]
+pub struct MobModel {
+    kind: EntityType,
+    model: Option<model::ModelHandle>,
+    joints: Vec<Joint>,
+    baby_head: Option<BabyHead>,
+
+    dir: i32,
+    time: f64,
+    still_time: f64,
+}
+
+impl MobModel {
+    pub fn new(kind: EntityType) -> Self {
+        Self {
+            kind,
+            model: None,
+            joints: vec![],
+            baby_head: None,
+            dir: 0,
+            time: 0.0,
+            still_time: 0.0,
+        }
+    }
+
+    /// Whether there is a model for the given type of mob.
+    pub fn supports(kind: EntityType) -> bool {
+        definition(kind).is_some()
+    }
+
+    /// Whether the given type of mob has a baby form.
+    pub fn has_baby_form(kind: EntityType) -> bool {
+        definition(kind).is_some_and(|definition| definition.baby_head.is_some())
+    }
+
+    /// Moves the walk cycle along, returning how far the limbs swing out.
+    fn walk(&mut self, moved: bool, delta: f64) -> f64 {
+        use std::f64::consts::PI;
+        let mut time = self.time;
+        let mut dir = self.dir;
+        if dir == 0 {
+            dir = 1;
+            time = 15.0;
+        }
+        let ang = ((time / 15.0) - 1.0) * (PI / 4.0);
+
+        let mut update = true;
+        if moved {
+            self.still_time = 0.0;
+        } else if self.still_time > 2.0 {
+            if (time - 15.0).abs() <= 1.5 * delta {
+                time = 15.0;
+                update = false;
+            }
+            dir = (15.0 - time).signum() as i32;
+        } else {
+            self.still_time += delta;
+        }
+
+        if update {
+            time += delta * 1.5 * (dir as f64);
+            if time > 30.0 {
+                time = 30.0;
+                dir = -1;
+            } else if time < 0.0 {
+                time = 0.0;
+                dir = 1;
+            }
+        }
+        self.time = time;
+        self.dir = dir;
+        ang
+    }
+}
+
+pub fn added_mob(
+    renderer: Res<RendererResource>,
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut MobModel), Added<MobModel>>,
+) {
+    let renderer = &renderer.0;
+    for (entity, mut mob_model) in query.iter_mut() {
+        let definition = match definition(mob_model.kind) {
+            Some(definition) => definition,
+            None => continue,
+        };
+        let (width, height) = definition.size;
+        commands.entity(entity).insert(Bounds::new(Aabb3::new(
+            Point3::new(-width / 2.0, 0.0, -width / 2.0),
+            Point3::new(width / 2.0, height, width / 2.0),
+        )));
+
+        let textures = definition
+            .textures
+            .iter()
+            .map(|name| Renderer::get_texture(renderer.get_textures_ref(), name))
+            .collect::<Vec<_>>();
+        let parts = definition
+            .parts
+            .iter()
+            .map(|part| {
+                let mut verts: Vec<Vertex> = vec![];
+                for cuboid in &part.cuboids {
+                    append_part_box(
+                        &mut verts,
+                        &textures[cuboid.texture],
+                        cuboid.origin,
+                        cuboid.size,
+                        cuboid.inflate,
+                        cuboid.uv,
+                    );
+                }
+                verts
+            })
+            .collect();
+        mob_model.joints = definition.parts.iter().map(|part| part.joint).collect();
+        mob_model.baby_head = definition.baby_head;
+        mob_model.model.replace(renderer.models.lock().create_model(
+            model::DEFAULT,
+            parts,
+            renderer.clone(),
+        ));
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn update_mob(
+    game_info: Res<GameInfo>,
+    renderer: Res<RendererResource>,
+    mut query: Query<(
+        &mut MobModel,
+        &Position,
+        &Rotation,
+        Option<&HeadRotation>,
+        &Light,
+        &Scale,
+    )>,
+) {
+    use std::f32::consts::PI;
+
+    let renderer = &renderer.0;
+    for (mut mob_model, position, rotation, head, light, scale) in query.iter_mut() {
+        let ang = mob_model.walk(position.moved, game_info.delta) as f32;
+
+        if let Some(mmodel) = &mob_model.model {
+            let mut models = renderer.models.lock();
+            let mdl = models.get_model(mmodel).unwrap();
+
+            mdl.block_light = light.block_light;
+            mdl.sky_light = light.sky_light;
+
+            // Only the head turns to look around, the body follows behind
+            let (body_yaw, head_yaw) = head.map_or((rotation.yaw, 0.0), |head| {
+                (head.body_yaw, head.relative_yaw())
+            });
+            let offset_matrix = Matrix4::from(Decomposed {
+                scale: 1.0,
+                rot: Quaternion::from_angle_y(Rad(PI + body_yaw as f32)),
+                disp: Vector3::new(
+                    position.position.x as f32,
+                    -position.position.y as f32,
+                    position.position.z as f32,
+                ),
+            });
+            let head_matrix = offset_matrix * scale.part_matrix(mob_model.baby_head);
+            let body_matrix = offset_matrix * scale.part_matrix(None);
+
+            for (i, joint) in mob_model.joints.iter().enumerate() {
+                let mut rot = joint.rotation;
+                let mut matrix = body_matrix;
+                match joint.motion {
+                    Motion::Still => {}
+                    Motion::Head => {
+                        matrix = head_matrix;
+                        rot[0] -= rotation.pitch as f32;
+                        rot[1] += head_yaw as f32;
+                    }
+                    Motion::Swing(factor) => rot[0] += ang * factor,
+                }
+                mdl.matrix[i] = matrix
+                    * Matrix4::from(Decomposed {
+                        scale: 1.0,
+                        rot: Quaternion::from_angle_z(Rad(rot[2]))
+                            * Quaternion::from_angle_y(Rad(rot[1]))
+                            * Quaternion::from_angle_x(Rad(rot[0])),
+                        disp: Vector3::new(
+                            joint.pivot[0] / 16.0,
+                            (joint.pivot[1] - 24.0) / 16.0,
+                            joint.pivot[2] / 16.0,
+                        ),
+                    });
+            }
+        }
+    }
+}
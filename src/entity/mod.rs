@@ -1,20 +1,34 @@
+pub mod armor_stand;
 pub mod block_entity;
+pub mod dropped_item;
+pub mod item_frame;
+pub mod mob;
 pub mod player;
 
 use crate::ecs::{Manager, SystemExecStage};
+use crate::entity::armor_stand::ArmorStandModel;
+use crate::entity::dropped_item::DroppedItemModel;
+use crate::entity::mob::MobModel;
 use crate::entity::slime::SlimeModel;
 use crate::entity::zombie::ZombieModel;
-use crate::render::Texture;
+use crate::inventory::Material;
+use crate::render::{Renderer, Texture};
 use crate::world::block;
 use bevy_ecs::component::Component;
 use bevy_ecs::prelude::*;
 use block::MiningTime;
-use cgmath::Vector3;
+use cgmath::{Matrix4, Vector3};
 use collision::Aabb3;
+use leafish_protocol::protocol::mapped_packet::EntityProperty;
+use leafish_protocol::protocol::UUID;
+use leafish_protocol::types::{self, Value};
+use shared::Version;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub mod player_like;
 pub mod slime;
+pub mod spatial_index;
 mod systems;
 pub mod versions;
 pub mod zombie;
@@ -63,7 +77,8 @@ pub fn add_systems(sched: &mut Schedule, render_sched: &mut Schedule) {
     sched
         .add_systems(systems::apply_velocity.in_set(SystemExecStage::Normal))
         .add_systems(systems::apply_gravity.in_set(SystemExecStage::Normal))
-        .add_systems(systems::apply_digging.in_set(SystemExecStage::Normal));
+        .add_systems(systems::apply_digging.in_set(SystemExecStage::Normal))
+        .add_systems(systems::update_entity_index.in_set(SystemExecStage::Normal));
 
     render_sched /*sync*/
         .add_systems(
@@ -76,6 +91,12 @@ pub fn add_systems(sched: &mut Schedule, render_sched: &mut Schedule) {
                 .in_set(SystemExecStage::Render)
                 .after(SystemExecStage::Normal),
         )
+        .add_systems(
+            systems::update_head_rotation
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal)
+                .after(systems::lerp_position),
+        )
         .add_systems(
             systems::light_entity
                 .in_set(SystemExecStage::Render)
@@ -108,17 +129,34 @@ impl Position {
     }
 }
 
+/// Ticks taken to move between two position or rotation updates, like
+/// vanilla's interpolation of other entities.
+pub const LERP_TICKS: f64 = 3.0;
+
 #[derive(Component, Debug, Clone)]
 pub struct TargetPosition {
     pub position: Vector3<f64>,
     pub lerp_amount: f64,
+    /// Whether to move linearly to each new target over `LERP_TICKS`
+    /// instead of easing towards it by `lerp_amount` every frame.
+    pub interpolate: bool,
+
+    lerp_start: Vector3<f64>,
+    last_target: Vector3<f64>,
+    /// Ticks since the target last changed
+    lerp_progress: f64,
 }
 
 impl TargetPosition {
     pub fn new(x: f64, y: f64, z: f64) -> TargetPosition {
+        let position = Vector3::new(x, y, z);
         TargetPosition {
-            position: Vector3::new(x, y, z),
+            position,
             lerp_amount: 0.2,
+            interpolate: true,
+            lerp_start: position,
+            last_target: position,
+            lerp_progress: LERP_TICKS,
         }
     }
 
@@ -165,11 +203,21 @@ impl Rotation {
 pub struct TargetRotation {
     pub yaw: f64,
     pub pitch: f64,
+
+    lerp_start: (f64, f64),
+    last_target: (f64, f64),
+    lerp_progress: f64,
 }
 
 impl TargetRotation {
     pub fn new(yaw: f64, pitch: f64) -> Self {
-        Self { yaw, pitch }
+        Self {
+            yaw,
+            pitch,
+            lerp_start: (yaw, pitch),
+            last_target: (yaw, pitch),
+            lerp_progress: LERP_TICKS,
+        }
     }
 
     pub fn zero() -> Self {
@@ -177,6 +225,34 @@ impl TargetRotation {
     }
 }
 
+/// Yaw of an entity's head, which looks around on its own while the body
+/// only turns to follow it.
+#[derive(Component, Debug)]
+pub struct HeadRotation {
+    pub yaw: f64,
+    /// Head yaw sent by the server, `yaw` eases towards it
+    pub target_yaw: f64,
+    pub body_yaw: f64,
+
+    last_position: Option<Vector3<f64>>,
+}
+
+impl HeadRotation {
+    pub fn new(yaw: f64) -> Self {
+        Self {
+            yaw,
+            target_yaw: yaw,
+            body_yaw: yaw,
+            last_position: None,
+        }
+    }
+
+    /// Yaw of the head relative to the body, for rotating the head bone.
+    pub fn relative_yaw(&self) -> f64 {
+        systems::shortest_angle(self.body_yaw, self.yaw)
+    }
+}
+
 #[derive(Component, Default)]
 pub struct Gravity {
     pub on_ground: bool,
@@ -188,6 +264,10 @@ impl Gravity {
     }
 }
 
+/// The id the server refers to the entity by.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServerId(pub i32);
+
 #[derive(Component)]
 pub struct Bounds {
     pub bounds: Aabb3<f64>,
@@ -222,6 +302,194 @@ impl Light {
     }
 }
 
+/// How the head of a mob's baby form is drawn, scaled by `scale` and then
+/// moved by `offset` pixels. The rest of a baby is drawn at half size.
+#[derive(Clone, Copy, Debug)]
+pub struct BabyHead {
+    pub scale: f32,
+    pub offset: [f32; 3],
+}
+
+impl BabyHead {
+    /// Zombies, villagers and other humanoids keep a large head.
+    pub const HUMANOID: BabyHead = BabyHead {
+        scale: 0.75,
+        offset: [0.0, 12.0, 0.0],
+    };
+}
+
+/// The attributes the server sent for an entity, such as its movement
+/// speed or maximum health.
+#[derive(Component, Debug, Default)]
+pub struct Attributes {
+    attributes: HashMap<String, Attribute>,
+}
+
+#[derive(Debug)]
+pub struct Attribute {
+    pub base: f64,
+    pub modifiers: Vec<AttributeModifier>,
+}
+
+#[derive(Debug)]
+pub struct AttributeModifier {
+    pub uuid: UUID,
+    pub amount: f64,
+    pub operation: i8,
+}
+
+impl Attributes {
+    /// Updates the attributes in the packet, leaving the others as they were.
+    pub fn apply_properties(&mut self, properties: &[EntityProperty]) {
+        for property in properties {
+            let modifiers = property
+                .modifiers
+                .iter()
+                .map(|modifier| AttributeModifier {
+                    uuid: modifier.uuid.clone(),
+                    amount: modifier.amount,
+                    operation: modifier.operation,
+                })
+                .collect();
+            self.attributes.insert(
+                attribute_name(&property.key),
+                Attribute {
+                    base: property.value,
+                    modifiers,
+                },
+            );
+        }
+    }
+
+    /// Looks up an attribute by its name without namespace, e.g.
+    /// `movement_speed`.
+    pub fn get(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.get(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.get(name).map(|attribute| attribute.value(|_| true))
+    }
+}
+
+impl Attribute {
+    /// The value after applying the modifiers `filter` accepts. They are
+    /// added to the base value, then multiply it and finally multiply the
+    /// total, like vanilla does.
+    pub fn value(&self, filter: impl Fn(&AttributeModifier) -> bool) -> f64 {
+        let modifiers = || self.modifiers.iter().filter(|m| filter(m));
+        let mut value = self.base;
+        for modifier in modifiers().filter(|m| m.operation == 0) {
+            value += modifier.amount;
+        }
+        let base = value;
+        for modifier in modifiers().filter(|m| m.operation == 1) {
+            value += base * modifier.amount;
+        }
+        for modifier in modifiers().filter(|m| m.operation == 2) {
+            value *= 1.0 + modifier.amount;
+        }
+        value
+    }
+}
+
+/// Attribute keys changed from e.g. `generic.movementSpeed` to
+/// `minecraft:generic.movement_speed` and later `minecraft:movement_speed`,
+/// so they are all turned into the last form without namespace.
+fn attribute_name(key: &str) -> String {
+    let name = key.rsplit(['.', ':']).next().unwrap_or(key);
+    let mut snake = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            snake.push('_');
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+/// How much smaller or larger than usual an entity is drawn, either because
+/// it is a baby or because the server changed its scale attribute.
+#[derive(Component, Debug)]
+pub struct Scale {
+    pub baby: bool,
+    pub factor: f32,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Self {
+            baby: false,
+            factor: 1.0,
+        }
+    }
+}
+
+impl Scale {
+    pub fn apply_metadata(
+        &mut self,
+        metadata: &types::Metadata,
+        version: Version,
+        kind: EntityType,
+    ) {
+        if !kind.has_baby_form() {
+            return;
+        }
+        // Ageable mobs and zombies both keep their baby flag straight after
+        // the fields every mob has
+        let index = match version {
+            Version::V1_7 | Version::V1_8 => 12,
+            Version::V1_9 => 11,
+            Version::V1_10
+            | Version::V1_11
+            | Version::V1_12
+            | Version::V1_13
+            | Version::V1_13_2 => 12,
+            Version::V1_14 => 14,
+            Version::V1_15 | Version::V1_16 | Version::V1_16_2 => 15,
+            _ => 16,
+        };
+        self.baby = match metadata.get_raw(index) {
+            Some(Value::Bool(baby)) => *baby,
+            // Before 1.9 zombies had a child flag, while other mobs sent
+            // their age which is negative while growing up
+            Some(Value::Byte(value)) if kind.is_zombie() => *value != 0,
+            Some(Value::Byte(age)) => *age < 0,
+            Some(Value::Int(age)) => *age < 0,
+            _ => return,
+        };
+    }
+
+    /// Picks up the scale attribute added in 1.20.5.
+    pub fn apply_attributes(&mut self, attributes: &Attributes) {
+        if let Some(scale) = attributes.value("scale") {
+            self.factor = scale.clamp(0.0625, 16.0) as f32;
+        }
+    }
+
+    /// Transform for a part of a model, applied between the entity's
+    /// position and the part's own offset. `head` gives the proportions of
+    /// the head for parts that belong to it.
+    pub fn part_matrix(&self, head: Option<BabyHead>) -> Matrix4<f32> {
+        let (scale, offset) = match head {
+            _ if !self.baby => (1.0, [0.0, 0.0, 0.0]),
+            Some(head) => (head.scale, head.offset),
+            None => (0.5, [0.0, 12.0, 0.0]),
+        };
+        // Parts are scaled around the ground under the entity, 24 pixels
+        // below the origin of the model
+        Matrix4::from_scale(self.factor)
+            * Matrix4::from_translation(Vector3::new(
+                offset[0] / 16.0,
+                (24.0 * scale + offset[1] - 24.0) / 16.0,
+                offset[2] / 16.0,
+            ))
+            * Matrix4::from_scale(scale)
+    }
+}
+
 #[derive(Component, Copy, Clone, Eq, PartialEq, Hash, Debug)]
 pub enum EntityType {
     DroppedItem,
@@ -397,17 +665,58 @@ impl EntityType {
     fn create_model(&self, m: &mut Manager, entity: Entity) {
         match self {
             EntityType::Zombie => {
-                m.world.entity_mut(entity).insert(ZombieModel::new(None));
+                let yaw = m.world.get::<Rotation>(entity).unwrap().yaw;
+                m.world
+                    .entity_mut(entity)
+                    .insert(ZombieModel::new(None))
+                    .insert(HeadRotation::new(yaw))
+                    .insert(Scale::default());
             }
             EntityType::Slime => {
                 m.world.entity_mut(entity).insert(SlimeModel::new(None));
             }
+            EntityType::ArmorStand => {
+                m.world
+                    .entity_mut(entity)
+                    .insert(ArmorStandModel::default());
+            }
+            EntityType::DroppedItem => {
+                m.world
+                    .entity_mut(entity)
+                    .insert(DroppedItemModel::default());
+            }
+            kind if MobModel::supports(*kind) => {
+                let yaw = m.world.get::<Rotation>(entity).unwrap().yaw;
+                m.world
+                    .entity_mut(entity)
+                    .insert(MobModel::new(*kind))
+                    .insert(HeadRotation::new(yaw))
+                    .insert(Scale::default());
+            }
             _ => {}
         };
     }
 
+    fn is_zombie(&self) -> bool {
+        matches!(
+            self,
+            EntityType::Zombie | EntityType::PigZombie | EntityType::ZombieVillager
+        )
+    }
+
+    /// Whether the mob can be a baby, which is drawn smaller.
+    pub fn has_baby_form(&self) -> bool {
+        self.is_zombie() || MobModel::has_baby_form(*self)
+    }
+
     fn supported(&self) -> bool {
-        matches!(self, EntityType::Zombie)
+        matches!(
+            self,
+            EntityType::Zombie
+                | EntityType::ItemFrame
+                | EntityType::ArmorStand
+                | EntityType::DroppedItem
+        ) || MobModel::supports(*self)
     }
 }
 
@@ -483,12 +792,42 @@ pub fn resolve_textures(
     ]
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Looks up the flat icon of an item, falling back to the block texture for
+/// items that are only ever drawn as blocks.
+pub fn item_texture(renderer: &Renderer, material: Material) -> Texture {
+    let textures = renderer.get_textures_ref();
+    let (item, block) = material.texture_locations();
+    match Renderer::get_texture_optional(textures, &format!("minecraft:{}", item)) {
+        Some(tex) if !tex.is_dummy() => tex,
+        _ => Renderer::get_texture(textures, &format!("minecraft:{}", block)),
+    }
+}
+
+/// The name armor textures use for the material the piece is made of.
+pub fn armor_texture_name(material: Material) -> Option<&'static str> {
+    use Material::*;
+    Some(match material {
+        LeatherHelmet | LeatherChestplate | LeatherLeggings | LeatherBoots => "leather",
+        ChainmailHelmet | ChainmailChestplate | ChainmailLeggings | ChainmailBoots => "chainmail",
+        IronHelmet | IronChestplate | IronLeggings | IronBoots => "iron",
+        DiamondHelmet | DiamondChestplate | DiamondLeggings | DiamondBoots => "diamond",
+        GoldHelmet | GoldChestplate | GoldLeggings | GoldBoots | GoldenHelmet
+        | GoldenChestplate | GoldenLeggings | GoldenBoots => "gold",
+        NetheriteHelmet | NetheriteChestplate | NetheriteLeggings | NetheriteBoots => "netherite",
+        TurtleHelmet => "turtle",
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct DiggingState {
     pub block: block::Block,
     pub position: shared::Position,
     pub face: shared::Direction,
-    pub start: std::time::Instant,
+    /// Accumulated mining progress, the block breaks once this reaches 1.0
+    pub progress: f32,
+    /// Whether the block breaks as soon as digging starts, as in creative
+    pub instant: bool,
     pub finished: bool,
 }
 
@@ -500,6 +839,35 @@ pub enum DiggingFinishState {
 }
 
 impl DiggingState {
+    pub fn new(
+        block: block::Block,
+        position: shared::Position,
+        face: shared::Direction,
+        instant: bool,
+    ) -> Self {
+        DiggingState {
+            block,
+            position,
+            face,
+            progress: 0.0,
+            instant,
+            finished: false,
+        }
+    }
+
+    /// Advances the mining progress by one tick.
+    pub fn advance(&mut self, tool: &Option<block::Tool>, on_ground: bool) {
+        if let MiningTime::Time(mining_time) = self.block.get_mining_time(tool) {
+            let ticks = (mining_time.as_secs_f32() * 20.0).max(1.0);
+            let mut damage = 1.0 / ticks;
+            // Mining while airborne is five times slower
+            if !on_ground {
+                damage /= 5.0;
+            }
+            self.progress += damage;
+        }
+    }
+
     pub fn is_finished(&self, tool: &Option<block::Tool>) -> DiggingFinishState {
         // If marked as finished, we don't need to calculate the mining time
         // again.
@@ -507,21 +875,23 @@ impl DiggingState {
             return DiggingFinishState::Finished;
         }
 
-        let mining_time = self.block.get_mining_time(tool);
-        match mining_time {
-            block::MiningTime::Instant => DiggingFinishState::FinishedInstant,
-            block::MiningTime::Time(mining_time) => {
-                let finish_time = self.start + mining_time;
-                if finish_time < std::time::Instant::now() {
-                    DiggingFinishState::Finished
-                } else {
-                    DiggingFinishState::NotFinished
-                }
-            }
-            block::MiningTime::Never => DiggingFinishState::NotFinished,
+        if self.instant {
+            return DiggingFinishState::FinishedInstant;
+        }
+
+        match self.block.get_mining_time(tool) {
+            MiningTime::Instant => DiggingFinishState::FinishedInstant,
+            MiningTime::Time(_) if self.progress >= 1.0 => DiggingFinishState::Finished,
+            MiningTime::Time(_) | MiningTime::Never => DiggingFinishState::NotFinished,
         }
     }
 
+    /// Whether the block can be mined at all, unbreakable blocks don't show
+    /// any break progress.
+    pub fn is_breakable(&self, tool: &Option<block::Tool>) -> bool {
+        !matches!(self.block.get_mining_time(tool), MiningTime::Never)
+    }
+
     pub fn get_ratio(&self, tool: &Option<block::Tool>) -> f32 {
         // If marked as finished, we don't need to calculate the mining time
         // again.
@@ -529,16 +899,11 @@ impl DiggingState {
             return 1.0;
         }
 
-        let mining_time = self.block.get_mining_time(tool);
-        let mining_time = match mining_time {
-            MiningTime::Instant => return 1.0,
-            MiningTime::Never => return 0.0,
-            MiningTime::Time(time) => time,
-        };
-        let now = std::time::Instant::now();
-        let expected = now - self.start;
-        let ratio = expected.as_secs_f32() / mining_time.as_secs_f32();
-        ratio.min(1.0)
+        match self.block.get_mining_time(tool) {
+            MiningTime::Instant => 1.0,
+            MiningTime::Never => 0.0,
+            MiningTime::Time(_) => self.progress.min(1.0),
+        }
     }
 }
 
@@ -548,6 +913,8 @@ pub struct Digging {
     pub current: Option<DiggingState>,
     pub processed: bool,
     pub effect: Option<Entity>,
+    /// Ticks left before another block can be broken instantly.
+    pub cooldown: u8,
 }
 
 impl Digging {
@@ -567,3 +934,32 @@ impl MouseButtons {
         Default::default()
     }
 }
+
+/// The food level the server reported for the local player.
+#[derive(Component)]
+pub struct Food {
+    pub level: u8,
+    pub saturation: u8,
+}
+
+impl Food {
+    /// Sprinting isn't possible at or below this food level.
+    const SPRINT_LEVEL: u8 = 6;
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn can_sprint(&self) -> bool {
+        self.level > Food::SPRINT_LEVEL
+    }
+}
+
+impl Default for Food {
+    fn default() -> Self {
+        Food {
+            level: 20,
+            saturation: 5,
+        }
+    }
+}
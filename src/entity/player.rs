@@ -1,31 +1,41 @@
 use super::{
-    Bounds, Digging, GameInfo, Gravity, Light, MouseButtons, Position, Rotation, TargetPosition,
-    TargetRotation, Velocity,
+    AttributeModifier, Attributes, Bounds, Digging, Food, GameInfo, Gravity, HeadRotation, Light,
+    MouseButtons, Position, Rotation, TargetPosition, TargetRotation, Velocity,
 };
 use crate::ecs::{Manager, SystemExecStage};
+use crate::entity::armor_stand::{added_armor_stand, update_armor_stand};
+use crate::entity::dropped_item::{added_dropped_item, update_dropped_item};
+use crate::entity::item_frame::{added_item_frame, update_item_frame};
+use crate::entity::mob::{added_mob, update_mob};
 use crate::entity::slime::{added_slime, update_slime};
 use crate::entity::zombie::{added_zombie, update_zombie};
-use crate::entity::{resolve_textures, EntityType};
+use crate::entity::{armor_texture_name, item_texture, resolve_textures, EntityType};
 use crate::format;
+use crate::inventory::material::versions::to_material;
+use crate::inventory::Material;
 use crate::render;
 use crate::render::model::{self, FormatState};
-use crate::render::Renderer;
+use crate::render::{CameraShake, Renderer, Texture};
 use crate::server::{RendererResource, ScreenSystemResource, WorldResource};
 use crate::settings::Actionkey;
+use crate::shared::Direction;
 use crate::shared::Position as BPosition;
 use crate::types::hash::FNVHash;
 use crate::types::GameMode;
 use crate::world;
 use arc_swap::ArcSwapOption;
 use bevy_ecs::prelude::*;
-use cgmath::{Decomposed, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
+use cgmath::{Decomposed, InnerSpace, Matrix4, Point3, Quaternion, Rad, Rotation3, Vector3};
 use collision::{Aabb, Aabb3};
 use instant::Instant;
 use leafish_protocol::format::Component;
+use leafish_protocol::item::Stack;
+use leafish_protocol::protocol::UUID;
+use shared::Version;
 use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 pub fn add_systems(sched: &mut Schedule, render_sched: &mut Schedule) {
     // TODO: Check sync/async usage!
@@ -66,6 +76,46 @@ pub fn add_systems(sched: &mut Schedule, render_sched: &mut Schedule) {
             added_zombie
                 .in_set(SystemExecStage::Render)
                 .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            update_item_frame
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            added_item_frame
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            update_armor_stand
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            added_armor_stand
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            update_dropped_item
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            added_dropped_item
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            update_mob
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
+        )
+        .add_systems(
+            added_mob
+                .in_set(SystemExecStage::Render)
+                .after(SystemExecStage::Normal),
         );
 }
 
@@ -73,6 +123,8 @@ pub fn create_local(m: &mut Manager) -> Entity {
     let mut entity = m.world.spawn_empty();
     let mut tpos = TargetPosition::new(0.0, 0.0, 0.0);
     tpos.lerp_amount = 1.0 / 3.0;
+    // Movement is simulated locally every frame, so just ease towards it
+    tpos.interpolate = false;
     entity
         .insert(Position::new(0.0, 0.0, 0.0))
         .insert(tpos)
@@ -94,11 +146,53 @@ pub fn create_local(m: &mut Manager) -> Entity {
         .insert(Light::new())
         .insert(Digging::new())
         .insert(MouseButtons::new())
+        .insert(Food::new())
         .insert(MovementDelta::default())
+        .insert(ViewBob::default())
         .insert(EntityType::Player);
     entity.id()
 }
 
+/// Tracks how far the local player walked on the ground, which sways the
+/// view like vanilla's view bobbing.
+#[derive(Component, Default)]
+pub struct ViewBob {
+    walk_distance: f64,
+    /// How strongly the view sways, follows the walking speed.
+    amount: f64,
+    last_position: Option<Vector3<f64>>,
+}
+
+impl ViewBob {
+    /// `delta` is in 1/60ths of a second, like the frame deltas.
+    pub fn update(&mut self, position: Vector3<f64>, on_ground: bool, delta: f64) {
+        let last = self.last_position.replace(position).unwrap_or(position);
+        let distance = (position.x - last.x).hypot(position.z - last.z);
+        self.walk_distance += distance * 0.6;
+        let ticks = delta / 3.0;
+        let target = if on_ground && ticks > 0.0 {
+            (distance / ticks).min(0.1)
+        } else {
+            0.0
+        };
+        // Vanilla moves 40% of the way there every tick
+        self.amount += (target - self.amount) * (1.0 - 0.6_f64.powf(ticks));
+    }
+
+    pub fn shake(&self) -> CameraShake {
+        use std::f64::consts::PI;
+        let walked = -self.walk_distance * PI;
+        CameraShake {
+            x: walked.sin() * self.amount * 0.5,
+            y: -(walked.cos() * self.amount).abs(),
+            roll: (walked.sin() * self.amount * 3.0).to_radians(),
+            pitch: ((walked - 0.2).cos() * self.amount * 5.0)
+                .abs()
+                .to_radians(),
+        }
+    }
+}
+
 pub fn create_remote(m: &mut Manager, name: Component) -> Entity {
     let mut entity = m.world.spawn_empty();
     entity
@@ -128,6 +222,11 @@ pub struct PlayerModel {
     has_name_tag: bool,
     first_person: bool,
 
+    /// Items held in the main and off hand.
+    hands: [Option<Material>; 2],
+    /// Worn armor from the boots up to the helmet.
+    armor: [Option<Material>; 4],
+
     dir: i32,
     time: f64,
     still_time: f64,
@@ -147,6 +246,9 @@ impl PlayerModel {
             has_name_tag,
             first_person,
 
+            hands: [None; 2],
+            armor: [None; 4],
+
             dir: 0,
             time: 0.0,
             still_time: 0.0,
@@ -161,16 +263,48 @@ impl PlayerModel {
             self.dirty.store(true, Ordering::Release);
         }
     }
+
+    /// Handles an equipment update for one of the player's slots.
+    pub fn set_equipment(&mut self, slot: i32, item: Option<&Stack>, version: Version) {
+        let material = item.map(|stack| {
+            to_material(
+                stack.id as u16,
+                Some(stack.damage.unwrap_or(0)),
+                None,
+                version,
+            )
+        });
+        // Before 1.9 there was no offhand slot before the armor
+        let current = match (slot, version <= Version::V1_8) {
+            (0, _) => &mut self.hands[0],
+            (1, false) => &mut self.hands[1],
+            (1..=4, true) => &mut self.armor[slot as usize - 1],
+            (2..=5, false) => &mut self.armor[slot as usize - 2],
+            _ => return,
+        };
+        if *current != material {
+            *current = material;
+            self.dirty.store(true, Ordering::Release);
+        }
+    }
 }
 
+#[allow(clippy::type_complexity)]
 fn update_render_players(
     renderer: Res<RendererResource>,
     game_info: Res<GameInfo>,
-    mut query: Query<(&mut PlayerModel, &Position, &Rotation, &Light)>,
+    mut query: Query<(
+        &mut PlayerModel,
+        &Position,
+        &Rotation,
+        Option<&HeadRotation>,
+        &Light,
+        Option<&GameMode>,
+    )>,
 ) {
     let renderer = &renderer.0;
     let delta = game_info.delta;
-    for (mut player_model, position, rotation, light) in query.iter_mut() {
+    for (mut player_model, position, rotation, head, light, game_mode) in query.iter_mut() {
         use std::f32::consts::PI;
         use std::f64::consts::PI as PI64;
 
@@ -188,6 +322,16 @@ fn update_render_players(
             mdl.block_light = light.block_light;
             mdl.sky_light = light.sky_light;
 
+            // Spectators only see a faint outline of themselves
+            let alpha = if game_mode == Some(&GameMode::Spectator) {
+                0.3
+            } else {
+                1.0
+            };
+            for color in &mut mdl.colors {
+                color[3] = alpha;
+            }
+
             let offset = if player_model.first_person {
                 let ox = (rotation.yaw - PI64 / 2.0).cos() * 0.25;
                 let oz = -(rotation.yaw - PI64 / 2.0).sin() * 0.25;
@@ -203,9 +347,13 @@ fn update_render_players(
                     position.position.z as f32,
                 )
             };
+            // Only the head turns to look around, the body follows behind
+            let (body_yaw, head_yaw) = head.map_or((rotation.yaw, 0.0), |head| {
+                (head.body_yaw, head.relative_yaw())
+            });
             let offset_matrix = Matrix4::from(Decomposed {
                 scale: 1.0,
-                rot: Quaternion::from_angle_y(Rad(PI + rotation.yaw as f32)),
+                rot: Quaternion::from_angle_y(Rad(PI + body_yaw as f32)),
                 disp: offset,
             });
 
@@ -222,7 +370,8 @@ fn update_render_players(
             mdl.matrix[PlayerModelPart::Head as usize] = offset_matrix
                 * Matrix4::from(Decomposed {
                     scale: 1.0,
-                    rot: Quaternion::from_angle_x(Rad(-rotation.pitch as f32)),
+                    rot: Quaternion::from_angle_y(Rad(head_yaw as f32))
+                        * Quaternion::from_angle_x(Rad(-rotation.pitch as f32)),
                     disp: Vector3::new(0.0, -12.0 / 16.0 - 12.0 / 16.0, 0.0),
                 });
             mdl.matrix[PlayerModelPart::Body as usize] = offset_matrix
@@ -452,6 +601,30 @@ fn add_player(renderer: Arc<Renderer>, player_model: &mut PlayerModel) {
         );
     }
 
+    add_armor(
+        &renderer,
+        player_model,
+        &mut head_verts,
+        &mut body_verts,
+        &mut part_verts,
+    );
+    // Held items stick out of the bottom of the arms
+    for (hand, arm) in [(0, 3), (1, 2)] {
+        if let Some(item) = player_model.hands[hand] {
+            let tex = item_texture(&renderer, item);
+            model::append_box(
+                &mut part_verts[arm],
+                -0.05 / 16.0,
+                -16.0 / 16.0,
+                -7.0 / 16.0,
+                0.1 / 16.0,
+                8.0 / 16.0,
+                8.0 / 16.0,
+                [None, None, None, None, Some(tex.clone()), Some(tex)],
+            );
+        }
+    }
+
     let mut name_verts = vec![];
     if player_model.has_name_tag {
         let mut state = FormatState {
@@ -510,6 +683,93 @@ fn add_player(renderer: Arc<Renderer>, player_model: &mut PlayerModel) {
     player_model.model.replace(model);
 }
 
+/// Appends a piece of armor in pixels, with y pointing up from the part's
+/// pivot, grown by `inflate` pixels on every side.
+fn append_armor_box(
+    verts: &mut Vec<model::Vertex>,
+    tex: &Texture,
+    (x, y, z): (f32, f32, f32),
+    (w, h, d): (f32, f32, f32),
+    inflate: f32,
+    (u, v): (f32, f32),
+) {
+    model::append_box(
+        verts,
+        (x - inflate) / 16.0,
+        (y - inflate) / 16.0,
+        (z - inflate) / 16.0,
+        (w + inflate * 2.0) / 16.0,
+        (h + inflate * 2.0) / 16.0,
+        (d + inflate * 2.0) / 16.0,
+        resolve_textures(tex, w, h, d, u, v),
+    );
+}
+
+fn add_armor(
+    renderer: &Arc<Renderer>,
+    player_model: &PlayerModel,
+    head_verts: &mut Vec<model::Vertex>,
+    body_verts: &mut Vec<model::Vertex>,
+    part_verts: &mut [Vec<model::Vertex>],
+) {
+    let limb = (4.0, 12.0, 4.0);
+    let body = (8.0, 12.0, 4.0);
+    for (slot, material) in player_model.armor.iter().enumerate() {
+        let name = match material.and_then(armor_texture_name) {
+            Some(name) => name,
+            None => continue,
+        };
+        // Leggings are drawn from the second, slightly tighter layer
+        let (layer, inflate) = if slot == 1 { (2, 0.5) } else { (1, 1.0) };
+        let tex = Renderer::get_texture(
+            renderer.get_textures_ref(),
+            &format!("minecraft:models/armor/{}_layer_{}", name, layer),
+        );
+        match slot {
+            0 | 1 => {
+                for leg in &mut part_verts[..2] {
+                    append_armor_box(leg, &tex, (-2.0, -12.0, -2.0), limb, inflate, (0.0, 16.0));
+                }
+                if slot == 1 {
+                    append_armor_box(
+                        body_verts,
+                        &tex,
+                        (-4.0, -6.0, -2.0),
+                        body,
+                        inflate,
+                        (16.0, 16.0),
+                    );
+                }
+            }
+            2 => {
+                append_armor_box(
+                    body_verts,
+                    &tex,
+                    (-4.0, -6.0, -2.0),
+                    body,
+                    inflate,
+                    (16.0, 16.0),
+                );
+                for arm in &mut part_verts[2..] {
+                    append_armor_box(arm, &tex, (-2.0, -12.0, -2.0), limb, inflate, (40.0, 16.0));
+                }
+            }
+            _ => {
+                if player_model.has_head {
+                    append_armor_box(
+                        head_verts,
+                        &tex,
+                        (-4.0, 0.0, -4.0),
+                        (8.0, 8.0, 8.0),
+                        inflate,
+                        (0.0, 0.0),
+                    );
+                }
+            }
+        }
+    }
+}
+
 enum PlayerModelPart {
     Head = 0,
     Body = 1,
@@ -528,6 +788,16 @@ pub struct PlayerMovement {
     pub when_last_jump_pressed: Option<Instant>,
     pub when_last_jump_released: Option<Instant>,
     pub did_touch_ground: bool,
+    /// How far the player has fallen since last standing on the ground.
+    pub fall_distance: f64,
+    /// How far the player fell before landing, until the landing has been
+    /// looked at for fall damage.
+    pub landed_fall_distance: Option<f64>,
+    /// Whether to jump up blocks the player walks into.
+    pub auto_jump: bool,
+    /// Whether the player walked into a wall last tick, which makes them
+    /// climb when on a ladder.
+    pub collided_horizontally: bool,
     pub pressed_keys: HashMap<Actionkey, bool, BuildHasherDefault<FNVHash>>,
 }
 
@@ -570,11 +840,22 @@ impl PlayerMovement {
         )
     }
 
-    fn is_key_pressed(&self, key: Actionkey) -> bool {
+    pub fn is_key_pressed(&self, key: Actionkey) -> bool {
         self.pressed_keys.get(&key).map_or(false, |v| *v)
     }
 }
 
+/// The movement speed attribute of a player without modifiers.
+const BASE_MOVEMENT_SPEED: f64 = 0.1;
+
+/// Vanilla adds this modifier to the movement speed while sprinting, which
+/// is applied here on its own.
+fn is_sprint_modifier(modifier: &AttributeModifier) -> bool {
+    static SPRINT: OnceLock<UUID> = OnceLock::new();
+    let sprint = SPRINT.get_or_init(|| "662a6b8d-da3e-4c1c-8813-96ea6097278d".parse().unwrap());
+    modifier.uuid == *sprint
+}
+
 // FIXME: look at this to review our impl: https://www.mcpk.wiki/wiki/Movement_Formulas
 #[allow(clippy::type_complexity)]
 #[allow(unused_mut)] // we ignore this warning, as this case seems to be a clippy bug
@@ -590,6 +871,8 @@ pub fn handle_movement(
         &Bounds,
         &Rotation,
         &GameMode,
+        Option<&Food>,
+        Option<&Attributes>,
         Option<&mut Gravity>,
     )>,
 ) {
@@ -603,6 +886,8 @@ pub fn handle_movement(
         bounds,
         rotation,
         gamemode,
+        food,
+        attributes,
         mut gravity,
     ) in query.iter_mut()
     {
@@ -612,6 +897,9 @@ pub fn handle_movement(
             commands.entity(entity).insert(Gravity::new());
         }
         movement.flying |= gamemode.always_fly();
+        if movement.flying {
+            movement.fall_distance = 0.0;
+        }
         if !screen_sys.is_current_ingame()
             && (movement.pressed_keys.len() > 1
                 || (!movement.pressed_keys.is_empty()
@@ -651,18 +939,50 @@ pub fn handle_movement(
         let player_bounds = bounds.bounds;
 
         let mut last_position = position.position;
+        let start_y = position.position.y;
 
         if world.is_chunk_loaded(
             (position.position.x as i32) >> 4,
             (position.position.z as i32) >> 4,
         ) {
-            let (forward, yaw, is_forward) = movement.calculate_movement(rotation.yaw);
-            let mut speed = 0.21585;
-            let mut additional_speed = if movement.is_key_pressed(Actionkey::Sprint) && is_forward {
-                0.2806 - 0.21585
+            let climbing = !movement.flying
+                && world
+                    .get_block(BPosition::new(
+                        position.position.x.floor() as i32,
+                        position.position.y.floor() as i32,
+                        position.position.z.floor() as i32,
+                    ))
+                    .is_climbable();
+            let fluid = if movement.flying {
+                None
             } else {
-                0.0
+                fluid_at(world, position.position)
             };
+            let (forward, yaw, is_forward) = movement.calculate_movement(rotation.yaw);
+            let mut speed = 0.21585;
+            // Sprinting needs enough food, unless the player is allowed to fly
+            let can_sprint =
+                gamemode.can_fly() || food.map(|food| food.can_sprint()).unwrap_or(true);
+            let mut additional_speed =
+                if movement.is_key_pressed(Actionkey::Sprint) && is_forward && can_sprint {
+                    0.2806 - 0.21585
+                } else {
+                    0.0
+                };
+            // Potions and servers change how fast players walk through their
+            // movement speed
+            if let Some(movement_speed) = attributes
+                .and_then(|attributes| attributes.get("movement_speed"))
+                .filter(|_| !movement.flying)
+            {
+                let factor = movement_speed.value(|m| !is_sprint_modifier(m)) / BASE_MOVEMENT_SPEED;
+                speed *= factor;
+                additional_speed *= factor;
+            }
+            if let Some(fluid) = &fluid {
+                speed *= fluid.kind.speed_factor();
+                additional_speed *= fluid.kind.speed_factor();
+            }
             let looking_vec = calculate_looking_vector(rotation.yaw, rotation.pitch);
             if movement.flying {
                 speed *= 2.5;
@@ -678,12 +998,37 @@ pub fn handle_movement(
                 if movement.is_key_pressed(Actionkey::Jump) && velocity.velocity.y.abs() < 0.001 {
                     velocity.velocity.y = 0.42;
                 }
+            } else if fluid.is_some() {
+                // Fluids hold the player up, so they sink slowly
+                velocity.velocity.y -= 0.02;
             } else {
                 velocity.velocity.y -= 0.08;
                 if velocity.velocity.y < -3.92 {
                     velocity.velocity.y = -3.92;
                 }
             }
+            if climbing {
+                // Ladders slow falling down, holding sneak stops on them
+                // and walking into the wall or jumping climbs up
+                velocity.velocity.y = velocity.velocity.y.max(-0.15);
+                if movement.is_key_pressed(Actionkey::Sneak) {
+                    velocity.velocity.y = velocity.velocity.y.max(0.0);
+                }
+                if movement.collided_horizontally || movement.is_key_pressed(Actionkey::Jump) {
+                    velocity.velocity.y = 0.2;
+                }
+            }
+            if let Some(fluid) = &fluid {
+                // Holding jump swims up and sneak dives, letting go of both
+                // bobs at the surface as the player leaves and reenters it
+                if movement.is_key_pressed(Actionkey::Jump) {
+                    velocity.velocity.y += 0.04;
+                }
+                if movement.is_key_pressed(Actionkey::Sneak) {
+                    velocity.velocity.y -= 0.04;
+                }
+                velocity.velocity.y *= fluid.kind.drag();
+            }
             velocity.velocity.y *= 0.98;
             velocity.velocity.x *= 0.98;
             velocity.velocity.z *= 0.98;
@@ -692,6 +1037,9 @@ pub fn handle_movement(
             position.position.x += forward * yaw.cos() * (speed + looking_vec.0 * additional_speed); // TODO: Multiply with speed only for walking forwards
             position.position.z -= forward * yaw.sin() * (speed + looking_vec.1 * additional_speed);
             position.position.y += velocity.velocity.y;
+            if let Some(fluid) = &fluid {
+                position.position += fluid.flow * fluid.kind.push();
+            }
             if (velocity.velocity.x.abs() * 0.2) < 0.005 {
                 velocity.velocity.x = 0.0;
             }
@@ -722,6 +1070,7 @@ pub fn handle_movement(
                     check_collisions(world, &mut position, &last_position, player_bounds);
                 position.position.z = bounds.min.z + 0.3;
                 last_position.z = position.position.z;
+                movement.collided_horizontally = xhit || zhit;
 
                 // Half block jumps
                 // Minecraft lets you 'jump' up 0.5 blocks
@@ -734,6 +1083,7 @@ pub fn handle_movement(
                     let mut oz = position.position.z;
                     position.position.x = target.x;
                     position.position.z = target.z;
+                    let mut stepped = false;
                     for offset in 1..9 {
                         let mini = player_bounds.add_v(cgmath::Vector3::new(
                             0.0,
@@ -745,9 +1095,25 @@ pub fn handle_movement(
                             target.y += offset as f64 / 16.0;
                             ox = target.x;
                             oz = target.z;
+                            stepped = true;
                             break;
                         }
                     }
+                    // Auto-jump when walking into something that's at most
+                    // a block high and has room to stand on top
+                    if !stepped
+                        && movement.auto_jump
+                        && is_forward
+                        && !movement.is_key_pressed(Actionkey::Sneak)
+                        && velocity.velocity.y.abs() < 0.001
+                    {
+                        let raised = player_bounds.add_v(cgmath::Vector3::new(0.0, 1.0, 0.0));
+                        let (_, hit) =
+                            check_collisions(world, &mut position, &last_position, raised);
+                        if !hit {
+                            velocity.velocity.y = 0.42;
+                        }
+                    }
                     position.position.x = ox;
                     position.position.z = oz;
                 }
@@ -770,12 +1136,131 @@ pub fn handle_movement(
                     if !prev && gravity.on_ground {
                         movement.did_touch_ground = true;
                     }
+                    if gravity.on_ground {
+                        if !prev {
+                            movement.landed_fall_distance = Some(movement.fall_distance);
+                        }
+                        movement.fall_distance = 0.0;
+                    } else if climbing || fluid.as_ref().is_some_and(|f| f.kind == FluidKind::Water)
+                    {
+                        // Climbing and water break the fall
+                        movement.fall_distance = 0.0;
+                    } else if fluid.is_some() {
+                        // Lava only softens it
+                        movement.fall_distance *= 0.5;
+                    } else if position.position.y < start_y {
+                        movement.fall_distance += start_y - position.position.y;
+                    }
                 }
             }
         }
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FluidKind {
+    Water,
+    Lava,
+}
+
+impl FluidKind {
+    fn of(block: world::block::Block) -> Option<FluidKind> {
+        match block {
+            world::block::Block::Lava { .. } => Some(FluidKind::Lava),
+            world::block::Block::Water { .. } | world::block::Block::BubbleColumn { .. } => {
+                Some(FluidKind::Water)
+            }
+            b if b.is_waterlogged() => Some(FluidKind::Water),
+            _ => None,
+        }
+    }
+
+    /// How much of the player's walking speed is left while in the fluid.
+    fn speed_factor(self) -> f64 {
+        match self {
+            FluidKind::Water => 0.5,
+            FluidKind::Lava => 0.2,
+        }
+    }
+
+    /// How much of the vertical velocity is kept each tick.
+    fn drag(self) -> f64 {
+        match self {
+            FluidKind::Water => 0.8,
+            FluidKind::Lava => 0.5,
+        }
+    }
+
+    /// How far a current moves the player each tick.
+    fn push(self) -> f64 {
+        match self {
+            FluidKind::Water => 0.014,
+            FluidKind::Lava => 0.0023,
+        }
+    }
+}
+
+/// The fluid the player is in and the direction it flows in, which is
+/// either zero or has a length of one.
+struct Fluid {
+    kind: FluidKind,
+    flow: Vector3<f64>,
+}
+
+/// Looks for fluid at the player's feet, then around their body.
+fn fluid_at(world: &world::World, position: Vector3<f64>) -> Option<Fluid> {
+    let feet = BPosition::new(
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    );
+    [feet, feet.shift(Direction::Up)].iter().find_map(|&pos| {
+        FluidKind::of(world.get_block(pos)).map(|kind| Fluid {
+            kind,
+            flow: fluid_flow(world, pos, kind),
+        })
+    })
+}
+
+/// Fluids flow from higher to lower levels, so the flow at a block points
+/// towards the neighbours with less fluid in them.
+fn fluid_flow(world: &world::World, pos: BPosition, kind: FluidKind) -> Vector3<f64> {
+    // How far the fluid has spread from its source, falling fluid and
+    // waterlogged blocks are full
+    let depth = |block| match block {
+        world::block::Block::Water { level } | world::block::Block::Lava { level } if level < 8 => {
+            Some(level as f64)
+        }
+        block if FluidKind::of(block) == Some(kind) => Some(0.0),
+        _ => None,
+    };
+    let own = match depth(world.get_block(pos)) {
+        Some(own) => own,
+        None => return Vector3::new(0.0, 0.0, 0.0),
+    };
+    let mut flow = Vector3::new(0.0, 0.0, 0.0);
+    for dir in [
+        Direction::North,
+        Direction::South,
+        Direction::West,
+        Direction::East,
+    ] {
+        let neighbour = world.get_block(pos.shift(dir));
+        if FluidKind::of(neighbour) != Some(kind) {
+            continue;
+        }
+        if let Some(other) = depth(neighbour) {
+            let (ox, _, oz) = dir.get_offset();
+            flow += Vector3::new(ox as f64, 0.0, oz as f64) * (other - own);
+        }
+    }
+    if flow.magnitude2() > 0.0 {
+        flow.normalize()
+    } else {
+        flow
+    }
+}
+
 fn calculate_looking_vector(yaw: f64, pitch: f64) -> (f64, f64) {
     let xz = pitch.to_radians().cos();
     let x = -xz * yaw.to_radians().sin();
@@ -878,6 +1363,7 @@ impl Collidable<Aabb3<f64>> for Aabb3<f64> {
 pub struct MovementDelta {
     pub prev_pos: Vector3<f64>,
     pub prev_rot: Rotation,
+    pub ticks_since_position: u32,
 }
 
 impl Default for MovementDelta {
@@ -888,6 +1374,7 @@ impl Default for MovementDelta {
                 yaw: f64::MAX,
                 pitch: f64::MAX,
             },
+            ticks_since_position: 0,
         }
     }
 }
@@ -0,0 +1,85 @@
+use crate::types::hash::FNVHash;
+use bevy_ecs::prelude::*;
+use collision::Aabb3;
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+/// The width of a bucket in blocks, this matches the size of a chunk.
+const BUCKET_SIZE: f64 = 16.0;
+
+type Bucket = (i32, i32);
+
+/// Buckets the bounding boxes of entities by the chunk column they are in, so
+/// ray traces only have to test the entities close to the ray.
+#[derive(Resource, Default)]
+pub struct EntityIndex {
+    buckets: HashMap<Bucket, Vec<Entity>, BuildHasherDefault<FNVHash>>,
+    entries: HashMap<Entity, (Bucket, Aabb3<f64>), BuildHasherDefault<FNVHash>>,
+}
+
+impl EntityIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn bucket_of(x: f64, z: f64) -> Bucket {
+        (
+            (x / BUCKET_SIZE).floor() as i32,
+            (z / BUCKET_SIZE).floor() as i32,
+        )
+    }
+
+    /// Stores the bounding box of an entity in world space, moving it to a
+    /// new bucket if required.
+    pub fn update(&mut self, entity: Entity, bounds: Aabb3<f64>) {
+        let bucket = EntityIndex::bucket_of(
+            (bounds.min.x + bounds.max.x) / 2.0,
+            (bounds.min.z + bounds.max.z) / 2.0,
+        );
+        if let Some((old_bucket, _)) = self.entries.insert(entity, (bucket, bounds)) {
+            if old_bucket == bucket {
+                return;
+            }
+            self.remove_from_bucket(entity, old_bucket);
+        }
+        self.buckets.entry(bucket).or_default().push(entity);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some((bucket, _)) = self.entries.remove(&entity) {
+            self.remove_from_bucket(entity, bucket);
+        }
+    }
+
+    fn remove_from_bucket(&mut self, entity: Entity, bucket: Bucket) {
+        if let Some(entities) = self.buckets.get_mut(&bucket) {
+            entities.retain(|e| *e != entity);
+            if entities.is_empty() {
+                self.buckets.remove(&bucket);
+            }
+        }
+    }
+
+    /// Returns the entities which could intersect the given area. As
+    /// entities are bucketed by their center, neighbouring buckets are
+    /// included too.
+    pub fn query(
+        &self,
+        min_x: f64,
+        min_z: f64,
+        max_x: f64,
+        max_z: f64,
+    ) -> impl Iterator<Item = (Entity, &Aabb3<f64>)> {
+        let min = EntityIndex::bucket_of(min_x, min_z);
+        let max = EntityIndex::bucket_of(max_x, max_z);
+        (min.0 - 1..=max.0 + 1)
+            .flat_map(move |x| (min.1 - 1..=max.1 + 1).map(move |z| (x, z)))
+            .filter_map(move |bucket| self.buckets.get(&bucket))
+            .flatten()
+            .filter_map(move |entity| {
+                self.entries
+                    .get(entity)
+                    .map(|(_, bounds)| (*entity, bounds))
+            })
+    }
+}
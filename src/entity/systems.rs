@@ -1,5 +1,6 @@
 use super::*;
 use crate::entity::player::PlayerMovement;
+use crate::entity::spatial_index::EntityIndex;
 use crate::particle::block_break_effect::{BlockBreakEffect, BlockEffectData};
 use crate::server::{ConnResource, InventoryContextResource, RendererResource, WorldResource};
 use crate::shared::Position as BPos;
@@ -36,14 +37,54 @@ pub fn update_last_position(mut query: Query<&mut Position>) {
     }
 }
 
-pub fn lerp_position(game_info: Res<GameInfo>, mut query: Query<(&mut Position, &TargetPosition)>) {
+pub fn update_entity_index(
+    mut index: ResMut<EntityIndex>,
+    query: Query<(Entity, &Position, &Bounds), Changed<Position>>,
+    mut removed: RemovedComponents<Bounds>,
+) {
+    use collision::Aabb;
+
+    for entity in removed.read() {
+        index.remove(entity);
+    }
+    for (entity, position, bounds) in query.iter() {
+        index.update(entity, bounds.bounds.add_v(position.position));
+    }
+}
+
+pub fn lerp_position(
+    game_info: Res<GameInfo>,
+    mut query: Query<(&mut Position, &mut TargetPosition)>,
+) {
     let delta = game_info.delta.min(5.0);
-    for (mut pos, target_pos) in query.iter_mut() {
-        pos.position =
-            pos.position + (target_pos.position - pos.position) * delta * target_pos.lerp_amount;
-        let len = (pos.position - target_pos.position).magnitude2();
-        if !(0.001..=100.0 * 100.0).contains(&len) {
+    for (mut pos, mut target_pos) in query.iter_mut() {
+        if !target_pos.interpolate {
+            pos.position = pos.position
+                + (target_pos.position - pos.position) * delta * target_pos.lerp_amount;
+            let len = (pos.position - target_pos.position).magnitude2();
+            if !(0.001..=100.0 * 100.0).contains(&len) {
+                pos.position = target_pos.position;
+            }
+            continue;
+        }
+
+        if target_pos.position != target_pos.last_target {
+            target_pos.last_target = target_pos.position;
+            target_pos.lerp_start = pos.position;
+            target_pos.lerp_progress = 0.0;
+        }
+        // Frame deltas are measured in thirds of a game tick
+        target_pos.lerp_progress += delta / 3.0;
+        // Never move past the target, when updates stop arriving during a
+        // lag spike the entity waits there instead of drifting off
+        let progress = (target_pos.lerp_progress / LERP_TICKS).min(1.0);
+        let start = target_pos.lerp_start;
+        let new_position = start + (target_pos.position - start) * progress;
+        // Teleports shouldn't be smoothed
+        if (target_pos.position - start).magnitude2() > 100.0 * 100.0 {
             pos.position = target_pos.position;
+        } else if pos.position != new_position {
+            pos.position = new_position;
         }
     }
 }
@@ -58,20 +99,70 @@ pub fn lerp_rotation(
         target_rot.yaw = (PI * 2.0 + target_rot.yaw) % (PI * 2.0);
         target_rot.pitch = (PI * 2.0 + target_rot.pitch) % (PI * 2.0);
 
-        let mut delta_yaw = target_rot.yaw - rot.yaw;
-        let mut delta_pitch = target_rot.pitch - rot.pitch;
-
-        if delta_yaw.abs() > PI {
-            delta_yaw = (PI - delta_yaw.abs()) * delta_yaw.signum();
+        let target = (target_rot.yaw, target_rot.pitch);
+        if target != target_rot.last_target {
+            target_rot.last_target = target;
+            target_rot.lerp_start = (rot.yaw, rot.pitch);
+            target_rot.lerp_progress = 0.0;
         }
-        if delta_pitch.abs() > PI {
-            delta_pitch = (PI - delta_pitch.abs()) * delta_pitch.signum();
+        target_rot.lerp_progress += delta / 3.0;
+        let progress = (target_rot.lerp_progress / LERP_TICKS).min(1.0);
+
+        let (start_yaw, start_pitch) = target_rot.lerp_start;
+        let yaw = start_yaw + shortest_angle(start_yaw, target.0) * progress;
+        let pitch = start_pitch + shortest_angle(start_pitch, target.1) * progress;
+        let rotation = Rotation::new(
+            (PI * 2.0 + yaw) % (PI * 2.0),
+            (PI * 2.0 + pitch) % (PI * 2.0),
+        );
+        if *rot != rotation {
+            *rot = rotation;
         }
+    }
+}
 
-        rot.yaw += delta_yaw * 0.2 * delta;
-        rot.pitch += delta_pitch * 0.2 * delta;
-        rot.yaw = (PI * 2.0 + rot.yaw) % (PI * 2.0);
-        rot.pitch = (PI * 2.0 + rot.pitch) % (PI * 2.0);
+/// Difference between two angles, going the shortest way around.
+pub fn shortest_angle(from: f64, to: f64) -> f64 {
+    use std::f64::consts::PI;
+    let mut diff = (to - from) % (PI * 2.0);
+    if diff.abs() > PI {
+        diff -= PI * 2.0 * diff.signum();
+    }
+    diff
+}
+
+/// How far the head can look to the side before the body turns with it.
+const BODY_TURN_THRESHOLD: f64 = 50.0 * std::f64::consts::PI / 180.0;
+
+pub fn update_head_rotation(
+    game_info: Res<GameInfo>,
+    mut query: Query<(&mut HeadRotation, &Position)>,
+) {
+    use std::f64::consts::PI;
+    let delta = game_info.delta.min(5.0);
+    // Frame deltas are measured in thirds of a game tick
+    let ticks = delta / 3.0;
+    for (mut head, position) in query.iter_mut() {
+        let turn = shortest_angle(head.yaw, head.target_yaw) * (ticks / LERP_TICKS).min(1.0);
+        head.yaw = (PI * 2.0 + head.yaw + turn) % (PI * 2.0);
+
+        let moved = head
+            .last_position
+            .map_or(Vector3::new(0.0, 0.0, 0.0), |last| position.position - last);
+        head.last_position = Some(position.position);
+        // Vanilla's threshold for walking, 0.05 blocks a tick
+        let moving = moved.x * moved.x + moved.z * moved.z > 0.0025 * ticks * ticks;
+
+        let diff = head.relative_yaw();
+        let turn = if moving {
+            // Walking brings the body round to face where the head looks
+            diff * (delta * 0.1).min(1.0)
+        } else if diff.abs() > BODY_TURN_THRESHOLD {
+            diff - BODY_TURN_THRESHOLD * diff.signum()
+        } else {
+            0.0
+        };
+        head.body_yaw = (PI * 2.0 + head.body_yaw + turn) % (PI * 2.0);
     }
 }
 
@@ -123,7 +214,7 @@ pub fn apply_digging(
     conn: Res<ConnResource>,
     inventory: Res<InventoryContextResource>,
     commands: Commands,
-    mut query: Query<(&MouseButtons, &GameMode, &mut Digging)>,
+    mut query: Query<(&MouseButtons, &GameMode, &mut Digging, Option<&Gravity>)>,
     mut effect_query: Query<&mut BlockBreakEffect>,
 ) {
     use crate::server::target::{test_block, trace_ray};
@@ -134,9 +225,15 @@ pub fn apply_digging(
     let conn = &conn.0;
     let inventory = &inventory.0;
 
+    // Only the local player can dig
+    let reach = query
+        .iter()
+        .next()
+        .map_or(GameMode::Survival, |(_, game_mode, _, _)| *game_mode)
+        .reach_distance();
     let target = trace_ray(
         world.as_ref(),
-        4.0,
+        reach,
         renderer.camera.lock().pos.to_vec(),
         renderer.view_vector.lock().cast().unwrap(),
         test_block,
@@ -152,8 +249,10 @@ pub fn apply_digging(
 
     let mut system = ApplyDigging::new(target, conn.clone(), commands, tool);
 
-    for (mouse_buttons, game_mode, mut digging) in query.iter_mut() {
+    for (mouse_buttons, game_mode, mut digging, gravity) in query.iter_mut() {
         if game_mode.can_interact_with_world() {
+            let on_ground = gravity.map(|gravity| gravity.on_ground).unwrap_or(true);
+            let instant = game_mode.breaks_instantly();
             if let Some(effect) = digging.effect {
                 if let Ok(mut effect) = effect_query.get_mut(effect) {
                     system.update(
@@ -161,15 +260,27 @@ pub fn apply_digging(
                         digging.as_mut(),
                         Some(effect.as_mut()),
                         world,
+                        on_ground,
+                        instant,
                     );
                     continue;
                 }
             }
-            system.update(mouse_buttons, digging.as_mut(), None, world);
+            system.update(
+                mouse_buttons,
+                digging.as_mut(),
+                None,
+                world,
+                on_ground,
+                instant,
+            );
         }
     }
 }
 
+/// The number of ticks to wait between two instantly broken blocks.
+const INSTANT_BREAK_COOLDOWN: u8 = 5;
+
 struct ApplyDigging<'w, 's> {
     target: Option<(shared::Position, Block, Direction, Vector3<f64>)>,
     conn: Arc<RwLock<Option<protocol::Conn>>>,
@@ -198,11 +309,23 @@ impl ApplyDigging<'_, '_> {
         digging: &mut Digging,
         effect: Option<&mut BlockBreakEffect>,
         world: &Arc<World>,
+        on_ground: bool,
+        instant: bool,
     ) {
+        // Instantly broken blocks are rate limited, otherwise holding the
+        // button would break a block every tick.
+        if digging.cooldown > 0 {
+            digging.cooldown -= 1;
+        }
+
         // Move the previous current value into last, and then calculate the
         // new current value.
         std::mem::swap(&mut digging.last, &mut digging.current);
-        digging.current = self.next_state(&digging.last, mouse_buttons, self.target);
+        digging.current = if digging.cooldown > 0 {
+            None
+        } else {
+            self.next_state(&digging.last, mouse_buttons, self.target, instant)
+        };
 
         // Send required digging packets
         match (&digging.last, &mut digging.current) {
@@ -212,6 +335,9 @@ impl ApplyDigging<'_, '_> {
                 if current.is_finished(&self.tool) == DiggingFinishState::FinishedInstant {
                     current.finished = true;
                     self.finish_digging(current, &mut digging.effect, world, false);
+                    if current.instant {
+                        digging.cooldown = INSTANT_BREAK_COOLDOWN;
+                    }
                 }
             }
             // Cancel the previous digging operation.
@@ -240,26 +366,36 @@ impl ApplyDigging<'_, '_> {
                 if current.is_finished(&self.tool) == DiggingFinishState::FinishedInstant {
                     current.finished = true;
                     self.finish_digging(current, &mut digging.effect, world, false);
+                    if current.instant {
+                        digging.cooldown = INSTANT_BREAK_COOLDOWN;
+                    }
                 }
             }
             // Finish the new digging operation.
-            (Some(_), Some(current)) => match current.is_finished(&self.tool) {
-                DiggingFinishState::Finished => {
-                    current.finished = true;
-                    self.finish_digging(current, &mut digging.effect, world, true);
+            (Some(_), Some(current)) => {
+                if !current.finished {
+                    current.advance(&self.tool, on_ground);
                 }
-                DiggingFinishState::FinishedInstant => {
-                    // noop as the breaking should already have been performed at this point
+                match current.is_finished(&self.tool) {
+                    DiggingFinishState::Finished => {
+                        current.finished = true;
+                        self.finish_digging(current, &mut digging.effect, world, true);
+                    }
+                    DiggingFinishState::FinishedInstant => {
+                        // noop as the breaking should already have been performed at this point
+                    }
+                    DiggingFinishState::NotFinished => {}
                 }
-                DiggingFinishState::NotFinished => {}
-            },
+            }
             _ => {}
         }
 
         if let Some(current) = &digging.current {
             // Update the block break animation progress.
             if let Some(effect) = effect {
-                effect.update_ratio(current.get_ratio(&self.tool));
+                if current.is_breakable(&self.tool) {
+                    effect.update_ratio(current.get_ratio(&self.tool));
+                }
             }
             self.swing_arm();
         }
@@ -275,6 +411,7 @@ impl ApplyDigging<'_, '_> {
             shared::Direction,
             Vector3<f64>,
         )>,
+        instant: bool,
     ) -> Option<DiggingState> {
         if !mouse_buttons.left {
             return None;
@@ -282,26 +419,16 @@ impl ApplyDigging<'_, '_> {
 
         match (last, target) {
             // Started digging
-            (None, Some((position, block, face, _))) => Some(DiggingState {
-                block,
-                face,
-                position,
-                start: std::time::Instant::now(),
-                finished: false,
-            }),
+            (None, Some((position, block, face, _))) => {
+                Some(DiggingState::new(block, position, face, instant))
+            }
             (Some(current), Some((position, block, face, ..))) => {
                 if position == current.position {
                     // Continue digging
                     last.clone()
                 } else {
                     // Start digging a different block.
-                    Some(DiggingState {
-                        block,
-                        face,
-                        position,
-                        start: std::time::Instant::now(),
-                        finished: false,
-                    })
+                    Some(DiggingState::new(block, position, face, instant))
                 }
             }
             // Not pointing at any target
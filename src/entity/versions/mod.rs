@@ -46,3 +46,42 @@ pub fn to_entity_type(id: i16, version: Version) -> EntityType {
         _ => EntityType::Unknown,
     }
 }
+
+/// Maps the type sent in SpawnObject, which used its own list of ids until
+/// 1.14 merged it into the entity type registry.
+pub fn object_to_entity_type(id: i16, version: Version) -> EntityType {
+    if version >= Version::V1_14 {
+        return to_entity_type(id, version);
+    }
+    match id {
+        1 => EntityType::Boat,
+        2 => EntityType::DroppedItem,
+        3 => EntityType::AreaEffectCloud,
+        10 => EntityType::Minecart,
+        50 => EntityType::PrimedTnt,
+        51 => EntityType::EnderCrystal,
+        60 => EntityType::Arrow,
+        61 => EntityType::Snowball,
+        62 => EntityType::Egg,
+        63 => EntityType::Fireball,
+        64 => EntityType::SmallFireball,
+        65 => EntityType::EnderPearl,
+        66 => EntityType::WitherSkull,
+        67 => EntityType::ShulkerBullet,
+        68 => EntityType::LlamaSpit,
+        70 => EntityType::FallingBlock,
+        71 => EntityType::ItemFrame,
+        72 => EntityType::EnderSignal,
+        73 => EntityType::SplashPotion,
+        75 => EntityType::ThrownExpBottle,
+        76 => EntityType::Firework,
+        77 => EntityType::LeashHitch,
+        78 => EntityType::ArmorStand,
+        79 => EntityType::EvokerFangs,
+        90 => EntityType::FishingHook,
+        91 => EntityType::SpectralArrow,
+        93 => EntityType::DragonFireball,
+        94 => EntityType::Trident,
+        _ => EntityType::Unknown,
+    }
+}
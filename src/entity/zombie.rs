@@ -1,4 +1,4 @@
-use super::{Bounds, GameInfo, Light, Position, Rotation};
+use super::{BabyHead, Bounds, GameInfo, HeadRotation, Light, Position, Rotation, Scale};
 use crate::entity::player_like::{compute_player_model_components, PlayerLikeModelPart};
 use crate::render::model;
 use crate::render::Renderer;
@@ -63,13 +63,21 @@ pub fn added_zombie(
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn update_zombie(
     game_info: Res<GameInfo>,
     renderer: Res<RendererResource>,
-    mut query: Query<(&mut ZombieModel, &Position, &Rotation, &Light)>,
+    mut query: Query<(
+        &mut ZombieModel,
+        &Position,
+        &Rotation,
+        Option<&HeadRotation>,
+        &Light,
+        &Scale,
+    )>,
 ) {
     let renderer = &renderer.0;
-    for (mut zombie_model, position, rotation, light) in query.iter_mut() {
+    for (mut zombie_model, position, rotation, head, light, scale) in query.iter_mut() {
         use std::f32::consts::PI;
         use std::f64::consts::PI as PI64;
         let delta = game_info.delta;
@@ -87,11 +95,17 @@ pub fn update_zombie(
                 -position.position.y as f32,
                 position.position.z as f32,
             );
+            // Only the head turns to look around, the body follows behind
+            let (body_yaw, head_yaw) = head.map_or((rotation.yaw, 0.0), |head| {
+                (head.body_yaw, head.relative_yaw())
+            });
             let offset_matrix = Matrix4::from(Decomposed {
                 scale: 1.0,
-                rot: Quaternion::from_angle_y(Rad(PI + rotation.yaw as f32)),
+                rot: Quaternion::from_angle_y(Rad(PI + body_yaw as f32)),
                 disp: offset,
             });
+            let head_matrix = offset_matrix * scale.part_matrix(Some(BabyHead::HUMANOID));
+            let offset_matrix = offset_matrix * scale.part_matrix(None);
 
             // TODO This sucks
             /* if zombie_model.has_name_tag {
@@ -105,10 +119,11 @@ pub fn update_zombie(
                 });
             }*/
 
-            mdl.matrix[PlayerLikeModelPart::Head as usize] = offset_matrix
+            mdl.matrix[PlayerLikeModelPart::Head as usize] = head_matrix
                 * Matrix4::from(Decomposed {
                     scale: 1.0,
-                    rot: Quaternion::from_angle_x(Rad(-rotation.pitch as f32)),
+                    rot: Quaternion::from_angle_y(Rad(head_yaw as f32))
+                        * Quaternion::from_angle_x(Rad(-rotation.pitch as f32)),
                     disp: Vector3::new(0.0, -12.0 / 16.0 - 12.0 / 16.0, 0.0),
                 });
             mdl.matrix[PlayerLikeModelPart::Body as usize] = offset_matrix
@@ -0,0 +1,68 @@
+//! Caps the frame rate without the uneven pacing of a plain sleep.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long before the deadline to stop sleeping and start spinning, as
+/// sleeps can overshoot by about a scheduler tick.
+const SPIN_MARGIN: Duration = Duration::from_millis(2);
+
+/// Waits out the rest of each frame against a fixed schedule of deadlines,
+/// so an overslept frame is made up for by the next one instead of every
+/// frame running long.
+pub struct FrameLimiter {
+    deadline: Option<Instant>,
+    /// Ignores the cap, for timing how fast frames can go.
+    uncapped: bool,
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FrameLimiter {
+    pub fn new() -> Self {
+        Self {
+            deadline: None,
+            uncapped: false,
+        }
+    }
+
+    /// A limiter that never waits, whatever the cap is set to.
+    pub fn uncapped() -> Self {
+        Self {
+            deadline: None,
+            uncapped: true,
+        }
+    }
+
+    /// Forgets the schedule, for when the frame rate isn't capped.
+    pub fn reset(&mut self) {
+        self.deadline = None;
+    }
+
+    /// Blocks until the next frame is due at `fps` frames per second.
+    pub fn wait(&mut self, fps: u32) {
+        if self.uncapped {
+            return;
+        }
+        let interval = Duration::from_secs(1) / fps.max(1);
+        let now = Instant::now();
+        let deadline = match self.deadline {
+            // Start over if we fell more than a frame behind, rather than
+            // rushing through frames to catch up
+            Some(deadline) if deadline + interval > now => deadline + interval,
+            _ => now + interval,
+        };
+        self.deadline = Some(deadline);
+
+        if let Some(sleep) = deadline.checked_duration_since(now + SPIN_MARGIN) {
+            thread::sleep(sleep);
+        }
+        while Instant::now() < deadline {
+            thread::yield_now();
+        }
+    }
+}
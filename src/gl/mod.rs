@@ -912,6 +912,30 @@ impl Drop for Framebuffer {
     }
 }
 
+/// Reads the pixels of the bound read framebuffer into `pixels`, starting
+/// from the bottom left corner.
+pub fn read_pixels(
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    format: TextureFormat,
+    ty: Type,
+    pixels: &mut [u8],
+) {
+    unsafe {
+        glow_context().read_pixels(
+            x,
+            y,
+            width,
+            height,
+            format,
+            ty,
+            PixelPackData::Slice(pixels),
+        );
+    }
+}
+
 pub fn unbind_framebuffer() {
     unsafe {
         glow_context().bind_framebuffer(gl::FRAMEBUFFER, None);
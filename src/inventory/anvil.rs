@@ -3,13 +3,13 @@ use crate::inventory::{Inventory, InventoryType, Item};
 use crate::render::hud::Hud;
 use crate::render::inventory::InventoryWindow;
 use crate::render::Renderer;
+use crate::server::plugin_messages;
 use crate::ui;
 use crate::ui::{Container, HAttach, VAttach};
 use leafish_protocol::types::GameMode;
 use log::warn;
 use std::sync::Arc;
 
-use leafish_protocol::protocol::packet;
 use parking_lot::RwLock;
 
 const WINDOW_WIDTH: i32 = 176;
@@ -282,14 +282,12 @@ impl Inventory for AnvilInventory {
                 .clone();
             if current_textbox_content != self.last_name {
                 self.last_name = current_textbox_content;
-                inventory_window
-                    .inventory_context
-                    .write()
-                    .get_conn()
-                    .write_packet(packet::play::serverbound::NameItem {
-                        item_name: self.last_name.clone(),
-                    })
-                    .expect("couldn't send anvil rename packet");
+                let mut conn = inventory_window.inventory_context.write().get_conn();
+                plugin_messages::ItemName {
+                    name: self.last_name.clone(),
+                }
+                .write_to(&mut conn)
+                .expect("couldn't send anvil rename packet");
             }
         } else {
             // hide anvil rename bar
@@ -433,7 +433,7 @@ enum ButtonState {
 #[derive(Clone, Copy, Debug, PartialEq)]
 #[repr(u8)]
 #[allow(dead_code)]
-enum Effect {
+pub enum Effect {
     Speed,
     Slowness,
     Haste,
@@ -470,7 +470,44 @@ enum Effect {
 }
 
 impl Effect {
-    fn get_texture(&self) -> String {
+    /// Every effect, in the order of their IDs starting at 1.
+    const ALL: [Effect; 33] = [
+        Effect::Speed,
+        Effect::Slowness,
+        Effect::Haste,
+        Effect::MiningFatigue,
+        Effect::Strength,
+        Effect::InstantHealth,
+        Effect::InstantDamage,
+        Effect::JumpBoost,
+        Effect::Nausea,
+        Effect::Regeneration,
+        Effect::Resistance,
+        Effect::FireResistance,
+        Effect::WaterBreathing,
+        Effect::Invisibility,
+        Effect::Blindness,
+        Effect::NightVision,
+        Effect::Hunger,
+        Effect::Weakness,
+        Effect::Poison,
+        Effect::Wither,
+        Effect::HealthBoost,
+        Effect::Absorption,
+        Effect::Saturation,
+        Effect::Glowing,
+        Effect::Levitation,
+        Effect::Luck,
+        Effect::BadLuck,
+        Effect::SlowFalling,
+        Effect::ConduitPower,
+        Effect::DolphinsGrace,
+        Effect::BadOmen,
+        Effect::HerooftheVillage,
+        Effect::Darkness,
+    ];
+
+    pub fn get_texture(&self) -> String {
         match self {
             Effect::MiningFatigue => "minecraft:mob_effect/minig_fatigue".to_owned(),
             Effect::InstantHealth => "minecraft:mob_effect/instant_health".to_owned(),
@@ -494,15 +531,14 @@ impl Effect {
 impl TryFrom<i16> for Effect {
     type Error = &'static str;
     fn try_from(value: i16) -> Result<Self, Self::Error> {
-        // only allow the 33 IDs that map to the 33 effects
-        // if more effects are added this number should be increased
-        if (1..34).contains(&value) {
-            Ok(unsafe { std::mem::transmute::<u8, Effect>(value as u8 - 1) })
-        } else if value == -1 {
-            Err("no effect")
-        } else {
-            Err("tried to set a effect that is unknown")
+        if value == -1 {
+            return Err("no effect");
         }
+        usize::try_from(value)
+            .ok()
+            .and_then(|id| Effect::ALL.get(id.checked_sub(1)?))
+            .copied()
+            .ok_or("tried to set a effect that is unknown")
     }
 }
 
@@ -149,6 +149,7 @@ impl Inventory for ChestInventory {
             "container.chest" => "Chest",
             "container.chestDouble" => "Large Chest",
             "container.enderchest" => "Ender Chest",
+            "container.shulkerBox" => "Shulker Box",
             name => name,
         };
         basic_text_elements.push(
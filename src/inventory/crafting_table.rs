@@ -5,6 +5,7 @@ use crate::render::inventory::InventoryWindow;
 use crate::render::Renderer;
 use crate::ui;
 use crate::ui::{Container, HAttach, VAttach};
+use std::ops::Range;
 use std::sync::Arc;
 
 use parking_lot::RwLock;
@@ -24,11 +25,9 @@ impl CraftingTableInventory {
         slots.set_child(base_slots, (8, 84), (10..46).collect());
 
         // Crafting output
-        // TODO: Use different click rules for crafting output
         slots.add_slot(0, (124, 35));
 
         // Crafting input
-        // TODO: Reduce the count on each of these slots when output is taken
         for x in 0..3 {
             for y in 0..3 {
                 let slot_id = (1 + x + y * 3) as u16;
@@ -153,4 +152,8 @@ impl Inventory for CraftingTableInventory {
     fn ty(&self) -> InventoryType {
         InventoryType::CraftingTable
     }
+
+    fn crafting_slots(&self) -> Option<(u16, Range<u16>)> {
+        Some((0, 1..10))
+    }
 }
@@ -0,0 +1,430 @@
+use crate::inventory::material::versions::to_material;
+use crate::inventory::player_inventory::PlayerInventory;
+use crate::inventory::slot_mapping::SlotMapping;
+use crate::inventory::{Inventory, InventoryType, Item, Material};
+use crate::render::hud::Hud;
+use crate::render::inventory::InventoryWindow;
+use crate::render::Renderer;
+use crate::ui;
+use crate::ui::{Container, HAttach, VAttach};
+use leafish_protocol::item::Stack;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use parking_lot::{Mutex, RwLock};
+use shared::Version;
+
+const WINDOW_WIDTH: i32 = 195;
+const WINDOW_HEIGHT: i32 = 136;
+const TAB_WIDTH: i32 = 28;
+const TAB_HEIGHT: i32 = 32;
+const SLOT_SIZE: i32 = 18;
+const COLUMNS: usize = 9;
+const ROWS: usize = 5;
+
+/// The hotbar uses the same slot ids as in the player inventory, as these are
+/// the ids the creative inventory action packet expects.
+const HOTBAR_START: u16 = 36;
+const PALETTE_START: u16 = 100;
+const TAB_START: u16 = 200;
+
+/// The highest item id used by any supported version.
+const MAX_ITEM_ID: u16 = 2267;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreativeTab {
+    BuildingBlocks,
+    Tools,
+    All,
+}
+
+impl CreativeTab {
+    const VALUES: [CreativeTab; 3] = [
+        CreativeTab::BuildingBlocks,
+        CreativeTab::Tools,
+        CreativeTab::All,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            CreativeTab::BuildingBlocks => "Building Blocks",
+            CreativeTab::Tools => "Tools",
+            CreativeTab::All => "All Items",
+        }
+    }
+
+    fn icon(&self) -> Material {
+        match self {
+            CreativeTab::BuildingBlocks => Material::Stone,
+            CreativeTab::Tools => Material::IronPickaxe,
+            CreativeTab::All => Material::Compass,
+        }
+    }
+
+    fn contains(&self, item: &Item, version: Version) -> bool {
+        match self {
+            CreativeTab::BuildingBlocks => {
+                item.material.as_tool().is_none()
+                    && item.material.is_placable_block(version, item.stack.id)
+            }
+            CreativeTab::Tools => item.material.as_tool().is_some(),
+            CreativeTab::All => true,
+        }
+    }
+}
+
+/// What a slot of the creative inventory is used for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreativeSlot {
+    /// A tab selector at the top of the window.
+    Tab(usize),
+    /// An item of the currently selected tab, these are never consumed.
+    Palette,
+    /// A hotbar slot of the player inventory.
+    Hotbar,
+}
+
+pub struct CreativeInventory {
+    slots: SlotMapping,
+    player_inventory: Arc<RwLock<PlayerInventory>>,
+    version: Version,
+    client_state_id: i16,
+    /// Every item known for the current version.
+    items: Arc<Vec<Item>>,
+    /// The items shown in the selected tab.
+    tab_items: Vec<Item>,
+    tab: usize,
+    /// The first row of the tab that is visible.
+    row: usize,
+    dirty: bool,
+}
+
+impl CreativeInventory {
+    pub fn new(
+        version: Version,
+        renderer: &Arc<Renderer>,
+        player_inventory: Arc<RwLock<PlayerInventory>>,
+    ) -> Self {
+        let mut slots = SlotMapping::new((WINDOW_WIDTH, WINDOW_HEIGHT));
+
+        // Tab items
+        for tab in 0..CreativeTab::VALUES.len() {
+            slots.add_slot(
+                TAB_START + tab as u16,
+                (tab as i32 * (TAB_WIDTH + 1) + 6, 9 - (TAB_HEIGHT - 4)),
+            );
+        }
+
+        // Item grid
+        for y in 0..ROWS as i32 {
+            for x in 0..COLUMNS as i32 {
+                slots.add_slot(
+                    PALETTE_START + (x + y * COLUMNS as i32) as u16,
+                    (9 + x * SLOT_SIZE, 18 + y * SLOT_SIZE),
+                );
+            }
+        }
+
+        // Hotbar
+        for x in 0..COLUMNS as i32 {
+            slots.add_slot(HOTBAR_START + x as u16, (9 + x * SLOT_SIZE, 112));
+        }
+
+        slots.update_icons(renderer, (0, 0), None);
+
+        let mut inventory = Self {
+            slots,
+            player_inventory,
+            version,
+            client_state_id: 0,
+            items: all_items(version),
+            tab_items: vec![],
+            tab: 0,
+            row: 0,
+            dirty: false,
+        };
+        for (i, tab) in CreativeTab::VALUES.iter().enumerate() {
+            let icon = Item {
+                stack: Stack {
+                    count: 1,
+                    ..Default::default()
+                },
+                material: tab.icon(),
+            };
+            inventory.slots.set_item(TAB_START + i as u16, Some(icon));
+        }
+        inventory.select_tab(0);
+        inventory
+    }
+
+    pub fn slot_type(&self, slot_id: u16) -> CreativeSlot {
+        if slot_id >= TAB_START {
+            CreativeSlot::Tab((slot_id - TAB_START) as usize)
+        } else if slot_id >= PALETTE_START {
+            CreativeSlot::Palette
+        } else {
+            CreativeSlot::Hotbar
+        }
+    }
+
+    pub fn select_tab(&mut self, tab: usize) {
+        let tab = tab.min(CreativeTab::VALUES.len() - 1);
+        let version = self.version;
+        self.tab_items = self
+            .items
+            .iter()
+            .filter(|item| CreativeTab::VALUES[tab].contains(item, version))
+            .cloned()
+            .collect();
+        self.tab = tab;
+        self.row = 0;
+        self.update_palette();
+    }
+
+    fn max_row(&self) -> usize {
+        let rows = self.tab_items.len().div_ceil(COLUMNS);
+        rows.saturating_sub(ROWS)
+    }
+
+    fn update_palette(&mut self) {
+        for i in 0..COLUMNS * ROWS {
+            let item = self.tab_items.get(self.row * COLUMNS + i).cloned();
+            self.slots.set_item(PALETTE_START + i as u16, item);
+        }
+        self.dirty = true;
+    }
+
+    fn draw_background(
+        &self,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        inventory_window: &mut InventoryWindow,
+    ) {
+        let basic_elements = inventory_window.elements.get_mut(0).unwrap();
+        basic_elements.clear();
+
+        let center = renderer.screen_data.read().center();
+        let icon_scale = Hud::icon_scale(renderer);
+        let left = center.0 as f64 - icon_scale * WINDOW_WIDTH as f64 / 2.0;
+        let top = center.1 as f64 - icon_scale * WINDOW_HEIGHT as f64 / 2.0;
+
+        let tab_element = |tab: usize, ui_container: &mut Container| {
+            let selected = if tab == self.tab { TAB_HEIGHT } else { 0 };
+            ui::ImageBuilder::new()
+                .texture_coords((
+                    (tab as i32 * TAB_WIDTH) as f64,
+                    selected as f64,
+                    TAB_WIDTH as f64,
+                    TAB_HEIGHT as f64,
+                ))
+                .position(
+                    left + icon_scale * (tab as i32 * (TAB_WIDTH + 1)) as f64,
+                    top - icon_scale * (TAB_HEIGHT - 4) as f64,
+                )
+                .alignment(VAttach::Top, HAttach::Left)
+                .size(
+                    icon_scale * TAB_WIDTH as f64,
+                    icon_scale * TAB_HEIGHT as f64,
+                )
+                .texture("minecraft:gui/container/creative_inventory/tabs")
+                .create(ui_container)
+        };
+
+        // The selected tab overlaps the window, all others are behind it.
+        for tab in 0..CreativeTab::VALUES.len() {
+            if tab != self.tab {
+                basic_elements.push(tab_element(tab, ui_container));
+            }
+        }
+
+        // Window texture
+        basic_elements.push(
+            ui::ImageBuilder::new()
+                .texture_coords((0.0, 0.0, WINDOW_WIDTH as f64, WINDOW_HEIGHT as f64))
+                .position(left, top)
+                .alignment(VAttach::Top, HAttach::Left)
+                .size(
+                    icon_scale * WINDOW_WIDTH as f64,
+                    icon_scale * WINDOW_HEIGHT as f64,
+                )
+                .texture("minecraft:gui/container/creative_inventory/tab_items")
+                .create(ui_container),
+        );
+
+        basic_elements.push(tab_element(self.tab, ui_container));
+
+        // Scrollbar
+        let scroll = if self.max_row() == 0 {
+            0.0
+        } else {
+            self.row as f64 / self.max_row() as f64
+        };
+        let scrollable = self.max_row() > 0;
+        basic_elements.push(
+            ui::ImageBuilder::new()
+                .texture_coords((if scrollable { 232.0 } else { 244.0 }, 0.0, 12.0, 15.0))
+                .position(
+                    left + icon_scale * 175.0,
+                    top + icon_scale * (18.0 + (112.0 - 18.0 - 17.0) * scroll),
+                )
+                .alignment(VAttach::Top, HAttach::Left)
+                .size(icon_scale * 12.0, icon_scale * 15.0)
+                .texture("minecraft:gui/container/creative_inventory/tabs")
+                .create(ui_container),
+        );
+
+        // Title text
+        let basic_text_elements = inventory_window.text_elements.get_mut(0).unwrap();
+        basic_text_elements.clear();
+        basic_text_elements.push(
+            ui::TextBuilder::new()
+                .alignment(VAttach::Top, HAttach::Left)
+                .scale_x(icon_scale / 2.0)
+                .scale_y(icon_scale / 2.0)
+                .position(left + icon_scale * 8.0, top + icon_scale * 6.0)
+                .text(CreativeTab::VALUES[self.tab].title())
+                .colour((64, 64, 64, 255))
+                .shadow(false)
+                .create(ui_container),
+        );
+    }
+}
+
+/// The version the item list was last collected for, and the list.
+static ALL_ITEMS: Mutex<Option<(Version, Arc<Vec<Item>>)>> = Mutex::new(None);
+
+/// One item of every material the given version knows about, collected
+/// once per version.
+fn all_items(version: Version) -> Arc<Vec<Item>> {
+    let mut all_items = ALL_ITEMS.lock();
+    match all_items.as_ref() {
+        Some((items_version, items)) if *items_version == version => items.clone(),
+        _ => {
+            let items = Arc::new(collect_items(version));
+            *all_items = Some((version, items.clone()));
+            items
+        }
+    }
+}
+
+fn collect_items(version: Version) -> Vec<Item> {
+    // Before 1.13, variants of a material are told apart by their damage value
+    let damages = if version < Version::V1_13 {
+        0..16
+    } else {
+        0..1
+    };
+    let mut items: Vec<Item> = vec![];
+    let mut seen = HashSet::new();
+    for id in 1..=MAX_ITEM_ID {
+        for damage in damages.clone() {
+            let material = to_material(id, Some(damage), None, version);
+            if material == Material::Air || !seen.insert(material) {
+                continue;
+            }
+            items.push(Item {
+                stack: Stack {
+                    id: id as isize,
+                    count: 1,
+                    damage: if version < Version::V1_13 {
+                        Some(damage)
+                    } else {
+                        None
+                    },
+                    ..Default::default()
+                },
+                material,
+            });
+        }
+    }
+    items
+}
+
+impl Inventory for CreativeInventory {
+    fn size(&self) -> u16 {
+        self.slots.size()
+    }
+
+    fn id(&self) -> i32 {
+        // The creative inventory is a view on the player inventory
+        0
+    }
+
+    fn get_client_state_id(&self) -> i16 {
+        self.client_state_id
+    }
+
+    fn set_client_state_id(&mut self, client_state_id: i16) {
+        self.client_state_id = client_state_id;
+    }
+
+    fn get_item(&self, slot_id: u16) -> Option<Item> {
+        self.slots.get_item(slot_id)
+    }
+
+    fn set_item(&mut self, slot_id: u16, item: Option<Item>) {
+        if self.slot_type(slot_id) == CreativeSlot::Hotbar {
+            self.player_inventory
+                .write()
+                .set_item(slot_id, item.clone());
+        }
+        self.slots.set_item(slot_id, item);
+    }
+
+    fn get_slot(&self, x: f64, y: f64) -> Option<u16> {
+        self.slots.get_slot(x, y)
+    }
+
+    fn init(
+        &mut self,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        inventory_window: &mut InventoryWindow,
+    ) {
+        inventory_window.elements.push(vec![]); // Window texture
+        inventory_window.elements.push(vec![]); // Creative slots
+        inventory_window.text_elements.push(vec![]);
+
+        // Pick up any hotbar changes made while the window was closed
+        {
+            let player_inventory = self.player_inventory.read();
+            for slot in HOTBAR_START..HOTBAR_START + COLUMNS as u16 {
+                self.slots.set_item(slot, player_inventory.get_item(slot));
+            }
+        }
+
+        self.draw_background(renderer, ui_container, inventory_window);
+        self.slots.update_icons(renderer, (0, 0), None);
+    }
+
+    fn tick(
+        &mut self,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        inventory_window: &mut InventoryWindow,
+    ) {
+        if self.dirty {
+            self.dirty = false;
+            self.draw_background(renderer, ui_container, inventory_window);
+        }
+        self.slots.tick(renderer, ui_container, inventory_window, 1);
+    }
+
+    fn ty(&self) -> InventoryType {
+        InventoryType::Creative
+    }
+
+    fn on_scroll(&mut self, _x: f64, y: f64) {
+        let row = if y > 0.0 {
+            self.row.saturating_sub(1)
+        } else if y < 0.0 {
+            (self.row + 1).min(self.max_row())
+        } else {
+            self.row
+        };
+        if row != self.row {
+            self.row = row;
+            self.update_palette();
+        }
+    }
+}
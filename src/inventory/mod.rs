@@ -3,6 +3,7 @@ pub mod beacon;
 pub mod brewing_stand;
 pub mod chest;
 pub mod crafting_table;
+pub mod creative_inventory;
 pub mod dropper;
 pub mod enchanting_table;
 pub mod furnace;
@@ -16,11 +17,12 @@ use crate::inventory::beacon::BeaconInventory;
 use crate::inventory::brewing_stand::BrewingStandInventory;
 use crate::inventory::chest::ChestInventory;
 use crate::inventory::crafting_table::CraftingTableInventory;
+use crate::inventory::creative_inventory::{CreativeInventory, CreativeSlot};
 use crate::inventory::dropper::DropperInventory;
 use crate::inventory::enchanting_table::EnchantmentTableInventory;
 use crate::inventory::furnace::FurnaceInventory;
 use crate::inventory::grindstone::GrindStoneInventory;
-use crate::inventory::player_inventory::PlayerInventory;
+use crate::inventory::player_inventory::{PlayerInventory, OFF_HAND_SLOT};
 use crate::inventory::slot_mapping::SlotMapping;
 use crate::render::hud::{Hud, HudContext};
 use crate::render::inventory::InventoryWindow;
@@ -32,9 +34,11 @@ use leafish_protocol::format::Component;
 use leafish_protocol::item::Stack;
 use leafish_protocol::protocol::packet::InventoryOperation;
 use leafish_protocol::protocol::{packet, Conn};
+use leafish_protocol::translate;
 use log::warn;
 use parking_lot::RwLock;
 use shared::Version;
+use std::ops::Range;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
@@ -93,6 +97,14 @@ pub trait Inventory {
 
     // for handeling the WindowProperty packet in inventorys that need it
     fn handle_property_packet(&mut self, _property: i16, _value: i16) {}
+
+    fn on_scroll(&mut self, _x: f64, _y: f64) {}
+
+    /// The result slot and the input slots of this window's crafting grid,
+    /// if it has one. The server computes the result from the inputs.
+    fn crafting_slots(&self) -> Option<(u16, Range<u16>)> {
+        None
+    }
 }
 
 pub fn inventory_from_type(
@@ -158,11 +170,18 @@ pub fn inventory_from_type(
         InventoryType::Grindstone => Arc::new(RwLock::new(GrindStoneInventory::new(
             renderer, base_slots, id,
         ))),
+        // Shulker boxes share the layout of a single chest
+        InventoryType::ShulkerBox => Arc::new(RwLock::new(ChestInventory::new(
+            renderer,
+            base_slots,
+            3,
+            title.to_string(),
+            id,
+        ))),
         /*
         InventoryType::Lectern => {}
         InventoryType::Loom => {}
         InventoryType::Merchant => {}
-        InventoryType::ShulkerBox => {}
         InventoryType::SmithingTable => {}
         InventoryType::CartographyTable => {}
         InventoryType::Stonecutter => {}
@@ -208,13 +227,21 @@ pub struct InventoryContext {
     pub safe_inventory: Option<Arc<RwLock<dyn Inventory + Send + Sync>>>,
     pub has_inv_open: bool,
     pub player_inventory: Arc<RwLock<PlayerInventory>>,
+    pub creative_inventory: Arc<RwLock<CreativeInventory>>,
     pub base_slots: Arc<RwLock<SlotMapping>>,
     pub hud_context: Arc<RwLock<HudContext>>,
     mouse_position: Option<(f64, f64)>,
+    drag: Option<Drag>,
     conn: Arc<RwLock<Option<Conn>>>,
     dirty: bool,
 }
 
+/// Slots the held stack is being dragged over while a mouse button is held.
+struct Drag {
+    left: bool,
+    slots: Vec<u16>,
+}
+
 impl InventoryContext {
     pub fn get_conn(&self) -> Conn {
         self.conn.write().clone().unwrap()
@@ -243,20 +270,28 @@ impl InventoryContext {
             Arc::new(RwLock::new(slots))
         };
 
+        let player_inventory = Arc::new(RwLock::new(PlayerInventory::new(
+            version,
+            renderer,
+            base_slots.clone(),
+        )));
+
         Self {
             cursor: None,
             hotbar_index: 0,
             inventory: None,
             safe_inventory: None,
             has_inv_open: false,
-            player_inventory: Arc::new(RwLock::new(PlayerInventory::new(
+            creative_inventory: Arc::new(RwLock::new(CreativeInventory::new(
                 version,
                 renderer,
-                base_slots.clone(),
+                player_inventory.clone(),
             ))),
+            player_inventory,
             base_slots,
             hud_context,
             mouse_position: None,
+            drag: None,
             conn,
             dirty: false,
         }
@@ -335,6 +370,8 @@ impl InventoryContext {
             }
             if let Some((x, y)) = &self.mouse_position {
                 inventory_window.formatted_elements.clear();
+                inventory_window.tooltip_elements.clear();
+                inventory_window.tooltip_text_elements.clear();
                 if let Some(item) = self.inventory.as_ref().and_then(|inv| {
                     inv.read()
                         .get_slot(*x, *y)
@@ -353,146 +390,553 @@ impl InventoryContext {
                             .create(ui_container);
                     inventory_window.formatted_elements.push(text);
                     // TODO: add lore support
+                    if item.material.name().ends_with("ShulkerBox") {
+                        Self::draw_shulker_box_preview(
+                            &item,
+                            *x + icon_scale * 6.0,
+                            *y,
+                            &renderer,
+                            ui_container,
+                            inventory_window,
+                        );
+                    }
                 }
             }
         }
     }
 
-    #[allow(clippy::collapsible_else_if)]
+    /// Draws the items inside a shulker box as a small 9x3 grid below its
+    /// name, empty boxes don't get a preview.
+    fn draw_shulker_box_preview(
+        item: &Item,
+        x: f64,
+        y: f64,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        inventory_window: &mut InventoryWindow,
+    ) {
+        let contents = item.stack.meta.block_entity_items();
+        if contents.is_empty() {
+            return;
+        }
+        let icon_scale = Hud::icon_scale(renderer);
+        inventory_window.tooltip_elements.push(
+            ui::ImageBuilder::new()
+                .texture("leafish:solid")
+                .position(x, y)
+                .alignment(VAttach::Top, ui::HAttach::Left)
+                .size(
+                    icon_scale * (9.0 * 18.0 + 2.0),
+                    icon_scale * (3.0 * 18.0 + 2.0),
+                )
+                .colour((16, 0, 16, 230))
+                .create(ui_container),
+        );
+        for stored in contents.iter().filter(|stored| stored.slot < 27) {
+            let name = stored.id.strip_prefix("minecraft:").unwrap_or(&stored.id);
+            InventoryWindow::draw_icon(
+                (format!("item/{}", name), format!("block/{}", name)),
+                stored.count as isize,
+                x + icon_scale * (2.0 + (stored.slot % 9) as f64 * 18.0),
+                y + icon_scale * (2.0 + (stored.slot / 9) as f64 * 18.0),
+                &mut inventory_window.tooltip_elements,
+                &mut inventory_window.tooltip_text_elements,
+                ui_container,
+                renderer,
+                VAttach::Top,
+            );
+        }
+    }
+
     pub fn on_click(&mut self, left: bool, shift: bool) {
+        let is_creative = self
+            .safe_inventory
+            .as_ref()
+            .map(|inventory| inventory.read().ty() == InventoryType::Creative)
+            .unwrap_or(false);
+        if is_creative {
+            self.on_creative_click(left);
+            return;
+        }
+
+        let Some((x, y)) = self.mouse_position else {
+            return;
+        };
+        let Some(slot) = self
+            .safe_inventory
+            .as_ref()
+            .map(|inventory| inventory.read().get_slot(x, y))
+        else {
+            return;
+        };
+
+        // Pressing a button while holding an item may start dragging it over
+        // several slots, so the click only happens once it is released.
+        if let (Some(slot), Some(_), false) = (slot, &self.cursor, shift) {
+            self.drag = Some(Drag {
+                left,
+                slots: vec![slot],
+            });
+            return;
+        }
+        self.click(slot, left, shift);
+    }
+
+    pub fn on_release(&mut self, left: bool) {
+        let Some(drag) = self.drag.take() else {
+            return;
+        };
+        if drag.left != left {
+            self.drag = Some(drag);
+            return;
+        }
+        if drag.slots.len() > 1 {
+            self.distribute(drag);
+        } else {
+            self.click(drag.slots.first().copied(), left, false);
+        }
+    }
+
+    #[allow(clippy::collapsible_else_if)]
+    fn click(&mut self, slot: Option<u16>, left: bool, shift: bool) {
         if let Some(inventory) = &self.safe_inventory {
             let mut inventory = inventory.write();
 
-            if let Some((x, y)) = self.mouse_position {
-                if let Some(slot) = inventory.get_slot(x, y) {
-                    self.dirty = true;
-                    let mut item = inventory.get_item(slot);
-                    let mut conn = self.conn.write();
-                    let conn = conn.as_mut().unwrap();
+            if let Some(slot) = slot {
+                self.dirty = true;
+                let mut item = inventory.get_item(slot);
+                let mut conn = self.conn.write();
+                let conn = conn.as_mut().unwrap();
 
-                    // Send the update to the server
-                    packet::send_click_container(
-                        conn,
-                        inventory.id() as u8,
-                        slot as i16,
-                        if left {
-                            if shift {
-                                InventoryOperation::ShiftLeftClick
-                            } else {
-                                InventoryOperation::LeftClick
-                            }
+                // Send the update to the server
+                packet::send_click_container(
+                    conn,
+                    inventory.id() as u8,
+                    slot as i16,
+                    if left {
+                        if shift {
+                            InventoryOperation::ShiftLeftClick
                         } else {
-                            if shift {
-                                InventoryOperation::ShiftRightClick
-                            } else {
-                                InventoryOperation::RightClick
-                            }
-                        },
-                        inventory.get_client_state_id() as u16,
-                        item.as_ref().map(|i| i.stack.clone()),
-                    )
-                    .unwrap();
+                            InventoryOperation::LeftClick
+                        }
+                    } else {
+                        if shift {
+                            InventoryOperation::ShiftRightClick
+                        } else {
+                            InventoryOperation::RightClick
+                        }
+                    },
+                    inventory.get_client_state_id() as u16,
+                    item.as_ref().map(|i| i.stack.clone()),
+                )
+                .unwrap();
 
-                    // Simulate the operation on the inventory screen.
-                    (self.cursor, item) = match (self.cursor.clone(), item) {
-                        (Some(mut cursor), Some(mut item)) => {
-                            // Merge the cursor into a slot stack of the same
-                            // material.
-                            if item.is_stackable(&cursor) {
-                                if left {
-                                    let max = item.material.get_stack_size(conn.get_version());
-                                    let total = (item.stack.count + cursor.stack.count) as u8;
-                                    item.stack.count = total.min(max) as isize;
-
-                                    if total > max {
-                                        cursor.stack.count = (total - max) as isize;
-                                        (Some(cursor), Some(item))
-                                    } else {
-                                        (None, Some(item))
-                                    }
+                let version = conn.get_version();
+                if let Some((result, inputs)) = inventory.crafting_slots() {
+                    if slot == result {
+                        // Shift-clicking crafts as much as possible, which is
+                        // left to the server to work out.
+                        if !shift {
+                            self.cursor = Self::take_crafting_result(
+                                &mut *inventory,
+                                self.cursor.take(),
+                                result,
+                                inputs,
+                                version,
+                            );
+                        }
+                        self.hud_context
+                            .write()
+                            .dirty_slots
+                            .store(true, Ordering::Relaxed);
+                        return;
+                    }
+                }
+
+                if shift {
+                    Self::quick_move(&mut *inventory, slot, version);
+                    self.hud_context
+                        .write()
+                        .dirty_slots
+                        .store(true, Ordering::Relaxed);
+                    return;
+                }
+
+                // Simulate the operation on the inventory screen.
+                (self.cursor, item) = match (self.cursor.clone(), item) {
+                    (Some(mut cursor), Some(mut item)) => {
+                        // Merge the cursor into a slot stack of the same
+                        // material.
+                        if item.is_stackable(&cursor) {
+                            if left {
+                                let max = item.material.get_stack_size(conn.get_version());
+                                let total = (item.stack.count + cursor.stack.count) as u8;
+                                item.stack.count = total.min(max) as isize;
+
+                                if total > max {
+                                    cursor.stack.count = (total - max) as isize;
+                                    (Some(cursor), Some(item))
                                 } else {
-                                    if item.stack.count
-                                        >= item.material.get_stack_size(conn.get_version()) as isize
-                                    {
-                                        (Some(cursor), Some(item))
+                                    (None, Some(item))
+                                }
+                            } else {
+                                if item.stack.count
+                                    >= item.material.get_stack_size(conn.get_version()) as isize
+                                {
+                                    (Some(cursor), Some(item))
+                                } else {
+                                    item.stack.count += 1;
+                                    if cursor.stack.count <= 1 {
+                                        (None, Some(item))
                                     } else {
-                                        item.stack.count += 1;
-                                        if cursor.stack.count <= 1 {
-                                            (None, Some(item))
-                                        } else {
-                                            cursor.stack.count -= 1;
-                                            (Some(cursor), Some(item))
-                                        }
+                                        cursor.stack.count -= 1;
+                                        (Some(cursor), Some(item))
                                     }
                                 }
-                            } else {
-                                (Some(item), Some(cursor))
                             }
+                        } else {
+                            (Some(item), Some(cursor))
                         }
-                        (Some(cursor), None) => {
-                            if !left {
-                                let mut item = cursor.clone();
-                                item.stack.count = 1;
-                                let mut cursor = cursor.clone();
-                                cursor.stack.count -= 1;
-                                (
-                                    if cursor.stack.count > 0 {
-                                        Some(cursor)
-                                    } else {
-                                        None
-                                    },
-                                    Some(item),
-                                )
-                            } else {
-                                (None, Some(cursor))
-                            }
+                    }
+                    (Some(cursor), None) => {
+                        if !left {
+                            let mut item = cursor.clone();
+                            item.stack.count = 1;
+                            let mut cursor = cursor.clone();
+                            cursor.stack.count -= 1;
+                            (
+                                if cursor.stack.count > 0 {
+                                    Some(cursor)
+                                } else {
+                                    None
+                                },
+                                Some(item),
+                            )
+                        } else {
+                            (None, Some(cursor))
+                        }
+                    }
+                    (None, Some(mut item)) => {
+                        if !left && item.stack.count > 1 {
+                            let mut cursor = item.clone();
+                            item.stack.count /= 2;
+                            cursor.stack.count -= item.stack.count;
+                            (Some(cursor), Some(item))
+                        } else {
+                            (Some(item), None)
                         }
-                        (None, Some(mut item)) => {
-                            if !left && item.stack.count > 1 {
-                                let mut cursor = item.clone();
-                                item.stack.count /= 2;
-                                cursor.stack.count -= item.stack.count;
-                                (Some(cursor), Some(item))
+                    }
+                    (None, None) => (None, None),
+                };
+                inventory.set_item(slot, item);
+                self.hud_context
+                    .write()
+                    .dirty_slots
+                    .store(true, Ordering::Relaxed);
+            } else if let Some(cursor) = self.cursor.take() {
+                // when right clicking we don't drop the whole stack but only one item of the stack
+                if !left {
+                    let mut cursor = cursor.clone();
+                    cursor.stack.count -= 1;
+                    self.cursor = Some(cursor);
+                }
+                self.dirty = true;
+                self.hud_context
+                    .write()
+                    .dirty_slots
+                    .store(true, Ordering::Relaxed);
+                let mut conn = self.conn.write();
+                let conn = conn.as_mut().unwrap();
+
+                // Send the update to the server
+                packet::send_click_container(
+                    conn,
+                    inventory.id() as u8,
+                    -999,
+                    if left {
+                        InventoryOperation::LeftClickOutside
+                    } else {
+                        InventoryOperation::RightClickOutside
+                    },
+                    inventory.get_client_state_id() as u16,
+                    Some(cursor.stack),
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    /// Moves the stack in a slot to the other section of the window, like
+    /// shift-clicking does in vanilla. Stacks are merged into matching stacks
+    /// first, then the first empty slot is used.
+    fn quick_move(inventory: &mut dyn Inventory, slot: u16, version: Version) {
+        let Some(targets) = quick_move_targets(inventory, slot) else {
+            // The server decides where items go in windows with special
+            // slots, it sends the resulting slots back to us.
+            return;
+        };
+        let Some(mut item) = inventory.get_item(slot) else {
+            return;
+        };
+        let max = item.material.get_stack_size(version) as isize;
+        for target in targets.iter().copied() {
+            if let Some(mut other) = inventory.get_item(target) {
+                if other.is_stackable(&item) && other.stack.count < max {
+                    let moved = (max - other.stack.count).min(item.stack.count);
+                    other.stack.count += moved;
+                    item.stack.count -= moved;
+                    inventory.set_item(target, Some(other));
+                    if item.stack.count <= 0 {
+                        inventory.set_item(slot, None);
+                        return;
+                    }
+                }
+            }
+        }
+        if let Some(target) = targets
+            .into_iter()
+            .find(|target| inventory.get_item(*target).is_none())
+        {
+            inventory.set_item(target, Some(item));
+            inventory.set_item(slot, None);
+        } else {
+            inventory.set_item(slot, Some(item));
+        }
+    }
+
+    /// Picks up the crafted item onto the cursor and uses up one item of
+    /// each input. The result can only be taken if it fits onto the cursor.
+    fn take_crafting_result(
+        inventory: &mut dyn Inventory,
+        cursor: Option<Item>,
+        result: u16,
+        inputs: Range<u16>,
+        version: Version,
+    ) -> Option<Item> {
+        let Some(item) = inventory.get_item(result) else {
+            return cursor;
+        };
+        let max = item.material.get_stack_size(version) as isize;
+        let cursor = match cursor {
+            None => item,
+            Some(mut cursor)
+                if cursor.is_stackable(&item) && cursor.stack.count + item.stack.count <= max =>
+            {
+                cursor.stack.count += item.stack.count;
+                cursor
+            }
+            cursor => return cursor,
+        };
+        for input in inputs {
+            if let Some(mut ingredient) = inventory.get_item(input) {
+                ingredient.stack.count -= 1;
+                inventory.set_item(input, Some(ingredient).filter(|i| i.stack.count > 0));
+            }
+        }
+        // The server sends the result of whatever is left in the grid
+        inventory.set_item(result, None);
+        Some(cursor)
+    }
+
+    /// Spreads the held stack over the dragged slots. Dragging with the left
+    /// button splits it evenly, the right button puts one item in each slot.
+    fn distribute(&mut self, drag: Drag) {
+        let Some(inventory) = &self.safe_inventory else {
+            return;
+        };
+        let Some(mut cursor) = self.cursor.take() else {
+            return;
+        };
+        let mut inventory = inventory.write();
+        let mut conn = self.conn.write();
+        let conn = conn.as_mut().unwrap();
+        let id = inventory.id() as u8;
+        let state_id = inventory.get_client_state_id() as u16;
+        let (start, add, end) = if drag.left {
+            (
+                InventoryOperation::StartLeftDrag,
+                InventoryOperation::AddLeftDragSlot,
+                InventoryOperation::EndLeftDrag,
+            )
+        } else {
+            (
+                InventoryOperation::StartRightDrag,
+                InventoryOperation::AddRightDragSlot,
+                InventoryOperation::EndRightDrag,
+            )
+        };
+
+        packet::send_click_container(conn, id, -999, start, state_id, None).unwrap();
+        let max = cursor.material.get_stack_size(conn.get_version()) as isize;
+        let per_slot = if drag.left {
+            cursor.stack.count / drag.slots.len() as isize
+        } else {
+            1
+        };
+        for slot in drag.slots {
+            packet::send_click_container(conn, id, slot as i16, add, state_id, None).unwrap();
+            let mut item = inventory.get_item(slot).unwrap_or_else(|| {
+                let mut item = cursor.clone();
+                item.stack.count = 0;
+                item
+            });
+            let moved = per_slot.min(max - item.stack.count).min(cursor.stack.count);
+            if moved > 0 {
+                item.stack.count += moved;
+                cursor.stack.count -= moved;
+                inventory.set_item(slot, Some(item));
+            }
+        }
+        packet::send_click_container(conn, id, -999, end, state_id, None).unwrap();
+
+        if cursor.stack.count > 0 {
+            self.cursor = Some(cursor);
+        }
+        self.dirty = true;
+        self.hud_context
+            .write()
+            .dirty_slots
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Swaps the hovered slot with a hotbar slot, `number` is the pressed
+    /// number key from 1 to 9.
+    pub fn on_number_key(&mut self, number: u8) {
+        let Some((x, y)) = self.mouse_position else {
+            return;
+        };
+        let Some(inventory) = &self.safe_inventory else {
+            return;
+        };
+        let mut inventory = inventory.write();
+        if inventory.ty() == InventoryType::Creative || self.cursor.is_some() {
+            return;
+        }
+        let Some(slot) = inventory.get_slot(x, y) else {
+            return;
+        };
+        let hotbar_slot = base_slots(&*inventory).end - 9 + (number - 1) as u16;
+        let item = inventory.get_item(slot);
+        let mut conn = self.conn.write();
+        let conn = conn.as_mut().unwrap();
+        packet::send_click_container(
+            conn,
+            inventory.id() as u8,
+            slot as i16,
+            InventoryOperation::NumberKey(number),
+            inventory.get_client_state_id() as u16,
+            item.as_ref().map(|i| i.stack.clone()),
+        )
+        .unwrap();
+
+        let hotbar_item = inventory.get_item(hotbar_slot);
+        inventory.set_item(hotbar_slot, item);
+        inventory.set_item(slot, hotbar_item);
+        self.dirty = true;
+        self.hud_context
+            .write()
+            .dirty_slots
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Swaps the hovered slot with the off hand, servers only accept this
+    /// click since 1.16.
+    pub fn on_swap_hands_key(&mut self) {
+        let Some((x, y)) = self.mouse_position else {
+            return;
+        };
+        let Some(inventory) = &self.safe_inventory else {
+            return;
+        };
+        let mut inventory = inventory.write();
+        if inventory.ty() == InventoryType::Creative || self.cursor.is_some() {
+            return;
+        }
+        let Some(slot) = inventory.get_slot(x, y) else {
+            return;
+        };
+        let mut conn = self.conn.write();
+        let conn = conn.as_mut().unwrap();
+        if conn.get_version() < Version::V1_16 {
+            return;
+        }
+        let item = inventory.get_item(slot);
+        packet::send_click_container(
+            conn,
+            inventory.id() as u8,
+            slot as i16,
+            InventoryOperation::OffHandSwap,
+            inventory.get_client_state_id() as u16,
+            item.as_ref().map(|i| i.stack.clone()),
+        )
+        .unwrap();
+
+        // The off hand is only part of the player's own inventory window
+        if inventory.ty() == InventoryType::Main {
+            let off_hand = inventory.get_item(OFF_HAND_SLOT);
+            inventory.set_item(OFF_HAND_SLOT, item);
+            inventory.set_item(slot, off_hand);
+        } else {
+            let mut player_inventory = self.player_inventory.write();
+            let off_hand = player_inventory.get_item(OFF_HAND_SLOT);
+            player_inventory.set_item(OFF_HAND_SLOT, item);
+            inventory.set_item(slot, off_hand);
+        }
+        self.dirty = true;
+        self.hud_context
+            .write()
+            .dirty_slots
+            .store(true, Ordering::Relaxed);
+    }
+
+    /// Items in creative mode are created out of thin air, so instead of
+    /// clicking slots, the client tells the server the new content of a slot.
+    fn on_creative_click(&mut self, left: bool) {
+        let Some((x, y)) = self.mouse_position else {
+            return;
+        };
+        let creative = self.creative_inventory.clone();
+        let mut creative = creative.write();
+        let mut conn = self.conn.write();
+        let conn = conn.as_mut().unwrap();
+        self.dirty = true;
+
+        match creative.get_slot(x, y) {
+            Some(slot) => match creative.slot_type(slot) {
+                CreativeSlot::Tab(tab) => creative.select_tab(tab),
+                CreativeSlot::Palette => {
+                    // Putting an item back into the palette deletes it
+                    if self.cursor.take().is_none() {
+                        if let Some(mut item) = creative.get_item(slot) {
+                            item.stack.count = if left {
+                                item.material.get_stack_size(conn.get_version()) as isize
                             } else {
-                                (Some(item), None)
-                            }
+                                1
+                            };
+                            self.cursor = Some(item);
                         }
-                        (None, None) => (None, None),
-                    };
-                    inventory.set_item(slot, item);
-                    self.hud_context
-                        .write()
-                        .dirty_slots
-                        .store(true, Ordering::Relaxed);
-                } else if let Some(cursor) = self.cursor.take() {
-                    // when right clicking we don't drop the whole stack but only one item of the stack
-                    if !left {
-                        let mut cursor = cursor.clone();
-                        cursor.stack.count -= 1;
-                        self.cursor = Some(cursor);
                     }
-                    self.dirty = true;
+                }
+                CreativeSlot::Hotbar => {
+                    let item = self.cursor.take();
+                    self.cursor = creative.get_item(slot);
+                    packet::send_creative_inventory_action(
+                        conn,
+                        slot as i16,
+                        item.as_ref().map(|i| i.stack.clone()),
+                    )
+                    .unwrap();
+                    creative.set_item(slot, item);
                     self.hud_context
                         .write()
                         .dirty_slots
                         .store(true, Ordering::Relaxed);
-                    let mut conn = self.conn.write();
-                    let conn = conn.as_mut().unwrap();
-
-                    // Send the update to the server
-                    packet::send_click_container(
-                        conn,
-                        inventory.id() as u8,
-                        -999,
-                        if left {
-                            InventoryOperation::LeftClickOutside
-                        } else {
-                            InventoryOperation::RightClickOutside
-                        },
-                        inventory.get_client_state_id() as u16,
-                        Some(cursor.stack),
-                    )
-                    .unwrap();
+                }
+            },
+            None => {
+                // Dropping an item outside of the window throws it
+                if let Some(cursor) = self.cursor.take() {
+                    packet::send_creative_inventory_action(conn, -1, Some(cursor.stack)).unwrap();
                 }
             }
         }
@@ -501,6 +945,25 @@ impl InventoryContext {
     pub fn on_cursor_moved(&mut self, x: f64, y: f64) {
         self.mouse_position = Some((x, y));
         self.dirty = true;
+
+        if let (Some(drag), Some(cursor), Some(inventory)) =
+            (&mut self.drag, &self.cursor, &self.safe_inventory)
+        {
+            let inventory = inventory.read();
+            if let Some(slot) = inventory.get_slot(x, y) {
+                let fits = match inventory.get_item(slot) {
+                    Some(item) => item.is_stackable(cursor),
+                    None => true,
+                };
+                // Every dragged slot gets at least one item
+                if fits
+                    && !drag.slots.contains(&slot)
+                    && (drag.slots.len() as isize) < cursor.stack.count
+                {
+                    drag.slots.push(slot);
+                }
+            }
+        }
     }
 
     pub fn set_cursor(&mut self, cursor: Option<Item>) {
@@ -528,11 +991,45 @@ impl InventoryContext {
     }
 }
 
+/// The slots of the player's main inventory and hotbar within a window.
+fn base_slots(inventory: &dyn Inventory) -> Range<u16> {
+    match inventory.ty() {
+        InventoryType::Main => 9..45,
+        _ => inventory.size() - 36..inventory.size(),
+    }
+}
+
+/// The slots a shift-clicked stack is moved to, in the order they are
+/// filled. Windows with slots that only accept some items are left to the
+/// server.
+fn quick_move_targets(inventory: &dyn Inventory, slot: u16) -> Option<Vec<u16>> {
+    let base = base_slots(inventory);
+    match inventory.ty() {
+        InventoryType::Main => Some(match slot {
+            9..=35 => (36..45).collect(),
+            36..=44 => (9..36).collect(),
+            _ => base.collect(),
+        }),
+        InventoryType::Chest(_)
+        | InventoryType::ShulkerBox
+        | InventoryType::Dropper
+        | InventoryType::Hopper => Some(if base.contains(&slot) {
+            (0..base.start).collect()
+        } else {
+            base.rev().collect()
+        }),
+        // Items are only moved out of the crafting grid
+        InventoryType::CraftingTable if !base.contains(&slot) => Some(base.collect()),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 #[allow(dead_code)]
 pub enum InventoryType {
     Internal, // For internal use only.
     Main,
+    Creative,  // For internal use only.
     Chest(u8), // rows
     Dropper,   // Dropper and Dispenser
     Anvil,
@@ -594,6 +1091,34 @@ impl InventoryType {
         })
     }
 
+    /// Lookup a window type based on the numeric ids used before 1.8.
+    pub fn from_legacy_id(id: i32, slot_count: u8) -> Option<Self> {
+        Some(match id {
+            0 => {
+                if !slot_count.is_multiple_of(9) {
+                    warn!("Chest slot count of {slot_count} wasn't divisible by 9");
+                    return None;
+                }
+                InventoryType::Chest(slot_count / 9)
+            }
+            1 => InventoryType::CraftingTable,
+            2 => InventoryType::Furnace,
+            3 => InventoryType::Dropper,
+            4 => InventoryType::EnchantingTable,
+            5 => InventoryType::BrewingStand,
+            6 => InventoryType::Merchant,
+            7 => InventoryType::Beacon,
+            8 => InventoryType::Anvil,
+            9 => InventoryType::Hopper,
+            10 => InventoryType::Dropper,
+            11 => InventoryType::Horse,
+            _ => {
+                warn!("Unhandled inventory type {id}");
+                return None;
+            }
+        })
+    }
+
     /// Lookup a window type based on the inventory type strings used between
     /// 1.8 and 1.13.
     pub fn from_name(name: &str, slot_count: u8) -> Option<Self> {
@@ -602,7 +1127,7 @@ impl InventoryType {
             "minecraft:beacon" => InventoryType::Beacon,
             "minecraft:brewing_stand" => InventoryType::BrewingStand,
             "minecraft:chest" | "minecraft:container" => {
-                if slot_count % 9 != 0 {
+                if !slot_count.is_multiple_of(9) {
                     warn!("Chest slot count of {slot_count} wasn't divisible by 9");
                     return None;
                 }
@@ -637,7 +1162,7 @@ impl Item {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum Material {
     #[default]
     Air, // 1.7.10 (id: 0, stack: 0)| 1.13 (id: 9648)
@@ -2066,7 +2591,8 @@ impl Material {
         format!("{:?}", self)
     }
 
-    pub fn texture_locations(&self) -> (String, String) {
+    /// The name vanilla uses for the material, like `oak_planks`.
+    pub fn id_name(&self) -> String {
         // TODO: Compute this at compile time and only lookup at runtime in (O(1))
         let mut result = String::new();
         for (i, c) in self.name().chars().enumerate() {
@@ -2079,6 +2605,34 @@ impl Material {
                 result.push(c);
             }
         }
+        result
+    }
+
+    /// The name shown to players, translated when the selected locale has
+    /// a name for the item or block, like `Oak Planks`.
+    pub fn display_name(&self) -> String {
+        let id_name = self.id_name();
+        ["item", "block"]
+            .iter()
+            .find_map(|kind| {
+                translate::translate_selected(&format!("{}.minecraft.{}", kind, id_name))
+            })
+            .unwrap_or_else(|| {
+                id_name
+                    .split('_')
+                    .map(|word| {
+                        let mut chars = word.chars();
+                        chars.next().map_or_else(String::new, |first| {
+                            first.to_uppercase().chain(chars).collect()
+                        })
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+    }
+
+    pub fn texture_locations(&self) -> (String, String) {
+        let result = self.id_name();
         (format!("item/{}", result), format!("block/{}", result))
     }
 
@@ -2125,6 +2679,35 @@ impl Material {
         }
     }
 
+    /// The armor points this material gives when worn.
+    pub fn armor_points(&self) -> u8 {
+        match *self {
+            Material::LeatherHelmet | Material::LeatherBoots => 1,
+            Material::LeatherLeggings => 2,
+            Material::LeatherChestplate => 3,
+
+            Material::ChainmailBoots | Material::GoldBoots | Material::GoldenBoots => 1,
+            Material::ChainmailHelmet | Material::GoldHelmet | Material::GoldenHelmet => 2,
+            Material::GoldLeggings | Material::GoldenLeggings => 3,
+            Material::ChainmailLeggings => 4,
+            Material::ChainmailChestplate
+            | Material::GoldChestplate
+            | Material::GoldenChestplate => 5,
+
+            Material::IronHelmet | Material::IronBoots | Material::TurtleHelmet => 2,
+            Material::IronLeggings => 5,
+            Material::IronChestplate => 6,
+
+            Material::DiamondHelmet
+            | Material::DiamondBoots
+            | Material::NetheriteHelmet
+            | Material::NetheriteBoots => 3,
+            Material::DiamondLeggings | Material::NetheriteLeggings => 6,
+            Material::DiamondChestplate | Material::NetheriteChestplate => 8,
+            _ => 0,
+        }
+    }
+
     pub fn get_stack_size(&self, version: Version) -> u8 {
         material::versions::get_stack_size(*self, version)
     }
@@ -5,6 +5,7 @@ use crate::render::inventory::InventoryWindow;
 use crate::render::Renderer;
 use crate::ui;
 use crate::ui::{Container, HAttach, VAttach};
+use std::ops::Range;
 use std::sync::Arc;
 
 use parking_lot::RwLock;
@@ -13,6 +14,9 @@ use shared::Version;
 const WINDOW_WIDTH: i32 = 176;
 const WINDOW_HEIGHT: i32 = 166;
 
+/// Slot of the off hand item, only present since 1.9.
+pub const OFF_HAND_SLOT: u16 = 45;
+
 pub struct PlayerInventory {
     slots: SlotMapping,
     version: Version,
@@ -29,11 +33,9 @@ impl PlayerInventory {
         slots.set_child(base_slots, (8, 84), (9..45).collect());
 
         // Crafting output
-        // TODO: Use different click rules for crafting output
         slots.add_slot(0, (154, 28));
 
         // Crafting input
-        // TODO: Reduce the count on each of these slots when output is taken
         slots.add_slot(1, (98, 18));
         slots.add_slot(2, (116, 18));
         slots.add_slot(3, (98, 36));
@@ -47,7 +49,7 @@ impl PlayerInventory {
         slots.add_slot(8, (8, 62));
 
         if version > Version::V1_8 {
-            slots.add_slot(45, (77, 62));
+            slots.add_slot(OFF_HAND_SLOT, (77, 62));
         }
 
         slots.update_icons(renderer, (0, 0), None);
@@ -58,6 +60,14 @@ impl PlayerInventory {
             client_state_id: 0,
         }
     }
+
+    /// The armor points of all worn armor pieces.
+    pub fn armor_points(&self) -> u8 {
+        (5..=8)
+            .filter_map(|slot| self.slots.get_item(slot))
+            .map(|item| item.material.armor_points())
+            .sum()
+    }
 }
 
 impl Inventory for PlayerInventory {
@@ -173,4 +183,8 @@ impl Inventory for PlayerInventory {
     fn ty(&self) -> InventoryType {
         InventoryType::Main
     }
+
+    fn crafting_slots(&self) -> Option<(u16, Range<u16>)> {
+        Some((0, 1..5))
+    }
 }
@@ -0,0 +1,55 @@
+//! Loads the translations of the selected locale from the resource packs,
+//! which are used for the menus and translated chat messages.
+
+use crate::resources;
+use leafish_protocol::translate;
+use log::warn;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+/// The locale the UI is written in, which needs no translations loaded.
+pub const DEFAULT_LOCALE: &str = "en_us";
+
+/// The resource version and locale the current translations were loaded for.
+static LOADED: Mutex<Option<(usize, String)>> = Mutex::new(None);
+
+/// Loads the translations for `locale` if it changed or the resource packs
+/// were reloaded since last time. Returns whether they were reloaded, so
+/// the menus can be rebuilt in the new language.
+pub fn update(res: &resources::Manager, locale: &str) -> bool {
+    let mut loaded = LOADED.lock();
+    let locale = locale.to_lowercase();
+    if loaded.as_ref().is_some_and(|(version, loaded_locale)| {
+        *version == res.version() && *loaded_locale == locale
+    }) {
+        return false;
+    }
+    let translations = if locale == DEFAULT_LOCALE {
+        HashMap::new()
+    } else {
+        load(res, &locale)
+    };
+    translate::set_language(translations);
+    *loaded = Some((res.version(), locale));
+    true
+}
+
+fn load(res: &resources::Manager, locale: &str) -> HashMap<String, String> {
+    let Some(file) = res.open("minecraft", &format!("lang/{}.json", locale)) else {
+        warn!("No translations found for locale {}", locale);
+        return HashMap::new();
+    };
+    serde_json::from_reader(file).unwrap_or_else(|err| {
+        warn!(
+            "Failed to read the translations for locale {}: {}",
+            locale, err
+        );
+        HashMap::new()
+    })
+}
+
+/// Translates a UI string into the selected locale, falling back to the
+/// given English text when there's no translation for `key`.
+pub fn tr(key: &str, english: &str) -> String {
+    translate::translate_selected(key).unwrap_or_else(|| english.to_owned())
+}
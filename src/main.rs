@@ -35,11 +35,9 @@ use glutin::surface::GlSurface;
 use glutin::surface::SwapInterval;
 use glutin_winit::DisplayBuilder;
 use glutin_winit::GlWindow;
-use instant::Duration;
 use leafish_protocol::protocol::login::AccountType;
 use log::{debug, error, info, warn};
 use raw_window_handle::HasRawWindowHandle;
-use shared::Version;
 use std::fs;
 use std::num::NonZeroU32;
 use std::sync::atomic::AtomicBool;
@@ -66,15 +64,19 @@ use leafish_protocol::nbt;
 use leafish_protocol::protocol;
 pub mod gl;
 use leafish_protocol::types;
+mod benchmark;
 pub mod chunk_builder;
 pub mod entity;
+mod frame_limiter;
 mod inventory;
+pub mod lang;
 pub mod model;
 pub mod particle;
 pub mod paths;
 pub mod render;
 pub mod resources;
 pub mod screen;
+pub mod screenshot;
 pub mod server;
 pub mod settings;
 pub mod ui;
@@ -83,6 +85,7 @@ pub mod world;
 use crate::entity::Rotation;
 use crate::render::hud::HudContext;
 use crate::settings::*;
+use leafish_protocol::protocol::forge;
 use leafish_protocol::protocol::login::Account;
 use leafish_protocol::protocol::Error;
 use parking_lot::Mutex;
@@ -109,8 +112,6 @@ pub struct Game {
     focused: AtomicBool,
     chunk_builder: Mutex<chunk_builder::ChunkBuilder>,
 
-    connect_error: ArcSwapOption<Error>,
-
     last_mouse_x: AtomicF64,
     last_mouse_y: AtomicF64,
     last_mouse_xrel: AtomicF64, // FIXME: try moving this out of Game into a local variable
@@ -209,35 +210,79 @@ impl Game {
         hud_context: Arc<RwLock<HudContext>>,
     ) -> Result<(), Error> {
         let default_protocol_version = self.settings.get_int(IntSetting::DefaultProtocolVersion);
-        let (protocol_version, forge_mods, fml_network_version) =
-            match protocol::Conn::new(address, default_protocol_version)
-                .and_then(|conn| conn.do_status())
-            {
-                Ok(res) => {
+        let mut pinged = true;
+        let mut attempt = ping_version(address, default_protocol_version).unwrap_or_else(|| {
+            warn!(
+                "Couldn't ping server {} to get protocol version, defaulting to {}",
+                address, default_protocol_version
+            );
+            pinged = false;
+            (default_protocol_version, vec![], None)
+        });
+        if !protocol::SUPPORTED_PROTOCOLS.contains(&attempt.0) {
+            return Err(Error::UnsupportedVersion(attempt.0));
+        }
+
+        // Servers that hide from pings, or proxies that don't tell the truth
+        // about the server behind them, need the version guessed instead.
+        // Every version is only tried once.
+        let mut tried = vec![];
+        loop {
+            let (protocol_version, forge_mods, fml_network_version) = attempt;
+            tried.push(protocol_version);
+            let err = match self.connect_with(
+                address,
+                protocol_version,
+                forge_mods,
+                fml_network_version,
+                hud_context.clone(),
+            ) {
+                Err(err) if err.is_version_mismatch() => err,
+                result => return result,
+            };
+
+            // The server may answer pings now that it had a chance to tell
+            // us off for the wrong version
+            let advertised = if pinged {
+                None
+            } else {
+                pinged = true;
+                ping_version(address, protocol_version)
+            };
+            let next = advertised
+                .filter(|(version, _, _)| {
+                    protocol::SUPPORTED_PROTOCOLS.contains(version) && !tried.contains(version)
+                })
+                .or_else(|| {
+                    FALLBACK_PROTOCOLS
+                        .iter()
+                        .find(|version| !tried.contains(version))
+                        .map(|&version| (version, vec![], None))
+                });
+            match next {
+                Some(next) => {
                     info!(
-                        "Detected server protocol version {}",
-                        res.0.version.protocol
+                        "Server {} rejected protocol version {}, retrying with {}",
+                        address, protocol_version, next.0
                     );
-                    (
-                        res.0.version.protocol,
-                        res.0.forge_mods,
-                        res.0.fml_network_version,
-                    )
+                    attempt = next;
                 }
-                Err(err) => {
-                    warn!(
-                        "Error pinging server {} to get protocol version: {:?}, defaulting to {}",
-                        address, err, default_protocol_version
-                    );
-                    (default_protocol_version, vec![], None)
-                }
-            };
-        if !Version::from_id(protocol_version as u32).is_supported() {
-            return Err(Error::Err(format!(
-                "The server's version isn't supported!\n(protocol version: {})",
-                protocol_version
-            )));
+                None => return Err(err),
+            }
         }
+    }
+
+    fn connect_with(
+        &self,
+        address: &str,
+        protocol_version: i32,
+        forge_mods: Vec<forge::ForgeMod>,
+        fml_network_version: Option<i64>,
+        hud_context: Arc<RwLock<HudContext>>,
+    ) -> Result<(), Error> {
+        let join_commands = screen::edit_server::saved_join_commands(address);
+        let forwarding = screen::edit_server::saved_forwarding(address);
+        let settings = screen::edit_server::saved_settings(address);
         let address = address.to_owned();
         let resources = self.resource_manager.clone();
         let renderer = self.renderer.clone();
@@ -251,6 +296,7 @@ impl Game {
                 protocol_version,
                 forge_mods,
                 fml_network_version,
+                &forwarding,
                 renderer,
                 hud_context.clone(),
                 screen_sys,
@@ -259,28 +305,45 @@ impl Game {
         .join();
         match result {
             Ok(result) => {
-                match result {
-                    Ok(srv) => {
-                        self.server.store(Some(srv));
-                        Ok(())
-                    }
-                    Err(err) => {
-                        let str = err.to_string();
-                        self.connect_error.store(Some(Arc::new(err)));
-                        // self.server.disconnect_reason = Some(Component::from_string(&*err.to_string()));
-                        Err(Error::Err(str))
-                    }
+                let srv = result?;
+                if let Some(join_commands) = join_commands {
+                    srv.set_join_commands(join_commands);
                 }
+                for name in self.settings.set_overrides(&settings) {
+                    warn!(
+                        "Ignoring invalid setting {} saved for {}",
+                        name, srv.address
+                    );
+                }
+                self.server.store(Some(srv));
+                Ok(())
             }
             Err(_) => Err(Error::Err("Unknown".to_string())),
         }
     }
+
+    pub fn replay_from(
+        &self,
+        path: &str,
+        hud_context: Arc<RwLock<HudContext>>,
+    ) -> Result<(), Error> {
+        let server = server::Server::replay(
+            self.resource_manager.clone(),
+            path,
+            self.renderer.clone(),
+            hud_context,
+            self.screen_sys.clone(),
+        )?;
+        self.server.store(Some(server));
+        Ok(())
+    }
 }
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "leafish")]
 struct Opt {
-    /// Log decoded packets received from network
+    /// Log decoded packets received from network, and summarize unknown
+    /// or unhandled packets on disconnect
     #[structopt(short = "n", long = "network-debug")]
     network_debug: bool,
 
@@ -288,6 +351,38 @@ struct Opt {
     #[structopt(short = "N", long = "network-parse-packet")]
     network_parse_packet: Option<String>,
 
+    /// Compress packets sent to the server above this size instead of the
+    /// server's threshold, which it can't go below, a negative value
+    /// disables compression of sent packets
+    #[structopt(long = "compression-threshold", allow_hyphen_values = true)]
+    compression_threshold: Option<i32>,
+
+    /// Write decoded packets as newline-delimited JSON to a file
+    #[structopt(long = "packet-log")]
+    packet_log: Option<String>,
+
+    /// Replay a packet log instead of connecting to a server
+    #[structopt(long)]
+    replay: Option<String>,
+
+    /// Replay a packet log as fast as possible in a hidden window, then
+    /// report how long loading, building chunks and rendering took
+    #[structopt(long)]
+    benchmark: Option<String>,
+
+    /// The number of frames --benchmark times once the chunks are built
+    #[structopt(long = "benchmark-frames", default_value = "600")]
+    benchmark_frames: usize,
+
+    /// Connect to a server on startup instead of showing the server list,
+    /// requires the account to be given with --name, --uuid and --token
+    #[structopt(long)]
+    server: Option<String>,
+
+    /// The port of the server given with --server
+    #[structopt(long = "server-port")]
+    server_port: Option<u16>,
+
     #[structopt(long)]
     uuid: Option<String>,
     #[structopt(long)]
@@ -306,7 +401,6 @@ struct Opt {
 // TODO: Simplify error messages in server list.
 // TODO: Render skin of players joining after one self.
 // TODO: Implement arm swing animation!
-// TODO: Implement attacking entities!
 // TODO: Fix cursor grabbing/visibility/transparency of window.
 // TODO: Improve clouds.
 // TODO: Fix pistons.
@@ -353,7 +447,8 @@ fn main() {
             .unwrap(),
         ))
         .with_inner_size(winit::dpi::LogicalSize::new(854.0, 480.0)) // FIXME: Why are we using this particular value here?
-        .with_maximized(true);
+        .with_maximized(true)
+        .with_visible(opt.benchmark.is_none());
 
     let (context, shader_version, window, surface, display) = {
         let template = ConfigTemplateBuilder::new()
@@ -404,7 +499,7 @@ fn main() {
         // Make it current.
         let gl_context = not_current_gl_context.make_current(&gl_surface).unwrap();
 
-        if vsync {
+        if vsync && opt.benchmark.is_none() {
             // Try setting vsync.
             if let Err(res) = gl_surface
                 .set_swap_interval(&gl_context, SwapInterval::Wait(NonZeroU32::new(1).unwrap()))
@@ -423,6 +518,11 @@ fn main() {
     let ui_container = ui::Container::new();
 
     let mut last_frame = Instant::now();
+    let mut frame_limiter = if opt.benchmark.is_some() {
+        frame_limiter::FrameLimiter::uncapped()
+    } else {
+        frame_limiter::FrameLimiter::new()
+    };
 
     let screen_sys = Arc::new(screen::ScreenSystem::new());
     let active_account = Arc::new(Mutex::new(None));
@@ -431,6 +531,7 @@ fn main() {
         screen_sys.clone(),
     )));
     let mut accounts = screen::launcher::load_accounts().unwrap_or_default();
+    let mut cli_account = None;
     if let Some((name, uuid, token)) = opt
         .name
         .clone()
@@ -442,13 +543,15 @@ fn main() {
         .flatten()
     {
         println!("Got microsoft credentials, adding account...");
-        accounts.push(Account {
+        let account = Account {
             name: name.clone(),
             uuid: Some(uuid),
             verification_tokens: vec![name, "".to_string(), token],
             head_img_data: None,
             account_type: AccountType::Microsoft,
-        });
+        };
+        accounts.push(account.clone());
+        cli_account = Some(account);
     }
     screen_sys.add_screen(Box::new(screen::launcher::Launcher::new(
         Arc::new(Mutex::new(accounts)),
@@ -492,7 +595,6 @@ fn main() {
         console: con,
         should_close: AtomicBool::new(false),
         chunk_builder: Mutex::new(chunk_builder::ChunkBuilder::new(resource_manager, textures)),
-        connect_error: ArcSwapOption::empty(),
         last_mouse_x: AtomicF64::new(0.0),
         last_mouse_y: AtomicF64::new(0.0),
         last_mouse_xrel: AtomicF64::new(0.0),
@@ -509,6 +611,15 @@ fn main() {
     if opt.network_debug {
         protocol::enable_network_debug();
     }
+    if let Some(threshold) = opt.compression_threshold {
+        protocol::set_compression_override(threshold);
+    }
+
+    if let Some(path) = opt.packet_log {
+        if let Err(err) = protocol::packet_log::enable_packet_log(&path) {
+            error!("Failed to open packet log {}: {}", path, err);
+        }
+    }
 
     if let Some(filename) = opt.network_parse_packet {
         let data = fs::read(filename).unwrap();
@@ -519,7 +630,61 @@ fn main() {
         return;
     }
 
+    let mut benchmark = None;
+    if let Some(path) = opt.benchmark {
+        let hud_context = Arc::new(RwLock::new(HudContext::new()));
+        benchmark = Some(benchmark::Benchmark::new(opt.benchmark_frames));
+        match game.replay_from(&path, hud_context.clone()) {
+            Ok(_) => game
+                .screen_sys
+                .replace_screen(Box::new(render::hud::Hud::new(hud_context))),
+            Err(err) => {
+                error!("Failed to replay packet log {}: {}", path, err);
+                return;
+            }
+        }
+    }
+
+    if let Some(path) = opt.replay {
+        let hud_context = Arc::new(RwLock::new(HudContext::new()));
+        match game.replay_from(&path, hud_context.clone()) {
+            Ok(_) => game
+                .screen_sys
+                .replace_screen(Box::new(render::hud::Hud::new(hud_context))),
+            Err(err) => error!("Failed to replay packet log {}: {}", path, err),
+        }
+    }
+
+    if let Some(server) = opt.server {
+        let address = match opt.server_port {
+            Some(port) => format!("{}:{}", server, port),
+            None => server,
+        };
+        if let Some(account) = cli_account {
+            game.current_account.lock().replace(account);
+            let hud_context = Arc::new(RwLock::new(HudContext::new()));
+            match game.connect_to(&address, hud_context.clone()) {
+                Ok(_) => game
+                    .screen_sys
+                    .add_screen(Box::new(render::hud::Hud::new(hud_context))),
+                Err(err) => {
+                    error!("Failed to connect to {}: {}", address, err);
+                    game.screen_sys.add_screen(disconnected_screen(
+                        Some(format::Component::new(format::ComponentType::new(
+                            &err.to_string(),
+                            None,
+                        ))),
+                        &address,
+                    ));
+                }
+            }
+        } else {
+            warn!("--server requires --name, --uuid and --token, showing the launcher instead");
+        }
+    }
+
     let mut last_resource_version = 0;
+    let mut window_title = String::new();
 
     let game = Rc::new(RefCell::new(game));
     let ui_container = Rc::new(RefCell::new(ui_container));
@@ -557,8 +722,10 @@ fn main() {
                 &game,
                 &mut ui_container,
                 &mut last_frame,
+                &mut frame_limiter,
                 &mut resui,
                 &mut last_resource_version,
+                &mut window_title,
                 vsync,
             );
             if DEBUG {
@@ -569,22 +736,92 @@ fn main() {
                 .swap_buffers(&context)
                 .expect("Failed to swap GL buffers");
 
+            if let Some(benchmark) = benchmark.as_mut() {
+                match benchmark.frame(&game, start.elapsed()) {
+                    Ok(true) => game.set_should_close(),
+                    Ok(false) => {}
+                    Err(err) => {
+                        // The window is hidden, so nobody could close it
+                        error!("Benchmark failed: {}", err);
+                        leave_server(&game);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
             if game.should_close() {
+                leave_server(&game);
                 event_loop.exit();
             }
         })
         .unwrap();
 }
 
+/// Closes the connection to the server before exiting, so the server sees
+/// the player leave straight away instead of waiting for them to time out.
+fn leave_server(game: &Game) {
+    if let Some(server) = game.server.swap(None) {
+        if server.is_connected() {
+            server.finish_disconnect();
+        }
+    }
+}
+
 const DEBUG: bool = false;
+/// Versions tried in turn when a server rejects the one we connected with
+/// and doesn't say which it runs, the most popular ones first.
+const FALLBACK_PROTOCOLS: [i32; 6] = [754, 340, 47, 578, 498, 404];
+
+/// Asks the server which version it runs, along with the mods it needs.
+fn ping_version(
+    address: &str,
+    protocol_version: i32,
+) -> Option<(i32, Vec<forge::ForgeMod>, Option<i64>)> {
+    match protocol::Conn::new(address, protocol_version).and_then(|conn| conn.do_status()) {
+        Ok((status, _)) => {
+            info!(
+                "Detected server protocol version {}",
+                status.version.protocol
+            );
+            Some((
+                status.version.protocol,
+                status.forge_mods,
+                status.fml_network_version,
+            ))
+        }
+        Err(err) => {
+            warn!("Error pinging server {}: {:?}", address, err);
+            None
+        }
+    }
+}
+/// How many pixels of touchpad scrolling count as one line of a mouse wheel.
+const PIXELS_PER_SCROLL_LINE: f64 = 20.0;
+
+/// The screen to show after leaving a server, which explains why when
+/// the server kicked us.
+fn disconnected_screen(
+    reason: Option<format::Component>,
+    address: &str,
+) -> Box<dyn screen::Screen> {
+    match reason {
+        Some(reason) => Box::new(screen::disconnected::Disconnected::new(
+            reason,
+            Some(address.to_string()).filter(|address| !address.is_empty()),
+        )),
+        None => Box::new(screen::ServerList::new()),
+    }
+}
 
 fn tick_all(
     window: &winit::window::Window,
     game: &Game,
     ui_container: &mut ui::Container,
     last_frame: &mut Instant,
+    frame_limiter: &mut frame_limiter::FrameLimiter,
     resui: &mut resources::ManagerUI,
     last_resource_version: &mut usize,
+    window_title: &mut String,
     vsync: bool,
 ) {
     let server = game.server.load();
@@ -593,17 +830,25 @@ fn tick_all(
             let disconnect_reason = server.disconnect_data.write().disconnect_reason.take();
             game.screen_sys.close_closable_screens();
             game.screen_sys
-                .replace_screen(Box::new(screen::ServerList::new(disconnect_reason)));
+                .replace_screen(disconnected_screen(disconnect_reason, &server.address));
             game.server.store(None);
+            game.settings.clear_overrides();
             game.renderer.reset();
+            game.resource_manager
+                .write()
+                .remove_layer(resources::PackLayer::Server);
         } else if server.disconnect_gracefully.load(Ordering::Relaxed) {
             server.finish_disconnect();
             let disconnect_reason = server.disconnect_data.write().disconnect_reason.take();
             game.screen_sys.close_closable_screens();
             game.screen_sys
-                .replace_screen(Box::new(screen::ServerList::new(disconnect_reason)));
+                .replace_screen(disconnected_screen(disconnect_reason, &server.address));
             game.server.store(None);
+            game.settings.clear_overrides();
             game.renderer.reset();
+            game.resource_manager
+                .write()
+                .remove_layer(resources::PackLayer::Server);
         }
     } else {
         game.chunk_builder.lock().reset();
@@ -619,18 +864,25 @@ fn tick_all(
         .to_logical::<f64>(window.scale_factor())
         .into();
 
-    let version = {
+    let (version, lang_changed) = {
         let try_res = game.resource_manager.try_write();
         if let Some(mut res) = try_res {
             res.tick(resui, ui_container, delta);
-            res.version()
+            res.load_client_packs(&game.settings.get_string(StringSetting::ResourcePacks));
+            let lang_changed = lang::update(&res, &game.settings.get_string(StringSetting::Locale));
+            (res.version(), lang_changed)
         } else {
             // TODO: why does game.resource_manager.write() sometimes deadlock?
             warn!("Failed to obtain mutable reference to resource manager!"); // was uncommented
-            *last_resource_version
+            (*last_resource_version, false)
         }
     };
     *last_resource_version = version;
+    // Screens built before the translations were loaded show English
+    if lang_changed {
+        game.screen_sys
+            .rebuild_current(game.renderer.clone(), ui_container);
+    }
 
     let vsync_changed = game.settings.get_bool(BoolSetting::Vsync);
     if vsync != vsync_changed {
@@ -645,6 +897,22 @@ fn tick_all(
         server.tick(delta, game);
     }
 
+    // Show which server we're on in the taskbar
+    let title = match game.server.load().as_ref() {
+        Some(server) if !server.address.is_empty() => {
+            let fps = server.hud_context.read().fps();
+            match server.ping() {
+                Some(ping) => format!("Leafish - {} ({} ms, {} fps)", server.address, ping, fps),
+                None => format!("Leafish - {} ({} fps)", server.address, fps),
+            }
+        }
+        _ => "Leafish".to_string(),
+    };
+    if *window_title != title {
+        window.set_title(&title);
+        *window_title = title;
+    }
+
     // Check if window is valid, it might be minimized
     if physical_width == 0 || physical_height == 0 {
         return;
@@ -652,9 +920,12 @@ fn tick_all(
 
     if let Some(server) = game.server.load().as_ref() {
         game.renderer.update_camera(physical_width, physical_height);
-        game.chunk_builder
-            .lock()
-            .tick(server.world.clone(), game.renderer.clone(), version);
+        game.chunk_builder.lock().tick(
+            server.world.clone(),
+            game.renderer.clone(),
+            version,
+            &game.settings,
+        );
     } else if game.renderer.screen_data.read().safe_width != physical_width
         || game.renderer.screen_data.read().safe_height != physical_height
     {
@@ -673,6 +944,9 @@ fn tick_all(
                 .unwrap();
             window.set_cursor_visible(true);
             game.set_focused(false);
+            if game.settings.get_bool(BoolSetting::CenterCursor) {
+                center_cursor(window, game, ui_container);
+            }
         }
     } else if !game.is_focused() {
         // see https://docs.rs/winit/latest/winit/window/enum.CursorGrabMode.html
@@ -697,16 +971,42 @@ fn tick_all(
         .tick(world, delta, width, height, physical_width, physical_height);
     if let Some(server) = game.server.load().as_ref() {
         server.render_list_computer.send(true).unwrap();
+        let screenshot_pending = server.hud_context.read().screenshot_pending();
+        if screenshot_pending && server.hud_context.write().finish_screenshot() {
+            screenshot::capture(physical_width, physical_height);
+        }
     }
 
     if fps_cap > 0 && !vsync {
-        let frame_time = now.elapsed();
-        let sleep_interval = Duration::from_millis(1000 / fps_cap as u64);
-        if frame_time < sleep_interval {
-            thread::sleep(sleep_interval - frame_time);
-        }
+        frame_limiter.wait(fps_cap as u32);
+    } else {
+        frame_limiter.reset();
     }
 }
+/// Moves the cursor to the middle of the window when it is released, like
+/// vanilla does when a menu opens. The cursor may have wandered off while
+/// it was hidden, so this keeps the tracked position in line with where
+/// it shows up again.
+fn center_cursor(window: &winit::window::Window, game: &Game, ui_container: &mut ui::Container) {
+    let (width, height): (f64, f64) = window
+        .inner_size()
+        .to_logical::<f64>(window.scale_factor())
+        .into();
+    let (x, y) = (width / 2.0, height / 2.0);
+    if window
+        .set_cursor_position(winit::dpi::LogicalPosition::new(x, y))
+        .is_err()
+    {
+        return;
+    }
+    game.set_last_mouse_x(x);
+    game.set_last_mouse_y(y);
+    ui_container.hover_at(game, x, y, width, height);
+    if let Some(server) = game.server.load().as_ref() {
+        server.on_cursor_moved(x, y);
+    }
+}
+
 // TODO: Improve perf of 3, 6 and 10
 // TODO: Reenable: [server/mod.rs:1924][WARN] Block entity at (1371,53,-484) missing id tag: NamedTag("", Compound({"y": Int(53), "Sign": String(""), "x": Int(1371), "z": Int(-484)}))
 
@@ -787,8 +1087,9 @@ fn handle_window_event<T>(
                     (ElementState::Released, MouseButton::Left) => {
                         let physical_size = window.inner_size();
                         let (width, height) = physical_size.to_logical::<f64>(dpi_factor).into();
+                        // Menus are clicked where the cursor is, while playing the
+                        // server uses the ray from the middle of the screen instead
                         if !game.screen_sys.is_current_ingame() && !game.is_focused() {
-                            // TODO: after Pointer Lock https://github.com/rust-windowing/winit/issues/1674
                             ui_container.click_at(
                                 game,
                                 game.get_last_mouse_x(),
@@ -833,14 +1134,16 @@ fn handle_window_event<T>(
                     }
                 }
                 WindowEvent::MouseWheel { delta, .. } => {
-                    // TODO: line vs pixel delta? does pixel scrolling (e.g. touchpad) need scaling?
+                    // Screens scroll by lines, so pixel deltas (e.g. from touchpads)
+                    // are scaled down to roughly match a mouse wheel
                     match delta {
                         MouseScrollDelta::LineDelta(x, y) => {
                             game.screen_sys.on_scroll(x.into(), y.into());
                         }
                         MouseScrollDelta::PixelDelta(position) => {
-                            let (x, y) = position.into();
-                            game.screen_sys.on_scroll(x, y);
+                            let (x, y): (f64, f64) = position.into();
+                            game.screen_sys
+                                .on_scroll(x / PIXELS_PER_SCROLL_LINE, y / PIXELS_PER_SCROLL_LINE);
                         }
                     }
                 }
@@ -848,6 +1151,14 @@ fn handle_window_event<T>(
                     const SEMICOLON: Key = Key::Character(SmolStr::new_inline(";"));
                     if event.state == ElementState::Pressed && event.logical_key == SEMICOLON {
                         game.console.lock().toggle();
+                    } else if game.console.lock().is_active() {
+                        // The open console takes all typing for its command line
+                        if event.state == ElementState::Pressed {
+                            let command = game.console.lock().key_press(&event.logical_key);
+                            if let Some(command) = command {
+                                console::execute(game, &command);
+                            }
+                        }
                     } else {
                         match (event.state, event.logical_key) {
                             (ElementState::Pressed, Key::Named(NamedKey::F11)) => {
@@ -0,0 +1,349 @@
+use crate::model::BlockVertex;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// The size of a single vertex in a chunk buffer.
+const VERTEX_SIZE: usize = 40;
+const SECTION_SIZE: usize = 16;
+
+/// A face covering a whole side of a block.
+#[derive(Clone)]
+struct Cell {
+    verts: [BlockVertex; 4],
+    /// The corner of the block side each vertex sits on, along the plane's
+    /// two axes.
+    corners: [(u8, u8); 4],
+}
+
+/// The axis a plane is perpendicular to, whether its faces point along or
+/// against that axis and the block coordinate along it.
+type PlaneKey = (usize, bool, i32);
+
+/// Merges block faces that look the same and lie next to each other into
+/// larger quads. Faces which aren't a full, evenly lit side of a block are
+/// passed through untouched.
+#[derive(Default)]
+pub struct GreedyMesher {
+    planes: HashMap<PlaneKey, Vec<Option<Cell>>>,
+}
+
+impl GreedyMesher {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Takes the faces a block model rendered at the given position. Faces
+    /// that can be merged are held back until `finish`, the rest are written
+    /// to `buf` straight away. Returns the number of indices written.
+    pub fn add<W: Write>(&mut self, x: i32, y: i32, z: i32, data: &[u8], buf: &mut W) -> usize {
+        let mut count = 0;
+        for mut face in data.chunks(VERTEX_SIZE * 4) {
+            let verts = [
+                BlockVertex::read(&mut face),
+                BlockVertex::read(&mut face),
+                BlockVertex::read(&mut face),
+                BlockVertex::read(&mut face),
+            ];
+            let verts = match verts {
+                [Ok(a), Ok(b), Ok(c), Ok(d)] => [a, b, c, d],
+                _ => continue,
+            };
+            match to_cell([x, y, z], &verts) {
+                Some((key, index, cell)) => {
+                    let slot = &mut self
+                        .planes
+                        .entry(key)
+                        .or_insert_with(|| vec![None; SECTION_SIZE * SECTION_SIZE])[index];
+                    match slot.take() {
+                        // Models can have several faces on the same block side,
+                        // like grass blocks drawing their overlay over the dirt.
+                        // Both are written as they are, in the model's order, so
+                        // the later one still ends up on top.
+                        Some(existing) => {
+                            for vert in existing.verts.iter().chain(&cell.verts) {
+                                vert.write(buf);
+                            }
+                            count += 12;
+                        }
+                        None => *slot = Some(cell),
+                    }
+                }
+                None => {
+                    for vert in &verts {
+                        vert.write(buf);
+                    }
+                    count += 6;
+                }
+            }
+        }
+        count
+    }
+
+    /// Writes out the merged faces, returning the number of indices written.
+    pub fn finish<W: Write>(&mut self, buf: &mut W) -> usize {
+        let mut count = 0;
+        for ((axis, _, _), mut cells) in self.planes.drain() {
+            let (ua, va) = plane_axes(axis);
+            for v in 0..SECTION_SIZE {
+                for u in 0..SECTION_SIZE {
+                    let cell = match cells[u + v * SECTION_SIZE].take() {
+                        Some(cell) => cell,
+                        None => continue,
+                    };
+                    let matches =
+                        |other: &Option<Cell>| other.as_ref().is_some_and(|o| cell.matches(o));
+
+                    let mut width = 1;
+                    while u + width < SECTION_SIZE
+                        && matches(&cells[u + width + v * SECTION_SIZE])
+                        && cell.fits(width + 1, 1)
+                    {
+                        width += 1;
+                    }
+                    let mut height = 1;
+                    while v + height < SECTION_SIZE
+                        && cell.fits(width, height + 1)
+                        && (u..u + width).all(|i| matches(&cells[i + (v + height) * SECTION_SIZE]))
+                    {
+                        height += 1;
+                    }
+                    for dv in 0..height {
+                        for du in 0..width {
+                            cells[u + du + (v + dv) * SECTION_SIZE] = None;
+                        }
+                    }
+
+                    for vert in &cell.stretch(ua, va, width, height) {
+                        vert.write(buf);
+                    }
+                    count += 6;
+                }
+            }
+        }
+        count
+    }
+}
+
+impl Cell {
+    fn matches(&self, other: &Cell) -> bool {
+        self.corners == other.corners
+            && self
+                .verts
+                .iter()
+                .zip(&other.verts)
+                .all(|(a, b)| same_texture(a, b) && same_shade(a, b))
+    }
+
+    /// How far the texture moves between the two corners on either side of
+    /// the given axis (0 for u, 1 for v), at `other` on the remaining axis.
+    fn texture_step(&self, along_v: bool, other: u8) -> (i32, i32) {
+        let corner = |d| if along_v { (other, d) } else { (d, other) };
+        let find = |c| self.verts[self.corners.iter().position(|v| *v == c).unwrap()].clone();
+        let (from, to) = (find(corner(0)), find(corner(1)));
+        (
+            to.toffsetx as i32 - from.toffsetx as i32,
+            to.toffsety as i32 - from.toffsety as i32,
+        )
+    }
+
+    /// The texture offsets of the vertices after stretching the face over
+    /// `width` by `height` blocks.
+    fn stretched_offsets(&self, width: usize, height: usize) -> [(i32, i32); 4] {
+        let mut offsets = [(0, 0); 4];
+        for (i, vert) in self.verts.iter().enumerate() {
+            let (du, dv) = self.corners[i];
+            let (mut tx, mut ty) = (vert.toffsetx as i32, vert.toffsety as i32);
+            if du == 1 {
+                let (sx, sy) = self.texture_step(false, dv);
+                tx += sx * (width as i32 - 1);
+                ty += sy * (width as i32 - 1);
+            }
+            if dv == 1 {
+                let (sx, sy) = self.texture_step(true, du);
+                tx += sx * (height as i32 - 1);
+                ty += sy * (height as i32 - 1);
+            }
+            offsets[i] = (tx, ty);
+        }
+        offsets
+    }
+
+    /// Whether the texture offsets still fit into a vertex when stretched,
+    /// which limits how far faces with large textures can be merged.
+    fn fits(&self, width: usize, height: usize) -> bool {
+        let range = i16::MIN as i32..=i16::MAX as i32;
+        self.stretched_offsets(width, height)
+            .iter()
+            .all(|(tx, ty)| range.contains(tx) && range.contains(ty))
+    }
+
+    fn stretch(&self, ua: usize, va: usize, width: usize, height: usize) -> [BlockVertex; 4] {
+        let offsets = self.stretched_offsets(width, height);
+        let mut verts = self.verts.clone();
+        for (i, vert) in verts.iter_mut().enumerate() {
+            let (du, dv) = self.corners[i];
+            if du == 1 {
+                *axis_mut(vert, ua) += (width - 1) as f32;
+            }
+            if dv == 1 {
+                *axis_mut(vert, va) += (height - 1) as f32;
+            }
+            vert.toffsetx = offsets[i].0 as i16;
+            vert.toffsety = offsets[i].1 as i16;
+        }
+        verts
+    }
+}
+
+/// Works out where a face sits if it covers a whole side of the block at
+/// `block` and is evenly lit and tinted, so that stretching it over several
+/// blocks doesn't change how it looks.
+fn to_cell(block: [i32; 3], verts: &[BlockVertex; 4]) -> Option<(PlaneKey, usize, Cell)> {
+    if !verts.iter().all(|v| same_shade(v, &verts[0])) {
+        return None;
+    }
+    let position = |v: &BlockVertex| [v.x, v.y, v.z];
+    let axis = (0..3).find(|&a| {
+        verts
+            .iter()
+            .all(|v| position(v)[a] == position(&verts[0])[a])
+    })?;
+    let plane = position(&verts[0])[axis] - block[axis] as f32;
+    let positive = if plane == 1.0 {
+        true
+    } else if plane == 0.0 {
+        false
+    } else {
+        return None;
+    };
+
+    let (ua, va) = plane_axes(axis);
+    let mut corners = [(0, 0); 4];
+    for (i, vert) in verts.iter().enumerate() {
+        let du = position(vert)[ua] - block[ua] as f32;
+        let dv = position(vert)[va] - block[va] as f32;
+        corners[i] = match (du, dv) {
+            (du, dv) if (du == 0.0 || du == 1.0) && (dv == 0.0 || dv == 1.0) => {
+                (du as u8, dv as u8)
+            }
+            _ => return None,
+        };
+    }
+    for (i, corner) in corners.iter().enumerate() {
+        if corners[..i].contains(corner) {
+            return None;
+        }
+    }
+
+    let index = block[ua] as usize + block[va] as usize * SECTION_SIZE;
+    Some((
+        (axis, positive, block[axis]),
+        index,
+        Cell {
+            verts: verts.clone(),
+            corners,
+        },
+    ))
+}
+
+fn plane_axes(axis: usize) -> (usize, usize) {
+    ((axis + 1) % 3, (axis + 2) % 3)
+}
+
+fn axis_mut(vert: &mut BlockVertex, axis: usize) -> &mut f32 {
+    match axis {
+        0 => &mut vert.x,
+        1 => &mut vert.y,
+        _ => &mut vert.z,
+    }
+}
+
+fn same_texture(a: &BlockVertex, b: &BlockVertex) -> bool {
+    a.tx == b.tx
+        && a.ty == b.ty
+        && a.tw == b.tw
+        && a.th == b.th
+        && a.tatlas == b.tatlas
+        && a.toffsetx == b.toffsetx
+        && a.toffsety == b.toffsety
+}
+
+fn same_shade(a: &BlockVertex, b: &BlockVertex) -> bool {
+    a.r == b.r
+        && a.g == b.g
+        && a.b == b.b
+        && a.block_light == b.block_light
+        && a.sky_light == b.sky_light
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The top face of the block at `x`, `z` with the given texture.
+    fn top_face(x: f32, z: f32, tx: u16) -> Vec<u8> {
+        let mut data = vec![];
+        for (dx, dz) in [(0.0, 0.0), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0)] {
+            BlockVertex {
+                x: x + dx,
+                y: 1.0,
+                z: z + dz,
+                tx,
+                ty: 0,
+                tw: 16,
+                th: 16,
+                toffsetx: dx as i16 * 16,
+                toffsety: dz as i16 * 16,
+                tatlas: 0,
+                r: 255,
+                g: 255,
+                b: 255,
+                block_light: 0,
+                sky_light: 15,
+            }
+            .write(&mut data);
+        }
+        data
+    }
+
+    fn read_faces(buf: &[u8]) -> Vec<Vec<BlockVertex>> {
+        buf.chunks(VERTEX_SIZE * 4)
+            .map(|mut face| {
+                (0..4)
+                    .map(|_| BlockVertex::read(&mut face).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn merges_matching_faces() {
+        let mut mesher = GreedyMesher::new();
+        let mut buf = vec![];
+        let mut count = mesher.add(0, 0, 0, &top_face(0.0, 0.0, 0), &mut buf);
+        count += mesher.add(1, 0, 0, &top_face(1.0, 0.0, 0), &mut buf);
+        count += mesher.finish(&mut buf);
+
+        assert_eq!(count, 6);
+        let faces = read_faces(&buf);
+        assert_eq!(faces.len(), 1);
+        assert!(faces[0].iter().any(|v| v.x == 2.0));
+    }
+
+    #[test]
+    fn keeps_coplanar_faces() {
+        let mut mesher = GreedyMesher::new();
+        let mut buf = vec![];
+        let mut data = top_face(0.0, 0.0, 0);
+        data.extend(top_face(0.0, 0.0, 16));
+        let mut count = mesher.add(0, 0, 0, &data, &mut buf);
+        count += mesher.finish(&mut buf);
+
+        assert_eq!(count, 12);
+        let faces = read_faces(&buf);
+        assert_eq!(faces.len(), 2);
+        // In the model's order, so the second face is drawn over the first
+        assert_eq!(faces[0][0].tx, 0);
+        assert_eq!(faces[1][0].tx, 16);
+    }
+}
@@ -3,12 +3,18 @@ use crate::render;
 use crate::shared::Direction;
 use crate::world::{self, block};
 use parking_lot::RwLock;
+use std::f32::consts::FRAC_PI_2;
 use std::io::Write;
 use std::sync::Arc;
 
+/// The height of a source block or falling fluid, slightly below a full
+/// block like in vanilla.
+const FULL_HEIGHT: f32 = 8.0 / 9.0;
+
 pub fn render_liquid<W: Write>(
     textures: Arc<RwLock<render::TextureManager>>,
     lava: bool,
+    biome_blend: i32,
     snapshot: &world::ChunkSectionSnapshotGroup,
     x: i32,
     y: i32,
@@ -24,134 +30,178 @@ pub fn render_liquid<W: Write>(
     let mut count = 0;
 
     let (tl, tr, bl, br) = if get_liquid(snapshot, x, y + 1, z).is_some() {
-        (8, 8, 8, 8)
+        (1.0, 1.0, 1.0, 1.0)
     } else {
         (
-            average_liquid_level(get_liquid, snapshot, x, y, z),
-            average_liquid_level(get_liquid, snapshot, x + 1, y, z),
-            average_liquid_level(get_liquid, snapshot, x, y, z + 1),
-            average_liquid_level(get_liquid, snapshot, x + 1, y, z + 1),
+            corner_height(get_liquid, snapshot, x, y, z),
+            corner_height(get_liquid, snapshot, x + 1, y, z),
+            corner_height(get_liquid, snapshot, x, y, z + 1),
+            corner_height(get_liquid, snapshot, x + 1, y, z + 1),
         )
     };
+    // The surface flows downhill, from the higher corners to the lower ones
+    let flow_x = (tl + bl) - (tr + br);
+    let flow_z = (tl + tr) - (bl + br);
+    let flowing = flow_x.abs() > 0.001 || flow_z.abs() > 0.001;
 
-    let (cr, cg, cb) = if lava {
-        (255, 255, 255)
+    let (still, flow) = if lava {
+        ("minecraft:block/lava_still", "minecraft:block/lava_flow")
     } else {
-        // TODO: Choose water color based on biome
-        (63, 118, 228)
-    };
-
-    let tex = match snapshot.get_block(x, y, z) {
-        block::Block::Water { .. } => {
-            render::Renderer::get_texture(&textures, "minecraft:block/water_still")
-        }
-        block::Block::Lava { .. } => {
-            render::Renderer::get_texture(&textures, "minecraft:block/lava_still")
-        }
-        b if b.is_waterlogged() => {
-            render::Renderer::get_texture(&textures, "minecraft:block/water_still")
-        }
-        _ => unreachable!(),
+        ("minecraft:block/water_still", "minecraft:block/water_flow")
     };
-    let ux1 = 0i16;
-    let ux2 = 16i16 * tex.get_width() as i16;
-    let uy1 = 0i16;
-    let uy2 = 16i16 * tex.get_height() as i16;
+    let still = render::Renderer::get_texture(&textures, still);
+    let flow = render::Renderer::get_texture(&textures, flow);
 
     for dir in Direction::all() {
-        // TODO: Fix -1
         let (ox, oy, oz) = dir.get_offset();
-        let special = dir == Direction::Up && (tl < 8 || tr < 8 || bl < 8 || br < 8);
+        let special = dir == Direction::Up && (tl < 1.0 || tr < 1.0 || bl < 1.0 || br < 1.0);
         let block = snapshot.get_block(x + ox, y + oy, z + oz);
-        if special
-            || (!block.get_material().should_cull_against
-                && get_liquid(snapshot, x + ox, y + oy, z + oz).is_none())
+        if !special
+            && (block.get_material().should_cull_against
+                || get_liquid(snapshot, x + ox, y + oy, z + oz).is_some())
         {
-            let verts = BlockVertex::face_by_direction(dir);
-            for vert in verts {
-                let mut vert = vert.clone();
-                vert.tx = tex.get_x() as u16;
-                vert.ty = tex.get_y() as u16;
-                vert.tw = tex.get_width() as u16;
-                vert.th = tex.get_height() as u16;
-                vert.tatlas = tex.atlas as i16;
-                vert.r = cr;
-                vert.g = cg;
-                vert.b = cb;
-
-                if vert.y == 0.0 {
-                    vert.y = y as f32;
-                } else {
-                    let height = match (vert.x as i64, vert.z as i64) {
-                        (0, 0) => ((16.0 / 8.0) * (tl as f32)) as i32,
-                        (_, 0) => ((16.0 / 8.0) * (tr as f32)) as i32,
-                        (0, _) => ((16.0 / 8.0) * (bl as f32)) as i32,
-                        (_, _) => ((16.0 / 8.0) * (br as f32)) as i32,
-                    };
-                    vert.y = (height as f32) / 16.0 + (y as f32);
-                }
-
-                vert.x += x as f32;
-                vert.z += z as f32;
-
-                let (bl, sl) = super::calculate_light(
-                    snapshot,
-                    x,
-                    y,
-                    z,
-                    vert.x as f64,
-                    vert.y as f64,
-                    vert.z as f64,
-                    dir,
-                    !lava,
-                    false,
-                );
-                vert.block_light = bl;
-                vert.sky_light = sl;
-
-                if vert.toffsetx == 0 {
-                    vert.toffsetx = ux1;
-                } else {
-                    vert.toffsetx = ux2;
-                }
+            continue;
+        }
 
-                if vert.toffsety == 0 {
-                    vert.toffsety = uy1;
-                } else {
-                    vert.toffsety = uy2;
+        // Still surfaces show the whole still texture, everything else a
+        // quarter of the flowing one, which is twice the size
+        let tex = if dir == Direction::Down || (dir == Direction::Up && !flowing) {
+            &still
+        } else {
+            &flow
+        };
+        let tw = 16.0 * tex.get_width() as f32;
+        let th = 16.0 * tex.get_height() as f32;
+
+        let verts = BlockVertex::face_by_direction(dir);
+        for vert in verts {
+            let mut vert = vert.clone();
+            vert.tx = tex.get_x() as u16;
+            vert.ty = tex.get_y() as u16;
+            vert.tw = tex.get_width() as u16;
+            vert.th = tex.get_height() as u16;
+            vert.tatlas = tex.atlas as i16;
+            let (cr, cg, cb) = if lava {
+                (255, 255, 255)
+            } else {
+                super::calculate_water(snapshot, x + vert.x as i32, z + vert.z as i32, biome_blend)
+            };
+            vert.r = cr;
+            vert.g = cg;
+            vert.b = cb;
+
+            let (cx, cz) = (vert.x as i64, vert.z as i64);
+            let height = match (cx, cz) {
+                (0, 0) => tl,
+                (_, 0) => tr,
+                (0, _) => bl,
+                (_, _) => br,
+            };
+            let top = vert.y != 0.0;
+
+            let (u, v) = match dir {
+                Direction::Up if flowing => {
+                    // Rotate the flowing texture to point along the slope
+                    let angle = flow_z.atan2(flow_x) - FRAC_PI_2;
+                    let (s, c) = (angle.sin() * 0.25, angle.cos() * 0.25);
+                    match (cx, cz) {
+                        (0, 0) => (0.5 - c - s, 0.5 - c + s),
+                        (0, _) => (0.5 - c + s, 0.5 + c + s),
+                        (_, 0) => (0.5 + c - s, 0.5 - c - s),
+                        (_, _) => (0.5 + c + s, 0.5 + c - s),
+                    }
                 }
-
-                vert.write(buf);
-            }
-            count += 6;
+                Direction::Up | Direction::Down => (
+                    if vert.toffsetx == 0 { 0.0 } else { 1.0 },
+                    if vert.toffsety == 0 { 0.0 } else { 1.0 },
+                ),
+                _ => (
+                    if vert.toffsetx == 0 { 0.0 } else { 0.5 },
+                    if top { (1.0 - height) * 0.5 } else { 0.5 },
+                ),
+            };
+            vert.toffsetx = (u * tw) as i16;
+            vert.toffsety = (v * th) as i16;
+
+            vert.y = if top { height } else { 0.0 } + y as f32;
+            vert.x += x as f32;
+            vert.z += z as f32;
+
+            let (bl, sl) = super::calculate_light(
+                snapshot,
+                x,
+                y,
+                z,
+                vert.x as f64,
+                vert.y as f64,
+                vert.z as f64,
+                dir,
+                !lava,
+                false,
+            );
+            vert.block_light = bl;
+            vert.sky_light = sl;
+
+            vert.write(buf);
         }
+        count += 6;
     }
 
     count
 }
 
-fn average_liquid_level(
+/// Works out the height of the surface at the corner shared by the four
+/// columns touching `x`, `z`, the way vanilla does: full when fluid sits
+/// above any of them, otherwise an average of their heights which favours
+/// nearly full columns, with open neighbours pulling the corner down.
+fn corner_height(
     get: fn(&world::ChunkSectionSnapshotGroup, i32, i32, i32) -> Option<i32>,
     snapshot: &world::ChunkSectionSnapshotGroup,
     x: i32,
     y: i32,
     z: i32,
-) -> i32 {
-    let mut level = 0;
-    for xx in -1..1 {
-        for zz in -1..1 {
-            if get(snapshot, x + xx, y + 1, z + zz).is_some() {
-                return 8;
+) -> f32 {
+    let mut total = 0.0;
+    let mut weight = 0;
+    for &(xx, zz) in &[(-1, -1), (0, -1), (-1, 0), (0, 0)] {
+        if get(snapshot, x + xx, y + 1, z + zz).is_some() {
+            return 1.0;
+        }
+        match get(snapshot, x + xx, y, z + zz) {
+            Some(level) => {
+                let height = level_height(level);
+                if height >= 0.8 {
+                    total += height * 10.0;
+                    weight += 10;
+                } else {
+                    total += height;
+                    weight += 1;
+                }
             }
-            if let Some(l) = get(snapshot, x + xx, y, z + zz) {
-                let nl = 7 - (l & 0x7);
-                if nl > level {
-                    level = nl;
+            None => {
+                if !snapshot
+                    .get_block(x + xx, y, z + zz)
+                    .get_material()
+                    .should_cull_against
+                {
+                    weight += 1;
                 }
             }
         }
     }
-    level
+    if weight == 0 {
+        return 0.0;
+    }
+    total / weight as f32
+}
+
+/// The height of a fluid block from its level, where 0 is a source block,
+/// 1 to 7 flow further and further away and 8 and up are falling.
+fn level_height(level: i32) -> f32 {
+    match level {
+        1..=7 => (8 - level) as f32 / 9.0,
+        _ => FULL_HEIGHT,
+    }
 }
 
 fn get_water_level(
@@ -162,7 +212,7 @@ fn get_water_level(
 ) -> Option<i32> {
     match snapshot.get_block(x, y, z) {
         block::Block::Water { level } => Some(level as i32),
-        b if b.is_waterlogged() => Some(15),
+        b if b.is_waterlogged() => Some(0),
         _ => None,
     }
 }
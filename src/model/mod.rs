@@ -1,14 +1,16 @@
+pub mod greedy;
 pub mod liquid;
+pub mod portal;
 
 use crate::render;
 use crate::resources;
 use crate::shared::Direction;
 use crate::world;
-use crate::world::block::{Block, TintType};
-use byteorder::{NativeEndian, WriteBytesExt};
+use crate::world::block::{Block, StateId, TintType};
+use byteorder::{NativeEndian, ReadBytesExt, WriteBytesExt};
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::io::Write;
+use std::io::{self, Read, Write};
 use std::sync::Arc;
 
 use crate::types::hash::FNVHash;
@@ -28,6 +30,14 @@ pub struct Factory {
 
     grass_colors: image::DynamicImage,
     foliage_colors: image::DynamicImage,
+    /// How many blocks around a block are averaged for its biome tint.
+    biome_blend: i32,
+    /// Bumped whenever the loaded models change, which invalidates the
+    /// per thread block model caches.
+    generation: usize,
+    /// Whether the per thread block model caches are used at all, so
+    /// `--benchmark` can time chunk building with and without them.
+    block_model_cache: bool,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -54,8 +64,40 @@ macro_rules! try_log {
     };
 }
 
+/// What a block state resolved to in its block state file.
+enum CachedModel {
+    Variants(Variants),
+    Multipart(Model),
+}
+
+impl CachedModel {
+    fn render<R: Rng, W: Write>(
+        &self,
+        factory: &Factory,
+        rng: &mut R,
+        snapshot: &world::ChunkSectionSnapshotGroup,
+        x: i32,
+        y: i32,
+        z: i32,
+        buf: &mut W,
+    ) -> usize {
+        match self {
+            CachedModel::Variants(variants) => variants
+                .choose_model(rng)
+                .render(factory, snapshot, x, y, z, buf),
+            // Multipart models were picked when they were cached, so they
+            // don't use the rng again
+            CachedModel::Multipart(model) => model.render(factory, snapshot, x, y, z, buf),
+        }
+    }
+}
+
 thread_local!(
-    static MULTIPART_CACHE: RefCell<HashMap<(Key, Block), Model, BuildHasherDefault<FNVHash>>> = RefCell::new(HashMap::with_hasher(BuildHasherDefault::default()))
+    /// The models each block state resolved to, indexed by the interned
+    /// state id so meshing doesn't have to build the model name and variant
+    /// strings, or even hash the block, for every block. Tagged with the
+    /// factory generation it was filled in.
+    static BLOCK_MODEL_CACHE: RefCell<(usize, Vec<Option<CachedModel>>)> = const { RefCell::new((0, Vec::new())) }
 );
 
 impl Factory {
@@ -70,9 +112,24 @@ impl Factory {
             textures,
 
             models: HashMap::with_hasher(BuildHasherDefault::default()),
+            biome_blend: 1,
+            generation: 0,
+            block_model_cache: true,
         }
     }
 
+    pub fn biome_blend(&self) -> i32 {
+        self.biome_blend
+    }
+
+    pub fn set_biome_blend(&mut self, radius: i32) {
+        self.biome_blend = radius;
+    }
+
+    pub fn set_block_model_cache(&mut self, enabled: bool) {
+        self.block_model_cache = enabled;
+    }
+
     fn load_biome_colors(res: Arc<RwLock<resources::Manager>>, name: &str) -> image::DynamicImage {
         let mut val = match res
             .read()
@@ -88,6 +145,7 @@ impl Factory {
 
     pub fn version_change(&mut self) {
         self.models.clear();
+        self.generation += 1;
         self.grass_colors = Factory::load_biome_colors(self.resources.clone(), "grass");
         self.foliage_colors = Factory::load_biome_colors(self.resources.clone(), "foliage");
     }
@@ -95,7 +153,7 @@ impl Factory {
     fn get_model<R: Rng, W: Write>(
         &self,
         key: Key,
-        block: Block,
+        state: StateId,
         rng: &mut R,
         snapshot: &world::ChunkSectionSnapshotGroup,
         x: i32,
@@ -103,47 +161,78 @@ impl Factory {
         z: i32,
         buf: &mut W,
     ) -> Result<usize, bool> {
-        use std::collections::hash_map::Entry;
         if let Some(model) = self.models.get(&key) {
-            if model.multipart.is_empty() {
+            let block = state.block();
+            let cached = if model.multipart.is_empty() {
                 let variant = block.get_model_variant();
-                if let Some(var) = model.get_variants(&variant) {
-                    let model = var.choose_model(rng);
-                    return Ok(model.render(self, snapshot, x, y, z, buf));
-                }
+                model
+                    .get_variants(&variant)
+                    .map(|var| CachedModel::Variants(var.clone()))
             } else {
-                return MULTIPART_CACHE.with(|cache| {
-                    let mut cache = cache.borrow_mut();
-                    let entry = cache.entry((key.clone(), block));
-                    match entry {
-                        Entry::Occupied(e) => {
-                            return Ok(e.get().render(self, snapshot, x, y, z, buf));
+                let mut res: Option<Model> = None;
+                for rule in &model.multipart {
+                    let ok = Self::eval_rules(block, &rule.rules);
+                    if ok {
+                        if let Some(res) = &mut res {
+                            res.join(rule.apply.choose_model(rng));
+                        } else {
+                            res = Some(rule.apply.choose_model(rng).clone());
                         }
-                        Entry::Vacant(e) => {
-                            let mut res: Option<Model> = None;
-                            for rule in &model.multipart {
-                                let ok = Self::eval_rules(block, &rule.rules);
-                                if ok {
-                                    if let Some(res) = &mut res {
-                                        res.join(rule.apply.choose_model(rng));
-                                    } else {
-                                        res = Some(rule.apply.choose_model(rng).clone());
-                                    }
-                                }
-                            }
-                            if let Some(mdl) = res {
-                                return Ok(e.insert(mdl).render(self, snapshot, x, y, z, buf));
+                    }
+                }
+                res.map(CachedModel::Multipart)
+            };
+            return match cached {
+                Some(cached) => {
+                    let count = cached.render(self, rng, snapshot, x, y, z, buf);
+                    BLOCK_MODEL_CACHE.with(|cache| {
+                        let mut cache = cache.borrow_mut();
+                        if self.block_model_cache && cache.0 == self.generation {
+                            let idx = state.index();
+                            if cache.1.len() <= idx {
+                                cache.1.resize_with(idx + 1, || None);
                             }
+                            cache.1[idx] = Some(cached);
                         }
-                    };
-                    Err(true)
-                });
-            }
-            return Err(true);
+                    });
+                    Ok(count)
+                }
+                None => Err(true),
+            };
         }
         Err(false)
     }
 
+    /// Renders the block from the model it resolved to last time, if it
+    /// has been rendered since the models were last reloaded.
+    fn get_cached_model<R: Rng, W: Write>(
+        &self,
+        state: StateId,
+        rng: &mut R,
+        snapshot: &world::ChunkSectionSnapshotGroup,
+        x: i32,
+        y: i32,
+        z: i32,
+        buf: &mut W,
+    ) -> Option<usize> {
+        if !self.block_model_cache {
+            return None;
+        }
+        BLOCK_MODEL_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            if cache.0 != self.generation {
+                cache.0 = self.generation;
+                cache.1.clear();
+                return None;
+            }
+            cache
+                .1
+                .get(state.index())
+                .and_then(Option::as_ref)
+                .map(|model| model.render(self, rng, snapshot, x, y, z, buf))
+        })
+    }
+
     fn eval_rules(block: Block, rules: &[Rule]) -> bool {
         for mrule in rules {
             match *mrule {
@@ -171,7 +260,7 @@ impl Factory {
 
     pub fn get_state_model<R: Rng, W: Write>(
         models: &Arc<RwLock<Factory>>,
-        block: Block,
+        state: StateId,
         rng: &mut R,
         snapshot: &world::ChunkSectionSnapshotGroup,
         x: i32,
@@ -179,12 +268,19 @@ impl Factory {
         z: i32,
         buf: &mut W,
     ) -> usize {
+        if let Some(count) = models
+            .read()
+            .get_cached_model(state, rng, snapshot, x, y, z, buf)
+        {
+            return count;
+        }
+        let block = state.block();
         let (plugin, name) = block.get_model();
         let key = Key(plugin.to_owned(), name.to_owned());
         let mut missing_variant;
         {
             let m = models.read();
-            match m.get_model(key.clone(), block, rng, snapshot, x, y, z, buf) {
+            match m.get_model(key.clone(), state, rng, snapshot, x, y, z, buf) {
                 Ok(val) => return val,
                 Err(val) => missing_variant = val,
             };
@@ -195,12 +291,21 @@ impl Factory {
             if !m.models.contains_key(&key) && !m.load_model(plugin, name) {
                 error!("Error loading model {}:{}", plugin, name);
             }
-            match m.get_model(key.clone(), block, rng, snapshot, x, y, z, buf) {
+            match m.get_model(key.clone(), state, rng, snapshot, x, y, z, buf) {
                 Ok(val) => return val,
                 Err(val) => missing_variant = val,
             };
         }
-        let ret = Factory::get_state_model(models, Block::Missing {}, rng, snapshot, x, y, z, buf);
+        let ret = Factory::get_state_model(
+            models,
+            StateId::of(Block::Missing {}),
+            rng,
+            snapshot,
+            x,
+            y,
+            z,
+            buf,
+        );
         if !missing_variant {
             // Still no model, replace with placeholder
             let mut m = models.write();
@@ -303,24 +408,24 @@ impl Factory {
             }
         };
 
-        let filename = if let Some((_, model_name)) = model_name.split_once(':') {
-            format!("models/{}.json", model_name)
+        let (plugin, filename) = if let Some((plugin, model_name)) = model_name.split_once(':') {
+            (plugin, format!("models/{}.json", model_name))
         } else if model_name.starts_with("block/") {
-            format!("models/{}.json", &model_name)
+            (plugin, format!("models/{}.json", &model_name))
         } else {
-            format!("models/block/{}.json", model_name)
+            (plugin, format!("models/block/{}.json", model_name))
         };
 
         let file = match self.resources.read().open(plugin, &filename) {
             Some(val) => val,
             None => {
-                error!("Couldn't find model {}", filename);
+                error!("Couldn't find model {}:{}", plugin, filename);
                 return None;
             }
         };
         let block_model: serde_json::Value = try_log!(opt serde_json::from_reader(file));
 
-        let mut model = match self.parse_model(plugin, &block_model) {
+        let mut model = match self.parse_model(&block_model) {
             Some(val) => val,
             None => {
                 error!("Failed to parse model {}", filename);
@@ -335,13 +440,14 @@ impl Factory {
         Some(model)
     }
 
-    fn parse_model(&self, plugin: &str, v: &serde_json::Value) -> Option<RawModel> {
+    /// Parses a model file, starting from the model it inherits from. The
+    /// child's textures and display transforms are layered over the
+    /// parent's, and its elements replace the parent's if it has any.
+    fn parse_model(&self, v: &serde_json::Value) -> Option<RawModel> {
         let parent = v.get("parent").and_then(|v| v.as_str()).unwrap_or("");
         let mut model = if !parent.is_empty() && !parent.starts_with("builtin/") {
-            let parent = match parent.split_once(':') {
-                Some(parent) => parent.1,
-                None => parent,
-            };
+            // Parents without a namespace are vanilla models
+            let (plugin, parent) = parent.split_once(':').unwrap_or(("minecraft", parent));
             let file = match self
                 .resources
                 .read()
@@ -349,18 +455,15 @@ impl Factory {
             {
                 Some(val) => val,
                 None => {
-                    error!("Couldn't find model {}", format!("models/{}.json", parent));
+                    error!("Couldn't find model {}:models/{}.json", plugin, parent);
                     return None;
                 }
             };
             let block_model: serde_json::Value = try_log!(opt serde_json::from_reader(file));
-            match self.parse_model(plugin, &block_model) {
+            match self.parse_model(&block_model) {
                 Some(val) => val,
                 None => {
-                    error!(
-                        "Failed to parse model {}",
-                        format!("models/{}.json", parent)
-                    );
+                    error!("Failed to parse model {}:models/{}.json", plugin, parent);
                     return None;
                 }
             }
@@ -403,47 +506,46 @@ impl Factory {
         }
 
         if let Some(elements) = v.get("elements").and_then(|v| v.as_array()) {
+            model.elements.clear();
             for e in elements {
-                model.elements.push(self.parse_block_element(e));
+                match self.parse_block_element(e) {
+                    Some(element) => model.elements.push(element),
+                    None => error!("Skipping invalid model element {}", e),
+                }
             }
         }
 
-        // TODO: Display
+        if let Some(display) = v.get("display").and_then(|v| v.as_object()) {
+            for (k, v) in display {
+                model.display.insert(
+                    k.clone(),
+                    ModelDisplay {
+                        rotation: parse_vec3(v.get("rotation")).unwrap_or([0.0; 3]),
+                        translation: parse_vec3(v.get("translation")).unwrap_or([0.0; 3]),
+                        scale: parse_vec3(v.get("scale")).unwrap_or([1.0; 3]),
+                    },
+                );
+            }
+        }
 
         Some(model)
     }
 
-    fn parse_block_element(&self, v: &serde_json::Value) -> ModelElement {
+    fn parse_block_element(&self, v: &serde_json::Value) -> Option<ModelElement> {
         let mut element = ModelElement {
-            from: v
-                .get("from")
-                .and_then(|v| v.as_array())
-                .map(|v| {
-                    [
-                        v[0].as_f64().unwrap(),
-                        v[1].as_f64().unwrap(),
-                        v[2].as_f64().unwrap(),
-                    ]
-                })
-                .unwrap(),
-            to: v
-                .get("to")
-                .and_then(|v| v.as_array())
-                .map(|v| {
-                    [
-                        v[0].as_f64().unwrap(),
-                        v[1].as_f64().unwrap(),
-                        v[2].as_f64().unwrap(),
-                    ]
-                })
-                .unwrap(),
-            shade: v.get("shade").and_then(|v| v.as_bool()).unwrap_or(false),
+            from: parse_vec3(v.get("from"))?,
+            to: parse_vec3(v.get("to"))?,
+            shade: v.get("shade").and_then(|v| v.as_bool()).unwrap_or(true),
             faces: [None, None, None, None, None, None],
             rotation: None,
         };
         if let Some(faces) = v.get("faces").and_then(|v| v.as_object()) {
             for dir in Direction::all() {
                 if let Some(face) = faces.get(dir.as_string()) {
+                    let texture = match face.get("texture").and_then(|v| v.as_str()) {
+                        Some(texture) => texture,
+                        None => continue,
+                    };
                     element.faces[dir.index()] = Some(BlockFace {
                         uv: face.get("uv").and_then(|v| v.as_array()).map_or_else(
                             || {
@@ -472,25 +574,16 @@ impl Factory {
                                 uv
                             },
                             |v| {
-                                [
-                                    v[0].as_f64().unwrap(),
-                                    v[1].as_f64().unwrap(),
-                                    v[2].as_f64().unwrap(),
-                                    v[3].as_f64().unwrap(),
-                                ]
+                                let uv =
+                                    |i: usize| v.get(i).and_then(|v| v.as_f64()).unwrap_or(0.0);
+                                [uv(0), uv(1), uv(2), uv(3)]
                             },
                         ),
-                        texture: face
-                            .get("texture")
-                            .and_then(|v| v.as_str())
-                            .map(|v| {
-                                if v.starts_with('#') {
-                                    v.to_owned()
-                                } else {
-                                    "#".to_owned() + v
-                                }
-                            })
-                            .unwrap(),
+                        texture: if texture.starts_with('#') {
+                            texture.to_owned()
+                        } else {
+                            "#".to_owned() + texture
+                        },
                         cull_face: Direction::from_string(
                             face.get("cullface")
                                 .and_then(|v| v.as_str())
@@ -511,16 +604,7 @@ impl Factory {
 
         if let Some(rotation) = v.get("rotation") {
             element.rotation = Some(BlockRotation {
-                origin: rotation.get("origin").and_then(|v| v.as_array()).map_or(
-                    [8.0, 8.0, 8.0],
-                    |v| {
-                        [
-                            v[0].as_f64().unwrap(),
-                            v[1].as_f64().unwrap(),
-                            v[2].as_f64().unwrap(),
-                        ]
-                    },
-                ),
+                origin: parse_vec3(rotation.get("origin")).unwrap_or([8.0, 8.0, 8.0]),
                 axis: rotation
                     .get("axis")
                     .and_then(|v| v.as_str())
@@ -537,7 +621,7 @@ impl Factory {
             });
         }
 
-        element
+        Some(element)
     }
 
     fn process_model(&self, mut raw: RawModel) -> Model {
@@ -793,6 +877,15 @@ impl Factory {
     }
 }
 
+fn parse_vec3(v: Option<&serde_json::Value>) -> Option<[f64; 3]> {
+    let v = v?.as_array()?;
+    Some([
+        v.first()?.as_f64()?,
+        v.get(1)?.as_f64()?,
+        v.get(2)?.as_f64()?,
+    ])
+}
+
 const FACE_ROTATION: &[Direction] = &[
     Direction::North,
     Direction::East,
@@ -878,6 +971,8 @@ struct RawModel {
     y: f64,
     uvlock: bool,
     weight: f64,
+    /// How the model is placed when held, worn or shown in the GUI, by
+    /// the name of the position.
     #[allow(dead_code)]
     display: HashMap<String, ModelDisplay, BuildHasherDefault<FNVHash>>,
     #[allow(dead_code)]
@@ -886,15 +981,15 @@ struct RawModel {
 
 impl RawModel {
     fn lookup_texture(&self, name: &str) -> String {
-        if !name.is_empty() && name.starts_with('#') {
-            let tex = self
-                .texture_vars
-                .get(&name[1..])
-                .cloned()
-                .unwrap_or_else(|| "".to_owned());
-            return self.lookup_texture(&tex);
+        let mut name = name;
+        // Bounded so variables referring to each other can't loop forever
+        for _ in 0..=self.texture_vars.len() {
+            match name.strip_prefix('#') {
+                Some(var) => name = self.texture_vars.get(var).map_or("", |v| v.as_str()),
+                None => return name.to_owned(),
+            }
         }
-        name.to_owned()
+        "".to_owned()
     }
 }
 
@@ -949,7 +1044,6 @@ struct Face {
     vertices: Vec<BlockVertex>,
     vertices_texture: Vec<render::Texture>,
     indices: usize,
-    #[allow(dead_code)]
     shade: bool,
     tint_index: i32,
 }
@@ -999,21 +1093,28 @@ impl Model {
                             snapshot,
                             vert.x as i32,
                             vert.z as i32,
+                            factory.biome_blend,
                             &factory.grass_colors,
                         ),
                         TintType::Foliage => calculate_biome(
                             snapshot,
                             vert.x as i32,
                             vert.z as i32,
+                            factory.biome_blend,
                             &factory.foliage_colors,
                         ),
-                        // TODO: Choose water color based on biome
-                        TintType::Water => (63, 118, 228),
+                        TintType::Water => calculate_water(
+                            snapshot,
+                            vert.x as i32,
+                            vert.z as i32,
+                            factory.biome_blend,
+                        ),
                     }
                 } else {
                     (255, 255, 255)
                 };
-                if face.facing == Direction::West || face.facing == Direction::East {
+                if face.shade && (face.facing == Direction::West || face.facing == Direction::East)
+                {
                     cr = ((cr as f64) * 0.8) as u8;
                     cg = ((cg as f64) * 0.8) as u8;
                     cb = ((cb as f64) * 0.8) as u8;
@@ -1044,18 +1145,21 @@ impl Model {
     }
 }
 
+/// Averages the grass or foliage color of the biomes within `radius` blocks,
+/// so biome borders fade into each other.
 fn calculate_biome(
     snapshot: &world::ChunkSectionSnapshotGroup,
     x: i32,
     z: i32,
+    radius: i32,
     img: &image::DynamicImage,
 ) -> (u8, u8, u8) {
     let mut count = 0;
     let mut r = 0;
     let mut g = 0;
     let mut b = 0;
-    for xx in -1..2 {
-        for zz in -1..2 {
+    for xx in -radius..=radius {
+        for zz in -radius..=radius {
             let bi = snapshot.get_biome(x + xx, z + zz);
             let color_index = bi.get_color_index();
             let ix = color_index & 0xFF;
@@ -1075,6 +1179,28 @@ fn calculate_biome(
     ((r / count) as u8, (g / count) as u8, (b / count) as u8)
 }
 
+fn calculate_water(
+    snapshot: &world::ChunkSectionSnapshotGroup,
+    x: i32,
+    z: i32,
+    radius: i32,
+) -> (u8, u8, u8) {
+    let mut count = 0;
+    let mut r = 0;
+    let mut g = 0;
+    let mut b = 0;
+    for xx in -radius..=radius {
+        for zz in -radius..=radius {
+            let (cr, cg, cb) = snapshot.get_biome(x + xx, z + zz).water_color();
+            r += cr as u32;
+            g += cg as u32;
+            b += cb as u32;
+            count += 1;
+        }
+    }
+    ((r / count) as u8, (g / count) as u8, (b / count) as u8)
+}
+
 fn calculate_light(
     snapshot: &world::ChunkSectionSnapshotGroup,
     orig_x: i32,
@@ -1241,6 +1367,46 @@ impl BlockVertex {
         let _ = w.write_u16::<NativeEndian>(0);
     }
 
+    /// Reads back a vertex written by `write`.
+    pub fn read<R: Read>(r: &mut R) -> io::Result<BlockVertex> {
+        let x = r.read_f32::<NativeEndian>()?;
+        let y = r.read_f32::<NativeEndian>()?;
+        let z = r.read_f32::<NativeEndian>()?;
+        let tx = r.read_u16::<NativeEndian>()?;
+        let ty = r.read_u16::<NativeEndian>()?;
+        let tw = r.read_u16::<NativeEndian>()?;
+        let th = r.read_u16::<NativeEndian>()?;
+        let toffsetx = r.read_i16::<NativeEndian>()?;
+        let toffsety = r.read_i16::<NativeEndian>()?;
+        let tatlas = r.read_i16::<NativeEndian>()?;
+        r.read_i16::<NativeEndian>()?;
+        let red = r.read_u8()?;
+        let green = r.read_u8()?;
+        let blue = r.read_u8()?;
+        r.read_u8()?;
+        let block_light = r.read_u16::<NativeEndian>()?;
+        let sky_light = r.read_u16::<NativeEndian>()?;
+        r.read_u16::<NativeEndian>()?;
+        r.read_u16::<NativeEndian>()?;
+        Ok(BlockVertex {
+            x,
+            y,
+            z,
+            tx,
+            ty,
+            tw,
+            th,
+            toffsetx,
+            toffsety,
+            tatlas,
+            r: red,
+            g: green,
+            b: blue,
+            block_light,
+            sky_light,
+        })
+    }
+
     pub fn face_by_direction(dir: Direction) -> &'static [BlockVertex; 4] {
         match dir {
             Direction::Up => PRECOMPUTED_VERTS[0],
@@ -0,0 +1,72 @@
+use crate::model::BlockVertex;
+use crate::render;
+use crate::shared::Direction;
+use crate::world;
+use parking_lot::RwLock;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Height of the end portal's surface within its block.
+const END_PORTAL_HEIGHT: f32 = 12.0 / 16.0;
+
+/// End portals have no block model in vanilla, they are drawn by a block
+/// entity renderer instead. This draws their surface using the animated
+/// starfield texture.
+pub fn render_end_portal<W: Write>(
+    textures: Arc<RwLock<render::TextureManager>>,
+    snapshot: &world::ChunkSectionSnapshotGroup,
+    x: i32,
+    y: i32,
+    z: i32,
+    buf: &mut W,
+) -> usize {
+    let tex = render::Renderer::get_texture(&textures, "leafish:end_portal");
+    let ux2 = 16i16 * tex.get_width() as i16;
+    let uy2 = 16i16 * tex.get_height() as i16;
+
+    let mut count = 0;
+    for dir in [Direction::Up, Direction::Down].iter().copied() {
+        let (ox, oy, oz) = dir.get_offset();
+        if snapshot
+            .get_block(x + ox, y + oy, z + oz)
+            .get_material()
+            .should_cull_against
+        {
+            continue;
+        }
+        for vert in BlockVertex::face_by_direction(dir) {
+            let mut vert = vert.clone();
+            vert.tx = tex.get_x() as u16;
+            vert.ty = tex.get_y() as u16;
+            vert.tw = tex.get_width() as u16;
+            vert.th = tex.get_height() as u16;
+            vert.tatlas = tex.atlas as i16;
+            vert.r = 255;
+            vert.g = 255;
+            vert.b = 255;
+
+            if dir == Direction::Up {
+                vert.y = END_PORTAL_HEIGHT;
+            }
+            vert.x += x as f32;
+            vert.y += y as f32;
+            vert.z += z as f32;
+
+            // The surface glows on its own
+            vert.block_light = 15 * 4000;
+            vert.sky_light = 15 * 4000;
+
+            if vert.toffsetx != 0 {
+                vert.toffsetx = ux2;
+            }
+            if vert.toffsety != 0 {
+                vert.toffsety = uy2;
+            }
+
+            vert.write(buf);
+        }
+        count += 6;
+    }
+
+    count
+}
@@ -0,0 +1,144 @@
+use crate::render;
+use crate::render::model::{self, ModelHandle};
+use crate::render::Renderer;
+use cgmath::Vector3;
+use rand::Rng;
+use std::sync::Arc;
+
+/// How long a bolt stays visible, in 1/60ths of a second.
+const BOLT_LIFETIME: f64 = 30.0;
+/// How long the sky stays lit up after a strike, in 1/60ths of a second.
+const FLASH_LIFETIME: f64 = 12.0;
+/// Bolts reach this far above the point they strike.
+const BOLT_HEIGHT: f64 = 128.0;
+const SEGMENT_HEIGHT: f64 = 8.0;
+const BOLT_WIDTH: f64 = 0.25;
+
+struct Bolt {
+    /// The corners of the bolt, from the strike point upwards.
+    points: Vec<Vector3<f64>>,
+    remaining: f64,
+}
+
+/// Lightning bolts and the flash of the sky that follows them.
+pub struct LightningEffect {
+    bolts: Vec<Bolt>,
+    flash: f64,
+    model: Option<ModelHandle>,
+    dirty: bool,
+}
+
+impl Default for LightningEffect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LightningEffect {
+    pub fn new() -> Self {
+        Self {
+            bolts: vec![],
+            flash: 0.0,
+            model: None,
+            dirty: false,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.bolts.clear();
+        self.flash = 0.0;
+        self.model.take();
+    }
+
+    /// Adds a bolt striking the given position.
+    pub fn strike(&mut self, position: Vector3<f64>) {
+        let mut rng = rand::thread_rng();
+        let mut points = vec![position];
+        let mut point = position;
+        while point.y < position.y + BOLT_HEIGHT {
+            point.x += rng.gen_range(-1.5..1.5);
+            point.y += SEGMENT_HEIGHT;
+            point.z += rng.gen_range(-1.5..1.5);
+            points.push(point);
+        }
+        self.bolts.push(Bolt {
+            points,
+            remaining: BOLT_LIFETIME,
+        });
+        self.flash = FLASH_LIFETIME;
+        self.dirty = true;
+    }
+
+    /// How strongly the sky is lit up by recent strikes, from 0.0 to 1.0.
+    pub fn flash_level(&self) -> f32 {
+        (self.flash / FLASH_LIFETIME) as f32
+    }
+
+    pub fn tick(&mut self, renderer: Arc<Renderer>, delta: f64) {
+        self.flash = (self.flash - delta).max(0.0);
+        if self.bolts.is_empty() {
+            return;
+        }
+        let count = self.bolts.len();
+        for bolt in &mut self.bolts {
+            bolt.remaining -= delta;
+        }
+        self.bolts.retain(|bolt| bolt.remaining > 0.0);
+        if self.dirty || self.bolts.len() != count {
+            self.dirty = false;
+            self.rebuild_model(renderer);
+        }
+    }
+
+    fn rebuild_model(&mut self, renderer: Arc<Renderer>) {
+        self.model.take();
+        if self.bolts.is_empty() {
+            return;
+        }
+
+        let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "leafish:solid");
+        let textures = [
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex),
+        ];
+        let mut verts = vec![];
+        for bolt in &self.bolts {
+            // Segments are drawn as a stack of one block tall steps between
+            // their corners, so the bolt zig-zags its way up to the clouds
+            for pair in bolt.points.windows(2) {
+                let (from, to) = (pair[0], pair[1]);
+                let steps = (to.y - from.y).ceil() as usize;
+                for step in 0..steps {
+                    let point = from + (to - from) * (step as f64 / steps as f64);
+                    let start = verts.len();
+                    model::append_box(
+                        &mut verts,
+                        (point.x - BOLT_WIDTH / 2.0) as f32,
+                        point.y as f32,
+                        (point.z - BOLT_WIDTH / 2.0) as f32,
+                        BOLT_WIDTH as f32,
+                        1.0,
+                        BOLT_WIDTH as f32,
+                        textures.clone(),
+                    );
+                    for vert in &mut verts[start..] {
+                        vert.r = 220;
+                        vert.g = 230;
+                        vert.b = 255;
+                        vert.a = 200;
+                    }
+                }
+            }
+        }
+
+        self.model = Some(renderer.models.lock().create_model(
+            model::DEFAULT,
+            vec![verts],
+            renderer.clone(),
+        ));
+    }
+}
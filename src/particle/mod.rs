@@ -1,6 +1,9 @@
 use bevy_ecs::prelude::*;
 
 pub mod block_break_effect;
+pub mod lightning;
+pub mod void_fog;
+pub mod weather;
 
 #[derive(Component, Copy, Clone)]
 pub struct EntityMetadata(pub Entity);
@@ -9,3 +12,19 @@ pub struct EntityMetadata(pub Entity);
 pub enum ParticleType {
     BlockBreak,
 }
+
+/// Numbers between 0.0 and 1.0 that stay the same for a column, so it
+/// keeps its particles in the same places while the camera moves around.
+pub(crate) fn column_noise(x: i32, z: i32) -> [f64; 4] {
+    let mut hash = (x as u32 as u64) << 32 | z as u32 as u64;
+    let mut next = || {
+        // splitmix64
+        hash = hash.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = hash;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^= value >> 31;
+        (value >> 11) as f64 / (1u64 << 53) as f64
+    };
+    [next(), next(), next(), next()]
+}
@@ -0,0 +1,176 @@
+use super::column_noise;
+use crate::render;
+use crate::render::model::{self, ModelHandle};
+use crate::render::Renderer;
+use cgmath::{Matrix4, Vector3};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// The fog starts darkening this far above the bottom of the world and is
+/// at its darkest `FOG_DEPTH` blocks below it, well before the void kills.
+const FOG_START: f64 = 8.0;
+const FOG_DEPTH: f64 = 40.0;
+/// How far above the bottom of the world specks start drifting around.
+const SPECK_HEIGHT: f64 = 16.0;
+/// How far from the camera specks are kept, in blocks.
+const RADIUS: i32 = 8;
+/// The share of columns around the camera with specks drifting up them at
+/// the bottom of the world.
+const SPECK_DENSITY: f64 = 0.15;
+/// The vertical distance between the specks drifting up a column.
+const SPECK_SPACING: f64 = 4.0;
+/// How long specks take to fade in and out again, in 1/60ths of a second.
+const SPECK_LIFETIME: f64 = 120.0;
+/// How fast specks drift upwards, in blocks per 1/60th of a second.
+const SPECK_SPEED: f64 = 0.002;
+const SPECK_SIZE: f64 = 1.0 / 16.0;
+/// Specks are split between this many model parts, which fade in and out
+/// one after the other.
+const SPECK_GROUPS: usize = 2;
+
+/// The fog and drifting specks that show up near the bottom of worlds with
+/// a sky, before falling out of the world.
+///
+/// Like the weather, the specks are built into a model for the columns
+/// around the camera and animated by moving and fading the model's parts,
+/// so the model is only rebuilt when the camera moves to another block.
+pub struct VoidEffect {
+    /// The block the camera was in and the speck strength the model was
+    /// built for.
+    center: Option<((i32, i32, i32), f64)>,
+    /// How long the specks have been drifting, in 1/60ths of a second.
+    drift_time: f64,
+    model: Option<ModelHandle>,
+    darkness: f32,
+}
+
+impl Default for VoidEffect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VoidEffect {
+    pub fn new() -> Self {
+        Self {
+            center: None,
+            drift_time: 0.0,
+            model: None,
+            darkness: 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.center = None;
+        self.model.take();
+        self.darkness = 0.0;
+    }
+
+    /// How dark the void fog is around the camera, from 0.0 to 1.0.
+    pub fn darkness(&self) -> f32 {
+        self.darkness
+    }
+
+    /// Updates the fog for a camera at `center` in a world starting at
+    /// `min_y`, and keeps specks drifting around it.
+    pub fn tick(&mut self, renderer: Arc<Renderer>, center: Vector3<f64>, min_y: i32, delta: f64) {
+        let depth = center.y - min_y as f64;
+        self.darkness = ((FOG_START - depth) / (FOG_START + FOG_DEPTH)).clamp(0.0, 1.0) as f32;
+        // The strength changes in steps, so the specks aren't rebuilt every
+        // frame while the camera moves up or down within a block
+        let strength = ((SPECK_HEIGHT - depth) / SPECK_HEIGHT).clamp(0.0, 1.0);
+        let strength = (strength * 20.0).ceil() / 20.0;
+        if strength <= 0.0 {
+            if self.center.is_some() {
+                self.center = None;
+                self.model.take();
+            }
+            return;
+        }
+
+        let block = (
+            center.x.floor() as i32,
+            center.y.floor() as i32,
+            center.z.floor() as i32,
+        );
+        if self.center != Some((block, strength)) {
+            self.center = Some((block, strength));
+            self.rebuild_model(renderer.clone(), block, strength);
+        }
+
+        self.drift_time += delta;
+        if let Some(model) = &self.model {
+            let mut models = renderer.models.lock();
+            if let Some(model) = models.get_model(model) {
+                let offset = (self.drift_time * SPECK_SPEED) % SPECK_SPACING;
+                for group in 0..SPECK_GROUPS {
+                    // Model space has y pointing down
+                    model.matrix[group] =
+                        Matrix4::from_translation(Vector3::new(0.0, -offset as f32, 0.0));
+                    let phase = group as f64 / SPECK_GROUPS as f64;
+                    let fade =
+                        0.5 - 0.5 * (2.0 * PI * (self.drift_time / SPECK_LIFETIME + phase)).cos();
+                    model.colors[group] = [1.0, 1.0, 1.0, fade as f32];
+                }
+            }
+        }
+    }
+
+    fn rebuild_model(&mut self, renderer: Arc<Renderer>, center: (i32, i32, i32), strength: f64) {
+        self.model.take();
+
+        let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "leafish:solid");
+        let textures = [
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex),
+        ];
+        let mut parts = vec![vec![]; SPECK_GROUPS];
+        let (cx, cy, cz) = center;
+        for z in cz - RADIUS..=cz + RADIUS {
+            for x in cx - RADIUS..=cx + RADIUS {
+                let [chance, ox, oz, phase] = column_noise(x, z);
+                if chance >= strength * SPECK_DENSITY {
+                    continue;
+                }
+                let group = chance / (strength * SPECK_DENSITY) * SPECK_GROUPS as f64;
+                let verts = &mut parts[group as usize % SPECK_GROUPS];
+                // The specks end a full spacing below the top, as moving the
+                // model brings them up by up to that much
+                let mut y = (cy - RADIUS) as f64 + SPECK_SPACING * phase;
+                while y <= (cy + RADIUS) as f64 - SPECK_SPACING {
+                    let start = verts.len();
+                    model::append_box(
+                        verts,
+                        (x as f64 + ox - SPECK_SIZE / 2.0) as f32,
+                        y as f32,
+                        (z as f64 + oz - SPECK_SIZE / 2.0) as f32,
+                        SPECK_SIZE as f32,
+                        SPECK_SIZE as f32,
+                        SPECK_SIZE as f32,
+                        textures.clone(),
+                    );
+                    for vert in &mut verts[start..] {
+                        vert.r = 100;
+                        vert.g = 100;
+                        vert.b = 140;
+                        vert.a = 200;
+                    }
+                    y += SPECK_SPACING;
+                }
+            }
+        }
+
+        if parts.iter().all(|part| part.is_empty()) {
+            return;
+        }
+        self.model = Some(renderer.models.lock().create_model(
+            model::DEFAULT,
+            parts,
+            renderer.clone(),
+        ));
+    }
+}
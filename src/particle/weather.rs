@@ -0,0 +1,236 @@
+use super::column_noise;
+use crate::render;
+use crate::render::model::{self, ModelHandle};
+use crate::render::Renderer;
+use crate::shared::Position;
+use crate::world::World;
+use cgmath::{Matrix4, Vector3};
+use std::sync::Arc;
+
+/// How far from the camera drops fall, horizontally and vertically, in
+/// blocks.
+const RADIUS: i32 = 10;
+const HEIGHT: i32 = 10;
+/// The share of columns around the camera with drops falling in them while
+/// it rains at full strength.
+const COLUMN_DENSITY: f32 = 0.4;
+/// The vertical distance between the drops falling in a column.
+const DROP_SPACING: f64 = 4.0;
+/// Falling speeds in blocks per 1/60th of a second.
+const RAIN_SPEED: f64 = 0.25;
+const SNOW_SPEED: f64 = 0.03;
+/// Biomes colder than this get snow instead of rain.
+const SNOW_TEMPERATURE: i16 = 15;
+/// How often the columns are checked for blocks placed or broken in them,
+/// in 1/60ths of a second.
+const COLUMN_REFRESH: f64 = 20.0;
+
+/// The model's part for each kind of drop, which is moved to animate them.
+const RAIN_PART: usize = 0;
+const SNOW_PART: usize = 1;
+
+/// A block column around the camera that drops fall through.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Column {
+    x: i32,
+    z: i32,
+    /// The lowest block in the column the drops fall through, above the
+    /// first roof or ground below the top.
+    bottom: i32,
+    top: i32,
+    snow: bool,
+}
+
+/// Rain and snow falling around the camera.
+///
+/// The drops are built into a model once for the columns they fall through,
+/// and are animated by moving the model down by less than the space between
+/// two drops, so the model is only rebuilt when the columns change.
+pub struct WeatherEffect {
+    columns: Vec<Column>,
+    /// The block the camera was in and the rain level when the columns were
+    /// last found.
+    center: Option<(Position, f32)>,
+    since_refresh: f64,
+    /// How far the drops have fallen, in 1/60ths of a second.
+    fall_time: f64,
+    model: Option<ModelHandle>,
+}
+
+impl Default for WeatherEffect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WeatherEffect {
+    pub fn new() -> Self {
+        Self {
+            columns: vec![],
+            center: None,
+            since_refresh: 0.0,
+            fall_time: 0.0,
+            model: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.columns.clear();
+        self.center = None;
+        self.model.take();
+    }
+
+    /// Moves the falling drops and keeps them falling around `center` for
+    /// the given rain level, which ranges from 0.0 to 1.0.
+    pub fn tick(
+        &mut self,
+        renderer: Arc<Renderer>,
+        world: &World,
+        center: Vector3<f64>,
+        rain_level: f32,
+        delta: f64,
+    ) {
+        if rain_level <= 0.0 {
+            if self.center.is_some() {
+                self.clear();
+            }
+            return;
+        }
+
+        // Rain fades in and out in steps, so columns don't start and stop
+        // dropping every frame while the level changes
+        let rain_level = (rain_level * 20.0).ceil() / 20.0;
+        let center = block_position(center);
+        self.since_refresh += delta;
+        if self.center != Some((center, rain_level)) || self.since_refresh >= COLUMN_REFRESH {
+            self.center = Some((center, rain_level));
+            self.since_refresh = 0.0;
+            let columns = find_columns(world, center, rain_level);
+            if columns != self.columns {
+                self.columns = columns;
+                self.rebuild_model(renderer.clone());
+            }
+        }
+
+        self.fall_time += delta;
+        if let Some(model) = &self.model {
+            let mut models = renderer.models.lock();
+            if let Some(model) = models.get_model(model) {
+                for (part, speed) in [(RAIN_PART, RAIN_SPEED), (SNOW_PART, SNOW_SPEED)] {
+                    let offset = (self.fall_time * speed) % DROP_SPACING;
+                    // Model space has y pointing down
+                    model.matrix[part] =
+                        Matrix4::from_translation(Vector3::new(0.0, offset as f32, 0.0));
+                }
+            }
+        }
+    }
+
+    fn rebuild_model(&mut self, renderer: Arc<Renderer>) {
+        self.model.take();
+        if self.columns.is_empty() {
+            return;
+        }
+
+        let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "leafish:solid");
+        let textures = [
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex.clone()),
+            Some(tex),
+        ];
+        let mut rain = vec![];
+        let mut snow = vec![];
+        for column in &self.columns {
+            let (verts, width, height, colour) = if column.snow {
+                (&mut snow, 1.0 / 16.0, 1.0 / 16.0, (255, 255, 255, 220))
+            } else {
+                (&mut rain, 1.0 / 64.0, 0.5, (64, 96, 255, 140))
+            };
+            let [_, x, z, phase] = column_noise(column.x, column.z);
+            let x = column.x as f64 + x;
+            let z = column.z as f64 + z;
+            // The drops start a full spacing above the bottom, as moving the
+            // model brings them down by up to that much
+            let mut y = column.bottom as f64 + DROP_SPACING * (1.0 + phase);
+            while y <= (column.top + 1) as f64 + DROP_SPACING {
+                let start = verts.len();
+                model::append_box(
+                    verts,
+                    (x - width / 2.0) as f32,
+                    y as f32,
+                    (z - width / 2.0) as f32,
+                    width as f32,
+                    height as f32,
+                    width as f32,
+                    textures.clone(),
+                );
+                for vert in &mut verts[start..] {
+                    vert.r = colour.0;
+                    vert.g = colour.1;
+                    vert.b = colour.2;
+                    vert.a = colour.3;
+                }
+                y += DROP_SPACING;
+            }
+        }
+
+        let mut parts = vec![vec![]; 2];
+        parts[RAIN_PART] = rain;
+        parts[SNOW_PART] = snow;
+        self.model = Some(renderer.models.lock().create_model(
+            model::DEFAULT,
+            parts,
+            renderer.clone(),
+        ));
+    }
+}
+
+/// Finds the columns around `center` drops fall through at the given rain
+/// level.
+fn find_columns(world: &World, center: Position, rain_level: f32) -> Vec<Column> {
+    let mut columns = vec![];
+    for z in center.z - RADIUS..=center.z + RADIUS {
+        for x in center.x - RADIUS..=center.x + RADIUS {
+            let [chance, ..] = column_noise(x, z);
+            let top = center.y + HEIGHT;
+            if chance as f32 >= rain_level * COLUMN_DENSITY
+                || !is_exposed(world, Position::new(x, top, z))
+            {
+                continue;
+            }
+            let mut bottom = top;
+            while bottom > center.y - HEIGHT && is_exposed(world, Position::new(x, bottom - 1, z)) {
+                bottom -= 1;
+            }
+            let snow = world
+                .get_biome(Position::new(x, bottom, z))
+                .is_some_and(|biome| biome.temperature < SNOW_TEMPERATURE);
+            columns.push(Column {
+                x,
+                z,
+                bottom,
+                top,
+                snow,
+            });
+        }
+    }
+    columns
+}
+
+fn block_position(position: Vector3<f64>) -> Position {
+    Position::new(
+        position.x.floor() as i32,
+        position.y.floor() as i32,
+        position.z.floor() as i32,
+    )
+}
+
+/// Whether drops can fall through the block at `position`, as they stop at
+/// roofs and don't fall through blocks.
+fn is_exposed(world: &World, position: Position) -> bool {
+    world.get_sky_light(position) == 15
+        && world.get_block(position).get_collision_boxes().is_empty()
+}
@@ -13,7 +13,8 @@
 // limitations under the License.
 
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
 fn get_dir(dirtype: Option<PathBuf>) -> PathBuf {
     match dirtype {
@@ -40,3 +41,18 @@ pub fn get_cache_dir() -> PathBuf {
 pub fn get_data_dir() -> PathBuf {
     get_dir(dirs::data_dir())
 }
+
+/// Replaces the file at `path` with `contents` by writing a temporary file
+/// next to it and renaming it over the old one, so a crash mid-save leaves
+/// either the old or the new file behind, never a partial one.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    {
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
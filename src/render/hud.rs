@@ -14,6 +14,7 @@
 
 use std::cmp;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -21,10 +22,13 @@ use leafish_protocol::format::Component;
 use log::debug;
 use parking_lot::RwLock;
 use rand::{thread_rng, Rng};
+use shared::Version;
 use winit::keyboard::{Key, NamedKey, PhysicalKey};
 
+use crate::inventory::beacon::Effect;
+use crate::inventory::player_inventory::OFF_HAND_SLOT;
 use crate::inventory::slot_mapping::SlotMapping;
-use crate::inventory::Item;
+use crate::inventory::{Inventory, Item};
 use crate::render;
 use crate::render::Renderer;
 use crate::screen::{Screen, ScreenSystem, ScreenType};
@@ -41,6 +45,22 @@ use std::sync::atomic::Ordering as AtomicOrdering;
 
 // Textures can be found at: assets/minecraft/textures/gui/icons.png
 
+/// How often the debug overlay is rebuilt, so the coordinates follow the player.
+const DEBUG_REFRESH_MS: u128 = 100;
+
+/// How long the held item's name stays above the hotbar after switching slots.
+const HELD_ITEM_NAME_MS: u128 = 3000;
+/// The last part of `HELD_ITEM_NAME_MS` during which the name fades out.
+const HELD_ITEM_NAME_FADE_MS: u128 = 1000;
+/// How long the level number pops after gaining a level.
+const LEVEL_UP_MS: u128 = 500;
+/// How often the effect countdowns are redrawn.
+const EFFECT_REFRESH_MS: u128 = 1000;
+/// How long the view tilts and the screen flashes red after taking damage.
+const HURT_MS: u128 = 500;
+/// How many bands the damage vignette fades over, from the edge inwards.
+const HURT_VIGNETTE_BANDS: usize = 8;
+
 // TODO: read out "regen: bool"
 #[allow(dead_code)]
 pub struct HudContext {
@@ -48,15 +68,15 @@ pub struct HudContext {
     pub debug: bool,
     fps: u32,
     dirty_debug: bool,
-    hardcore: bool,  // TODO: Update this!
-    wither: bool,    // TODO: Update this!
-    poison: bool,    // TODO: Update this!
-    regen: bool,     // TODO: Update this!
-    absorbtion: f32, // TODO: Update this!
+    hardcore: bool, // TODO: Update this!
+    wither: bool,   // TODO: Update this!
+    poison: bool,   // TODO: Update this!
+    regen: bool,    // TODO: Update this!
+    absorbtion: f32,
     last_health_update: u128,
     last_health: f32,
     health: f32,
-    max_health: f32, // TODO: Update this!
+    max_health: f32,
     dirty_health: bool,
     hunger: bool, // TODO: Update this!
     saturation: u8,
@@ -64,11 +84,15 @@ pub struct HudContext {
     last_food: u8,
     food: u8,
     dirty_food: bool,
-    armor: u8, // TODO: Update this!
+    armor: u8,
     dirty_armor: bool,
     pub exp: f32,
     pub exp_level: i32,
     dirty_exp: bool,
+    /// Whether the experience has been received yet, so joining doesn't
+    /// count as a level up.
+    exp_received: bool,
+    leveled_up: bool,
     breath: i16, // TODO: Update this!
     dirty_breath: bool,
     pub slots: Option<Arc<RwLock<SlotMapping>>>,
@@ -76,10 +100,152 @@ pub struct HudContext {
     pub dirty_slots: AtomicBool,
     slot_index: u8,
     dirty_slot_index: bool,
+    dirty_held_item_name: bool,
     pub game_mode: GameMode,
     dirty_game_mode: bool,
     pub action_bar: Option<Component>,
     dirty_action_bar: bool,
+    in_portal: bool,
+    crosshair_style: CrosshairStyle,
+    attack_indicator: bool,
+    dirty_crosshair: bool,
+    /// Attacks per second, which sets how long the attack cooldown takes
+    /// to recharge.
+    attack_speed: f64,
+    last_attack: Instant,
+    effects: Vec<ActiveEffect>,
+    dirty_effects: bool,
+    /// Items that can't be used for a while, by item id, with when the
+    /// cooldown started and how many ticks it lasts.
+    cooldowns: HashMap<i32, (Instant, i32)>,
+    last_damage: Option<Instant>,
+    /// Whether the hurt animation was started for damage the server is
+    /// yet to deal.
+    damage_predicted: bool,
+    chat_visibility: ChatVisibility,
+    /// How dark the void fog is around the camera, 0.0 - 1.0
+    void_darkness: f32,
+    /// Whether the server asked for the debug overlay to leave out where
+    /// the player is.
+    reduced_debug_info: bool,
+    hide_coords: bool,
+    /// Whether the HUD is hidden while taking a screenshot.
+    screenshot_hides_hud: bool,
+    /// A screenshot waiting for the next frame, with whether the HUD was
+    /// shown before it was taken.
+    pending_screenshot: Option<bool>,
+}
+
+/// A status effect the local player has.
+pub struct ActiveEffect {
+    pub effect: Effect,
+    pub amplifier: i8,
+    /// In ticks, from when the effect was received.
+    duration: i32,
+    received: Instant,
+}
+
+impl ActiveEffect {
+    /// Ticks until the effect wears off, or `None` if it lasts forever.
+    pub fn remaining_ticks(&self) -> Option<i32> {
+        // Effects that don't wear off are sent with a duration of -1 since
+        // 1.19.4, before that they used the largest duration the client shows
+        if self.duration < 0 || self.duration >= i16::MAX as i32 {
+            return None;
+        }
+        let elapsed = (self.received.elapsed().as_millis() / 50) as i32;
+        Some((self.duration - elapsed).max(0))
+    }
+
+    /// The countdown vanilla shows next to the effect.
+    fn countdown(&self) -> String {
+        match self.remaining_ticks() {
+            Some(ticks) => {
+                let seconds = ticks / 20;
+                format!("{}:{:02}", seconds / 60, seconds % 60)
+            }
+            None => "**:**".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum CrosshairStyle {
+    Default,
+    Dot,
+    Cross,
+    None,
+}
+
+impl CrosshairStyle {
+    pub const ALL: [CrosshairStyle; 4] = [
+        CrosshairStyle::Default,
+        CrosshairStyle::Dot,
+        CrosshairStyle::Cross,
+        CrosshairStyle::None,
+    ];
+
+    /// The style stored in the crosshair style setting, falling back to the
+    /// default one for unknown values.
+    pub fn from_setting(value: i32) -> Self {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .unwrap_or(CrosshairStyle::Default)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CrosshairStyle::Default => "Default",
+            CrosshairStyle::Dot => "Dot",
+            CrosshairStyle::Cross => "Cross",
+            CrosshairStyle::None => "None",
+        }
+    }
+}
+
+/// Which chat messages are shown, in the order the protocol numbers the
+/// chat modes.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ChatVisibility {
+    Full,
+    CommandsOnly,
+    Hidden,
+}
+
+impl ChatVisibility {
+    pub const ALL: [ChatVisibility; 3] = [
+        ChatVisibility::Full,
+        ChatVisibility::CommandsOnly,
+        ChatVisibility::Hidden,
+    ];
+
+    /// The visibility stored in the chat visibility setting, falling back to
+    /// showing all chat for unknown values.
+    pub fn from_setting(value: i32) -> Self {
+        Self::ALL
+            .get(value as usize)
+            .copied()
+            .unwrap_or(ChatVisibility::Full)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ChatVisibility::Full => "Shown",
+            ChatVisibility::CommandsOnly => "Commands Only",
+            ChatVisibility::Hidden => "Hidden",
+        }
+    }
+
+    /// Whether a message is shown, system messages being command feedback
+    /// and other messages not sent by players.
+    pub fn shows(self, system: bool) -> bool {
+        match self {
+            ChatVisibility::Full => true,
+            ChatVisibility::CommandsOnly => system,
+            ChatVisibility::Hidden => false,
+        }
+    }
 }
 
 impl Default for render::hud::HudContext {
@@ -115,6 +281,8 @@ impl HudContext {
             exp: 0.0, // 0.0 - 1.0
             exp_level: 0,
             dirty_exp: false,
+            exp_received: false,
+            leveled_up: false,
             breath: 0, /*-1*/
             // -1 = disabled (not under water) | 1 bubble = 30 | +2 = broken bubble -- -1 is causing crashes when attempting to join servers!
             dirty_breath: false,
@@ -123,10 +291,28 @@ impl HudContext {
             dirty_slots: AtomicBool::new(false),
             slot_index: 0,
             dirty_slot_index: false,
+            dirty_held_item_name: false,
             game_mode: GameMode::Survival,
             dirty_game_mode: false,
             action_bar: None,
             dirty_action_bar: false,
+            in_portal: false,
+            crosshair_style: CrosshairStyle::Default,
+            attack_indicator: true,
+            dirty_crosshair: false,
+            attack_speed: 4.0,
+            last_attack: Instant::now(),
+            effects: vec![],
+            dirty_effects: false,
+            cooldowns: HashMap::new(),
+            last_damage: None,
+            damage_predicted: false,
+            chat_visibility: ChatVisibility::Full,
+            void_darkness: 0.0,
+            reduced_debug_info: false,
+            hide_coords: false,
+            screenshot_hides_hud: false,
+            pending_screenshot: None,
         }
     }
 
@@ -135,6 +321,14 @@ impl HudContext {
     pub fn update_health_and_food(&mut self, health: f32, food: u8, saturation: u8) {
         let start = SystemTime::now();
         let time = start.duration_since(UNIX_EPOCH).unwrap().as_millis();
+        // The first update only tells us the health we joined with
+        if self.last_health_update != 0 && health < self.health {
+            // Predicted damage already started the animation
+            if !self.damage_predicted || self.hurt_time() <= 0.0 {
+                self.last_damage = Some(Instant::now());
+            }
+            self.damage_predicted = false;
+        }
         self.last_health_update = time;
         self.last_health = self.health;
         self.health = health;
@@ -169,20 +363,246 @@ impl HudContext {
         self.dirty_breath = true;
     }
 
+    pub fn in_portal(&self) -> bool {
+        self.in_portal
+    }
+
+    pub fn set_in_portal(&mut self, in_portal: bool) {
+        self.in_portal = in_portal;
+    }
+
     pub fn update_exp(&mut self, exp: f32, level: i32) {
+        if self.exp_received && level > self.exp_level {
+            self.leveled_up = true;
+        }
+        self.exp_received = true;
         self.exp = exp;
         self.exp_level = level;
         self.dirty_exp = true;
     }
 
+    /// The crosshair style and whether the attack indicator is shown.
+    pub fn crosshair(&self) -> (CrosshairStyle, bool) {
+        (self.crosshair_style, self.attack_indicator)
+    }
+
+    pub fn update_crosshair(&mut self, style: CrosshairStyle, attack_indicator: bool) {
+        if style != self.crosshair_style || attack_indicator != self.attack_indicator {
+            self.crosshair_style = style;
+            self.attack_indicator = attack_indicator;
+            self.dirty_crosshair = true;
+        }
+    }
+
+    /// Returns whether the visibility changed, so the server can be told.
+    pub fn update_chat_visibility(&mut self, chat_visibility: ChatVisibility) -> bool {
+        let changed = chat_visibility != self.chat_visibility;
+        self.chat_visibility = chat_visibility;
+        changed
+    }
+
+    pub fn chat_visibility(&self) -> ChatVisibility {
+        self.chat_visibility
+    }
+
+    pub fn update_reduced_debug_info(&mut self, reduced_debug_info: bool) {
+        if reduced_debug_info != self.reduced_debug_info {
+            self.reduced_debug_info = reduced_debug_info;
+            self.dirty_debug = true;
+        }
+    }
+
+    pub fn hide_coords(&self) -> bool {
+        self.hide_coords
+    }
+
+    pub fn update_hide_coords(&mut self, hide_coords: bool) {
+        if hide_coords != self.hide_coords {
+            self.hide_coords = hide_coords;
+            self.dirty_debug = true;
+        }
+    }
+
+    /// Whether the debug overlay leaves out coordinates, because either the
+    /// server or the user asked for it.
+    pub fn coords_hidden(&self) -> bool {
+        self.reduced_debug_info || self.hide_coords
+    }
+
+    pub fn screenshot_hides_hud(&self) -> bool {
+        self.screenshot_hides_hud
+    }
+
+    pub fn update_screenshot_hides_hud(&mut self, hides_hud: bool) {
+        self.screenshot_hides_hud = hides_hud;
+    }
+
+    pub fn screenshot_pending(&self) -> bool {
+        self.pending_screenshot.is_some()
+    }
+
+    /// Takes a screenshot of the next frame, hiding the HUD for just that
+    /// frame if the user asked for clean screenshots.
+    pub fn request_screenshot(&mut self) {
+        if self.pending_screenshot.is_some() {
+            return;
+        }
+        self.pending_screenshot = Some(self.enabled);
+        if self.screenshot_hides_hud {
+            self.enabled = false;
+        }
+    }
+
+    /// Called after a frame was drawn, returns whether it should be saved as
+    /// a screenshot. Shows the HUD again if it was hidden for it.
+    pub fn finish_screenshot(&mut self) -> bool {
+        match self.pending_screenshot.take() {
+            Some(enabled) => {
+                self.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn update_attack_speed(&mut self, attack_speed: f64) {
+        self.attack_speed = attack_speed;
+    }
+
+    /// Starts recharging the attack cooldown, as swinging or switching
+    /// items does.
+    pub fn reset_attack_cooldown(&mut self) {
+        self.last_attack = Instant::now();
+    }
+
+    /// How far the attack cooldown has recharged, 0.0 - 1.0
+    pub fn attack_strength(&self) -> f64 {
+        if self.attack_speed <= 0.0 {
+            return 1.0;
+        }
+        let ticks = self.last_attack.elapsed().as_secs_f64() * 20.0;
+        let cooldown = 20.0 / self.attack_speed;
+        ((ticks + 0.5) / cooldown).clamp(0.0, 1.0)
+    }
+
+    /// How much of the hurt animation is left, 1.0 right after taking
+    /// damage down to 0.0 once it's over.
+    pub fn hurt_time(&self) -> f64 {
+        self.last_damage.map_or(0.0, |damage| {
+            1.0 - (damage.elapsed().as_millis() as f64 / HURT_MS as f64).min(1.0)
+        })
+    }
+
+    /// Starts the hurt animation for damage the server is expected to deal,
+    /// so it shows without waiting for the server.
+    pub fn predict_damage(&mut self) {
+        self.last_damage = Some(Instant::now());
+        self.damage_predicted = true;
+    }
+
+    /// How far the view tilts while hurt, in radians. Servers don't say
+    /// where the damage came from, so like vanilla the view always tilts
+    /// the same way.
+    pub fn hurt_tilt(&self) -> f64 {
+        let time = self.hurt_time();
+        -(time.powi(4) * std::f64::consts::PI).sin() * 14f64.to_radians()
+    }
+
+    pub fn add_effect(&mut self, effect: Effect, amplifier: i8, duration: i32) {
+        self.effects.retain(|active| active.effect != effect);
+        self.effects.push(ActiveEffect {
+            effect,
+            amplifier,
+            duration,
+            received: Instant::now(),
+        });
+        self.dirty_effects = true;
+    }
+
+    pub fn remove_effect(&mut self, effect: Effect) {
+        self.effects.retain(|active| active.effect != effect);
+        self.dirty_effects = true;
+    }
+
+    pub fn clear_effects(&mut self) {
+        self.effects.clear();
+        self.dirty_effects = true;
+    }
+
+    pub fn has_effect(&self, effect: Effect) -> bool {
+        self.effects.iter().any(|active| {
+            active.effect == effect && active.remaining_ticks().is_none_or(|ticks| ticks > 0)
+        })
+    }
+
+    /// How strongly night vision brightens the world, 0.0 - 1.0. Like in
+    /// vanilla it flickers during the last 10 seconds.
+    pub fn night_vision_level(&self) -> f32 {
+        let Some(night_vision) = self
+            .effects
+            .iter()
+            .find(|active| active.effect == Effect::NightVision)
+        else {
+            return 0.0;
+        };
+        match night_vision.remaining_ticks() {
+            Some(0) => 0.0,
+            Some(ticks) if ticks <= 200 => {
+                0.7 + (ticks as f32 * std::f32::consts::PI * 0.2).sin() * 0.3
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// The level of the given effect, 0 if the player doesn't have it.
+    pub fn effect_level(&self, effect: Effect) -> i32 {
+        self.effects
+            .iter()
+            .find(|active| active.effect == effect)
+            .filter(|active| active.remaining_ticks().is_none_or(|ticks| ticks > 0))
+            .map_or(0, |active| active.amplifier as i32 + 1)
+    }
+
+    pub fn void_darkness(&self) -> f32 {
+        self.void_darkness
+    }
+
+    pub fn update_void_darkness(&mut self, void_darkness: f32) {
+        self.void_darkness = void_darkness;
+    }
+
+    pub fn set_cooldown(&mut self, item_id: i32, ticks: i32) {
+        if ticks > 0 {
+            self.cooldowns.insert(item_id, (Instant::now(), ticks));
+        } else {
+            self.cooldowns.remove(&item_id);
+        }
+    }
+
+    /// How much of the item's cooldown is left, 0.0 - 1.0
+    pub fn cooldown_left(&self, item_id: i32) -> f64 {
+        self.cooldowns.get(&item_id).map_or(0.0, |(start, ticks)| {
+            let elapsed = start.elapsed().as_secs_f64() * 20.0;
+            (1.0 - elapsed / *ticks as f64).clamp(0.0, 1.0)
+        })
+    }
+
     pub fn update_slot_index(&mut self, slot_index: u8) {
+        if slot_index != self.slot_index {
+            self.reset_attack_cooldown();
+        }
         self.slot_index = slot_index;
         self.dirty_slot_index = true;
+        self.dirty_held_item_name = true;
         self.server.as_ref().unwrap().write_packet(HeldItemChange {
             slot: slot_index as i16,
         });
     }
 
+    pub fn fps(&self) -> u32 {
+        self.fps
+    }
+
     pub fn update_fps(&mut self, fps: u32) {
         self.fps = fps;
         if self.debug {
@@ -222,18 +642,44 @@ pub struct Hud {
     health_elements: Vec<ImageRef>,
     armor_elements: Vec<ImageRef>,
     food_elements: Vec<ImageRef>,
+    /// Which food icons are moved down by the starvation shake, rolled
+    /// once per tick.
+    food_shake: [bool; 10],
+    last_food_shake: Instant,
     breath_elements: Vec<ImageRef>,
     exp_elements: Vec<ImageRef>,
     exp_text_elements: Vec<TextRef>,
     slot_elements: Vec<ImageRef>,
     slot_text_elements: Vec<TextRef>,
     slot_index_elements: Vec<ImageRef>,
+    crosshair_elements: Vec<ImageRef>,
+    attack_indicator_elements: Vec<ImageRef>,
+    effect_elements: Vec<ImageRef>,
+    effect_text_elements: Vec<TextRef>,
+    last_effect_tick: Instant,
+    cooldown_elements: Vec<ImageRef>,
     debug_elements: Vec<TextRef>,
     chat_elements: Vec<FormattedRef>,
     chat_background_elements: Vec<ImageRef>,
     action_bar_text_elements: Option<FormattedRef>,
+    held_item_name: Option<FormattedRef>,
+    held_item_name_time: Instant,
+    level_up_time: Option<Instant>,
+    portal_overlay: Option<ImageRef>,
+    /// How far the portal overlay has faded in, 0.0 - 1.0
+    portal_time: f64,
+    blindness_overlay: Option<ImageRef>,
+    /// How far the blindness overlay has faded in, 0.0 - 1.0
+    blindness_time: f64,
+    nausea_overlay: Option<ImageRef>,
+    /// How far the nausea overlay has faded in, 0.0 - 1.0
+    nausea_time: f64,
+    /// Drives the pulsing of the nausea overlay, in ticks.
+    nausea_phase: f64,
+    hurt_elements: Vec<ImageRef>,
     hud_context: Arc<RwLock<HudContext>>,
     last_tick: Instant,
+    last_debug_tick: Instant,
     render_chat: bool,
 }
 
@@ -246,18 +692,38 @@ impl Hud {
             health_elements: vec![],
             armor_elements: vec![],
             food_elements: vec![],
+            food_shake: [false; 10],
+            last_food_shake: Instant::now(),
             breath_elements: vec![],
             exp_elements: vec![],
             exp_text_elements: vec![],
             slot_elements: vec![],
             slot_text_elements: vec![],
             slot_index_elements: vec![],
+            crosshair_elements: vec![],
+            attack_indicator_elements: vec![],
+            effect_elements: vec![],
+            effect_text_elements: vec![],
+            last_effect_tick: Instant::now(),
+            cooldown_elements: vec![],
             debug_elements: vec![],
             chat_elements: vec![],
             chat_background_elements: vec![],
             action_bar_text_elements: None,
+            held_item_name: None,
+            held_item_name_time: Instant::now(),
+            level_up_time: None,
+            portal_overlay: None,
+            portal_time: 0.0,
+            blindness_overlay: None,
+            blindness_time: 0.0,
+            nausea_overlay: None,
+            nausea_time: 0.0,
+            nausea_phase: 0.0,
+            hurt_elements: vec![],
             hud_context,
             last_tick: Instant::now(),
+            last_debug_tick: Instant::now(),
             render_chat: false,
         }
     }
@@ -304,9 +770,19 @@ impl Screen for Hud {
         self.slot_elements.clear();
         self.slot_text_elements.clear();
         self.slot_index_elements.clear();
+        self.crosshair_elements.clear();
+        self.attack_indicator_elements.clear();
+        self.effect_elements.clear();
+        self.effect_text_elements.clear();
+        self.cooldown_elements.clear();
         self.debug_elements.clear();
         self.chat_elements.clear();
         self.chat_background_elements.clear();
+        self.held_item_name = None;
+        self.portal_overlay = None;
+        self.blindness_overlay = None;
+        self.nausea_overlay = None;
+        self.hurt_elements.clear();
     }
 
     fn on_active(
@@ -330,26 +806,33 @@ impl Screen for Hud {
         screen_sys: &ScreenSystem,
         renderer: Arc<render::Renderer>,
         ui_container: &mut ui::Container,
-        _delta: f64,
+        delta: f64,
     ) {
+        // Blindness and the void fog stay, so hiding the HUD can't be used to
+        // see through them
+        self.update_effect_overlays(&renderer, ui_container, delta);
         if !self.hud_context.read().enabled {
             if self.last_enabled {
-                self.on_deactive(screen_sys, renderer, ui_container);
+                self.deinit(screen_sys, renderer, ui_container);
+                self.last_debug_enabled = false;
                 self.last_enabled = false;
             }
             return;
         }
-        if self.hud_context.read().enabled && !self.last_enabled {
-            self.on_active(screen_sys, renderer, ui_container);
+        if !self.last_enabled {
+            self.init(screen_sys, renderer, ui_container);
             self.last_enabled = true;
             return;
         }
-        if self.hud_context.read().debug {
-            self.render_debug(&renderer, ui_container);
-            self.last_debug_enabled = true;
-        } else if self.last_debug_enabled {
+        self.update_portal_overlay(&renderer, ui_container, delta);
+        self.update_hurt_overlay(&renderer, ui_container);
+        let debug = self.hud_context.read().debug;
+        if debug != self.last_debug_enabled {
             self.debug_elements.clear();
-            self.last_debug_enabled = false;
+            if debug {
+                self.render_debug(&renderer, ui_container);
+            }
+            self.last_debug_enabled = debug;
         }
         let game_mode = self.hud_context.read().game_mode;
         if self.hud_context.clone().read().dirty_game_mode {
@@ -384,6 +867,7 @@ impl Screen for Hud {
                 self.food_elements.clear();
                 self.render_food(&renderer, ui_container);
             }
+            self.update_level_up();
             if self.hud_context.read().dirty_exp {
                 self.exp_elements.clear();
                 self.exp_text_elements.clear();
@@ -404,11 +888,33 @@ impl Screen for Hud {
             self.slot_text_elements.clear();
             self.render_slots_items(&renderer, ui_container);
         }
+        self.update_cooldowns(&renderer, ui_container);
         if self.hud_context.read().dirty_slot_index {
             self.slot_index_elements.clear();
             self.render_slot_index(&renderer, ui_container);
         }
-        if self.hud_context.read().dirty_debug {
+        if self.hud_context.read().dirty_held_item_name {
+            self.render_held_item_name(&renderer, ui_container);
+        }
+        self.update_held_item_name();
+        if self.hud_context.read().dirty_crosshair {
+            self.hud_context.write().dirty_crosshair = false;
+            self.crosshair_elements.clear();
+            self.render_crosshair(&renderer, ui_container);
+        }
+        self.update_attack_indicator(&renderer, ui_container);
+        if std::mem::take(&mut self.hud_context.write().dirty_effects)
+            || (!self.effect_elements.is_empty()
+                && self.last_effect_tick.elapsed().as_millis() >= EFFECT_REFRESH_MS)
+        {
+            self.effect_elements.clear();
+            self.effect_text_elements.clear();
+            self.render_effects(&renderer, ui_container);
+        }
+        if debug
+            && (self.hud_context.read().dirty_debug
+                || self.last_debug_tick.elapsed().as_millis() >= DEBUG_REFRESH_MS)
+        {
             self.debug_elements.clear();
             self.render_debug(&renderer, ui_container);
         }
@@ -476,16 +982,7 @@ impl Screen for Hud {
             .inventory_context
             .write()
             .hotbar_index = new_slot;
-        self.hud_context.write().slot_index = new_slot;
-        self.hud_context.write().dirty_slot_index = true;
-        self.hud_context
-            .read()
-            .server
-            .as_ref()
-            .unwrap()
-            .write_packet(HeldItemChange {
-                slot: new_slot as i16,
-            });
+        self.hud_context.write().update_slot_index(new_slot);
     }
 
     fn on_resize(
@@ -547,6 +1044,19 @@ impl Hud {
         Hud::icon_scale_by_dims(screen.safe_width, screen.safe_height)
     }
 
+    /// The number of lines the message takes up once wrapped to the chat width.
+    pub fn chat_lines(renderer: &Arc<Renderer>, message: &Component, scale: f64) -> usize {
+        let (_, height) = ui::Formatted::compute_size(
+            renderer.clone(),
+            message,
+            CHAT_WIDTH * scale,
+            1.0,
+            1.0,
+            1.0,
+        );
+        (height / 18.0) as usize
+    }
+
     pub fn icon_scale_by_dims(width: u32, height: u32) -> f64 {
         // See https://minecraft.fandom.com/wiki/Options#Video_Settings
         (width / 320).min(height / 240).max(1) as f64
@@ -720,6 +1230,17 @@ impl Hud {
         let hud_context = hud_context.read();
         let food = hud_context.food;
         let _last_food = hud_context.last_food;
+        // The food bar shakes once the saturation is used up, the less food
+        // is left the more often it does so.
+        let starving = hud_context.saturation == 0;
+        if !starving {
+            self.food_shake = [false; 10];
+        } else if self.last_food_shake.elapsed().as_millis() >= 50 {
+            self.last_food_shake = Instant::now();
+            let mut rng = thread_rng();
+            let shake = rng.gen_range(0..food as u32 * 3 + 1) == 0;
+            self.food_shake = std::array::from_fn(|_| shake && rng.gen_range(0..2) == 1);
+        }
         let x_offset = icon_scale * 182.0 / 2.0 + icon_scale * 9.0 / 2.0;
         let y_offset = icon_scale * 30.0;
 
@@ -733,8 +1254,13 @@ impl Hud {
 
         drop(hud_context);
 
-        for i in 0..10 {
+        for i in 0..10u8 {
             let x = x_offset - i as f64 * (icon_scale * 8.0) - icon_scale * 9.0;
+            let y_offset = if self.food_shake[i as usize] {
+                y_offset + icon_scale
+            } else {
+                y_offset
+            };
             let image = ui::ImageBuilder::new()
                 .draw_index(HUD_PRIORITY)
                 .texture_coords(((16.0 + j8 * 9.0), 27.0, 9.0, 9.0))
@@ -771,7 +1297,8 @@ impl Hud {
                 Ordering::Greater => debug!("Nothing happens here, but that's probably wrong"),
             }
         }
-        self.hud_context.write().dirty_food = false;
+        // Keep redrawing while starving to animate the shaking
+        self.hud_context.write().dirty_food = starving;
     }
 
     fn render_exp(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
@@ -813,15 +1340,29 @@ impl Hud {
         }
         if hud_context.exp_level > 0 {
             let level_str = format!("{}", hud_context.exp_level);
-            let scale = icon_scale / 2.0;
-            let y = icon_scale * 26.0;
+            // Rises from 0.0 to 1.0 and back while the level up pops
+            let pop = self.level_up_time.map_or(0.0, |time| {
+                let progress = time.elapsed().as_millis() as f64 / LEVEL_UP_MS as f64;
+                (progress.min(1.0) * std::f64::consts::PI).sin()
+            });
+            let grow = 1.0 + pop * 0.5;
+            let scale = icon_scale / 2.0 * grow;
+            // Keep the number centered on the bar while it grows
+            let y = icon_scale * 26.0 - icon_scale * 9.0 * (grow - 1.0) / 2.0;
+            let outline = icon_scale * grow;
+            let colour = (
+                128 + (pop * 127.0) as u8,
+                255,
+                32 + (pop * 160.0) as u8,
+                255,
+            );
             self.exp_text_elements.push(
                 ui::TextBuilder::new()
                     .draw_index(HUD_PRIORITY)
                     .alignment(VAttach::Bottom, HAttach::Center)
                     .scale_x(scale)
                     .scale_y(scale)
-                    .position(icon_scale * 1.0, y)
+                    .position(outline, y)
                     .text(&level_str)
                     .colour((0, 0, 0, 255))
                     .shadow(false)
@@ -833,7 +1374,7 @@ impl Hud {
                     .alignment(VAttach::Bottom, HAttach::Center)
                     .scale_x(scale)
                     .scale_y(scale)
-                    .position(-(icon_scale * 1.0), y)
+                    .position(-outline, y)
                     .text(&level_str)
                     .colour((0, 0, 0, 1))
                     .shadow(false)
@@ -845,7 +1386,7 @@ impl Hud {
                     .alignment(VAttach::Bottom, HAttach::Center)
                     .scale_x(scale)
                     .scale_y(scale)
-                    .position(0.0, y + (icon_scale * 1.0))
+                    .position(0.0, y + outline)
                     .text(&level_str)
                     .colour((0, 0, 0, 255))
                     .shadow(false)
@@ -857,7 +1398,7 @@ impl Hud {
                     .alignment(VAttach::Bottom, HAttach::Center)
                     .scale_x(scale)
                     .scale_y(scale)
-                    .position(0.0, y - (icon_scale * 1.0))
+                    .position(0.0, y - outline)
                     .text(&level_str)
                     .colour((0, 0, 0, 255))
                     .shadow(false)
@@ -871,7 +1412,7 @@ impl Hud {
                     .scale_y(scale)
                     .position(0.0, y)
                     .text(&level_str)
-                    .colour((128, 255, 32, 255))
+                    .colour(colour)
                     .shadow(false)
                     .create(ui_container),
             );
@@ -880,6 +1421,20 @@ impl Hud {
         self.hud_context.write().dirty_exp = false;
     }
 
+    /// Starts the level number pop after a level up and keeps redrawing it
+    /// until the animation is over.
+    fn update_level_up(&mut self) {
+        if std::mem::take(&mut self.hud_context.write().leveled_up) {
+            self.level_up_time = Some(Instant::now());
+        }
+        if let Some(time) = self.level_up_time {
+            if time.elapsed().as_millis() >= LEVEL_UP_MS {
+                self.level_up_time = None;
+            }
+            self.hud_context.write().dirty_exp = true;
+        }
+    }
+
     fn render_slots(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
         let icon_scale = Hud::icon_scale(renderer);
         let image = ui::ImageBuilder::new()
@@ -923,12 +1478,119 @@ impl Hud {
                 }
             }
         }
+        self.render_off_hand(renderer, ui_container);
         self.hud_context
             .write()
             .dirty_slots
             .store(false, AtomicOrdering::Relaxed);
     }
 
+    /// Covers the hotbar items that are cooling down with a white sweep
+    /// shrinking towards the bottom of the slot.
+    fn update_cooldowns(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        self.cooldown_elements.clear();
+        let expired =
+            |(start, ticks): &(Instant, i32)| start.elapsed().as_secs_f64() * 20.0 >= *ticks as f64;
+        if self.hud_context.read().cooldowns.values().any(expired) {
+            self.hud_context
+                .write()
+                .cooldowns
+                .retain(|_, cooldown| !expired(cooldown));
+        }
+        let hud_context = self.hud_context.read();
+        if hud_context.cooldowns.is_empty() {
+            return;
+        }
+        let icon_scale = Hud::icon_scale(renderer);
+        let mut items = vec![];
+        if let Some(inventory) = &hud_context.slots {
+            let inventory = inventory.read();
+            for i in 0..9 {
+                if let Some(item) = inventory.get_item(27 + i as u16) {
+                    // Same positions as in `render_slots_items`
+                    let x = icon_scale * (i as f64 * 20.0 - 80.0);
+                    items.push((x, item));
+                }
+            }
+        }
+        if let Some(server) = hud_context.server.as_ref() {
+            if server.mapped_protocol_version >= Version::V1_9 {
+                let off_hand = server
+                    .inventory_context
+                    .read()
+                    .player_inventory
+                    .read()
+                    .get_item(OFF_HAND_SLOT);
+                if let Some(item) = off_hand {
+                    items.push((-(icon_scale * 109.0), item));
+                }
+            }
+        }
+
+        for (x, item) in items {
+            let left = hud_context.cooldown_left(item.stack.id as i32);
+            if left <= 0.0 {
+                continue;
+            }
+            let height = (16.0 * left).ceil();
+            self.cooldown_elements.push(
+                ui::ImageBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .texture("leafish:solid")
+                    .position(x, icon_scale * 3.0)
+                    .alignment(ui::VAttach::Bottom, ui::HAttach::Center)
+                    .size(icon_scale * 16.0, icon_scale * height)
+                    .colour((255, 255, 255, 127))
+                    .create(ui_container),
+            );
+        }
+    }
+
+    /// Draws the off hand item in its own slot left of the hotbar, the slot
+    /// is only shown while the off hand holds something.
+    fn render_off_hand(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        let item = {
+            let hud_ctx = self.hud_context.read();
+            let Some(server) = hud_ctx.server.as_ref() else {
+                return;
+            };
+            if server.mapped_protocol_version < Version::V1_9 {
+                return;
+            }
+            let item = server
+                .inventory_context
+                .read()
+                .player_inventory
+                .read()
+                .get_item(OFF_HAND_SLOT);
+            item
+        };
+        let Some(item) = item else {
+            return;
+        };
+        let icon_scale = Hud::icon_scale(renderer);
+        let image = ui::ImageBuilder::new()
+            .draw_index(HUD_PRIORITY)
+            .texture_coords((24.0, 22.0, 29.0, 24.0))
+            .position(-(icon_scale * 105.5), 0.0)
+            .alignment(ui::VAttach::Bottom, ui::HAttach::Center)
+            .size(icon_scale * 29.0, icon_scale * 24.0)
+            .texture("minecraft:gui/widgets")
+            .create(ui_container);
+        self.slot_elements.push(image);
+        let (slot_item, stack_count) = self.draw_item(
+            &item,
+            -(icon_scale * 109.0),
+            icon_scale * 3.0,
+            ui_container,
+            renderer,
+        );
+        self.slot_elements.push(slot_item);
+        if let Some(stack_count) = stack_count {
+            self.slot_text_elements.push(stack_count);
+        }
+    }
+
     fn render_slot_index(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
         let icon_scale = Hud::icon_scale(renderer);
         let slot = self.hud_context.read().slot_index as f64;
@@ -950,21 +1612,358 @@ impl Hud {
         self.hud_context.write().dirty_slot_index = false;
     }
 
+    /// Shows the name of the item in the selected hotbar slot above the
+    /// hotbar, like vanilla does after switching slots.
+    fn render_held_item_name(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        self.hud_context.write().dirty_held_item_name = false;
+        let hud_ctx = self.hud_context.read();
+        let item = hud_ctx
+            .slots
+            .as_ref()
+            .and_then(|slots| slots.read().get_item(27 + hud_ctx.slot_index as u16));
+        if let Some(item) = item {
+            let scale = Hud::icon_scale(renderer);
+            // Without health and food bars the name sits closer to the hotbar
+            let y = if matches!(hud_ctx.game_mode, GameMode::Adventure | GameMode::Survival) {
+                scale * 48.0
+            } else {
+                scale * 34.0
+            };
+            let name = item
+                .stack
+                .meta
+                .display_name()
+                .unwrap_or_else(|| Component::from_str(&item.material.display_name()));
+            let text = ui::FormattedBuilder::new()
+                .draw_index(HUD_PRIORITY + 1)
+                .scale_x(scale / 2.0)
+                .scale_y(scale / 2.0)
+                .alignment(VAttach::Bottom, HAttach::Center)
+                .position(0.0, y)
+                .text(name)
+                .create(ui_container);
+            self.held_item_name = Some(text);
+            self.held_item_name_time = Instant::now();
+        } else {
+            self.held_item_name = None;
+        }
+    }
+
+    fn update_held_item_name(&mut self) {
+        let Some(text) = self.held_item_name.as_ref() else {
+            return;
+        };
+        let elapsed = self.held_item_name_time.elapsed().as_millis();
+        if elapsed >= HELD_ITEM_NAME_MS {
+            self.held_item_name = None;
+            return;
+        }
+        let fade_start = HELD_ITEM_NAME_MS - HELD_ITEM_NAME_FADE_MS;
+        if elapsed > fade_start {
+            let transparency = 1.0 - (elapsed - fade_start) as f64 / HELD_ITEM_NAME_FADE_MS as f64;
+            text.borrow_mut().set_transparency(transparency);
+        }
+    }
+
     // TODO: make use of "render_item" (in right hand)
     #[allow(dead_code)]
     fn render_item(&mut self, _renderer: &Arc<Renderer>, _ui_container: &mut Container) {}
 
     fn render_crosshair(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
         let icon_scale = Hud::icon_scale(renderer);
-        let image = ui::ImageBuilder::new()
-            .draw_index(HUD_PRIORITY)
-            .texture_coords((0.0, 0.0, 16.0, 16.0))
-            .position(0.0, 0.0)
-            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
-            .size(icon_scale * 16.0, icon_scale * 16.0)
-            .texture("minecraft:gui/icons")
-            .create(ui_container);
-        self.elements.push(image);
+        let style = self.hud_context.read().crosshair_style;
+        // The dot and cross are drawn as plain bars (width, height)
+        let bars: &[(f64, f64)] = match style {
+            CrosshairStyle::Default => {
+                let image = ui::ImageBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .texture_coords((0.0, 0.0, 16.0, 16.0))
+                    .position(0.0, 0.0)
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .size(icon_scale * 16.0, icon_scale * 16.0)
+                    .texture("minecraft:gui/icons")
+                    .create(ui_container);
+                self.crosshair_elements.push(image);
+                return;
+            }
+            CrosshairStyle::Dot => &[(2.0, 2.0)],
+            CrosshairStyle::Cross => &[(9.0, 1.0), (1.0, 9.0)],
+            CrosshairStyle::None => return,
+        };
+        for (width, height) in bars {
+            let image = ui::ImageBuilder::new()
+                .draw_index(HUD_PRIORITY)
+                .texture("leafish:solid")
+                .position(0.0, 0.0)
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .size(icon_scale * width, icon_scale * height)
+                .colour((255, 255, 255, 200))
+                .create(ui_container);
+            self.crosshair_elements.push(image);
+        }
+    }
+
+    /// Shows how far the attack cooldown has recharged below the crosshair
+    /// while it is recharging. Attacks only have a cooldown since 1.9.
+    fn update_attack_indicator(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        let hud_context = self.hud_context.read();
+        let has_cooldown = hud_context
+            .server
+            .as_ref()
+            .is_some_and(|server| server.mapped_protocol_version >= Version::V1_9);
+        let strength = hud_context.attack_strength();
+        if !hud_context.attack_indicator
+            || hud_context.crosshair_style == CrosshairStyle::None
+            || !has_cooldown
+            || strength >= 1.0
+        {
+            drop(hud_context);
+            self.attack_indicator_elements.clear();
+            return;
+        }
+        drop(hud_context);
+
+        let icon_scale = Hud::icon_scale(renderer);
+        if self.attack_indicator_elements.is_empty() {
+            // The background, then the bar drawn over it
+            for u in [36.0, 52.0] {
+                let image = ui::ImageBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .texture_coords((u, 94.0, 16.0, 4.0))
+                    .position(0.0, icon_scale * 11.0)
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .size(icon_scale * 16.0, icon_scale * 4.0)
+                    .texture("minecraft:gui/icons")
+                    .create(ui_container);
+                self.attack_indicator_elements.push(image);
+            }
+        }
+        // The bar fills up from the left edge of the background
+        let width = (strength * 17.0).floor();
+        let mut fill = self.attack_indicator_elements[1].borrow_mut();
+        fill.texture_coords = (52.0, 94.0, width, 4.0);
+        fill.width = icon_scale * width;
+        fill.x = icon_scale * (width / 2.0 - 8.0);
+    }
+
+    /// Fades the nether portal texture in over the screen while the player
+    /// stands in a portal and back out once they leave it.
+    fn update_portal_overlay(
+        &mut self,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        delta: f64,
+    ) {
+        // Frame deltas are measured in thirds of a game tick
+        let ticks = delta / 3.0;
+        if self.hud_context.read().in_portal {
+            self.portal_time = (self.portal_time + ticks / 80.0).min(1.0);
+        } else {
+            self.portal_time = (self.portal_time - ticks / 20.0).max(0.0);
+        }
+        if self.portal_time <= 0.0 {
+            self.portal_overlay = None;
+            return;
+        }
+
+        let overlay = self.portal_overlay.get_or_insert_with(|| {
+            ui::ImageBuilder::new()
+                .draw_index(OVERLAY_PRIORITY)
+                .texture("minecraft:block/nether_portal")
+                .position(0.0, 0.0)
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .size(0.0, 0.0)
+                .create(ui_container)
+        });
+        let mut overlay = overlay.borrow_mut();
+        (overlay.width, overlay.height) = Hud::screen_size(renderer, ui_container);
+        // Same curve as vanilla, the overlay appears quickly at first
+        let strength = if self.portal_time < 1.0 {
+            self.portal_time.powi(4) * 0.8 + 0.2
+        } else {
+            1.0
+        };
+        overlay.colour.3 = (strength * 255.0) as u8;
+    }
+
+    /// Darkens the screen while blind and makes it swirl while nauseous.
+    fn update_effect_overlays(
+        &mut self,
+        renderer: &Arc<Renderer>,
+        ui_container: &mut Container,
+        delta: f64,
+    ) {
+        // Frame deltas are measured in thirds of a game tick
+        let ticks = delta / 3.0;
+        let (blind, nauseous, void_darkness) = {
+            let hud_context = self.hud_context.read();
+            (
+                hud_context.has_effect(Effect::Blindness),
+                hud_context.has_effect(Effect::Nausea),
+                hud_context.void_darkness as f64,
+            )
+        };
+        let fade = |time: f64, active: bool, fade_ticks: f64| {
+            let step = ticks / fade_ticks;
+            if active {
+                (time + step).min(1.0)
+            } else {
+                (time - step).max(0.0)
+            }
+        };
+        self.blindness_time = fade(self.blindness_time, blind, 20.0);
+        self.nausea_time = fade(self.nausea_time, nauseous, 60.0);
+        let size = Hud::screen_size(renderer, ui_container);
+
+        // The void fog darkens the screen the same way blindness does
+        let darkness = (self.blindness_time * 230.0).max(void_darkness * 245.0);
+        if darkness <= 0.0 {
+            self.blindness_overlay = None;
+        } else {
+            let overlay = self.blindness_overlay.get_or_insert_with(|| {
+                ui::ImageBuilder::new()
+                    .draw_index(OVERLAY_PRIORITY)
+                    .texture("leafish:solid")
+                    .position(0.0, 0.0)
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .size(0.0, 0.0)
+                    .colour((0, 0, 0, 0))
+                    .create(ui_container)
+            });
+            let mut overlay = overlay.borrow_mut();
+            (overlay.width, overlay.height) = size;
+            overlay.colour.3 = darkness as u8;
+        }
+
+        if self.nausea_time <= 0.0 {
+            self.nausea_overlay = None;
+            self.nausea_phase = 0.0;
+        } else {
+            self.nausea_phase += ticks;
+            let overlay = self.nausea_overlay.get_or_insert_with(|| {
+                ui::ImageBuilder::new()
+                    .draw_index(OVERLAY_PRIORITY)
+                    .texture("minecraft:block/nether_portal")
+                    .position(0.0, 0.0)
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .size(0.0, 0.0)
+                    .create(ui_container)
+            });
+            let mut overlay = overlay.borrow_mut();
+            (overlay.width, overlay.height) = size;
+            let pulse = 0.25 + (self.nausea_phase * 0.1).sin() * 0.15;
+            overlay.colour.3 = (self.nausea_time * pulse * 255.0) as u8;
+        }
+    }
+
+    /// Flashes a red vignette after taking damage, made of bands around the
+    /// edge of the screen that get fainter towards the middle.
+    fn update_hurt_overlay(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        let time = self.hud_context.read().hurt_time();
+        if time <= 0.0 {
+            self.hurt_elements.clear();
+            return;
+        }
+        if self.hurt_elements.is_empty() {
+            let sides = [
+                (ui::VAttach::Top, ui::HAttach::Center),
+                (ui::VAttach::Bottom, ui::HAttach::Center),
+                (ui::VAttach::Middle, ui::HAttach::Left),
+                (ui::VAttach::Middle, ui::HAttach::Right),
+            ];
+            for _ in 0..HURT_VIGNETTE_BANDS {
+                for (v_attach, h_attach) in sides {
+                    self.hurt_elements.push(
+                        ui::ImageBuilder::new()
+                            .draw_index(OVERLAY_PRIORITY)
+                            .texture("leafish:solid")
+                            .position(0.0, 0.0)
+                            .alignment(v_attach, h_attach)
+                            .size(0.0, 0.0)
+                            .colour((255, 0, 0, 0))
+                            .create(ui_container),
+                    );
+                }
+            }
+        }
+
+        let (width, height) = Hud::screen_size(renderer, ui_container);
+        let band = width.min(height) * 0.2 / HURT_VIGNETTE_BANDS as f64;
+        for (i, bands) in self.hurt_elements.chunks(4).enumerate() {
+            let inset = band * i as f64;
+            let strength = 1.0 - i as f64 / HURT_VIGNETTE_BANDS as f64;
+            let alpha = (time * strength * 120.0) as u8;
+            for (side, element) in bands.iter().enumerate() {
+                let mut element = element.borrow_mut();
+                if side < 2 {
+                    (element.x, element.y) = (0.0, inset);
+                    (element.width, element.height) = (width - inset * 2.0, band);
+                } else {
+                    (element.x, element.y) = (inset, 0.0);
+                    (element.width, element.height) = (band, height - (inset + band) * 2.0);
+                }
+                element.colour.3 = alpha;
+            }
+        }
+    }
+
+    /// The size of an element covering the whole screen.
+    fn screen_size(renderer: &Arc<Renderer>, ui_container: &Container) -> (f64, f64) {
+        match ui_container.mode {
+            ui::Mode::Unscaled(scale) => (854.0 / scale, 480.0 / scale),
+            ui::Mode::Scaled => {
+                let screen = renderer.screen_data.read();
+                (screen.width as f64, screen.height as f64)
+            }
+        }
+    }
+
+    /// Shows the player's effects in the top right corner, with the time
+    /// left below each of them.
+    fn render_effects(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        self.last_effect_tick = Instant::now();
+        let icon_scale = Hud::icon_scale(renderer);
+        let hud_context = self.hud_context.read();
+        let effects = hud_context
+            .effects
+            .iter()
+            .filter(|active| active.remaining_ticks().is_none_or(|ticks| ticks > 0));
+        for (i, active) in effects.enumerate() {
+            let x = icon_scale * (1.0 + i as f64 * 25.0);
+            let y = icon_scale;
+            self.effect_elements.push(
+                ui::ImageBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .texture("leafish:solid")
+                    .position(x, y)
+                    .alignment(ui::VAttach::Top, ui::HAttach::Right)
+                    .size(icon_scale * 24.0, icon_scale * 33.0)
+                    .colour((0, 0, 0, 100))
+                    .create(ui_container),
+            );
+            self.effect_elements.push(
+                ui::ImageBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .texture(active.effect.get_texture())
+                    .position(x + icon_scale * 3.0, y + icon_scale * 3.0)
+                    .alignment(ui::VAttach::Top, ui::HAttach::Right)
+                    .size(icon_scale * 18.0, icon_scale * 18.0)
+                    .create(ui_container),
+            );
+
+            let countdown = active.countdown();
+            let width = renderer.ui.lock().size_of_string(&countdown) * icon_scale / 2.0;
+            self.effect_text_elements.push(
+                ui::TextBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .text(countdown)
+                    .scale_x(icon_scale / 2.0)
+                    .scale_y(icon_scale / 2.0)
+                    .position(x + (icon_scale * 24.0 - width) / 2.0, y + icon_scale * 23.0)
+                    .alignment(ui::VAttach::Top, ui::HAttach::Right)
+                    .create(ui_container),
+            );
+        }
     }
 
     fn render_breath(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
@@ -1013,24 +2012,103 @@ impl Hud {
     }
 
     pub fn render_debug(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
-        let hud_context = self.hud_context.read();
+        self.last_debug_tick = Instant::now();
+        // Don't hold the context while querying the server, it locks the
+        // context itself
+        let (fps, server, coords_hidden) = {
+            let mut hud_context = self.hud_context.write();
+            hud_context.dirty_debug = false;
+            (
+                hud_context.fps,
+                hud_context.server.clone(),
+                hud_context.coords_hidden(),
+            )
+        };
         let icon_scale = Hud::icon_scale(renderer);
         let scale = icon_scale / 2.0;
-        self.debug_elements.push(
-            ui::TextBuilder::new()
-                .draw_index(HUD_PRIORITY)
-                .alignment(VAttach::Top, HAttach::Left)
-                .scale_x(scale)
-                .scale_y(scale)
-                .position(icon_scale, icon_scale)
-                .text(format!("FPS: {}", hud_context.fps))
-                .colour((0, 102, 204, 255))
-                .shadow(false)
-                .create(ui_container),
-        );
+
+        let mut lines = vec![format!("FPS: {}", fps)];
+        if let Some(server) = server {
+            lines.extend(Hud::debug_lines(&server, coords_hidden));
+        }
+        for (i, line) in lines.into_iter().enumerate() {
+            self.debug_elements.push(
+                ui::TextBuilder::new()
+                    .draw_index(HUD_PRIORITY)
+                    .alignment(VAttach::Top, HAttach::Left)
+                    .scale_x(scale)
+                    .scale_y(scale)
+                    .position(icon_scale, icon_scale + i as f64 * 10.0 * scale)
+                    .text(line)
+                    .colour((0, 102, 204, 255))
+                    .shadow(false)
+                    .create(ui_container),
+            );
+        }
+    }
+
+    fn debug_lines(server: &Server, coords_hidden: bool) -> Vec<String> {
+        let mut lines = vec![];
+        if let Some(ping) = server.ping() {
+            lines.push(format!("Ping: {} ms", ping));
+        }
+        if let Some(player) = server.player.load().as_ref() {
+            let entities = server.entities.read();
+            let position = entities.world.get::<crate::entity::Position>(player.1);
+            let rotation = entities.world.get::<crate::entity::Rotation>(player.1);
+            if let Some(position) = position.filter(|_| !coords_hidden) {
+                let pos = position.position;
+                let (bx, by, bz) = (
+                    pos.x.floor() as i32,
+                    pos.y.floor() as i32,
+                    pos.z.floor() as i32,
+                );
+                lines.push(format!("XYZ: {:.3} / {:.5} / {:.3}", pos.x, pos.y, pos.z));
+                lines.push(format!("Block: {} {} {}", bx, by, bz));
+                lines.push(format!(
+                    "Chunk: {} {} {} in {} {} {}",
+                    bx & 0xF,
+                    by & 0xF,
+                    bz & 0xF,
+                    bx >> 4,
+                    by >> 4,
+                    bz >> 4
+                ));
+            }
+            if let Some(rotation) = rotation {
+                // Convert to the angles the server uses
+                let yaw = (-rotation.yaw.to_degrees()).rem_euclid(360.0);
+                let pitch = (-rotation.pitch.to_degrees() + 180.0).min(90.0);
+                let facing = match ((yaw / 90.0).round() as i32) & 3 {
+                    0 => "south (Towards positive Z)",
+                    1 => "west (Towards negative X)",
+                    2 => "north (Towards negative Z)",
+                    _ => "east (Towards positive X)",
+                };
+                let yaw = if yaw > 180.0 { yaw - 360.0 } else { yaw };
+                lines.push(format!("Facing: {} ({:.1} / {:.1})", facing, yaw, pitch));
+            }
+        }
+        if let Some((pos, block)) = server.target_block() {
+            let (namespace, name) = block.get_model();
+            if coords_hidden {
+                lines.push(format!("Looking at: {}:{}", namespace, name));
+            } else {
+                lines.push(format!(
+                    "Looking at: {} {} {} ({}:{})",
+                    pos.x, pos.y, pos.z, namespace, name
+                ));
+            }
+        }
+        lines
     }
 
     fn render_chat(&mut self, renderer: &Arc<Renderer>, ui_container: &mut Container) {
+        if self.hud_context.read().chat_visibility == ChatVisibility::Hidden {
+            self.chat_elements.clear();
+            self.chat_background_elements.clear();
+            return;
+        }
         let now = Instant::now();
         if now.duration_since(self.last_tick).as_millis() >= 50 {
             self.last_tick = now;
@@ -1049,10 +2127,7 @@ impl Hud {
 
             let mut component_lines = 0;
             for message in messages.iter().take(cmp::min(10, history_size)) {
-                let lines = (renderer.ui.lock().size_of_string(&message.1.to_string())
-                    / (CHAT_WIDTH * scale))
-                    .ceil() as u8;
-                component_lines += lines;
+                component_lines += Hud::chat_lines(renderer, &message.1, scale);
             }
 
             if history_size > 0 {
@@ -1074,9 +2149,7 @@ impl Hud {
 
             let mut component_lines = 0;
             for message in messages.iter().take(cmp::min(10, history_size)).enumerate() {
-                let lines = (renderer.ui.lock().size_of_string(&message.1 .1.to_string())
-                    / (CHAT_WIDTH * scale))
-                    .ceil() as u8;
+                let lines = Hud::chat_lines(renderer, &message.1 .1, scale);
                 let transparency = if message.1 .0 >= FADE_OUT_START_TICKS {
                     1.0
                 } else {
@@ -1173,5 +2246,8 @@ impl Hud {
 
 pub const CHAT_WIDTH: f64 = 490.0 / 2.0;
 const HUD_PRIORITY: isize = -2;
+/// Full screen overlays like the portal one go underneath the rest of the
+/// HUD, which stays readable on top of them.
+const OVERLAY_PRIORITY: isize = HUD_PRIORITY - 1;
 pub const START_TICKS: usize = 10 * 20;
 pub const FADE_OUT_START_TICKS: usize = 20;
@@ -13,6 +13,8 @@ pub struct InventoryWindow {
     pub elements: Vec<Vec<ImageRef>>,
     pub text_elements: Vec<Vec<TextRef>>,
     pub formatted_elements: Vec<FormattedRef>,
+    pub tooltip_elements: Vec<ImageRef>,
+    pub tooltip_text_elements: Vec<TextRef>,
     pub cursor_element: Vec<ImageRef>,
     pub text_box: Vec<TextBoxRef>,
     pub inventory: Arc<RwLock<dyn Inventory + Sync + Send>>,
@@ -98,17 +100,43 @@ impl Screen for InventoryWindow {
         );
     }
 
+    fn on_scroll(&mut self, x: f64, y: f64) {
+        self.inventory.clone().write().on_scroll(x, y);
+    }
+
     fn on_key_press(&mut self, key: (Key, PhysicalKey), down: bool, repeat: bool, game: &Game) {
-        let is_inv = if let PhysicalKey::Code(code) = key.1 {
-            game.keybinds.get(code, &key.0).map(|kb| kb.action) == Some(Actionkey::OpenInv)
+        let action = if let PhysicalKey::Code(code) = key.1 {
+            game.keybinds.get(code, &key.0).map(|kb| kb.action)
         } else {
-            false
+            None
         };
-        if (key.0 == Key::Named(NamedKey::Escape) || is_inv) && down && !repeat {
+        if !down || repeat {
+            return;
+        }
+        if key.0 == Key::Named(NamedKey::Escape) || action == Some(Actionkey::OpenInv) {
             self.inventory_context
                 .write()
                 .try_close_inventory(&game.screen_sys, false);
+            return;
         }
+        if action == Some(Actionkey::SwapHands) {
+            self.inventory_context.write().on_swap_hands_key();
+            return;
+        }
+        // The hotbar keys swap the hovered slot with a hotbar slot
+        let number = match action {
+            Some(Actionkey::Hotbar1) => 1,
+            Some(Actionkey::Hotbar2) => 2,
+            Some(Actionkey::Hotbar3) => 3,
+            Some(Actionkey::Hotbar4) => 4,
+            Some(Actionkey::Hotbar5) => 5,
+            Some(Actionkey::Hotbar6) => 6,
+            Some(Actionkey::Hotbar7) => 7,
+            Some(Actionkey::Hotbar8) => 8,
+            Some(Actionkey::Hotbar9) => 9,
+            _ => return,
+        };
+        self.inventory_context.write().on_number_key(number);
     }
 
     fn is_closable(&self) -> bool {
@@ -133,6 +161,8 @@ impl InventoryWindow {
             cursor_element: vec![],
             text_box: vec![],
             formatted_elements: vec![],
+            tooltip_elements: vec![],
+            tooltip_text_elements: vec![],
         }
     }
 }
@@ -147,9 +177,34 @@ impl InventoryWindow {
         ui_container: &mut Container,
         renderer: &Arc<Renderer>,
         v_attach: VAttach,
+    ) {
+        Self::draw_icon(
+            item.material.texture_locations(),
+            item.stack.count,
+            x,
+            y,
+            elements,
+            text_elements,
+            ui_container,
+            renderer,
+            v_attach,
+        );
+    }
+
+    /// Draws an item icon from its item and block texture locations, the
+    /// block texture is used if the item has none of its own.
+    pub fn draw_icon(
+        textures: (String, String),
+        count: isize,
+        x: f64,
+        y: f64,
+        elements: &mut Vec<ImageRef>,
+        text_elements: &mut Vec<TextRef>,
+        ui_container: &mut Container,
+        renderer: &Arc<Renderer>,
+        v_attach: VAttach,
     ) {
         let icon_scale = Hud::icon_scale(renderer);
-        let textures = item.material.texture_locations();
         let texture =
             if let Some(tex) = Renderer::get_texture_optional(&renderer.textures, &textures.0) {
                 if tex.dummy {
@@ -169,11 +224,11 @@ impl InventoryWindow {
             .create(ui_container);
         elements.push(image);
 
-        if item.stack.count != 1 {
+        if count != 1 {
             let text = ui::TextBuilder::new()
                 .scale_x(icon_scale / 2.25)
                 .scale_y(icon_scale / 2.25)
-                .text(item.stack.count.to_string())
+                .text(count.to_string())
                 .position(x + icon_scale * 6.0, y + icon_scale * 9.0)
                 .alignment(v_attach, ui::HAttach::Left)
                 .colour((255, 255, 255, 255))
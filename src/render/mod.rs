@@ -30,8 +30,10 @@ use crate::resources;
 use byteorder::{NativeEndian, WriteBytesExt};
 use cgmath::prelude::*;
 use image::{GenericImage, GenericImageView, RgbaImage};
-use log::error;
+use log::{error, warn};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::io::Write;
 use std::sync::Arc;
 
@@ -51,6 +53,20 @@ pub struct Camera {
     pub pos: cgmath::Point3<f64>,
     pub yaw: f64,
     pub pitch: f64,
+    pub shake: CameraShake,
+}
+
+/// Moves and tilts the view without changing where the player looks, so
+/// aiming isn't affected.
+#[derive(Clone, Copy, Default)]
+pub struct CameraShake {
+    /// Offsets along the screen's axes, in blocks.
+    pub x: f64,
+    pub y: f64,
+    /// Rotation around the view direction, in radians.
+    pub roll: f64,
+    /// Rotation around the screen's horizontal axis, in radians.
+    pub pitch: f64,
 }
 
 pub struct Renderer {
@@ -103,6 +119,8 @@ pub struct LightData {
     // Light rendering
     pub light_level: f32,
     pub sky_offset: f32,
+    /// Whether chunks are lit in linear color space instead of sRGB.
+    pub linear_lighting: bool,
 }
 
 #[derive(Copy, Clone)]
@@ -156,6 +174,7 @@ init_shader! {
             required texture => "textures",
             required light_level => "lightLevel",
             required sky_offset => "skyOffset",
+            required linear_lighting => "linearLighting",
         },
     }
 }
@@ -178,6 +197,7 @@ init_shader! {
             required texture => "textures",
             required light_level => "lightLevel",
             required sky_offset => "skyOffset",
+            required linear_lighting => "linearLighting",
         },
     }
 }
@@ -250,6 +270,7 @@ impl Renderer {
                 pos: cgmath::Point3::new(0.0, 0.0, 0.0),
                 yaw: 0.0,
                 pitch: ::std::f64::consts::PI,
+                shake: CameraShake::default(),
             }),
             perspective_matrix: Mutex::new(cgmath::Matrix4::identity()),
             camera_matrix: Mutex::new(cgmath::Matrix4::identity()),
@@ -267,6 +288,7 @@ impl Renderer {
             light_data: Mutex::new(LightData {
                 light_level: 0.8,
                 sky_offset: 1.0,
+                linear_lighting: false,
             }),
             element_buffer_data: Mutex::new(ElementBufferData {
                 element_buffer: gl::Buffer::new(),
@@ -340,8 +362,15 @@ impl Renderer {
             cgmath::Vector3::new(0.0, -1.0, 0.0),
         );
         drop(view_vec);
+        let shake = tmp_cam.shake;
+        let shake_matrix = cgmath::Matrix4::from_translation(cgmath::Vector3::new(
+            shake.x as f32,
+            shake.y as f32,
+            0.0,
+        )) * cgmath::Matrix4::from_angle_z(cgmath::Rad(shake.roll as f32))
+            * cgmath::Matrix4::from_angle_x(cgmath::Rad(shake.pitch as f32));
         *self.camera_matrix.lock() =
-            camera_matrix * cgmath::Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0);
+            shake_matrix * camera_matrix * cgmath::Matrix4::from_nonuniform_scale(-1.0, 1.0, 1.0);
         *self.frustum.lock() = collision::Frustum::from_matrix4(
             *self.perspective_matrix.lock() * *self.camera_matrix.lock(),
         )
@@ -414,6 +443,15 @@ impl Renderer {
                 .chunk_shader
                 .sky_offset
                 .set_float(self.light_data.lock().sky_offset);
+            self.chunk_render_data
+                .lock()
+                .chunk_shader
+                .linear_lighting
+                .set_float(if self.light_data.lock().linear_lighting {
+                    1.0
+                } else {
+                    0.0
+                });
 
             let tmp_world = world.as_ref().unwrap().clone();
 
@@ -494,6 +532,11 @@ impl Renderer {
                     .chunk_shader_alpha
                     .sky_offset
                     .set_float(light_data.sky_offset);
+                self.chunk_render_data
+                    .lock()
+                    .chunk_shader_alpha
+                    .linear_lighting
+                    .set_float(if light_data.linear_lighting { 1.0 } else { 0.0 });
 
                 // Copy the depth buffer
                 let chunk_data = self.chunk_render_data.lock();
@@ -854,12 +897,16 @@ impl Renderer {
         self.do_pending_textures();
 
         for ani in &mut self.textures.write().animated_textures {
-            if ani.remaining_time <= 0.0 {
+            ani.remaining_time -= delta / 3.0;
+            // Interpolated textures blend into the next frame continuously
+            let mut changed = ani.interpolate;
+            while ani.remaining_time <= 0.0 {
                 ani.current_frame = (ani.current_frame + 1) % ani.frames.len();
                 ani.remaining_time += ani.frames[ani.current_frame].time as f64;
-                let offset =
-                    ani.texture.width * ani.texture.width * ani.frames[ani.current_frame].index * 4;
-                let offset2 = offset + ani.texture.width * ani.texture.width * 4;
+                changed = true;
+            }
+            if changed {
+                let data = ani.current_data();
                 self.texture_data.lock().gl_texture.sub_image_3d(
                     gl::TEXTURE_2D_ARRAY,
                     0,
@@ -871,10 +918,8 @@ impl Renderer {
                     1,
                     gl::RGBA,
                     gl::UNSIGNED_BYTE,
-                    &ani.data[offset..offset2],
+                    &data,
                 );
-            } else {
-                ani.remaining_time -= delta / 3.0;
             }
         }
     }
@@ -1477,6 +1522,10 @@ impl TextureManager {
         } else {
             ("minecraft", name)
         };
+        if plugin == "leafish" && name == "end_portal" {
+            self.load_end_portal();
+            return;
+        }
         let path = if plugin != "global" {
             format!("textures/{}.png", name)
         } else {
@@ -1491,8 +1540,8 @@ impl TextureManager {
                 // Might be animated
                 if (name.starts_with("block/") || name.starts_with("item/")) && width != height {
                     let id = img.to_rgba8().into_vec();
-                    let frame = id[..(width * width * 4) as usize].to_owned();
                     if let Some(mut ani) = self.load_animation(plugin, name, &img, id) {
+                        let frame = ani.frame_data(0).to_owned();
                         ani.texture = self.put_texture(plugin, name, width, width, frame);
                         self.animated_textures.push(ani);
                         return;
@@ -1505,6 +1554,82 @@ impl TextureManager {
         self.insert_texture_dummy(plugin, name);
     }
 
+    /// Builds the end portal's starfield as an animated texture, layering
+    /// the star texture at different scales, tints and scroll speeds much
+    /// like vanilla's end portal shader.
+    fn load_end_portal(&mut self) {
+        const SIZE: u32 = 64;
+        const FRAMES: u32 = 64;
+        // Teal and blue tints close to the ones vanilla picks per layer
+        const LAYERS: [[f32; 3]; 6] = [
+            [0.10, 0.40, 0.50],
+            [0.16, 0.35, 0.48],
+            [0.06, 0.44, 0.42],
+            [0.20, 0.30, 0.52],
+            [0.08, 0.36, 0.46],
+            [0.14, 0.42, 0.55],
+        ];
+        let res = self.resources.clone();
+        let stars = res
+            .read()
+            .open("minecraft", "textures/entity/end_portal.png")
+            .and_then(|mut val| {
+                let mut data = Vec::new();
+                val.read_to_end(&mut data).ok()?;
+                image::load_from_memory(&data).ok()
+            })
+            .map(|img| img.to_rgba8());
+        let stars = match stars {
+            Some(stars) => stars,
+            None => {
+                self.insert_texture_dummy("leafish", "end_portal");
+                return;
+            }
+        };
+        let (width, height) = stars.dimensions();
+
+        let mut data = Vec::with_capacity((SIZE * SIZE * 4 * FRAMES) as usize);
+        for frame in 0..FRAMES {
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let mut col = [0.03, 0.05, 0.08];
+                    for (i, tint) in LAYERS.iter().enumerate() {
+                        let layer = i as u32 + 1;
+                        // Each layer scrolls a whole number of times per
+                        // cycle so the last frame runs into the first
+                        let sx = (x * layer + frame * width * layer / FRAMES) % width;
+                        let sy = (y * layer + frame * height / FRAMES) % height;
+                        let star = stars.get_pixel(sx, sy).0;
+                        let brightness = (star[0] as f32 + star[1] as f32 + star[2] as f32)
+                            / (3.0 * 255.0)
+                            * (star[3] as f32 / 255.0);
+                        for (c, t) in col.iter_mut().zip(tint.iter()) {
+                            *c += brightness * t;
+                        }
+                    }
+                    for c in &col {
+                        data.push((c.min(1.0) * 255.0) as u8);
+                    }
+                    data.push(255);
+                }
+            }
+        }
+
+        let frame = data[..(SIZE * SIZE * 4) as usize].to_owned();
+        let texture = self.put_texture("leafish", "end_portal", SIZE, SIZE, frame);
+        self.animated_textures.push(AnimatedTexture {
+            frames: (0..FRAMES as usize)
+                .map(|index| AnimationFrame { index, time: 2 })
+                .collect(),
+            data,
+            size: SIZE as usize,
+            interpolate: false,
+            current_frame: 0,
+            remaining_time: 0.0,
+            texture,
+        });
+    }
+
     fn load_animation(
         &mut self,
         plugin: &str,
@@ -1514,56 +1639,70 @@ impl TextureManager {
     ) -> Option<AnimatedTexture> {
         let path = format!("textures/{}.png.mcmeta", name);
         let res = self.resources.clone();
-        if let Some(val) = res.read().open(plugin, &path) {
-            let meta: serde_json::Value = serde_json::from_reader(val).unwrap();
-            let animation = meta.get("animation").unwrap();
-            let frame_time = animation
-                .get("frametime")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(1);
-            let interpolate = animation
-                .get("interpolate")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false);
-            let frames = if let Some(frames) = animation.get("frames").and_then(|v| v.as_array()) {
-                let mut out = Vec::with_capacity(frames.len());
-                for frame in frames {
-                    if let Some(index) = frame.as_i64() {
-                        out.push(AnimationFrame {
-                            index: index as usize,
-                            time: frame_time,
-                        })
-                    } else {
-                        out.push(AnimationFrame {
-                            index: frame.get("index").unwrap().as_i64().unwrap() as usize,
-                            time: frame_time * frame.get("frameTime").unwrap().as_i64().unwrap(),
-                        })
-                    }
-                }
-                out
-            } else {
-                let (width, height) = img.dimensions();
-                let count = height / width;
-                let mut frames = Vec::with_capacity(count as usize);
-                for i in 0..count {
-                    frames.push(AnimationFrame {
-                        index: i as usize,
-                        time: frame_time,
+        let val = res.read().open(plugin, &path)?;
+        let meta: serde_json::Value = match serde_json::from_reader(val) {
+            Ok(meta) => meta,
+            Err(err) => {
+                warn!("Invalid animation for {}:{}: {}", plugin, name, err);
+                return None;
+            }
+        };
+        let animation = meta.get("animation")?;
+        let frame_time = animation
+            .get("frametime")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(1)
+            .max(1);
+        let interpolate = animation
+            .get("interpolate")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        let (width, height) = img.dimensions();
+        let count = (height / width) as usize;
+        let frames = if let Some(frames) = animation.get("frames").and_then(|v| v.as_array()) {
+            frames
+                .iter()
+                .filter_map(|frame| {
+                    // Frames are either just an index or an index with its
+                    // own time in ticks
+                    let (index, time) = match frame.as_i64() {
+                        Some(index) => (index, frame_time),
+                        None => (
+                            frame.get("index")?.as_i64()?,
+                            frame
+                                .get("time")
+                                .and_then(|v| v.as_i64())
+                                .unwrap_or(frame_time)
+                                .max(1),
+                        ),
+                    };
+                    Some(AnimationFrame {
+                        index: usize::try_from(index).ok().filter(|index| *index < count)?,
+                        time,
                     })
-                }
-                frames
-            };
-
-            return Some(AnimatedTexture {
-                frames,
-                data,
-                interpolate,
-                current_frame: 0,
-                remaining_time: 0.0,
-                texture: self.get_texture("leafish:missing_texture").unwrap(),
-            });
+                })
+                .collect()
+        } else {
+            (0..count)
+                .map(|index| AnimationFrame {
+                    index,
+                    time: frame_time,
+                })
+                .collect::<Vec<_>>()
+        };
+        if frames.is_empty() {
+            return None;
         }
-        None
+
+        Some(AnimatedTexture {
+            remaining_time: frames[0].time as f64,
+            frames,
+            data,
+            size: width as usize,
+            interpolate,
+            current_frame: 0,
+            texture: self.get_texture("leafish:missing_texture").unwrap(),
+        })
     }
 
     fn put_texture(
@@ -1705,27 +1844,72 @@ impl TextureManager {
         }
     }
 
+    /// Finds the texture covering the given point of an atlas, which is
+    /// where vertices built from it point to.
+    pub fn texture_at(&self, atlas: i32, x: usize, y: usize) -> Option<&Texture> {
+        self.textures.values().find(|tex| {
+            tex.atlas == atlas
+                && (tex.x..tex.x + tex.width).contains(&x)
+                && (tex.y..tex.y + tex.height).contains(&y)
+        })
+    }
+
     pub fn remove_dynamic(&mut self, name: &str) {
         let desc = self.dynamic_textures.remove(name).unwrap();
         self.free_dynamics.push(desc.0);
     }
 }
 
-#[allow(dead_code)]
+/// A texture whose image holds several frames stacked on top of each other,
+/// which are shown in turn as described by its `.mcmeta` file.
 struct AnimatedTexture {
     frames: Vec<AnimationFrame>,
     data: Vec<u8>,
+    /// The width and height of each frame in pixels.
+    size: usize,
+    /// Whether to fade between frames instead of switching at once.
     interpolate: bool,
     current_frame: usize,
+    /// The ticks left until the next frame.
     remaining_time: f64,
     texture: Texture,
 }
 
 struct AnimationFrame {
+    /// The frame's position in the image, counting from the top.
     index: usize,
+    /// How long the frame is shown for in ticks.
     time: i64,
 }
 
+impl AnimatedTexture {
+    /// The pixels of the given frame of the animation.
+    fn frame_data(&self, frame: usize) -> &[u8] {
+        let size = self.size * self.size * 4;
+        let offset = size * self.frames[frame].index;
+        &self.data[offset..offset + size]
+    }
+
+    /// The pixels to show right now, blended with the next frame for
+    /// interpolated animations.
+    fn current_data(&self) -> Cow<'_, [u8]> {
+        let current = self.frame_data(self.current_frame);
+        if !self.interpolate {
+            return Cow::Borrowed(current);
+        }
+        let next = self.frame_data((self.current_frame + 1) % self.frames.len());
+        let time = self.frames[self.current_frame].time as f64;
+        let progress = (1.0 - self.remaining_time / time).clamp(0.0, 1.0);
+        Cow::Owned(
+            current
+                .iter()
+                .zip(next)
+                .map(|(a, b)| (*a as f64 + (*b as f64 - *a as f64) * progress) as u8)
+                .collect(),
+        )
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Texture {
     pub name: String,
@@ -1776,6 +1960,11 @@ impl Texture {
         }
     }
 
+    /// Whether this is the placeholder used for missing textures.
+    pub fn is_dummy(&self) -> bool {
+        self.dummy
+    }
+
     pub fn relative(&self, x: f32, y: f32, width: f32, height: f32) -> Texture {
         Texture {
             name: self.name.clone(),
@@ -6,6 +6,8 @@ in vec2 vTextureOffset;
 in float vAtlas;
 in vec3 vLighting;
 
+uniform float linearLighting;
+
 #ifdef ES
 layout(location = 2) out vec4 fragColor;
 #else
@@ -23,12 +25,17 @@ out float revealage;
 #include lookup_texture
 
 void main() {
-    vec4 col = atlasTexture();
+    vec4 col = atlasTextureRepeat();
     #ifndef alpha
     if (col.a < 0.5) discard;
     #endif
     col *= vec4(vColor, 1.0);
-    col.rgb *= vLighting;
+    if (linearLighting > 0.5) {
+        // Light the texture in linear space and convert back to sRGB for output
+        col.rgb = pow(pow(col.rgb, vec3(2.2)) * vLighting, vec3(1.0 / 2.2));
+    } else {
+        col.rgb *= vLighting;
+    }
 
     #ifndef alpha
     fragColor = col;
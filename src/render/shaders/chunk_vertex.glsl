@@ -9,6 +9,7 @@ uniform mat4 cameraMatrix;
 uniform ivec3 offset;
 uniform float lightLevel;
 uniform float skyOffset;
+uniform float linearLighting;
 
 out vec3 vColor;
 out vec4 vTextureInfo;
@@ -29,4 +30,8 @@ void main() {
     vAtlas = aTextureOffset.z;
 
     vLighting = getLight(aLighting / (4000.0));
+    // Interpolating the light in linear space keeps gradients from looking muddy
+    if (linearLighting > 0.5) {
+        vLighting = pow(vLighting, vec3(2.2));
+    }
 }
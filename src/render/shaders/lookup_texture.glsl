@@ -6,3 +6,16 @@ vec4 atlasTexture() {
     tPos *= invAtlasSize;
     return texture(textures, vec3(tPos, vAtlas));
 }
+
+// Like atlasTexture, but repeats the texture when the offset runs past its
+// edges, for faces stretched over several blocks
+vec4 atlasTextureRepeat() {
+    vec2 tPos = vTextureOffset;
+    if (any(lessThan(tPos, vec2(0.0))) || any(greaterThan(tPos, vTextureInfo.zw))) {
+        tPos = mod(tPos, vTextureInfo.zw);
+    }
+    tPos = clamp(tPos, vec2(0.1), vTextureInfo.zw - 0.1);
+    tPos += vTextureInfo.xy;
+    tPos *= invAtlasSize;
+    return texture(textures, vec3(tPos, vAtlas));
+}
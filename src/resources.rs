@@ -20,8 +20,8 @@ use crate::paths;
 
 use std::collections::HashMap;
 use std::fs;
-use std::hash::BuildHasherDefault;
-use std::io;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+use std::io::{self, Read};
 use std::path;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
@@ -51,12 +51,33 @@ const ASSET_INDEX_URL: &str =
 
 pub trait Pack: Sync + Send {
     fn open(&self, name: &str) -> Option<Box<dyn io::Read>>;
+    /// Identifies the pack across runs, changing when its contents are
+    /// replaced.
+    fn id(&self) -> String;
+}
+
+/// Where a pack sits in the stack of resource packs. Packs in later layers
+/// override the assets of packs in earlier ones, within a layer the pack
+/// added last wins. Assets a pack doesn't have fall through to the packs
+/// below it.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum PackLayer {
+    /// Leafish's own assets.
+    Internal,
+    /// The assets of the vanilla client.
+    Vanilla,
+    /// Packs picked by the user.
+    Client,
+    /// The pack the server asked for.
+    Server,
 }
 
 // This is a singleton, that keeps track of all available resource packs
 pub struct Manager {
-    packs: Vec<Box<dyn Pack>>,
+    packs: Vec<(PackLayer, Box<dyn Pack>)>,
     version: usize,
+    /// The names of the loaded client packs, as given to `load_client_packs`.
+    client_packs: String,
 
     vanilla_progress: Arc<Mutex<Progress>>,
     pending_downloads: Arc<AtomicUsize>,
@@ -103,10 +124,11 @@ impl Manager {
         let mut m = Manager {
             packs: Vec::new(),
             version: 0,
+            client_packs: String::new(),
             vanilla_progress: Arc::new(Mutex::new(Progress { tasks: vec![] })),
             pending_downloads: Arc::new(AtomicUsize::new(1)),
         };
-        m.add_pack(Box::new(InternalPack));
+        m.add_pack(PackLayer::Internal, Box::new(InternalPack));
         m.download_vanilla(provided_client);
         if let Some(assets) = provided_assets {
             m.preload_assets(assets);
@@ -128,6 +150,17 @@ impl Manager {
         self.version
     }
 
+    /// Hashes which packs are loaded. Unlike `version`, this stays the same
+    /// across runs as long as the same packs are used.
+    pub fn pack_hash(&self) -> u64 {
+        let mut hasher = FNVHash::default();
+        for (layer, pack) in &self.packs {
+            (*layer as u8).hash(&mut hasher);
+            pack.id().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
     pub fn open(&self, plugin: &str, name: &str) -> Option<Box<dyn io::Read>> {
         if plugin == "global" {
             let file = File::open(paths::get_data_dir().join(name));
@@ -138,7 +171,7 @@ impl Manager {
             }
         } else {
             let path = format!("assets/{}/{}", plugin, name);
-            for pack in self.packs.iter().rev() {
+            for (_, pack) in self.packs.iter().rev() {
                 if let Some(val) = pack.open(&path) {
                     return Some(val);
                 }
@@ -151,7 +184,7 @@ impl Manager {
     pub fn open_all(&self, plugin: &str, name: &str) -> Vec<Box<dyn io::Read>> {
         let mut ret = Vec::new();
         let path = format!("assets/{}/{}", plugin, name);
-        for pack in self.packs.iter().rev() {
+        for (_, pack) in self.packs.iter().rev() {
             if let Some(val) = pack.open(&path) {
                 ret.push(val);
             }
@@ -159,6 +192,63 @@ impl Manager {
         ret
     }
 
+    /// Adds a pack on top of the other packs in its layer. Everything using
+    /// resources reloads them once the version changes.
+    pub fn add_pack(&mut self, layer: PackLayer, pck: Box<dyn Pack>) {
+        let index = self
+            .packs
+            .iter()
+            .position(|(other, _)| *other > layer)
+            .unwrap_or(self.packs.len());
+        self.packs.insert(index, (layer, pck));
+        self.version += 1;
+    }
+
+    /// Adds the pack at `path`, which is either a directory or a zip file.
+    pub fn add_pack_path(&mut self, layer: PackLayer, path: &path::Path) -> io::Result<()> {
+        if path.is_dir() {
+            self.add_pack(
+                layer,
+                Box::new(DirPack {
+                    root: path.to_owned(),
+                }),
+            );
+        } else {
+            self.add_pack(layer, Box::new(ZipPack::new(path)?));
+        }
+        Ok(())
+    }
+
+    /// Removes all packs in the given layer.
+    pub fn remove_layer(&mut self, layer: PackLayer) {
+        let count = self.packs.len();
+        self.packs.retain(|(other, _)| *other != layer);
+        if self.packs.len() != count {
+            self.version += 1;
+        }
+    }
+
+    /// Loads the packs named in `names` from the resourcepacks folder,
+    /// separated by commas. Later packs override earlier ones. Nothing is
+    /// reloaded if the same packs are loaded already.
+    pub fn load_client_packs(&mut self, names: &str) {
+        if self.client_packs == names {
+            return;
+        }
+        self.client_packs = names.to_owned();
+        self.remove_layer(PackLayer::Client);
+        let dir = paths::get_data_dir().join("resourcepacks");
+        for name in names
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            if let Err(err) = self.add_pack_path(PackLayer::Client, &dir.join(name)) {
+                warn!("Failed to load resource pack {}: {}", name, err);
+            }
+        }
+    }
+
     pub fn tick(&mut self, mui: &mut ManagerUI, ui_container: &mut ui::Container, delta: f64) {
         let delta = delta.min(5.0);
 
@@ -312,26 +402,19 @@ impl Manager {
             .retain(|v| v.position >= -UI_HEIGHT || !v.closing);
     }
 
-    fn add_pack(&mut self, pck: Box<dyn Pack>) {
-        self.packs.push(pck);
-        self.version += 1;
-    }
-
     fn load_vanilla(&mut self) {
         let loc = format!("resources-{}", RESOURCES_VERSION);
         let location = paths::get_data_dir().join(loc);
-        self.packs.insert(1, Box::new(DirPack { root: location }));
-        self.version += 1;
+        self.add_pack(PackLayer::Vanilla, Box::new(DirPack { root: location }));
     }
 
     fn preload_assets(&mut self, path: String) {
-        self.packs.insert(1, Box::new(ObjectPack::new(path)));
-        self.version += 1;
+        self.add_pack(PackLayer::Vanilla, Box::new(ObjectPack::new(path)));
     }
 
     fn load_assets(&mut self) {
-        self.packs.insert(
-            1,
+        self.add_pack(
+            PackLayer::Vanilla,
             Box::new(ObjectPack::new(
                 paths::get_data_dir()
                     .join(format!("index/{}.json", ASSET_VERSION))
@@ -340,7 +423,6 @@ impl Manager {
                     .to_string(),
             )),
         );
-        self.version += 1;
     }
 
     fn download_assets(&mut self) {
@@ -583,6 +665,10 @@ impl Pack for DirPack {
             Err(_) => None,
         }
     }
+
+    fn id(&self) -> String {
+        format!("dir:{}", self.root.display())
+    }
 }
 
 // The internal resource pack
@@ -595,10 +681,53 @@ impl Pack for InternalPack {
             None => None,
         }
     }
+
+    fn id(&self) -> String {
+        format!("internal:{}", env!("CARGO_PKG_VERSION"))
+    }
+}
+
+// A resource pack in a zip file, like the ones servers send
+struct ZipPack {
+    archive: Mutex<zip::ZipArchive<fs::File>>,
+    id: String,
+}
+
+impl ZipPack {
+    fn new(path: &path::Path) -> io::Result<ZipPack> {
+        let file = fs::File::open(path)?;
+        let metadata = file.metadata()?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |time| time.as_secs());
+        let id = format!("zip:{}:{}:{}", path.display(), metadata.len(), modified);
+        let archive = zip::ZipArchive::new(file).map_err(io::Error::other)?;
+        Ok(ZipPack {
+            archive: Mutex::new(archive),
+            id,
+        })
+    }
+}
+
+impl Pack for ZipPack {
+    fn open(&self, name: &str) -> Option<Box<dyn io::Read>> {
+        let mut archive = self.archive.lock().unwrap();
+        let mut file = archive.by_name(name).ok()?;
+        let mut data = Vec::with_capacity(file.size() as usize);
+        file.read_to_end(&mut data).ok()?;
+        Some(Box::new(io::Cursor::new(data)))
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
 }
 
 struct ObjectPack {
     objects: HashMap<String, String, BuildHasherDefault<FNVHash>>,
+    location: String,
 }
 
 impl ObjectPack {
@@ -614,7 +743,10 @@ impl ObjectPack {
                 v.get("hash").and_then(|v| v.as_str()).unwrap().to_owned(),
             );
         }
-        ObjectPack { objects: hash_objs }
+        ObjectPack {
+            objects: hash_objs,
+            location: loc,
+        }
     }
 }
 
@@ -636,6 +768,10 @@ impl Pack for ObjectPack {
             None
         }
     }
+
+    fn id(&self) -> String {
+        format!("objects:{}", self.location)
+    }
 }
 
 struct ProgressRead<'a, T> {
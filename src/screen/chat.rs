@@ -12,17 +12,23 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::fs;
+use std::process::Command;
+use std::rc::Rc;
+use std::sync::{Arc, OnceLock};
 
 use crate::protocol::packet;
 use crate::render::hud::{Hud, START_TICKS};
 use crate::render::{hud, Renderer};
+use crate::screen::confirm_box::ConfirmBox;
 use crate::screen::{Screen, ScreenSystem, ScreenType};
 use crate::ui::{Container, FormattedRef, HAttach, ImageRef, TextBuilder, TextRef, VAttach};
 use crate::Game;
-use crate::{ui, KeyCmp};
-use core::cmp;
+use crate::{paths, ui, KeyCmp};
 use leafish_protocol::format::Component;
+use leafish_protocol::translate::ClickEvent;
+use log::warn;
 use parking_lot::RwLock;
 use shared::Version;
 use std::sync::atomic::{AtomicBool, Ordering};
@@ -31,6 +37,8 @@ use winit::keyboard::{Key, NamedKey, PhysicalKey};
 pub const MAX_MESSAGES: usize = 200;
 const MAX_MESSAGE_LENGTH_PRE_1_11: usize = 100;
 const MAX_MESSAGE_LENGTH_SINCE_1_11: usize = 256;
+/// How many messages the open chat shows at once.
+const VISIBLE_MESSAGES: usize = 10;
 
 pub struct ChatContext {
     messages: Arc<RwLock<Vec<(usize, Component)>>>,
@@ -79,6 +87,81 @@ impl Default for ChatContext {
     }
 }
 
+fn ignored_players() -> &'static RwLock<Vec<String>> {
+    static IGNORED_PLAYERS: OnceLock<RwLock<Vec<String>>> = OnceLock::new();
+    IGNORED_PLAYERS.get_or_init(|| {
+        let names = fs::read_to_string(paths::get_config_dir().join("ignored_players.txt"))
+            .map(|names| names.lines().map(str::to_owned).collect())
+            .unwrap_or_default();
+        RwLock::new(names)
+    })
+}
+
+fn save_ignored_players(names: &[String]) {
+    let path = paths::get_config_dir().join("ignored_players.txt");
+    if let Err(err) = paths::write_atomic(&path, names.join("\n").as_bytes()) {
+        warn!("Failed to save the ignored players: {}", err);
+    }
+}
+
+/// Hides chat from the player with the given name. Returns false if they
+/// were already ignored.
+pub fn ignore_player(name: &str) -> bool {
+    let mut names = ignored_players().write();
+    if names
+        .iter()
+        .any(|ignored| ignored.eq_ignore_ascii_case(name))
+    {
+        return false;
+    }
+    names.push(name.to_owned());
+    save_ignored_players(&names);
+    true
+}
+
+/// Shows chat from the player with the given name again. Returns false if
+/// they weren't ignored.
+pub fn unignore_player(name: &str) -> bool {
+    let mut names = ignored_players().write();
+    let count = names.len();
+    names.retain(|ignored| !ignored.eq_ignore_ascii_case(name));
+    if names.len() == count {
+        return false;
+    }
+    save_ignored_players(&names);
+    true
+}
+
+/// Whether the message was sent by an ignored player. Servers format chat
+/// differently, so the sender is looked for in the part before the message
+/// itself, either inside `<...>` like vanilla or before a separator such as
+/// `:` or `»`, which also catches ranks and whispers.
+pub fn is_from_ignored(message: &Component) -> bool {
+    let names = ignored_players().read();
+    if names.is_empty() {
+        return false;
+    }
+    let text = message.to_string();
+    let prefix = if let Some(rest) = text.strip_prefix('<') {
+        match rest.find('>') {
+            Some(end) => &rest[..end],
+            None => return false,
+        }
+    } else {
+        match [":", "»", ">"]
+            .iter()
+            .filter_map(|separator| text.find(separator))
+            .min()
+        {
+            Some(end) => &text[..end],
+            None => return false,
+        }
+    };
+    prefix
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .any(|word| names.iter().any(|name| name.eq_ignore_ascii_case(word)))
+}
+
 #[derive(Clone)]
 pub struct Chat {
     rendered_messages: Vec<FormattedRef>,
@@ -88,9 +171,11 @@ pub struct Chat {
     context: Arc<ChatContext>,
     written: String,
     animation: u8,
-    #[allow(dead_code)]
-    offset: f64, // TODO: Implement this (scrolling in chat)!
+    /// How many messages back the chat is scrolled.
+    offset: f64,
     dirty_written: bool,
+    dirty_scroll: bool,
+    suggested: Rc<RefCell<Option<String>>>,
 }
 
 impl Chat {
@@ -105,6 +190,8 @@ impl Chat {
             animation: 0,
             offset: 0.0,
             dirty_written: false,
+            dirty_scroll: false,
+            suggested: Rc::new(RefCell::new(None)),
         }
     }
 }
@@ -201,6 +288,10 @@ impl super::Screen for Chat {
         _delta: f64,
     ) {
         let scale = Hud::icon_scale(&renderer);
+        if let Some(suggested) = self.suggested.borrow_mut().take() {
+            self.written = suggested;
+            self.dirty_written = true;
+        }
         if self.animation == 0 {
             self.animation = 20;
             self.animated_tex = Some(
@@ -241,8 +332,9 @@ impl super::Screen for Chat {
                     .create(ui_container),
             );
         }
-        if self.context.dirty.load(Ordering::Acquire) {
+        if self.context.dirty.load(Ordering::Acquire) || self.dirty_scroll {
             self.context.dirty.store(false, Ordering::Release);
+            self.dirty_scroll = false;
             self.rendered_messages.clear();
             self.background.clear();
             self.render_chat(renderer, ui_container);
@@ -259,6 +351,16 @@ impl super::Screen for Chat {
         self.on_active(screen_sys, renderer, ui_container);
     }
 
+    fn on_scroll(&mut self, _: f64, y: f64) {
+        let history_size = self.context.messages.read().len();
+        let max = history_size.saturating_sub(VISIBLE_MESSAGES) as f64;
+        let offset = (self.offset + y).clamp(0.0, max);
+        if offset as usize != self.offset as usize {
+            self.dirty_scroll = true;
+        }
+        self.offset = offset;
+    }
+
     fn on_key_press(&mut self, key: (Key, PhysicalKey), down: bool, repeat: bool, game: &Game) {
         if !down {
             return;
@@ -269,11 +371,7 @@ impl super::Screen for Chat {
         }
         if key.0 == Key::Named(NamedKey::Enter) && !repeat {
             if !self.written.is_empty() {
-                game.server.load().as_ref().unwrap().write_packet(
-                    packet::play::serverbound::ChatMessage {
-                        message: self.written.clone(),
-                    },
-                );
+                send_message(game, self.written.clone());
             }
             game.screen_sys.pop_screen();
             return;
@@ -347,18 +445,23 @@ impl super::Screen for Chat {
 impl Chat {
     fn render_chat(&mut self, renderer: Arc<Renderer>, ui_container: &mut Container) {
         let scale = Hud::icon_scale(&renderer);
-        let history_size = self.context.messages.read().len();
+        let messages = self.context.messages.read();
+        // Skip the newest messages when scrolled back
+        let visible = messages
+            .iter()
+            .rev()
+            .skip(self.offset as usize)
+            .take(VISIBLE_MESSAGES)
+            .map(|(_, message)| message.clone())
+            .collect::<Vec<_>>();
+        drop(messages);
 
-        let mut component_lines = 0;
-        for i in 0..cmp::min(10, history_size) {
-            let message = self.context.messages.read()[history_size - 1 - i].clone();
-            let lines = (renderer.ui.lock().size_of_string(&message.1.to_string())
-                / (hud::CHAT_WIDTH * scale))
-                .ceil() as u8;
-            component_lines += lines;
-        }
+        let component_lines: usize = visible
+            .iter()
+            .map(|message| Hud::chat_lines(&renderer, message, scale))
+            .sum();
 
-        if history_size > 0 {
+        if !visible.is_empty() {
             self.background.push(
                 ui::ImageBuilder::new()
                     .draw_index(0)
@@ -367,8 +470,7 @@ impl Chat {
                     .position(1.0 * scale, scale * 85.0 / 2.0)
                     .size(
                         500.0 / 2.0 * scale,
-                        5.0 * scale * (component_lines as f64)
-                            + cmp::min(10, history_size) as f64 * 0.4 * scale,
+                        5.0 * scale * (component_lines as f64) + visible.len() as f64 * 0.4 * scale,
                     )
                     .colour((0, 0, 0, 100))
                     .create(ui_container),
@@ -389,11 +491,8 @@ impl Chat {
         );
 
         let mut component_lines = 0;
-        for i in 0..cmp::min(10, history_size) {
-            let message = self.context.messages.read()[history_size - 1 - i].clone();
-            let lines = (renderer.ui.lock().size_of_string(&message.1.to_string())
-                / (hud::CHAT_WIDTH * scale))
-                .ceil() as u8;
+        for (i, message) in visible.into_iter().enumerate() {
+            let lines = Hud::chat_lines(&renderer, &message, scale);
             let text = ui::FormattedBuilder::new()
                 .draw_index(0)
                 .alignment(VAttach::Bottom, HAttach::Left)
@@ -403,11 +502,72 @@ impl Chat {
                         + ((component_lines as f64) * 5.0) * scale
                         + i as f64 * 0.4 * scale,
                 )
-                .text(message.1)
+                .text(message)
                 .max_width(hud::CHAT_WIDTH * scale)
                 .create(ui_container);
+            let suggested = self.suggested.clone();
+            text.borrow_mut()
+                .set_click_handler(move |click_event, game| {
+                    handle_click_event(click_event, game, &suggested)
+                });
             self.rendered_messages.push(text);
             component_lines += lines;
         }
     }
 }
+
+pub fn send_message(game: &Game, message: String) {
+    if let Some(server) = game.server.load().as_ref() {
+        server.write_packet(packet::play::serverbound::ChatMessage { message });
+    }
+}
+
+fn handle_click_event(
+    click_event: &ClickEvent,
+    game: &Game,
+    suggested: &Rc<RefCell<Option<String>>>,
+) {
+    match click_event.action.as_str() {
+        "open_url" => {
+            let url = click_event.value.clone();
+            let lower = url.to_lowercase();
+            if !lower.starts_with("http://") && !lower.starts_with("https://") {
+                warn!("Not opening link with unsupported scheme: {}", url);
+                return;
+            }
+            let text = format!("Are you sure you want to open {}?", url);
+            game.screen_sys.clone().add_screen(Box::new(ConfirmBox::new(
+                text,
+                Rc::new(|game| {
+                    game.screen_sys.pop_screen();
+                }),
+                Rc::new(move |game| {
+                    game.screen_sys.pop_screen();
+                    if let Err(err) = open(&url) {
+                        warn!("Failed to open {}: {}", url, err);
+                    }
+                }),
+            )));
+        }
+        "suggest_command" => {
+            *suggested.borrow_mut() = Some(click_event.value.clone());
+        }
+        "run_command" => send_message(game, click_event.value.clone()),
+        _ => {}
+    }
+}
+
+/// Opens the url with the system's default handler, usually the browser.
+fn open(url: &str) -> std::io::Result<()> {
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+    command.arg(url).spawn().map(|_| ())
+}
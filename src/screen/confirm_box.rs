@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::lang;
 use crate::render;
 use crate::ui;
 use crate::Game;
@@ -106,7 +107,7 @@ impl super::Screen for ConfirmBox {
         {
             let mut cancel = cancel.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Cancel")
+                .text(lang::tr("gui.cancel", "Cancel"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *cancel);
             cancel.add_text(txt);
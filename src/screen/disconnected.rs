@@ -0,0 +1,165 @@
+use crate::format::Component;
+use crate::lang;
+use crate::render;
+use crate::screen::{Screen, ScreenSystem};
+use crate::ui;
+use std::sync::Arc;
+
+/// Widest a line of the reason may get before it wraps.
+const MAX_REASON_WIDTH: f64 = 600.0;
+
+/// Shows why the connection to a server was lost, keeping the colours and
+/// line breaks the server sent along.
+pub struct Disconnected {
+    elements: Option<UIElements>,
+    reason: Component,
+    /// Server to connect to again, if there is one
+    address: Option<String>,
+}
+
+impl Clone for Disconnected {
+    fn clone(&self) -> Self {
+        Disconnected {
+            elements: None,
+            reason: self.reason.clone(),
+            address: self.address.clone(),
+        }
+    }
+}
+
+struct UIElements {
+    _title: ui::TextRef,
+    _reason: ui::FormattedRef,
+    _back: ui::ButtonRef,
+    _reconnect: Option<ui::ButtonRef>,
+    _copy: ui::ButtonRef,
+}
+
+impl Disconnected {
+    pub fn new(reason: Component, address: Option<String>) -> Self {
+        Disconnected {
+            elements: None,
+            reason,
+            address,
+        }
+    }
+}
+
+impl super::Screen for Disconnected {
+    fn on_active(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        renderer: Arc<render::Renderer>,
+        ui_container: &mut ui::Container,
+    ) {
+        let (_, height) =
+            ui::Formatted::compute_size(renderer, &self.reason, MAX_REASON_WIDTH, 1.0, 1.0, 1.0);
+
+        let title = ui::TextBuilder::new()
+            .text(lang::tr("multiplayer.disconnect.generic", "Disconnected"))
+            .position(0.0, -height / 2.0 - 30.0)
+            .colour((170, 170, 170, 255))
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let reason = ui::FormattedBuilder::new()
+            .text(self.reason.clone())
+            .position(0.0, 0.0)
+            .max_width(MAX_REASON_WIDTH)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let buttons_y = height / 2.0 + 40.0;
+        let back = ui::ButtonBuilder::new()
+            .position(if self.address.is_some() { -110.0 } else { 0.0 }, buttons_y)
+            .size(200.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut back = back.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text("Back to server list")
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *back);
+            back.add_text(txt);
+            back.add_click_func(|_, game| {
+                game.screen_sys
+                    .clone()
+                    .replace_screen(Box::new(super::ServerList::new()));
+                true
+            });
+        }
+
+        let reconnect = self.address.clone().map(|address| {
+            let reconnect = ui::ButtonBuilder::new()
+                .position(110.0, buttons_y)
+                .size(200.0, 40.0)
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .create(ui_container);
+            {
+                let mut reconnect = reconnect.borrow_mut();
+                let txt = ui::TextBuilder::new()
+                    .text("Reconnect")
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .attach(&mut *reconnect);
+                reconnect.add_text(txt);
+                reconnect.add_click_func(move |_, game| {
+                    super::connect_to_server(game, &address);
+                    true
+                });
+            }
+            reconnect
+        });
+
+        // Lets the kick message be shared
+        let copy = ui::ButtonBuilder::new()
+            .position(0.0, buttons_y + 50.0)
+            .size(200.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut copy = copy.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(lang::tr("chat.copy", "Copy reason"))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *copy);
+            copy.add_text(txt);
+            let reason = self.reason.to_string();
+            copy.add_click_func(move |_, game| {
+                super::server_list::copy_to_clipboard(game, reason.clone());
+                true
+            });
+        }
+
+        self.elements = Some(UIElements {
+            _title: title,
+            _reason: reason,
+            _back: back,
+            _reconnect: reconnect,
+            _copy: copy,
+        });
+    }
+
+    fn on_deactive(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+    ) {
+        // Clean up
+        self.elements = None
+    }
+
+    fn tick(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+        _delta: f64,
+    ) {
+    }
+
+    fn clone_screen(&self) -> Box<dyn Screen> {
+        Box::new(self.clone())
+    }
+}
@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::lang;
 use crate::{ui, Game};
 
 use crate::render::Renderer;
@@ -104,7 +105,7 @@ impl super::Screen for EditAccountEntry {
         {
             let mut done = done.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done);
             done.add_text(txt);
@@ -131,7 +132,7 @@ impl super::Screen for EditAccountEntry {
         {
             let mut cancel = cancel.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Cancel")
+                .text(lang::tr("gui.cancel", "Cancel"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *cancel);
             cancel.add_text(txt);
@@ -12,16 +12,29 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::fs;
+use std::io;
+use std::rc::Rc;
 
+use crate::lang;
+use crate::protocol::forwarding::Forwarding;
 use crate::ui;
 use crate::{paths, render};
 
 use crate::screen::{Screen, ScreenSystem};
+use crate::server::JoinCommands;
+use instant::Duration;
 use serde_json::Value;
 use std::sync::Arc;
 
+/// How long to wait before each join command when no delay was given, in
+/// milliseconds.
+const DEFAULT_JOIN_DELAY: u64 = 1000;
+/// The proxy forwarding choices, in the order the button cycles through.
+const FORWARDING_MODES: [&str; 3] = ["Off", "BungeeCord", "Velocity"];
+
 pub struct EditServerEntry {
     elements: Option<UIElements>,
     entry_info: Option<(usize, String, String)>,
@@ -41,6 +54,11 @@ struct UIElements {
 
     _name: ui::TextBoxRef,
     _address: ui::TextBoxRef,
+    _join_commands: ui::TextBoxRef,
+    _join_delay: ui::TextBoxRef,
+    _forwarding: ui::ButtonRef,
+    _forwarding_secret: ui::TextBoxRef,
+    _settings: ui::TextBoxRef,
     _done: ui::ButtonRef,
     _cancel: ui::ButtonRef,
 }
@@ -53,40 +71,204 @@ impl EditServerEntry {
         }
     }
 
-    fn save_servers(index: Option<usize>, name: &str, address: &str) {
-        let mut servers_info = match fs::File::open(paths::get_data_dir().join("servers.json")) {
-            Ok(val) => serde_json::from_reader(val).unwrap(),
-            Err(_) => {
+    /// The join commands of the saved server, joined by `;`, and the delay
+    /// between them.
+    fn load_join_settings(index: usize) -> (String, String) {
+        let entry = Self::load_entry(index);
+        let commands = entry
+            .get("join_commands")
+            .and_then(Value::as_array)
+            .map(|commands| {
+                commands
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            })
+            .unwrap_or_default();
+        let delay = entry
+            .get("join_delay")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_JOIN_DELAY);
+        (commands, delay.to_string())
+    }
+
+    fn load_entry(index: usize) -> Value {
+        load_servers()
+            .and_then(|servers| servers.get("servers")?.get(index).cloned())
+            .unwrap_or(Value::Null)
+    }
+
+    /// Saves the server into servers.json, failing rather than replacing
+    /// the saved servers when the file can't be read.
+    fn save_servers(
+        index: Option<usize>,
+        name: &str,
+        address: &str,
+        join_commands: Vec<String>,
+        join_delay: u64,
+        forwarding: Forwarding,
+        settings: Vec<(String, String)>,
+    ) -> Result<(), String> {
+        let path = paths::get_data_dir().join("servers.json");
+        let mut servers_info = match fs::File::open(&path) {
+            Ok(file) => serde_json::from_reader::<_, Value>(file)
+                .map_err(|err| format!("Couldn't read servers.json: {}", err))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
                 let mut info = BTreeMap::default();
                 info.insert("servers".to_owned(), Value::Array(vec![]));
                 Value::Object(info.into_iter().collect())
             }
+            Err(err) => return Err(format!("Couldn't open servers.json: {}", err)),
         };
 
         let new_entry = {
             let mut entry = BTreeMap::default();
             entry.insert("name".to_owned(), Value::String(name.to_owned()));
             entry.insert("address".to_owned(), Value::String(address.to_owned()));
+            if !join_commands.is_empty() {
+                entry.insert(
+                    "join_commands".to_owned(),
+                    Value::Array(join_commands.into_iter().map(Value::String).collect()),
+                );
+                entry.insert("join_delay".to_owned(), Value::from(join_delay));
+            }
+            match forwarding {
+                Forwarding::None => {}
+                Forwarding::BungeeCord => {
+                    entry.insert("forwarding".to_owned(), Value::from("bungeecord"));
+                }
+                Forwarding::Velocity { secret } => {
+                    entry.insert("forwarding".to_owned(), Value::from("velocity"));
+                    entry.insert("forwarding_secret".to_owned(), Value::String(secret));
+                }
+            }
+            if !settings.is_empty() {
+                entry.insert(
+                    "settings".to_owned(),
+                    Value::Object(
+                        settings
+                            .into_iter()
+                            .map(|(name, value)| (name, Value::String(value)))
+                            .collect(),
+                    ),
+                );
+            }
             Value::Object(entry.into_iter().collect())
         };
 
         {
             let servers = servers_info
-                .as_object_mut()
-                .unwrap()
                 .get_mut("servers")
-                .unwrap()
-                .as_array_mut()
-                .unwrap();
-            if let Some(index) = index {
-                *servers.get_mut(index).unwrap() = new_entry;
-            } else {
-                servers.push(new_entry);
+                .and_then(Value::as_array_mut)
+                .ok_or("Couldn't read servers.json: no server list")?;
+            match index {
+                Some(index) => match servers.get_mut(index) {
+                    Some(entry) => *entry = new_entry,
+                    None => return Err("The server no longer exists".to_owned()),
+                },
+                None => servers.push(new_entry),
             }
         }
 
-        let mut out = fs::File::create(paths::get_data_dir().join("servers.json")).unwrap();
-        serde_json::to_writer_pretty(&mut out, &servers_info).unwrap();
+        let data = serde_json::to_vec_pretty(&servers_info)
+            .map_err(|err| format!("Couldn't save servers.json: {}", err))?;
+        paths::write_atomic(&path, &data)
+            .map_err(|err| format!("Couldn't save servers.json: {}", err))
+    }
+}
+
+fn load_servers() -> Option<Value> {
+    let file = fs::File::open(paths::get_data_dir().join("servers.json")).ok()?;
+    serde_json::from_reader(file).ok()
+}
+
+/// The commands to send after joining the saved server with the given
+/// address, if any were set up for it.
+pub fn saved_join_commands(address: &str) -> Option<JoinCommands> {
+    let servers = load_servers()?;
+    let entry = servers
+        .get("servers")?
+        .as_array()?
+        .iter()
+        .find(|entry| entry.get("address").and_then(Value::as_str) == Some(address))?;
+    let commands = entry
+        .get("join_commands")?
+        .as_array()?
+        .iter()
+        .filter_map(Value::as_str)
+        .map(str::to_owned)
+        .collect::<Vec<_>>();
+    if commands.is_empty() {
+        return None;
+    }
+    let delay = entry
+        .get("join_delay")
+        .and_then(Value::as_u64)
+        .unwrap_or(DEFAULT_JOIN_DELAY);
+    Some(JoinCommands::new(commands, Duration::from_millis(delay)))
+}
+
+/// How the saved server with the given address expects the player to be
+/// forwarded, for servers that usually sit behind a proxy.
+pub fn saved_forwarding(address: &str) -> Forwarding {
+    load_servers()
+        .and_then(|servers| {
+            servers
+                .get("servers")?
+                .as_array()?
+                .iter()
+                .find(|entry| entry.get("address").and_then(Value::as_str) == Some(address))
+                .map(forwarding_of)
+        })
+        .unwrap_or_default()
+}
+
+/// The settings to use instead of the user's own while connected to the
+/// saved server with the given address, by their config file names.
+pub fn saved_settings(address: &str) -> Vec<(String, String)> {
+    load_servers()
+        .and_then(|servers| {
+            servers
+                .get("servers")?
+                .as_array()?
+                .iter()
+                .find(|entry| entry.get("address").and_then(Value::as_str) == Some(address))
+                .map(settings_of)
+        })
+        .unwrap_or_default()
+}
+
+fn settings_of(entry: &Value) -> Vec<(String, String)> {
+    entry
+        .get("settings")
+        .and_then(Value::as_object)
+        .map(|settings| {
+            settings
+                .iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        Value::String(value) => value.clone(),
+                        value => value.to_string(),
+                    };
+                    (name.clone(), value)
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn forwarding_of(entry: &Value) -> Forwarding {
+    match entry.get("forwarding").and_then(Value::as_str) {
+        Some("bungeecord") => Forwarding::BungeeCord,
+        Some("velocity") => Forwarding::Velocity {
+            secret: entry
+                .get("forwarding_secret")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_owned(),
+        },
+        _ => Forwarding::None,
     }
 }
 
@@ -102,7 +284,7 @@ impl super::Screen for EditServerEntry {
         // Name
         let server_name = ui::TextBoxBuilder::new()
             .input(self.entry_info.as_ref().map_or("", |v| &v.1))
-            .position(0.0, -20.0)
+            .position(0.0, -80.0)
             .size(400.0, 40.0)
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
@@ -115,7 +297,7 @@ impl super::Screen for EditServerEntry {
         // Address
         let server_address = ui::TextBoxBuilder::new()
             .input(self.entry_info.as_ref().map_or("", |v| &v.2))
-            .position(0.0, 40.0)
+            .position(0.0, -20.0)
             .size(400.0, 40.0)
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
@@ -125,63 +307,225 @@ impl super::Screen for EditServerEntry {
             .position(0.0, -18.0)
             .attach(&mut *server_address.borrow_mut());
 
+        // Commands to send after joining, such as logging in
+        let (commands, delay) = self
+            .entry_info
+            .as_ref()
+            .map_or((String::new(), DEFAULT_JOIN_DELAY.to_string()), |v| {
+                Self::load_join_settings(v.0)
+            });
+        let join_commands = ui::TextBoxBuilder::new()
+            .input(&commands)
+            .position(-55.0, 40.0)
+            .size(290.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        ui::TextBox::make_focusable(&join_commands, ui_container);
+        ui::TextBuilder::new()
+            .text("On join (split commands with ;):")
+            .position(0.0, -18.0)
+            .attach(&mut *join_commands.borrow_mut());
+
+        let join_delay = ui::TextBoxBuilder::new()
+            .input(&delay)
+            .position(150.0, 40.0)
+            .size(100.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        ui::TextBox::make_focusable(&join_delay, ui_container);
+        ui::TextBuilder::new()
+            .text("Delay (ms):")
+            .position(0.0, -18.0)
+            .attach(&mut *join_delay.borrow_mut());
+
+        // Joining a server directly that expects a proxy in front of it
+        let forwarding = self
+            .entry_info
+            .as_ref()
+            .map_or(Forwarding::None, |v| forwarding_of(&Self::load_entry(v.0)));
+        let (mode, secret) = match forwarding {
+            Forwarding::None => (0, String::new()),
+            Forwarding::BungeeCord => (1, String::new()),
+            Forwarding::Velocity { secret } => (2, secret),
+        };
+        let forwarding_mode = Rc::new(Cell::new(mode));
+        let forwarding_button = ui::ButtonBuilder::new()
+            .position(-105.0, 100.0)
+            .size(190.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut forwarding_button = forwarding_button.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(format!("Forwarding: {}", FORWARDING_MODES[mode]))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *forwarding_button);
+            let txt_forwarding = txt.clone();
+            forwarding_button.add_text(txt);
+            let forwarding_mode = forwarding_mode.clone();
+            forwarding_button.add_click_func(move |_, _| {
+                let mode = (forwarding_mode.get() + 1) % FORWARDING_MODES.len();
+                forwarding_mode.set(mode);
+                txt_forwarding.borrow_mut().text =
+                    format!("Forwarding: {}", FORWARDING_MODES[mode]);
+                true
+            });
+        }
+
+        let forwarding_secret = ui::TextBoxBuilder::new()
+            .input(&secret)
+            .position(100.0, 100.0)
+            .size(200.0, 40.0)
+            .password(true)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        ui::TextBox::make_focusable(&forwarding_secret, ui_container);
+        ui::TextBuilder::new()
+            .text("Velocity secret:")
+            .position(0.0, -18.0)
+            .attach(&mut *forwarding_secret.borrow_mut());
+
+        // Settings to use instead of the user's own on this server
+        let settings = self
+            .entry_info
+            .as_ref()
+            .map(|v| settings_of(&Self::load_entry(v.0)))
+            .unwrap_or_default()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let server_settings = ui::TextBoxBuilder::new()
+            .input(&settings)
+            .position(0.0, 160.0)
+            .size(400.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        ui::TextBox::make_focusable(&server_settings, ui_container);
+        ui::TextBuilder::new()
+            .text("Settings (like view_distance=4, split with ;):")
+            .position(0.0, -18.0)
+            .attach(&mut *server_settings.borrow_mut());
+
         let save_server_error = ui::TextBuilder::new()
             .text("")
-            .position(0.0, 150.0)
+            .position(0.0, 265.0)
             .colour((255, 50, 50, 255))
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
 
         // Done
         let done = ui::ButtonBuilder::new()
-            .position(110.0, 100.0)
+            .position(110.0, 220.0)
             .size(200.0, 40.0)
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
         {
             let mut done = done.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done);
             done.add_text(txt);
             let index = self.entry_info.as_ref().map(|v| v.0);
             let server_name = server_name.clone();
             let server_address = server_address.clone();
+            let join_commands = join_commands.clone();
+            let join_delay = join_delay.clone();
+            let forwarding_secret = forwarding_secret.clone();
+            let server_settings = server_settings.clone();
             done.add_click_func(move |_, game| {
                 if server_address.borrow().input.is_empty() {
                     save_server_error.borrow_mut().text = "Please enter a Server Address".into();
                     return false;
                 }
-                Self::save_servers(
+                let Ok(delay) = join_delay.borrow().input.trim().parse::<u64>() else {
+                    save_server_error.borrow_mut().text =
+                        "Please enter the delay in milliseconds".into();
+                    return false;
+                };
+                let commands = join_commands
+                    .borrow()
+                    .input
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|command| !command.is_empty())
+                    .map(str::to_owned)
+                    .collect();
+                let forwarding = match forwarding_mode.get() {
+                    1 => Forwarding::BungeeCord,
+                    2 => {
+                        let secret = forwarding_secret.borrow().input.trim().to_owned();
+                        if secret.is_empty() {
+                            save_server_error.borrow_mut().text =
+                                "Please enter the Velocity forwarding secret".into();
+                            return false;
+                        }
+                        Forwarding::Velocity { secret }
+                    }
+                    _ => Forwarding::None,
+                };
+                let mut settings = vec![];
+                for setting in server_settings
+                    .borrow()
+                    .input
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|setting| !setting.is_empty())
+                {
+                    let Some((name, value)) = setting.split_once('=') else {
+                        save_server_error.borrow_mut().text =
+                            format!("Please write settings as name=value: {}", setting);
+                        return false;
+                    };
+                    let (name, value) = (name.trim(), value.trim());
+                    if game.settings.get_by_name(name).is_none() {
+                        save_server_error.borrow_mut().text = format!("Unknown setting: {}", name);
+                        return false;
+                    }
+                    if !game.settings.is_valid_value(name, value) {
+                        save_server_error.borrow_mut().text =
+                            format!("Invalid value for {}: {}", name, value);
+                        return false;
+                    }
+                    settings.push((name.to_owned(), value.to_owned()));
+                }
+                if let Err(err) = Self::save_servers(
                     index,
                     &server_name.borrow().input,
                     &server_address.borrow().input,
-                );
+                    commands,
+                    delay,
+                    forwarding,
+                    settings,
+                ) {
+                    save_server_error.borrow_mut().text = err;
+                    return false;
+                }
                 game.screen_sys
                     .clone()
-                    .replace_screen(Box::new(super::ServerList::new(None)));
+                    .replace_screen(Box::new(super::ServerList::new()));
                 true
             });
         }
 
         // Cancel
         let cancel = ui::ButtonBuilder::new()
-            .position(-110.0, 100.0)
+            .position(-110.0, 220.0)
             .size(200.0, 40.0)
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
         {
             let mut cancel = cancel.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Cancel")
+                .text(lang::tr("gui.cancel", "Cancel"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *cancel);
             cancel.add_text(txt);
             cancel.add_click_func(|_, game| {
                 game.screen_sys
                     .clone()
-                    .replace_screen(Box::new(super::ServerList::new(None)));
+                    .replace_screen(Box::new(super::ServerList::new()));
                 true
             });
         }
@@ -190,6 +534,11 @@ impl super::Screen for EditServerEntry {
             logo,
             _name: server_name,
             _address: server_address,
+            _join_commands: join_commands,
+            _join_delay: join_delay,
+            _forwarding: forwarding_button,
+            _forwarding_secret: forwarding_secret,
+            _settings: server_settings,
             _done: done,
             _cancel: cancel,
         });
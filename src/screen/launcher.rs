@@ -13,8 +13,12 @@
 // limitations under the License.
 
 use std::fs;
+use std::io::Cursor;
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use crate::lang;
 use crate::paths;
 use crate::protocol;
 use crate::ui;
@@ -23,12 +27,13 @@ use crate::render::Renderer;
 use crate::screen::{Screen, ScreenSystem, ServerList};
 use crate::ui::Container;
 use crate::StringSetting;
+use crossbeam_channel::{unbounded, Receiver};
 use leafish_protocol::protocol::login::{Account, AccountType};
+use log::warn;
 use parking_lot::Mutex;
 use rand::Rng;
 use rfd::FileDialog;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::io::Read;
 use std::rc::Rc;
 
 pub struct Launcher {
@@ -40,8 +45,13 @@ pub struct Launcher {
     background_selection: Option<ui::ButtonRef>,
     screen_sys: Arc<ScreenSystem>,
     active_account: Arc<Mutex<Option<Account>>>,
+    heads: Option<Receiver<(usize, image::DynamicImage)>>,
 }
 
+/// How long a cached head is used before it's fetched again, so skin
+/// changes show up eventually.
+const HEAD_REFRESH_TIME: Duration = Duration::from_secs(24 * 60 * 60);
+
 impl Clone for Launcher {
     fn clone(&self) -> Self {
         Launcher::new(
@@ -53,7 +63,9 @@ impl Clone for Launcher {
 }
 
 struct RenderAccount {
-    _head_picture: Option<ui::ImageRef>,
+    head_picture: ui::ImageRef,
+    head_hat: Option<ui::ImageRef>,
+    head_texture: Option<String>,
     _entry_back: Option<ui::ImageRef>,
     _account_name: Option<ui::TextRef>,
     _account_type: Option<ui::TextRef>,
@@ -74,6 +86,7 @@ impl Launcher {
             background_selection: None,
             screen_sys,
             active_account,
+            heads: None,
         }
     }
 }
@@ -133,7 +146,7 @@ impl super::Screen for Launcher {
         {
             let mut add = add.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Add")
+                .text(lang::tr("selectServer.add", "Add"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *add);
             add.add_text(txt);
@@ -169,7 +182,7 @@ impl super::Screen for Launcher {
         {
             let mut background_selection = background_selection.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Select background")
+                .text(lang::tr("leafish.selectBackground", "Select background"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *background_selection);
             background_selection.add_text(txt);
@@ -190,6 +203,8 @@ impl super::Screen for Launcher {
             })
         }
         self.background_selection.replace(background_selection);
+        let (send, heads) = unbounded();
+        self.heads = Some(heads);
         let mut offset = 0.0;
         let accounts = self.accounts.clone();
         let accounts = accounts.lock();
@@ -201,6 +216,22 @@ impl super::Screen for Launcher {
             let account_account_name_text = account.verification_tokens.get(0).unwrap().clone();
             let account_password_text = account.verification_tokens.get(1).unwrap().clone();
             let account_type = account.account_type.clone();
+            // The default head is shown until the real one is loaded
+            let default_skin = if account.uuid.as_deref().is_some_and(is_slim_default) {
+                "minecraft:entity/alex"
+            } else {
+                "minecraft:entity/steve"
+            };
+            if account.uuid.is_some() {
+                let send = send.clone();
+                let account = account.clone();
+                // Don't block the main thread whilst fetching the skin
+                thread::spawn(move || {
+                    if let Some(head) = load_head(account) {
+                        drop(send.send((idx, head)));
+                    }
+                });
+            }
             // Everything is attached to this
             let back = ui::ImageBuilder::new()
                 .texture("leafish:solid")
@@ -240,7 +271,7 @@ impl super::Screen for Launcher {
                         active_account.clone().lock().replace(result.ok().unwrap());
                         game.screen_sys
                             .clone()
-                            .add_screen(Box::new(ServerList::new(None)));
+                            .add_screen(Box::new(ServerList::new()));
                     } else {
                         println!(
                             "An error occurred while attempting to login {}",
@@ -314,10 +345,18 @@ impl super::Screen for Launcher {
                 .attach(&mut *back.borrow_mut());
 
             let head = ui::ImageBuilder::new()
-                .texture("none") // TODO: Load the actual head image!
+                .texture(default_skin)
+                .texture_coords((32.0, 32.0, 32.0, 32.0))
                 .position(-200.0, offset * 105.0)
                 .size(85.0, 85.0)
-                .colour((0, 0, 0, 255))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .create(ui_container);
+            let head_hat = ui::ImageBuilder::new()
+                .texture(default_skin)
+                .texture_coords((160.0, 32.0, 32.0, 32.0))
+                .position(-200.0, offset * 105.0)
+                .size(85.0, 85.0)
+                .draw_index(1)
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .create(ui_container);
             // Delete entry button
@@ -409,7 +448,9 @@ impl super::Screen for Launcher {
                 })
             }
             self.rendered_accounts.push(RenderAccount {
-                _head_picture: Some(head),
+                head_picture: head,
+                head_hat: Some(head_hat),
+                head_texture: None,
                 _entry_back: Some(back),
                 _account_name: Some(account_name),
                 _account_type: Some(text_account_type),
@@ -421,10 +462,19 @@ impl super::Screen for Launcher {
     fn on_deactive(
         &mut self,
         _screen_sys: &ScreenSystem,
-        _renderer: Arc<Renderer>,
+        renderer: Arc<Renderer>,
         _ui_container: &mut ui::Container,
     ) {
         // Clean up
+        {
+            let mut tex = renderer.get_textures_ref().write();
+            for account in &self.rendered_accounts {
+                if let Some(ref head) = account.head_texture {
+                    tex.remove_dynamic(head);
+                }
+            }
+        }
+        self.heads = None;
         self.options.take();
         self.disclaimer.take();
         self.rendered_accounts.clear();
@@ -435,10 +485,29 @@ impl super::Screen for Launcher {
     fn tick(
         &mut self,
         _screen_sys: &ScreenSystem,
-        _renderer: Arc<Renderer>,
+        renderer: Arc<Renderer>,
         _ui_container: &mut ui::Container,
         _: f64,
     ) {
+        let Some(heads) = &self.heads else {
+            return;
+        };
+        while let Ok((idx, head)) = heads.try_recv() {
+            let Some(account) = self.rendered_accounts.get_mut(idx) else {
+                continue;
+            };
+            let name = format!("account-head-{}", idx);
+            let tex = renderer.get_textures_ref();
+            if let Some(old) = account.head_texture.replace(name.clone()) {
+                tex.write().remove_dynamic(&old);
+            }
+            let head_tex = tex.write().put_dynamic(&name, head);
+            let mut picture = account.head_picture.borrow_mut();
+            picture.texture = head_tex.name;
+            picture.texture_coords = (0.0, 0.0, 256.0, 256.0);
+            // The hat is already part of the loaded head
+            account.head_hat = None;
+        }
     }
 
     fn on_resize(
@@ -480,7 +549,6 @@ impl super::Screen for Launcher {
 }
 
 fn save_accounts(accounts: &[Account]) {
-    let mut file = File::create(paths::get_config_dir().join("accounts.cfg")).unwrap();
     // filter out microsoft accounts as these will become invalid after ~1 day, so the launcher has to
     // provide us with a fresh token on startup
     let accounts = accounts
@@ -488,7 +556,69 @@ fn save_accounts(accounts: &[Account]) {
         .filter(|account| account.account_type != AccountType::Microsoft)
         .collect::<Vec<_>>();
     let json = serde_json::to_string(&accounts).unwrap();
-    file.write_all(json.as_bytes()).unwrap();
+    if let Err(err) = paths::write_atomic(
+        &paths::get_config_dir().join("accounts.cfg"),
+        json.as_bytes(),
+    ) {
+        warn!("couldnt save the accounts file: {err}");
+    }
+}
+
+/// Loads the head of an account, from the cache while it's fresh and from
+/// the account's skin otherwise. `None` means the default head is used.
+fn load_head(mut account: Account) -> Option<image::DynamicImage> {
+    let cache_dir = paths::get_cache_dir().join("head-cache");
+    let cache_path = cache_dir.join(format!("{}.png", account.uuid.as_ref()?));
+    let fresh = fs::metadata(&cache_path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.elapsed().ok())
+        .is_some_and(|age| age < HEAD_REFRESH_TIME);
+    if fresh {
+        return image::open(&cache_path).ok();
+    }
+    match account.append_head_img_data() {
+        Ok(()) => {
+            let skin = image::load_from_memory(account.head_img_data.as_ref()?).ok()?;
+            let head = crop_head(&skin);
+            let mut png = vec![];
+            if head
+                .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+                .is_ok()
+            {
+                let saved = fs::create_dir_all(&cache_dir)
+                    .and_then(|_| paths::write_atomic(&cache_path, &png));
+                if let Err(err) = saved {
+                    warn!("couldnt cache the head of {}: {}", account.name, err);
+                }
+            }
+            Some(head)
+        }
+        Err(err) => {
+            warn!("couldnt fetch the head of {}: {}", account.name, err);
+            // Better show an old head than the default one while offline
+            image::open(&cache_path).ok()
+        }
+    }
+}
+
+/// Cuts the face out of a skin and draws the hat layer over it.
+fn crop_head(skin: &image::DynamicImage) -> image::DynamicImage {
+    let mut head = skin.crop_imm(8, 8, 8, 8);
+    let hat = skin.crop_imm(40, 8, 8, 8);
+    image::imageops::overlay(&mut head, &hat, 0, 0);
+    head
+}
+
+/// Whether a player without a skin gets the slim default skin, decided by
+/// the uuid's hash like vanilla does.
+fn is_slim_default(uuid: &str) -> bool {
+    let Ok(uuid) = u128::from_str_radix(&uuid.replace('-', ""), 16) else {
+        return false;
+    };
+    let bits = ((uuid >> 64) as u64) ^ (uuid as u64);
+    let hash = ((bits >> 32) as i32) ^ (bits as i32);
+    hash & 1 == 1
 }
 
 pub fn load_accounts() -> Option<Vec<Account>> {
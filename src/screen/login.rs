@@ -19,6 +19,7 @@ use std::thread;
 
 use rand::Rng;
 
+use crate::lang;
 use crate::render;
 use crate::screen::{Screen, ScreenSystem};
 use crate::settings::SettingStore;
@@ -108,7 +109,7 @@ impl super::Screen for Login {
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .create(ui_container);
         let back_btn_text = ui::TextBuilder::new()
-            .text("Back")
+            .text(lang::tr("gui.back", "Back"))
             .position(0.0, 0.0)
             .alignment(ui::VAttach::Middle, ui::HAttach::Center)
             .attach(&mut *back_btn.borrow_mut());
@@ -19,6 +19,7 @@ pub use self::login::*;
 
 pub mod confirm_box;
 pub mod connecting;
+pub mod disconnected;
 pub mod edit_server;
 
 pub mod background;
@@ -27,6 +28,8 @@ pub mod edit_account;
 pub mod launcher;
 pub mod respawn;
 pub mod settings_menu;
+pub mod sign_editor;
+pub mod unsupported_version;
 
 pub use self::settings_menu::{AudioSettingsMenu, SettingsMenu, VideoSettingsMenu};
 
@@ -185,6 +188,25 @@ impl ScreenSystem {
         self.add_screen(screen);
     }
 
+    /// Builds the current screen again on the next tick, for when its text
+    /// changed, like after the language was switched.
+    pub fn rebuild_current(
+        &self,
+        renderer: Arc<render::Renderer>,
+        ui_container: &mut ui::Container,
+    ) {
+        if let Some(current) = self.screens.write().last_mut() {
+            if current.active {
+                current.active = false;
+                current
+                    .screen
+                    .clone()
+                    .lock()
+                    .on_deactive(self, renderer, ui_container);
+            }
+        }
+    }
+
     pub fn is_current_closable(&self) -> bool {
         if let Some(last) = self.pre_computed_screens.read().last() {
             return last.is_closable();
@@ -1,3 +1,4 @@
+use crate::lang;
 use crate::render::hud::Hud;
 use crate::render::Renderer;
 use crate::screen::{Screen, ScreenSystem};
@@ -58,7 +59,7 @@ impl super::Screen for Respawn {
             .colour((104, 0, 0, 100))
             .create(ui_container);
         let text = ui::TextBuilder::new()
-            .text("You died!")
+            .text(lang::tr("deathScreen.title", "You died!"))
             .position(0.0, -(icon_scale * 10.0 * 3.0))
             .colour((255, 255, 255, 255))
             .scale_y(icon_scale)
@@ -81,7 +82,7 @@ impl super::Screen for Respawn {
         {
             let mut respawn_button = respawn_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Respawn")
+                .text(lang::tr("deathScreen.respawn", "Respawn"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *respawn_button);
             respawn_button.add_text(txt);
@@ -111,7 +112,7 @@ impl super::Screen for Respawn {
         {
             let mut main_menu_button = main_menu_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Title screen")
+                .text(lang::tr("deathScreen.titleScreen", "Title screen"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *main_menu_button);
             main_menu_button.add_text(txt);
@@ -119,7 +120,7 @@ impl super::Screen for Respawn {
                 game.server.load().as_ref().unwrap().disconnect(None);
                 game.screen_sys.pop_screen();
                 game.screen_sys
-                    .replace_screen(Box::new(super::ServerList::new(None)));
+                    .replace_screen(Box::new(super::ServerList::new()));
                 true
             });
         }
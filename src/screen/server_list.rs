@@ -20,6 +20,7 @@ use std::thread;
 
 use crate::format;
 use crate::format::{Component, ComponentType};
+use crate::lang;
 use crate::paths;
 use crate::protocol;
 use crate::render;
@@ -29,29 +30,81 @@ use crate::render::hud::{Hud, HudContext};
 use crate::render::Renderer;
 use crate::screen::{Screen, ScreenSystem};
 use crate::ui::Container;
+use crate::Game;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use crossbeam_channel::unbounded;
 use crossbeam_channel::{Receiver, TryRecvError};
-use instant::Duration;
-use parking_lot::RwLock;
+use instant::{Duration, Instant};
+use lazy_static::lazy_static;
+use log::warn;
+use parking_lot::{Mutex, RwLock};
 use rand::Rng;
 use serde_json::Value;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+
+/// How long a ping result is reused before the server is pinged again
+/// when the list is reopened.
+const PING_CACHE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    /// Last known status of each server, keyed by address, so the list
+    /// can be shown straight away when it is reopened.
+    static ref PING_CACHE: Mutex<HashMap<String, CachedPing>> = Mutex::new(HashMap::new());
+}
+
+struct CachedPing {
+    info: PingInfo,
+    time: Instant,
+}
+
+/// Connects to `address` behind the connecting screen, then shows the game
+/// or why the connection failed.
+pub fn connect_to_server(game: &Game, address: &str) {
+    game.screen_sys
+        .clone()
+        .replace_screen(Box::new(super::connecting::Connecting::new(address)));
+    let hud_context = Arc::new(RwLock::new(HudContext::new()));
+    let result = game.connect_to(address, hud_context.clone());
+    game.screen_sys.clone().pop_screen();
+    if let Err(protocol::Error::UnsupportedVersion(version)) = result {
+        game.screen_sys.clone().add_screen(Box::new(
+            super::unsupported_version::UnsupportedVersion::new(version),
+        ));
+    } else if let Err(error) = result {
+        game.screen_sys
+            .clone()
+            .add_screen(Box::new(super::disconnected::Disconnected::new(
+                Component::new(ComponentType::new(&error.to_string(), None)),
+                Some(address.to_string()),
+            )));
+    } else {
+        game.screen_sys
+            .clone()
+            .add_screen(Box::new(Hud::new(hud_context)));
+    }
+}
 
 pub struct ServerList {
     elements: Option<UIElements>,
-    disconnect_reason: Option<Component>,
 
     needs_reload: Rc<RefCell<bool>>,
+    /// Whether the next reload should ignore cached pings
+    force_ping: Rc<RefCell<bool>>,
+}
+
+impl Default for ServerList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Clone for ServerList {
     fn clone(&self) -> Self {
         ServerList {
             elements: None,
-            disconnect_reason: self.disconnect_reason.clone(),
             needs_reload: Rc::new(RefCell::new(false)),
+            force_ping: Rc::new(RefCell::new(false)),
         }
     }
 }
@@ -64,8 +117,6 @@ struct UIElements {
     _refresh_btn: ui::ButtonRef,
     _options_btn: ui::ButtonRef,
     _disclaimer: ui::TextRef,
-
-    _disconnected: Option<ui::ImageRef>,
 }
 
 struct Server {
@@ -75,6 +126,7 @@ struct Server {
 
     motd: ui::FormattedRef,
     ping: ui::ImageRef,
+    ping_text: ui::TextRef,
     players: ui::TextRef,
     version: ui::FormattedRef,
 
@@ -82,12 +134,16 @@ struct Server {
     icon_texture: Option<String>,
 
     done_ping: bool,
+    got_ping: bool,
     recv: Receiver<PingInfo>,
 }
 
+#[derive(Clone)]
 struct PingInfo {
     motd: format::Component,
-    ping: Duration,
+    /// Round-trip time of the status ping, `None` if the server never
+    /// answered it.
+    ping: Option<Duration>,
     exists: bool,
     online: i32,
     max: i32,
@@ -97,6 +153,23 @@ struct PingInfo {
     favicon: Option<image::DynamicImage>,
 }
 
+/// Selects the signal bar in `gui/icons`, the label and its colour for
+/// the given ping, matching the ranges vanilla uses.
+fn ping_display(ping: Option<Duration>) -> (f64, String, (u8, u8, u8, u8)) {
+    let ping_ms = match ping {
+        Some(ping) => ping.as_millis(),
+        None => return (56.0, "?".to_owned(), (170, 170, 170, 255)),
+    };
+    let (y, colour) = match ping_ms {
+        0..=149 => (16.0, (85, 255, 85, 255)),
+        150..=299 => (24.0, (85, 255, 85, 255)),
+        300..=599 => (32.0, (255, 255, 85, 255)),
+        600..=999 => (40.0, (255, 170, 0, 255)),
+        _ => (48.0, (255, 85, 85, 255)),
+    };
+    (y, format!("{}ms", ping_ms), colour)
+}
+
 impl Server {
     fn update_position(&mut self) {
         if self.offset < 0.0 {
@@ -108,11 +181,11 @@ impl Server {
 }
 
 impl ServerList {
-    pub fn new(disconnect_reason: Option<Component>) -> ServerList {
+    pub fn new() -> ServerList {
         ServerList {
             elements: None,
-            disconnect_reason,
             needs_reload: Rc::new(RefCell::new(false)),
+            force_ping: Rc::new(RefCell::new(false)),
         }
     }
 
@@ -123,6 +196,7 @@ impl ServerList {
     ) {
         let elements = self.elements.as_mut().unwrap();
         *self.needs_reload.borrow_mut() = false;
+        let force_ping = self.force_ping.replace(false);
         {
             // Clean up previous list icons.
             let mut tex = renderer.get_textures_ref().write();
@@ -165,23 +239,7 @@ impl ServerList {
                     false
                 });
                 backr.add_click_func(move |_, game| {
-                    game.screen_sys
-                        .clone()
-                        .replace_screen(Box::new(super::connecting::Connecting::new(&address)));
-                    let hud_context = Arc::new(RwLock::new(HudContext::new()));
-                    let result = game.connect_to(&address, hud_context.clone());
-                    game.screen_sys.clone().pop_screen();
-                    if let Err(error) = result {
-                        game.screen_sys
-                            .clone()
-                            .add_screen(Box::new(ServerList::new(Some(Component::new(
-                                ComponentType::new(&error.to_string(), None),
-                            )))));
-                    } else {
-                        game.screen_sys
-                            .clone()
-                            .add_screen(Box::new(Hud::new(hud_context)));
-                    }
+                    connect_to_server(game, &address);
                     true
                 });
             }
@@ -208,6 +266,13 @@ impl ServerList {
                 .alignment(ui::VAttach::Top, ui::HAttach::Right)
                 .attach(&mut *back.borrow_mut());
 
+            // Ping in milliseconds, below the indicator
+            let ping_text = ui::TextBuilder::new()
+                .text("")
+                .position(5.0, 23.0)
+                .alignment(ui::VAttach::Top, ui::HAttach::Right)
+                .attach(&mut *back.borrow_mut());
+
             // Player count
             let players = ui::TextBuilder::new()
                 .text("???")
@@ -217,7 +282,10 @@ impl ServerList {
 
             // Server's message of the day
             let motd = ui::FormattedBuilder::new()
-                .text(Component::new(ComponentType::new("Connecting...", None)))
+                .text(Component::new(ComponentType::new(
+                    &lang::tr("multiplayer.status.pinging", "Connecting..."),
+                    None,
+                )))
                 .position(100.0, 23.0)
                 .max_width(700.0 - (90.0 + 10.0 + 5.0))
                 .attach(&mut *back.borrow_mut());
@@ -290,15 +358,37 @@ impl ServerList {
                 })
             }
 
+            // Copy address button
+            let copy_entry = ui::ButtonBuilder::new()
+                .position(50.0, 0.0)
+                .size(25.0, 25.0)
+                .alignment(ui::VAttach::Bottom, ui::HAttach::Right)
+                .attach(&mut *back.borrow_mut());
+            {
+                let mut btn = copy_entry.borrow_mut();
+                let txt = ui::TextBuilder::new()
+                    .text("C")
+                    .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                    .attach(&mut *btn);
+                btn.add_text(txt);
+                let saddr = address.clone();
+                btn.add_click_func(move |_, game| {
+                    copy_to_clipboard(game, saddr.clone());
+                    true
+                })
+            }
+
             let mut server = Server {
                 back,
                 offset,
                 y: 0.0,
                 done_ping: false,
+                got_ping: false,
                 recv,
 
                 motd,
                 ping,
+                ping_text,
                 players,
                 version,
 
@@ -309,54 +399,82 @@ impl ServerList {
             elements.servers.push(server);
             offset += 1.0;
 
-            // Don't block the main thread whilst pinging the server
-            thread::spawn(move || {
-                match protocol::Conn::new(&address, protocol::SUPPORTED_PROTOCOLS[0])
-                    .and_then(|conn| conn.do_status())
-                {
-                    Ok(res) => {
-                        let desc = res.0.description;
-                        let favicon = if let Some(icon) = res.0.favicon {
-                            let data_base64 = &icon["data:image/png;base64,".len()..];
-                            let data_base64: String =
-                                data_base64.chars().filter(|c| !c.is_whitespace()).collect();
-                            let data = STANDARD.decode(data_base64).unwrap();
-                            Some(image::load_from_memory(&data).unwrap())
-                        } else {
-                            None
-                        };
-                        drop(send.send(PingInfo {
-                            motd: desc,
-                            ping: res.1,
-                            exists: true,
-                            online: res.0.players.online,
-                            max: res.0.players.max,
-                            protocol_version: res.0.version.protocol,
-                            protocol_name: res.0.version.name,
-                            forge_mods: res.0.forge_mods,
-                            favicon,
-                        }));
-                    }
-                    Err(err) => {
-                        let e = format!("{}", err);
-                        let msg = ComponentType::new(&e, Some(format::Color::Red));
-                        let _ = send.send(PingInfo {
-                            motd: Component::new(msg),
-                            ping: Duration::new(99999, 0),
-                            exists: false,
-                            online: 0,
-                            max: 0,
-                            protocol_version: 0,
-                            protocol_name: "".to_owned(),
-                            forge_mods: vec![],
-                            favicon: None,
-                        });
+            // Show the last known status straight away and only go to the
+            // network if it is missing, stale or a refresh was requested
+            let needs_ping = {
+                let cache = PING_CACHE.lock();
+                match cache.get(&address) {
+                    Some(cached) => {
+                        drop(send.send(cached.info.clone()));
+                        force_ping || cached.time.elapsed() > PING_CACHE_TTL
                     }
+                    None => true,
                 }
+            };
+            if !needs_ping {
+                continue;
+            }
+
+            // Don't block the main thread whilst pinging the server
+            thread::spawn(move || {
+                let info = Self::ping_server(&address);
+                PING_CACHE.lock().insert(
+                    address,
+                    CachedPing {
+                        info: info.clone(),
+                        time: Instant::now(),
+                    },
+                );
+                drop(send.send(info));
             });
         }
     }
 
+    fn ping_server(address: &str) -> PingInfo {
+        match protocol::Conn::new(address, protocol::SUPPORTED_PROTOCOLS[0])
+            .and_then(|conn| conn.do_status())
+        {
+            Ok(res) => {
+                let desc = res.0.description;
+                let favicon = if let Some(icon) = res.0.favicon {
+                    let data_base64 = &icon["data:image/png;base64,".len()..];
+                    let data_base64: String =
+                        data_base64.chars().filter(|c| !c.is_whitespace()).collect();
+                    let data = STANDARD.decode(data_base64).unwrap();
+                    Some(image::load_from_memory(&data).unwrap())
+                } else {
+                    None
+                };
+                PingInfo {
+                    motd: desc,
+                    ping: res.1,
+                    exists: true,
+                    online: res.0.players.online,
+                    max: res.0.players.max,
+                    protocol_version: res.0.version.protocol,
+                    protocol_name: res.0.version.name,
+                    forge_mods: res.0.forge_mods,
+                    favicon,
+                }
+            }
+            Err(err) => {
+                let e = format!("{}", err);
+                let msg = ComponentType::new(&e, Some(format::Color::Red));
+                PingInfo {
+                    motd: Component::new(msg),
+                    ping: None,
+                    exists: false,
+                    online: 0,
+                    max: 0,
+                    protocol_version: 0,
+                    protocol_name: "".to_owned(),
+                    forge_mods: vec![],
+                    favicon: None,
+                }
+            }
+        }
+    }
+
     fn delete_server(index: usize) {
         let mut servers_info = match fs::File::open(paths::get_data_dir().join("servers.json")) {
             Ok(val) => serde_json::from_reader(val).unwrap(),
@@ -395,13 +513,15 @@ impl ServerList {
         {
             let mut refresh = refresh.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Refresh")
+                .text(lang::tr("selectServer.refresh", "Refresh"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *refresh);
             refresh.add_text(txt);
             let nr = self.needs_reload.clone();
+            let fp = self.force_ping.clone();
             refresh.add_click_func(move |_, _| {
                 *nr.borrow_mut() = true;
+                *fp.borrow_mut() = true;
                 true
             })
         }
@@ -416,7 +536,7 @@ impl ServerList {
         {
             let mut add = add.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Add")
+                .text(lang::tr("selectServer.add", "Add"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *add);
             add.add_text(txt);
@@ -463,44 +583,6 @@ impl ServerList {
             .alignment(ui::VAttach::Bottom, ui::HAttach::Right)
             .create(ui_container);
 
-        // If we are kicked from a server display the reason
-        let disconnected = if let Some(ref disconnect_reason) = self.disconnect_reason {
-            let (width, height) = ui::Formatted::compute_size(
-                renderer.clone(),
-                disconnect_reason,
-                600.0,
-                1.0,
-                1.0,
-                1.0,
-            );
-            let background = ui::ImageBuilder::new()
-                .texture("leafish:solid")
-                .position(0.0, 3.0)
-                .size(
-                    width.max(renderer.ui.lock().size_of_string("Disconnected")) + 4.0,
-                    height + 4.0 + 16.0,
-                )
-                .colour((0, 0, 0, 100))
-                .alignment(ui::VAttach::Top, ui::HAttach::Center)
-                .draw_index(10)
-                .create(ui_container);
-            ui::TextBuilder::new()
-                .text("Disconnected")
-                .position(0.0, 2.0)
-                .colour((255, 0, 0, 255))
-                .alignment(ui::VAttach::Top, ui::HAttach::Center)
-                .attach(&mut *background.borrow_mut());
-            ui::FormattedBuilder::new()
-                .text(disconnect_reason.clone())
-                .position(0.0, 18.0)
-                .max_width(600.0)
-                .alignment(ui::VAttach::Top, ui::HAttach::Center)
-                .attach(&mut *background.borrow_mut());
-            Some(background)
-        } else {
-            None
-        };
-
         self.elements = Some(UIElements {
             logo,
             servers: vec![],
@@ -509,8 +591,6 @@ impl ServerList {
             _refresh_btn: refresh,
             _options_btn: options,
             _disclaimer: disclaimer,
-
-            _disconnected: disconnected,
         });
     }
 }
@@ -579,25 +659,19 @@ impl super::Screen for ServerList {
             }
 
             // Keep checking to see if the server has finished being
-            // pinged
+            // pinged, a cached status may be followed by a fresh one
             if !s.done_ping {
                 match s.recv.try_recv() {
                     Ok(res) => {
-                        s.done_ping = true;
+                        s.got_ping = true;
                         s.motd.borrow_mut().set_text(res.motd);
-                        // Selects the icon for the given ping range
-                        // TODO: switch to as_millis() experimental duration_as_u128 #50202 once available?
-                        let ping_ms = (res.ping.subsec_nanos() as f64) / 1000000.0
-                            + (res.ping.as_secs() as f64) * 1000.0;
-                        let y = match ping_ms.round() as u64 {
-                            _x @ 0..=75 => 16.0,
-                            _x @ 76..=150 => 24.0,
-                            _x @ 151..=225 => 32.0,
-                            _x @ 226..=350 => 40.0,
-                            _x @ 351..=999 => 48.0,
-                            _ => 56.0,
-                        };
+                        let (y, text, colour) = ping_display(res.ping);
                         s.ping.borrow_mut().texture_coords.1 = y;
+                        {
+                            let mut ping_text = s.ping_text.borrow_mut();
+                            ping_text.text = text;
+                            ping_text.colour = colour;
+                        }
                         if res.exists {
                             {
                                 let mut players = s.players.borrow_mut();
@@ -634,11 +708,14 @@ impl super::Screen for ServerList {
                                 .take(30)
                                 .collect();
                             let tex = renderer.get_textures_ref();
-                            s.icon_texture = Some(name.clone());
+                            if let Some(old) = s.icon_texture.replace(name.clone()) {
+                                tex.write().remove_dynamic(&old);
+                            }
                             let icon_tex = tex.write().put_dynamic(&name, favicon);
                             s.icon.borrow_mut().texture = icon_tex.name;
                         }
                     }
+                    Err(TryRecvError::Disconnected) if s.got_ping => s.done_ping = true,
                     Err(TryRecvError::Disconnected) => {
                         s.done_ping = true;
                         s.motd
@@ -683,7 +760,8 @@ impl super::Screen for ServerList {
         renderer: Arc<Renderer>,
         ui_container: &mut Container,
     ) {
-        // TODO: Don't ping the servers on resize!
+        // Servers pinged recently are shown from the cache instead of
+        // being pinged again
         self.on_deactive(screen_sys, renderer.clone(), ui_container);
         self.on_active(screen_sys, renderer, ui_container);
     }
@@ -692,3 +770,9 @@ impl super::Screen for ServerList {
         Box::new(self.clone())
     }
 }
+
+pub(super) fn copy_to_clipboard(game: &Game, text: String) {
+    if let Err(err) = game.clipboard_provider.lock().set_contents(text) {
+        warn!("couldnt copy to the clipboard: {}", err);
+    }
+}
@@ -1,4 +1,6 @@
+use crate::lang;
 use crate::render;
+use crate::render::hud::{ChatVisibility, CrosshairStyle};
 use crate::settings::SettingStore;
 use crate::ui;
 
@@ -65,7 +67,7 @@ impl super::Screen for SettingsMenu {
         {
             let mut audio_settings = audio_settings.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Audio settings...")
+                .text(lang::tr("options.sounds", "Audio settings..."))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *audio_settings);
             audio_settings.add_text(txt);
@@ -85,7 +87,7 @@ impl super::Screen for SettingsMenu {
         {
             let mut video_settings = video_settings.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Video settings...")
+                .text(lang::tr("options.video", "Video settings..."))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *video_settings);
             video_settings.add_text(txt);
@@ -105,7 +107,7 @@ impl super::Screen for SettingsMenu {
         {
             let mut controls_settings = controls_settings.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Controls...")
+                .text(lang::tr("options.controls", "Controls..."))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *controls_settings);
             controls_settings.add_text(txt);
@@ -125,7 +127,7 @@ impl super::Screen for SettingsMenu {
         {
             let mut lang_settings = lang_settings.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Language...")
+                .text(lang::tr("options.language", "Language..."))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *lang_settings);
             lang_settings.add_text(txt);
@@ -140,7 +142,10 @@ impl super::Screen for SettingsMenu {
         {
             let mut skin_settings = skin_settings.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Skin Customization...")
+                .text(lang::tr(
+                    "options.skinCustomisation",
+                    "Skin Customization...",
+                ))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *skin_settings);
             skin_settings.add_text(txt);
@@ -153,6 +158,32 @@ impl super::Screen for SettingsMenu {
         }
         buttons.push(skin_settings);
 
+        let r_chat_visibility =
+            ChatVisibility::from_setting(self.settings.get_int(IntSetting::ChatVisibility));
+        let chat_setting = ui::ButtonBuilder::new()
+            .position(-160.0, -100.0)
+            .size(300.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut chat_setting = chat_setting.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(format!("Chat: {}", r_chat_visibility.name()))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *chat_setting);
+            let txt_chat = txt.clone();
+            chat_setting.add_text(txt);
+            chat_setting.add_click_func(move |_, game| {
+                let current = game.settings.get_int(IntSetting::ChatVisibility);
+                let next = (current.max(0) + 1) % ChatVisibility::ALL.len() as i32;
+                txt_chat.borrow_mut().text =
+                    format!("Chat: {}", ChatVisibility::from_setting(next).name());
+                game.settings.set_int(IntSetting::ChatVisibility, next);
+                true
+            });
+        }
+        buttons.push(chat_setting);
+
         // Center bottom items
         let done_button = ui::ButtonBuilder::new()
             .position(0.0, 50.0)
@@ -162,7 +193,7 @@ impl super::Screen for SettingsMenu {
         {
             let mut done_button = done_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done_button);
             done_button.add_text(txt);
@@ -182,7 +213,7 @@ impl super::Screen for SettingsMenu {
             {
                 let mut disconnect_button = disconnect_button.borrow_mut();
                 let txt = ui::TextBuilder::new()
-                    .text("Disconnect")
+                    .text(lang::tr("menu.disconnect", "Disconnect"))
                     .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                     .attach(&mut *disconnect_button);
                 disconnect_button.add_text(txt);
@@ -190,7 +221,7 @@ impl super::Screen for SettingsMenu {
                     game.server.load().as_ref().unwrap().disconnect(None);
                     game.screen_sys.pop_screen();
                     game.screen_sys
-                        .replace_screen(Box::new(super::ServerList::new(None)));
+                        .replace_screen(Box::new(super::ServerList::new()));
                     true
                 });
             }
@@ -291,6 +322,10 @@ impl super::Screen for VideoSettingsMenu {
         let r_max_fps = self.settings.get_int(IntSetting::MaxFps);
         let r_fov = self.settings.get_int(IntSetting::FOV);
         let r_vsync = self.settings.get_bool(BoolSetting::Vsync);
+        let r_view_bobbing = self.settings.get_bool(BoolSetting::ViewBobbing);
+        let r_linear_lighting = self.settings.get_bool(BoolSetting::LinearLighting);
+        let r_crosshair =
+            CrosshairStyle::from_setting(self.settings.get_int(IntSetting::CrosshairStyle));
 
         // Setting buttons
         // TODO: Slider
@@ -316,6 +351,30 @@ impl super::Screen for VideoSettingsMenu {
         }
         buttons.push(fov_setting);
 
+        let crosshair_setting = ui::ButtonBuilder::new()
+            .position(-160.0, -50.0)
+            .size(300.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut crosshair_setting = crosshair_setting.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(format!("Crosshair: {}", r_crosshair.name()))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *crosshair_setting);
+            let txt_crosshair = txt.clone();
+            crosshair_setting.add_text(txt);
+            crosshair_setting.add_click_func(move |_, game| {
+                let current = game.settings.get_int(IntSetting::CrosshairStyle);
+                let next = (current.max(0) + 1) % CrosshairStyle::ALL.len() as i32;
+                txt_crosshair.borrow_mut().text =
+                    format!("Crosshair: {}", CrosshairStyle::from_setting(next).name());
+                game.settings.set_int(IntSetting::CrosshairStyle, next);
+                true
+            });
+        }
+        buttons.push(crosshair_setting);
+
         let vsync_setting = ui::ButtonBuilder::new()
             .position(-160.0, 0.0)
             .size(300.0, 40.0)
@@ -342,6 +401,64 @@ impl super::Screen for VideoSettingsMenu {
         }
         buttons.push(vsync_setting);
 
+        let view_bobbing_setting = ui::ButtonBuilder::new()
+            .position(-160.0, 50.0)
+            .size(300.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut view_bobbing_setting = view_bobbing_setting.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(format!(
+                    "View Bobbing: {}",
+                    if r_view_bobbing { "ON" } else { "OFF" }
+                ))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *view_bobbing_setting);
+            let txt_view_bobbing = txt.clone();
+            view_bobbing_setting.add_text(txt);
+            view_bobbing_setting.add_click_func(move |_, game| {
+                let r_view_bobbing = !game.settings.get_bool(BoolSetting::ViewBobbing);
+                txt_view_bobbing.borrow_mut().text = format!(
+                    "View Bobbing: {}",
+                    if r_view_bobbing { "ON" } else { "OFF" }
+                );
+                game.settings
+                    .set_bool(BoolSetting::ViewBobbing, r_view_bobbing);
+                true
+            });
+        }
+        buttons.push(view_bobbing_setting);
+
+        let linear_lighting_setting = ui::ButtonBuilder::new()
+            .position(160.0, 50.0)
+            .size(300.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut linear_lighting_setting = linear_lighting_setting.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(format!(
+                    "Linear Lighting: {}",
+                    if r_linear_lighting { "ON" } else { "OFF" }
+                ))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *linear_lighting_setting);
+            let txt_linear_lighting = txt.clone();
+            linear_lighting_setting.add_text(txt);
+            linear_lighting_setting.add_click_func(move |_, game| {
+                let r_linear_lighting = !game.settings.get_bool(BoolSetting::LinearLighting);
+                txt_linear_lighting.borrow_mut().text = format!(
+                    "Linear Lighting: {}",
+                    if r_linear_lighting { "ON" } else { "OFF" }
+                );
+                game.settings
+                    .set_bool(BoolSetting::LinearLighting, r_linear_lighting);
+                true
+            });
+        }
+        buttons.push(linear_lighting_setting);
+
         // TODO: Slider
         let fps_setting = ui::ButtonBuilder::new()
             .position(160.0, 0.0)
@@ -372,7 +489,7 @@ impl super::Screen for VideoSettingsMenu {
         {
             let mut done_button = done_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done_button);
             done_button.add_text(txt);
@@ -481,7 +598,7 @@ impl super::Screen for AudioSettingsMenu {
         {
             let mut done_button = done_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done_button);
             done_button.add_text(txt);
@@ -682,7 +799,7 @@ impl super::Screen for SkinSettingsMenu {
         {
             let mut done_button = done_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done_button);
             done_button.add_text(txt);
@@ -791,7 +908,7 @@ impl super::Screen for ControlsMenu {
         {
             let mut done_button = done_button.borrow_mut();
             let txt = ui::TextBuilder::new()
-                .text("Done")
+                .text(lang::tr("gui.done", "Done"))
                 .alignment(ui::VAttach::Middle, ui::HAttach::Center)
                 .attach(&mut *done_button);
             done_button.add_text(txt);
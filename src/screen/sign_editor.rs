@@ -0,0 +1,160 @@
+use crate::lang;
+use crate::protocol::packet;
+use crate::render;
+use crate::screen::{Screen, ScreenSystem};
+use crate::shared::Position;
+use crate::ui;
+use crate::Game;
+use log::warn;
+use std::sync::Arc;
+use winit::keyboard::{Key, NamedKey, PhysicalKey};
+
+/// Characters that fit on a line of a sign.
+const MAX_LINE_LENGTH: usize = 15;
+
+/// Lets the player write on a sign they just placed, the server opens it
+/// and gets the text back once the editor is closed.
+pub struct SignEditor {
+    elements: Option<UIElements>,
+    location: Position,
+}
+
+impl Clone for SignEditor {
+    fn clone(&self) -> Self {
+        SignEditor {
+            elements: None,
+            location: self.location,
+        }
+    }
+}
+
+struct UIElements {
+    _background: ui::ImageRef,
+    _title: ui::TextRef,
+    lines: [ui::TextBoxRef; 4],
+    _done: ui::ButtonRef,
+}
+
+impl SignEditor {
+    pub fn new(location: Position) -> Self {
+        SignEditor {
+            elements: None,
+            location,
+        }
+    }
+
+    fn finish(game: &Game, location: Position, lines: &[ui::TextBoxRef; 4]) {
+        let lines = [0, 1, 2, 3].map(|i| lines[i].borrow().input.clone());
+        if let Some(server) = game.server.load().as_ref() {
+            if let Some(conn) = server.conn.write().as_mut() {
+                if let Err(err) = packet::send_set_sign(conn, location, lines) {
+                    warn!("Failed to send the text of the sign: {:?}", err);
+                }
+            }
+        }
+        game.screen_sys.pop_screen();
+    }
+}
+
+impl super::Screen for SignEditor {
+    fn on_active(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        renderer: Arc<render::Renderer>,
+        ui_container: &mut ui::Container,
+    ) {
+        let background = ui::ImageBuilder::new()
+            .texture("leafish:solid")
+            .position(0.0, 0.0)
+            .size(
+                renderer.screen_data.read().width as f64,
+                renderer.screen_data.read().height as f64,
+            )
+            .colour((0, 0, 0, 100))
+            .create(ui_container);
+
+        let title = ui::TextBuilder::new()
+            .text(lang::tr("sign.edit", "Edit sign message"))
+            .position(0.0, -120.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let lines = [0, 1, 2, 3].map(|i| {
+            let line = ui::TextBoxBuilder::new()
+                .position(0.0, -75.0 + i as f64 * 40.0)
+                .size(300.0, 36.0)
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .create(ui_container);
+            ui::TextBox::make_focusable(&line, ui_container);
+            line
+        });
+        // Start writing on the first line, tab moves on to the next
+        ui_container.cycle_focus();
+
+        let done = ui::ButtonBuilder::new()
+            .position(0.0, 110.0)
+            .size(200.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut done = done.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text(lang::tr("gui.done", "Done"))
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *done);
+            done.add_text(txt);
+            let location = self.location;
+            let lines = lines.clone();
+            done.add_click_func(move |_, game| {
+                Self::finish(game, location, &lines);
+                true
+            });
+        }
+
+        self.elements = Some(UIElements {
+            _background: background,
+            _title: title,
+            lines,
+            _done: done,
+        });
+    }
+
+    fn on_deactive(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+    ) {
+        // Clean up
+        self.elements = None
+    }
+
+    fn tick(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+        _delta: f64,
+    ) {
+        let elements = self.elements.as_mut().unwrap();
+        for line in &elements.lines {
+            let mut line = line.borrow_mut();
+            if let Some((end, _)) = line.input.char_indices().nth(MAX_LINE_LENGTH) {
+                line.input.truncate(end);
+            }
+        }
+    }
+
+    fn on_key_press(&mut self, key: (Key, PhysicalKey), down: bool, repeat: bool, game: &Game) {
+        // Closing the editor keeps what was written so far, like vanilla
+        if key.0 == Key::Named(NamedKey::Escape) && down && !repeat {
+            if let Some(elements) = &self.elements {
+                Self::finish(game, self.location, &elements.lines);
+            }
+        }
+    }
+
+    fn clone_screen(&self) -> Box<dyn Screen> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,152 @@
+use crate::format::{Color, Component, ComponentType};
+use crate::protocol::versions;
+use crate::render;
+use crate::screen::{Screen, ScreenSystem};
+use crate::ui;
+use std::sync::Arc;
+
+/// Widest a line of the explanation may get before it wraps.
+const MAX_TEXT_WIDTH: f64 = 500.0;
+
+/// Explains that a server runs a version Leafish can't connect to, and
+/// which versions it can.
+pub struct UnsupportedVersion {
+    elements: Option<UIElements>,
+    protocol_version: i32,
+}
+
+struct UIElements {
+    _title: ui::TextRef,
+    _server_version: ui::FormattedRef,
+    _supported: ui::FormattedRef,
+    _back: ui::ButtonRef,
+}
+
+impl UnsupportedVersion {
+    pub fn new(protocol_version: i32) -> Self {
+        UnsupportedVersion {
+            elements: None,
+            protocol_version,
+        }
+    }
+
+    fn server_version(&self) -> Component {
+        let version = match versions::protocol_version_to_release_name(self.protocol_version) {
+            Some(name) => format!("Minecraft {}", name),
+            None => format!("an unknown version (protocol {})", self.protocol_version),
+        };
+        Component {
+            list: vec![
+                ComponentType::new("This server runs ", Some(Color::Gray)),
+                ComponentType::new(&version, Some(Color::Gold)),
+                ComponentType::new(", which Leafish can't connect to.", Some(Color::Gray)),
+            ],
+        }
+    }
+
+    fn supported(&self) -> Component {
+        Component {
+            list: vec![
+                ComponentType::new("Supported versions: ", Some(Color::Gray)),
+                ComponentType::new(
+                    &versions::supported_release_names().join(", "),
+                    Some(Color::Green),
+                ),
+            ],
+        }
+    }
+}
+
+impl super::Screen for UnsupportedVersion {
+    fn on_active(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        renderer: Arc<render::Renderer>,
+        ui_container: &mut ui::Container,
+    ) {
+        let server_version = self.server_version();
+        let supported = self.supported();
+        let (_, version_height) = ui::Formatted::compute_size(
+            renderer.clone(),
+            &server_version,
+            MAX_TEXT_WIDTH,
+            1.0,
+            1.0,
+            1.0,
+        );
+        let (_, supported_height) =
+            ui::Formatted::compute_size(renderer, &supported, MAX_TEXT_WIDTH, 1.0, 1.0, 1.0);
+        let height = version_height + 10.0 + supported_height;
+
+        let title = ui::TextBuilder::new()
+            .text("Unsupported server version")
+            .position(0.0, -height / 2.0 - 30.0)
+            .colour((255, 85, 85, 255))
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let server_version = ui::FormattedBuilder::new()
+            .text(server_version)
+            .position(0.0, -height / 2.0 + version_height / 2.0)
+            .max_width(MAX_TEXT_WIDTH)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let supported = ui::FormattedBuilder::new()
+            .text(supported)
+            .position(0.0, height / 2.0 - supported_height / 2.0)
+            .max_width(MAX_TEXT_WIDTH)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+
+        let back = ui::ButtonBuilder::new()
+            .position(0.0, height / 2.0 + 40.0)
+            .size(200.0, 40.0)
+            .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+            .create(ui_container);
+        {
+            let mut back = back.borrow_mut();
+            let txt = ui::TextBuilder::new()
+                .text("Back to server list")
+                .alignment(ui::VAttach::Middle, ui::HAttach::Center)
+                .attach(&mut *back);
+            back.add_text(txt);
+            back.add_click_func(|_, game| {
+                game.screen_sys
+                    .clone()
+                    .replace_screen(Box::new(super::ServerList::new()));
+                true
+            });
+        }
+
+        self.elements = Some(UIElements {
+            _title: title,
+            _server_version: server_version,
+            _supported: supported,
+            _back: back,
+        });
+    }
+
+    fn on_deactive(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+    ) {
+        // Clean up
+        self.elements = None
+    }
+
+    fn tick(
+        &mut self,
+        _screen_sys: &ScreenSystem,
+        _renderer: Arc<render::Renderer>,
+        _ui_container: &mut ui::Container,
+        _delta: f64,
+    ) {
+    }
+
+    fn clone_screen(&self) -> Box<dyn Screen> {
+        Box::new(UnsupportedVersion::new(self.protocol_version))
+    }
+}
@@ -0,0 +1,50 @@
+//! Saves what's on screen into the screenshots folder.
+
+use crate::gl;
+use crate::paths;
+use image::RgbaImage;
+use log::{error, info};
+use std::fs;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Reads the frame that was just drawn and saves it as a PNG. Encoding
+/// happens on another thread so the game doesn't stutter.
+pub fn capture(width: u32, height: u32) {
+    let mut pixels = vec![0; width as usize * height as usize * 4];
+    gl::unbind_framebuffer_read();
+    gl::read_pixels(
+        0,
+        0,
+        width as i32,
+        height as i32,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        &mut pixels,
+    );
+
+    thread::spawn(move || {
+        let image = match RgbaImage::from_raw(width, height, pixels) {
+            Some(image) => image,
+            None => return,
+        };
+        // GL reads from the bottom up and the window has no use for alpha
+        let mut image = image::imageops::flip_vertical(&image);
+        for pixel in image.pixels_mut() {
+            pixel[3] = 255;
+        }
+
+        let dir = paths::get_data_dir().join("screenshots");
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_millis());
+        let path = dir.join(format!("screenshot-{}.png", timestamp));
+        let result = fs::create_dir_all(&dir)
+            .map_err(image::ImageError::IoError)
+            .and_then(|_| image.save(&path));
+        match result {
+            Ok(()) => info!("Saved screenshot to {}", path.display()),
+            Err(err) => error!("Failed to save the screenshot: {}", err),
+        }
+    });
+}
@@ -14,29 +14,44 @@
 
 use crate::ecs::{Manager, SystemExecStage};
 use crate::entity;
-use crate::entity::player::{create_local, MovementDelta, PlayerModel, PlayerMovement};
-use crate::entity::{EntityType, GameInfo, Gravity, MouseButtons, TargetPosition, TargetRotation};
+use crate::entity::armor_stand::ArmorStandModel;
+use crate::entity::dropped_item::DroppedItemModel;
+use crate::entity::item_frame::ItemFrameModel;
+use crate::entity::player::{create_local, MovementDelta, PlayerModel, PlayerMovement, ViewBob};
+use crate::entity::spatial_index::EntityIndex;
+use crate::entity::{
+    Attributes, EntityType, GameInfo, Gravity, HeadRotation, MouseButtons, Scale, ServerId,
+    TargetPosition, TargetRotation,
+};
 use crate::format;
+use crate::inventory::beacon::Effect;
 use crate::inventory::material::versions::to_material;
+use crate::inventory::player_inventory::OFF_HAND_SLOT;
 use crate::inventory::Inventory;
 use crate::inventory::{inventory_from_type, InventoryContext, InventoryType, Item};
 use crate::particle::block_break_effect::{BlockBreakEffect, BlockEffectData};
-use crate::protocol::{self, forge, mapped_packet, packet};
+use crate::particle::lightning::LightningEffect;
+use crate::particle::void_fog::VoidEffect;
+use crate::particle::weather::WeatherEffect;
+use crate::paths;
+use crate::protocol::{self, forge, forwarding, mapped_packet, packet};
 use crate::render;
-use crate::render::hud::HudContext;
-use crate::render::Renderer;
+use crate::render::hud::{ChatVisibility, CrosshairStyle, HudContext};
+use crate::render::{CameraShake, Renderer};
 use crate::resources;
-use crate::screen::chat::{Chat, ChatContext};
+use crate::screen::chat::{self, Chat, ChatContext};
 use crate::screen::respawn::Respawn;
+use crate::screen::sign_editor::SignEditor;
 use crate::screen::ScreenSystem;
-use crate::settings::Actionkey;
+use crate::settings::{Actionkey, BoolSetting, IntSetting};
 use crate::shared::Position;
+use crate::types;
 use crate::types::hash::FNVHash;
 use crate::types::GameMode;
 use crate::world::{self, World};
 use crate::world::{CPos, LightData, LightUpdate};
 use crate::{ecs, Game};
-use arc_swap::ArcSwapOption;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use atomic_float::AtomicF64;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
@@ -51,22 +66,29 @@ use dashmap::DashMap;
 use instant::{Duration, Instant};
 use leafish_protocol::format::Component;
 use leafish_protocol::item::Stack;
-use leafish_protocol::protocol::login::Account;
+use leafish_protocol::protocol::login::{Account, AccountType};
 use leafish_protocol::protocol::mapped_packet::MappablePacket;
 use leafish_protocol::protocol::mapped_packet::MappedPacket;
-use leafish_protocol::protocol::packet::{send_client_status, send_drop_item, ClientStatus, Hand};
+use leafish_protocol::protocol::packet::{
+    send_client_status, send_drop_item, send_swap_item_in_hand, ClientStatus, Hand,
+    ResourcePackResult,
+};
 use leafish_protocol::protocol::Conn;
 use log::{debug, error, info, warn};
 use parking_lot::Mutex;
 use parking_lot::RwLock;
 use rand::Rng;
 use rayon::ThreadPoolBuilder;
+use sha1::{Digest, Sha1};
 use shared::Version;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::fs;
 use std::hash::BuildHasherDefault;
-use std::io::Cursor;
+use std::io::{self, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -90,6 +112,11 @@ struct WorldData {
     world_time: f64,        // move to world?
     world_time_target: f64, // move to world?
     tick_time: bool,        // move to world?
+    // Eased towards the targets sent by the server, from 0.0 to 1.0
+    rain_level: f32,
+    rain_level_target: f32,
+    thunder_level: f32,
+    thunder_level_target: f32,
 }
 
 impl Default for WorldData {
@@ -99,17 +126,61 @@ impl Default for WorldData {
             world_time: 0.0,
             world_time_target: 0.0,
             tick_time: true,
+            rain_level: 0.0,
+            rain_level_target: 0.0,
+            thunder_level: 0.0,
+            thunder_level_target: 0.0,
         }
     }
 }
 
+/// How long the server may stay silent before the connection is considered
+/// dead. Servers send keep alives far more often than this.
+const KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Movement smaller than this (squared, in blocks) isn't reported to the
+/// server, matching vanilla.
+const MOVE_THRESHOLD_SQ: f64 = 2.0E-4 * 2.0E-4;
+/// Vanilla resends the full position at least once a second even when
+/// standing still.
+const POSITION_REMINDER_TICKS: u32 = 20;
+/// The most game ticks run in a single frame when catching up after a stall,
+/// so a hitch doesn't flush a burst of movement packets to the server.
+const MAX_CATCHUP_TICKS: u32 = 10;
+/// The entity statuses the server sends the player to toggle whether the
+/// debug overlay shows where they are.
+const ENTITY_STATUS_REDUCED_DEBUG_INFO: i8 = 22;
+const ENTITY_STATUS_FULL_DEBUG_INFO: i8 = 23;
+/// The plugin channels Leafish reads messages from, registered with
+/// servers that register them too.
+const HANDLED_CHANNELS: [&str; 3] = ["FML|HS", "MC|Brand", "minecraft:brand"];
+/// The plugin channels every server listens on without registering them.
+const BUILTIN_CHANNELS: [&str; 6] = [
+    "REGISTER",
+    "minecraft:register",
+    "UNREGISTER",
+    "minecraft:unregister",
+    "MC|Brand",
+    "minecraft:brand",
+];
+/// The view distance asked for until the settings have been read.
+const DEFAULT_VIEW_DISTANCE: u8 = 8;
+/// The largest server resource pack that will be downloaded, the same
+/// limit vanilla uses.
+const MAX_RESOURCE_PACK_SIZE: u64 = 250 * 1024 * 1024;
+
 pub struct Server {
     uuid: protocol::UUID,
     pub conn: Arc<RwLock<Option<protocol::Conn>>>,
+    /// Address of the server as shown to the user
+    pub address: String,
     pub(crate) disconnect_gracefully: AtomicBool,
     pub protocol_version: i32,
     pub mapped_protocol_version: Version,
     forge_mods: Vec<forge::ForgeMod>,
+    /// The plugin channels the server registered, which it can be sent
+    /// messages on.
+    server_channels: RwLock<HashSet<String>>,
     pub disconnect_data: Arc<RwLock<DisconnectData>>,
 
     pub world: Arc<world::World>,
@@ -120,14 +191,21 @@ pub struct Server {
 
     // Entity accessors
     pub player: ArcSwapOption<(i32, Entity)>,
+    /// The entity the camera follows instead of the player while spectating
+    camera_entity: ArcSwapOption<Entity>,
     entity_map: Arc<RwLock<HashMap<i32, Entity, BuildHasherDefault<FNVHash>>>>,
     players: Arc<RwLock<HashMap<protocol::UUID, PlayerInfo, BuildHasherDefault<FNVHash>>>>,
 
     tick_timer: AtomicF64,
     entity_tick_timer: AtomicF64,
     pub received_chat_at: ArcSwapOption<Instant>,
+    last_packet_at: ArcSwap<Instant>,
 
     target_info: Arc<RwLock<target::Info>>,
+    weather_effect: Arc<RwLock<WeatherEffect>>,
+    lightning_effect: Arc<RwLock<LightningEffect>>,
+    void_effect: Arc<RwLock<VoidEffect>>,
+    placement_preview: Arc<RwLock<target::PlacementPreview>>,
     pub render_list_computer: Sender<bool>,
     pub render_list_computer_notify: Receiver<bool>,
     pub hud_context: Arc<RwLock<HudContext>>,
@@ -136,10 +214,35 @@ pub struct Server {
     just_died: AtomicBool,
     last_chat_open: AtomicBool,
     pub chat_open: AtomicBool,
+    /// The view distance last sent to the server.
+    view_distance: AtomicU8,
     pub chat_ctx: Arc<ChatContext>,
     screen_sys: Arc<ScreenSystem>,
     renderer: Arc<Renderer>,
     active_block_break_anims: Arc<DashMap<i32, Entity>>,
+    join_commands: Mutex<Option<JoinCommands>>,
+    /// The resource pack the server asked for, waiting for the next tick to
+    /// check whether server resource packs are allowed.
+    pending_resource_pack: Mutex<Option<mapped_packet::play::clientbound::ResourcePackSend>>,
+}
+
+/// Commands sent for the player shortly after joining, such as logging in
+/// on servers that ask for a password.
+pub struct JoinCommands {
+    commands: VecDeque<String>,
+    /// How long to wait before sending each command.
+    delay: Duration,
+    next_at: Option<Instant>,
+}
+
+impl JoinCommands {
+    pub fn new(commands: Vec<String>, delay: Duration) -> Self {
+        Self {
+            commands: commands.into(),
+            delay,
+            next_at: None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -161,20 +264,28 @@ impl Server {
         protocol_version: i32,
         forge_mods: Vec<forge::ForgeMod>,
         fml_network_version: Option<i64>,
+        forwarding: &forwarding::Forwarding,
         renderer: Arc<Renderer>,
         hud_context: Arc<RwLock<HudContext>>,
         screen_sys: Arc<ScreenSystem>,
     ) -> Result<Arc<Server>, protocol::Error> {
         let mut conn = protocol::Conn::new(address, protocol_version)?;
+        let account_uuid = account
+            .uuid
+            .clone()
+            .unwrap_or_else(|| protocol::offline_acc::offline_uuid(&account.name));
+        let player_info = forwarding.velocity_player_info(&account.name, &account_uuid)?;
 
         let tag = match fml_network_version {
             Some(1) => "\0FML\0",
             Some(2) => "\0FML2\0",
             None => "",
-            _ => panic!("unsupported FML network version: {:?}", fml_network_version),
+            Some(version) => return Err(protocol::Error::UnsupportedForgeVersion(version)),
         };
 
-        let host = conn.host.clone() + tag;
+        let host = forwarding
+            .handshake_host(&conn.host, &account_uuid)
+            .unwrap_or_else(|| conn.host.clone() + tag);
         let port = conn.port;
         conn.write_packet(protocol::packet::handshake::serverbound::Handshake {
             protocol_version: protocol::VarInt(protocol_version),
@@ -243,10 +354,20 @@ impl Server {
                 protocol::packet::Packet::LoginDisconnect(val) => {
                     return Err(protocol::Error::Disconnect(val.reason))
                 }
-                val => return Err(protocol::Error::Err(format!("Wrong packet 1: {:?}", val))),
+                // Offline mode Forge servers negotiate before logging in
+                protocol::packet::Packet::LoginPluginRequest(req) => {
+                    Server::handle_login_plugin_request(&mut conn, req, player_info.as_deref())?;
+                }
+                val => return Err(protocol::Error::UnexpectedPacket(format!("{:?}", val))),
             };
         }
 
+        if account.account_type == AccountType::None {
+            return Err(protocol::Error::Auth(
+                "offline accounts can only join offline mode servers".to_owned(),
+            ));
+        }
+
         let mut shared = [0; 16];
         rand::thread_rng().fill(&mut shared);
 
@@ -272,7 +393,6 @@ impl Server {
         conn.enable_encyption(&shared);
 
         let uuid;
-        let compression_threshold = conn.compression_threshold;
         loop {
             match conn.read_packet()? {
                 protocol::packet::Packet::SetInitialCompression(val) => {
@@ -294,72 +414,9 @@ impl Server {
                     return Err(protocol::Error::Disconnect(val.reason))
                 }
                 protocol::packet::Packet::LoginPluginRequest(req) => {
-                    match req.channel.as_ref() {
-                        "fml:loginwrapper" => {
-                            let mut cursor = std::io::Cursor::new(req.data);
-                            let channel: String = protocol::Serializable::read_from(&mut cursor)?;
-
-                            let (id, mut data) = protocol::Conn::read_raw_packet_from(
-                                &mut cursor,
-                                compression_threshold,
-                            )?;
-
-                            match channel.as_ref() {
-                                "fml:handshake" => {
-                                    let packet =
-                                        forge::fml2::FmlHandshake::packet_by_id(id, &mut data)?;
-                                    use forge::fml2::FmlHandshake::*;
-                                    match packet {
-                                        ModList {
-                                            mod_names,
-                                            channels,
-                                            registries,
-                                        } => {
-                                            info!("ModList mod_names={:?} channels={:?} registries={:?}", mod_names, channels, registries);
-                                            conn.write_fml2_handshake_plugin_message(
-                                                req.message_id,
-                                                Some(&ModListReply {
-                                                    mod_names,
-                                                    channels,
-                                                    registries,
-                                                }),
-                                            )?;
-                                        }
-                                        ServerRegistry {
-                                            name,
-                                            snapshot_present: _,
-                                            snapshot: _,
-                                        } => {
-                                            info!("ServerRegistry {:?}", name);
-                                            conn.write_fml2_handshake_plugin_message(
-                                                req.message_id,
-                                                Some(&Acknowledgement),
-                                            )?;
-                                        }
-                                        ConfigurationData { filename, contents } => {
-                                            info!(
-                                                "ConfigurationData filename={:?} contents={}",
-                                                filename,
-                                                String::from_utf8_lossy(&contents)
-                                            );
-                                            conn.write_fml2_handshake_plugin_message(
-                                                req.message_id,
-                                                Some(&Acknowledgement),
-                                            )?;
-                                        }
-                                        _ => unimplemented!(),
-                                    }
-                                }
-                                _ => panic!(
-                                    "unknown LoginPluginRequest fml:loginwrapper channel: {:?}",
-                                    channel
-                                ),
-                            }
-                        }
-                        _ => panic!("unsupported LoginPluginRequest channel: {:?}", req.channel),
-                    }
+                    Server::handle_login_plugin_request(&mut conn, req, player_info.as_deref())?;
                 }
-                val => return Err(protocol::Error::Err(format!("Wrong packet 2: {:?}", val))),
+                val => return Err(protocol::Error::UnexpectedPacket(format!("{:?}", val))),
             }
         }
 
@@ -377,6 +434,110 @@ impl Server {
         Ok(server)
     }
 
+    /// Answers a server's login plugin request, which is how Forge servers
+    /// since 1.13 negotiate mods, channels and registries with the client,
+    /// and how Velocity servers ask for the forwarded player.
+    fn handle_login_plugin_request(
+        conn: &mut protocol::Conn,
+        req: protocol::packet::login::clientbound::LoginPluginRequest,
+        player_info: Option<&[u8]>,
+    ) -> Result<(), protocol::Error> {
+        if req.channel == forwarding::VELOCITY_CHANNEL {
+            return match player_info {
+                Some(player_info) => {
+                    conn.write_login_plugin_response(req.message_id, true, player_info)
+                }
+                None => {
+                    warn!("The server expects Velocity forwarding, which isn't set up for it");
+                    conn.write_login_plugin_response(req.message_id, false, &[])
+                }
+            };
+        }
+        if req.channel != "fml:loginwrapper" {
+            // Like vanilla, tell the server we don't understand the request
+            debug!("Ignoring login plugin request on {}", req.channel);
+            return conn.write_login_plugin_response(req.message_id, false, &[]);
+        }
+
+        let mut cursor = std::io::Cursor::new(req.data);
+        let channel: String = protocol::Serializable::read_from(&mut cursor)?;
+        let (id, mut data) =
+            protocol::Conn::read_raw_packet_from(&mut cursor, conn.compression_threshold)?;
+        if channel != "fml:handshake" {
+            warn!("Ignoring unknown fml:loginwrapper channel {}", channel);
+            return conn.write_fml2_handshake_plugin_message(req.message_id, None);
+        }
+
+        use forge::fml2::FmlHandshake::*;
+        match forge::fml2::FmlHandshake::packet_by_id(id, &mut data)? {
+            ModList {
+                mod_names,
+                channels,
+                registries,
+            } => {
+                info!(
+                    "ModList mod_names={:?} channels={:?} registries={:?}",
+                    mod_names, channels, registries
+                );
+                conn.write_fml2_handshake_plugin_message(
+                    req.message_id,
+                    Some(&ModListReply {
+                        mod_names,
+                        channels,
+                        registries,
+                    }),
+                )
+            }
+            ServerRegistry {
+                name,
+                snapshot_present: _,
+                snapshot: _,
+            } => {
+                info!("ServerRegistry {:?}", name);
+                conn.write_fml2_handshake_plugin_message(req.message_id, Some(&Acknowledgement))
+            }
+            ConfigurationData { filename, contents } => {
+                info!(
+                    "ConfigurationData filename={:?} contents={}",
+                    filename,
+                    String::from_utf8_lossy(&contents)
+                );
+                conn.write_fml2_handshake_plugin_message(req.message_id, Some(&Acknowledgement))
+            }
+            packet => Err(protocol::Error::UnexpectedPacket(format!(
+                "fml:handshake {:?}",
+                packet
+            ))),
+        }
+    }
+
+    /// Plays back a packet log recorded with `--packet-log` instead of
+    /// connecting to a server.
+    pub fn replay(
+        resources: Arc<RwLock<resources::Manager>>,
+        path: &str,
+        renderer: Arc<Renderer>,
+        hud_context: Arc<RwLock<HudContext>>,
+        screen_sys: Arc<ScreenSystem>,
+    ) -> Result<Arc<Server>, protocol::Error> {
+        let conn = protocol::Conn::replay(path)?;
+        let protocol_version = conn.protocol_version;
+        info!(
+            "Replaying packet log {} (protocol version {})",
+            path, protocol_version
+        );
+        Ok(Server::connect0(
+            conn,
+            protocol_version,
+            vec![],
+            protocol::UUID::default(),
+            resources,
+            renderer,
+            hud_context,
+            screen_sys,
+        ))
+    }
+
     fn connect0(
         conn: Conn,
         protocol_version: i32,
@@ -424,6 +585,7 @@ impl Server {
                 let pck = read.read_packet();
                 match pck {
                     Ok(pck) => {
+                        server.last_packet_at.store(Arc::new(Instant::now()));
                         let pck = pck.map();
 
                         match pck {
@@ -492,6 +654,11 @@ impl Server {
                             MappedPacket::UpdateSign(update_sign) => {
                                 server.on_sign_update(update_sign);
                             }
+                            MappedPacket::SignEditorOpen(open) => {
+                                server
+                                    .screen_sys
+                                    .add_screen(Box::new(SignEditor::new(open.location)));
+                            }
                             /*
                             MappedPacket::UpdateBlockEntity_Data(block_update) => {
                                 server.on_block_entity_update_data(block_update);
@@ -502,6 +669,9 @@ impl Server {
                             MappedPacket::EntityDestroy(entity_destroy) => {
                                 server.on_entity_destroy(entity_destroy);
                             }
+                            MappedPacket::Camera(camera) => {
+                                server.on_camera(camera);
+                            }
                             MappedPacket::EntityMove(m) => {
                                 server.on_entity_move(m);
                             }
@@ -519,8 +689,12 @@ impl Server {
                                     let mut entities = server.entities.write();
                                     let mut entity =
                                         entities.world.get_entity_mut(*entity).unwrap();
-                                    let mut rotation = entity.get_mut::<TargetRotation>().unwrap();
-                                    rotation.yaw = -(look.head_yaw as f64 / 256.0) * PI * 2.0;
+                                    let yaw = -(look.head_yaw as f64 / 256.0) * PI * 2.0;
+                                    if let Some(mut head) = entity.get_mut::<HeadRotation>() {
+                                        head.target_yaw = (PI * 2.0 + yaw) % (PI * 2.0);
+                                    } else {
+                                        entity.get_mut::<TargetRotation>().unwrap().yaw = yaw;
+                                    }
                                 }
                             }
                             MappedPacket::JoinGame(join) => {
@@ -531,10 +705,15 @@ impl Server {
                                     dimension_name,
                                     dimension,
                                     world_name,
+                                    reduced_debug_info,
                                     ..
                                 } = join;
 
                                 server.on_game_join(gamemode, entity_id);
+                                server
+                                    .hud_context
+                                    .write()
+                                    .update_reduced_debug_info(reduced_debug_info.unwrap_or(false));
 
                                 let dimension = dimension_id
                                     .map(world::Dimension::from_index)
@@ -567,16 +746,10 @@ impl Server {
                                 );
                             }
                             MappedPacket::SpawnObject(spawn) => {
-                                use std::f64::consts::PI;
-                                server.on_entity_spawn(
-                                    spawn.ty as i16,
-                                    spawn.entity_id,
-                                    spawn.x,
-                                    spawn.y,
-                                    spawn.z,
-                                    -(spawn.yaw as f64 / 256.0) * PI * 2.0,
-                                    -(spawn.pitch as f64 / 256.0) * PI * 2.0,
-                                );
+                                server.on_object_spawn(spawn);
+                            }
+                            MappedPacket::SpawnGlobalEntity(spawn) => {
+                                server.on_global_entity_spawn(spawn);
                             }
                             MappedPacket::EntityTeleport(entity_teleport) => {
                                 server.on_entity_teleport(
@@ -680,6 +853,12 @@ impl Server {
                             MappedPacket::ServerMessage(server_message) => {
                                 server.on_servermessage(server_message);
                             }
+                            MappedPacket::EntityStatus(status) => {
+                                server.on_entity_status(status);
+                            }
+                            MappedPacket::ResourcePackSend(resource_pack) => {
+                                server.on_resource_pack_send(resource_pack);
+                            }
                             MappedPacket::PlayerInfo_String(player_info) => {
                                 server.on_player_info_string(player_info);
                             }
@@ -705,6 +884,40 @@ impl Server {
                                     server.on_set_slot(items.id as i16, item.0 as i16, item.1);
                                 }
                             }
+                            MappedPacket::EntityMetadata(entity_metadata) => {
+                                server.on_entity_metadata(entity_metadata);
+                            }
+                            MappedPacket::EntityProperties(properties) => {
+                                server.on_entity_properties(properties);
+                            }
+                            MappedPacket::SetCooldown(cooldown) => {
+                                server
+                                    .hud_context
+                                    .write()
+                                    .set_cooldown(cooldown.item_id, cooldown.ticks);
+                            }
+                            MappedPacket::EntityEffect(effect) => {
+                                server.on_entity_effect(effect);
+                            }
+                            MappedPacket::EntityRemoveEffect(remove_effect) => {
+                                server.on_entity_remove_effect(remove_effect);
+                            }
+                            MappedPacket::EntityEquipment_Array(equipment) => {
+                                for entry in &equipment.equipments.equipments {
+                                    server.on_entity_equipment(
+                                        equipment.entity_id,
+                                        entry.slot as i32,
+                                        entry.item.as_ref(),
+                                    );
+                                }
+                            }
+                            MappedPacket::EntityEquipment_Single(equipment) => {
+                                server.on_entity_equipment(
+                                    equipment.entity_id,
+                                    equipment.slot,
+                                    equipment.item.as_ref(),
+                                );
+                            }
                             MappedPacket::WindowProperty(data) => {
                                 let win_id: i32 = data.id as i32;
                                 if let Some(inv) =
@@ -732,10 +945,15 @@ impl Server {
                                     .try_close_inventory(&server.screen_sys, true);
                             }
                             MappedPacket::WindowOpen(open) => {
+                                let version = server.mapped_protocol_version;
                                 let inv_type = if let Some(name) = &open.ty_name {
                                     InventoryType::from_name(name, open.slot_count.unwrap())
+                                } else if version < Version::V1_8 {
+                                    InventoryType::from_legacy_id(
+                                        open.ty.unwrap(),
+                                        open.slot_count.unwrap(),
+                                    )
                                 } else {
-                                    let version = server.mapped_protocol_version;
                                     InventoryType::from_id(version, open.ty.unwrap())
                                 };
 
@@ -821,12 +1039,22 @@ impl Server {
                                         .set_action_bar(format::Component::from_str(&bar));
                                 }
                             }
-                            _ => {
-                                // debug!("other packet!");
+                            pck => {
+                                let (id, len) = read.last_read();
+                                protocol::unhandled::note_unhandled(
+                                    pck.name(),
+                                    read.state,
+                                    id,
+                                    len,
+                                );
                             }
                         }
                     }
                     Err(err) => {
+                        // Nothing more will arrive once the connection was closed
+                        if !server.is_connected() {
+                            break;
+                        }
                         if server.disconnect_data.read().disconnect_reason.is_none() {
                             server.disconnect_data.write().disconnect_reason.replace(
                                 Component::new(format::ComponentType::new(
@@ -923,6 +1151,9 @@ impl Server {
             .insert_resource(ScreenSystemResource(screen_sys.clone()));
         entities.world.insert_resource(ConnResource(conn.clone()));
         entities.world.insert_resource(entity::GameInfo::new());
+        entities
+            .world
+            .insert_resource(entity::spatial_index::EntityIndex::new());
         entities.world.insert_resource(WorldResource(world.clone()));
         entities
             .world
@@ -939,9 +1170,22 @@ impl Server {
         hud_context.write().slots = Some(inventory_context.read().base_slots.clone());
 
         let version = resources.read().version();
+        let address = conn
+            .read()
+            .as_ref()
+            .map(|conn| {
+                // Replayed packet logs have no port, only the log's path
+                if conn.port == 25565 || conn.port == 0 {
+                    conn.host.clone()
+                } else {
+                    format!("{}:{}", conn.host, conn.port)
+                }
+            })
+            .unwrap_or_default();
         Self {
             uuid,
             conn,
+            address,
             disconnect_gracefully: Default::default(),
             protocol_version,
             mapped_protocol_version,
@@ -954,6 +1198,7 @@ impl Server {
 
             entities: Arc::new(RwLock::new(entities)),
             player: ArcSwapOption::new(None),
+            camera_entity: ArcSwapOption::new(None),
             entity_map: Arc::new(RwLock::new(HashMap::with_hasher(
                 BuildHasherDefault::default(),
             ))),
@@ -964,8 +1209,13 @@ impl Server {
             tick_timer: AtomicF64::new(0.0),
             entity_tick_timer: AtomicF64::new(0.0),
             received_chat_at: ArcSwapOption::new(None),
+            last_packet_at: ArcSwap::new(Arc::new(Instant::now())),
 
             target_info: Arc::new(RwLock::new(target::Info::new())),
+            weather_effect: Arc::new(RwLock::new(WeatherEffect::new())),
+            lightning_effect: Arc::new(RwLock::new(LightningEffect::new())),
+            void_effect: Arc::new(RwLock::new(VoidEffect::new())),
+            placement_preview: Arc::new(RwLock::new(target::PlacementPreview::new())),
             render_list_computer,
             render_list_computer_notify,
             hud_context,
@@ -974,10 +1224,46 @@ impl Server {
             just_died: AtomicBool::new(false),
             last_chat_open: AtomicBool::new(false),
             chat_open: AtomicBool::new(false),
+            view_distance: AtomicU8::new(DEFAULT_VIEW_DISTANCE),
             chat_ctx: Arc::new(ChatContext::new()),
             screen_sys,
             renderer,
             active_block_break_anims: Arc::new(Default::default()),
+            join_commands: Mutex::new(None),
+            pending_resource_pack: Mutex::new(None),
+            server_channels: RwLock::new(HashSet::new()),
+        }
+    }
+
+    pub fn set_join_commands(&self, commands: JoinCommands) {
+        self.join_commands.lock().replace(commands);
+    }
+
+    /// Sends the next join command once the player is in the world and the
+    /// delay since the last one has passed.
+    fn send_join_commands(&self) {
+        if self.player.load().is_none() {
+            return;
+        }
+        let mut join_commands = self.join_commands.lock();
+        let Some(pending) = join_commands.as_mut() else {
+            return;
+        };
+        let now = Instant::now();
+        let next_at = *pending.next_at.get_or_insert(now + pending.delay);
+        if now < next_at {
+            return;
+        }
+        if let Some(command) = pending.commands.pop_front() {
+            debug!(
+                "Sending join command {}",
+                command.split(' ').next().unwrap_or("")
+            );
+            self.write_packet(packet::play::serverbound::ChatMessage { message: command });
+            pending.next_at = Some(now + pending.delay);
+        }
+        if pending.commands.is_empty() {
+            join_commands.take();
         }
     }
 
@@ -1004,6 +1290,7 @@ impl Server {
 
     pub fn finish_disconnect(&self) {
         self.conn.write().take().unwrap().close();
+        protocol::unhandled::log_summary();
         if let Some(player) = self.player.swap(None) {
             // TODO: Is this even required if we have the despawn code below?
             self.entities.write().world.despawn(player.1);
@@ -1020,11 +1307,95 @@ impl Server {
         // FIXME: remove other resources!
     }
 
+    /// Disconnects when the server hasn't sent anything for a while, as it
+    /// would have sent a keep alive in the meantime otherwise.
+    fn check_timeout(&self) {
+        if self.disconnect_gracefully.load(Ordering::Relaxed)
+            || self.last_packet_at.load().elapsed() < KEEP_ALIVE_TIMEOUT
+        {
+            return;
+        }
+        let replay = match self.conn.read().as_ref() {
+            Some(conn) => conn.is_replay(),
+            None => return,
+        };
+        if !replay {
+            warn!("Server sent no packets for {:?}", KEEP_ALIVE_TIMEOUT);
+            self.disconnect_closed(Some(Component::new(format::ComponentType::new(
+                "Connection timed out",
+                None,
+            ))));
+        }
+    }
+
+    /// Returns the latency to the server as reported in the player list.
+    pub fn ping(&self) -> Option<i32> {
+        self.players.read().get(&self.uuid).map(|info| info.ping)
+    }
+
+    /// The local player's game mode, survival until the player has spawned.
+    pub fn game_mode(&self) -> GameMode {
+        self.player
+            .load()
+            .as_ref()
+            .and_then(|player| {
+                self.entities
+                    .read()
+                    .world
+                    .get::<GameMode>(player.1)
+                    .copied()
+            })
+            .unwrap_or(GameMode::Survival)
+    }
+
+    pub fn target_block(&self) -> Option<(Position, world::block::Block)> {
+        self.target_info.read().target()
+    }
+
     pub fn is_connected(&self) -> bool {
         self.conn.read().is_some()
     }
 
     pub fn tick(&self, delta: f64, game: &Game) {
+        self.check_timeout();
+        self.send_join_commands();
+        if let Some(pack) = self.pending_resource_pack.lock().take() {
+            self.load_resource_pack(
+                pack,
+                game.settings.get_bool(BoolSetting::ServerResourcePacks),
+            );
+        }
+        let crosshair = (
+            CrosshairStyle::from_setting(game.settings.get_int(IntSetting::CrosshairStyle)),
+            game.settings.get_bool(BoolSetting::AttackIndicator),
+        );
+        if self.hud_context.read().crosshair() != crosshair {
+            self.hud_context
+                .write()
+                .update_crosshair(crosshair.0, crosshair.1);
+        }
+        let hide_coords = game.settings.get_bool(BoolSetting::HideCoords);
+        if self.hud_context.read().hide_coords() != hide_coords {
+            self.hud_context.write().update_hide_coords(hide_coords);
+        }
+        let screenshot_hides_hud = game.settings.get_bool(BoolSetting::ScreenshotHideHud);
+        if self.hud_context.read().screenshot_hides_hud() != screenshot_hides_hud {
+            self.hud_context
+                .write()
+                .update_screenshot_hides_hud(screenshot_hides_hud);
+        }
+        let chat_visibility =
+            ChatVisibility::from_setting(game.settings.get_int(IntSetting::ChatVisibility));
+        let view_distance = game.settings.get_int(IntSetting::ViewDistance).clamp(2, 32) as u8;
+        let view_distance_changed =
+            self.view_distance.swap(view_distance, Ordering::Relaxed) != view_distance;
+        let chat_visibility_changed = self
+            .hud_context
+            .write()
+            .update_chat_visibility(chat_visibility);
+        if (chat_visibility_changed || view_distance_changed) && self.player.load().is_some() {
+            self.send_client_settings();
+        }
         {
             let mut entities = self.entities.write();
             // FIXME: is there another way to do this?
@@ -1073,24 +1444,70 @@ impl Server {
             }
             // Copy to camera
             if let Some(player) = self.player.load().as_ref() {
-                let entities = self.entities.read();
+                let mut entities = self.entities.write();
                 let position = entities
                     .world
                     .get_entity(player.1)
                     .unwrap()
                     .get::<crate::entity::Position>()
-                    .unwrap();
+                    .unwrap()
+                    .position;
                 let rotation = entities
                     .world
                     .get_entity(player.1)
                     .unwrap()
                     .get::<crate::entity::Rotation>()
-                    .unwrap();
-                renderer.camera.lock().pos = cgmath::Point3::from_vec(
-                    position.position + cgmath::Vector3::new(0.0, 1.62, 0.0),
+                    .unwrap()
+                    .clone();
+                let spectating = self.copy_spectated_to_camera(&entities);
+                if !spectating {
+                    renderer.camera.lock().pos =
+                        cgmath::Point3::from_vec(position + cgmath::Vector3::new(0.0, 1.62, 0.0));
+                    renderer.camera.lock().yaw = rotation.yaw;
+                    renderer.camera.lock().pitch = rotation.pitch;
+                }
+
+                let on_ground = entities
+                    .world
+                    .get::<Gravity>(player.1)
+                    .is_some_and(|gravity| gravity.on_ground);
+                let mut shake = CameraShake::default();
+                if let Some(mut view_bob) = entities.world.get_mut::<ViewBob>(player.1) {
+                    view_bob.update(position, on_ground, delta);
+                    if !spectating && game.settings.get_bool(BoolSetting::ViewBobbing) {
+                        shake = view_bob.shake();
+                    }
+                }
+                if !spectating {
+                    shake.roll += self.hud_context.read().hurt_tilt();
+                }
+                renderer.camera.lock().shake = shake;
+
+                let feet = Position::new(
+                    position.x.floor() as i32,
+                    position.y.floor() as i32,
+                    position.z.floor() as i32,
                 );
-                renderer.camera.lock().yaw = rotation.yaw;
-                renderer.camera.lock().pitch = rotation.pitch;
+                let in_portal = matches!(
+                    self.world.get_block(feet),
+                    world::block::Block::NetherPortal { .. }
+                );
+                if self.hud_context.read().in_portal() != in_portal {
+                    self.hud_context.write().set_in_portal(in_portal);
+                }
+
+                let landed =
+                    entities
+                        .world
+                        .get_mut::<PlayerMovement>(player.1)
+                        .and_then(|mut movement| {
+                            movement.auto_jump = game.settings.get_bool(BoolSetting::AutoJump);
+                            movement.landed_fall_distance.take()
+                        });
+                if let Some(fall_distance) = landed {
+                    let gamemode = entities.world.get::<GameMode>(player.1).copied();
+                    self.predict_fall_damage(gamemode, feet, fall_distance);
+                }
             }
         }
         self.entity_tick(delta, game.is_focused(), self.dead.load(Ordering::Acquire));
@@ -1099,15 +1516,24 @@ impl Server {
             self.tick_timer.load(Ordering::Acquire) + delta,
             Ordering::Release,
         );
+        let mut ticks = 0;
         while self.tick_timer.load(Ordering::Acquire) >= 3.0 && self.is_connected() {
+            if ticks == MAX_CATCHUP_TICKS {
+                // Drop the backlog instead of flooding the server
+                self.tick_timer.store(0.0, Ordering::Release);
+                break;
+            }
             self.minecraft_tick(game);
+            ticks += 1;
             self.tick_timer.store(
                 self.tick_timer.load(Ordering::Acquire) - 3.0,
                 Ordering::Release,
             );
         }
 
-        self.update_time(&renderer);
+        self.update_weather(&renderer, delta);
+        self.update_void_fog(&renderer, delta);
+        self.update_time(&renderer, game);
         // FIXME: tick sun in between!
         // self.world.tick(&mut self.entities.write());
 
@@ -1117,22 +1543,73 @@ impl Server {
                 game.screen_sys.close_closable_screens();
                 game.screen_sys.add_screen(Box::new(Respawn::new(0))); // TODO: Use the correct score!
             }
-            if let Some((pos, bl, _, _)) = target::trace_ray(
+            let hud_enabled = self.hud_context.read().enabled;
+            self.target_info.write().set_hidden(!hud_enabled);
+            if let Some((pos, bl, face, cursor)) = target::trace_ray(
                 &self.world,
-                4.0,
+                self.game_mode().reach_distance(),
                 renderer.camera.lock().pos.to_vec(),
                 renderer.view_vector.lock().cast().unwrap(),
                 target::test_block,
             ) {
-                self.target_info.write().update(renderer.clone(), pos, bl);
+                self.target_info
+                    .write()
+                    .update(renderer.clone(), pos, bl, cursor);
+                if hud_enabled {
+                    self.update_placement_preview(game, pos.shift(face));
+                } else {
+                    self.placement_preview.write().clear();
+                }
             } else {
                 self.target_info.write().clear();
+                self.placement_preview.write().clear();
             }
         } else {
             self.target_info.write().clear();
+            self.placement_preview.write().clear();
+        }
+    }
+
+    /// Shows a ghost of the held block at `pos` if it could be placed there.
+    fn update_placement_preview(&self, game: &Game, pos: Position) {
+        let texture = if game.settings.get_bool(BoolSetting::PlacementPreview)
+            && self.game_mode().can_interact_with_world()
+            && self.world.get_block(pos).get_collision_boxes().is_empty()
+        {
+            self.held_block_texture()
+        } else {
+            None
+        };
+        match texture {
+            Some(texture) => {
+                self.placement_preview
+                    .write()
+                    .update(self.renderer.clone(), pos, texture)
+            }
+            None => self.placement_preview.write().clear(),
         }
     }
 
+    /// Returns the block texture of the held item, if it is a placeable block.
+    fn held_block_texture(&self) -> Option<render::Texture> {
+        let hud_context = self.hud_context.read();
+        let slot = 27 + hud_context.get_slot_index() as u16;
+        let item = hud_context.slots.as_ref()?.read().get_item(slot)?;
+        drop(hud_context);
+        if !item
+            .material
+            .is_placable_block(self.mapped_protocol_version, item.stack.id)
+        {
+            return None;
+        }
+        let (_, block) = item.material.texture_locations();
+        render::Renderer::get_texture_optional(
+            self.renderer.get_textures_ref(),
+            &format!("minecraft:{}", block),
+        )
+        .filter(|texture| !texture.is_dummy())
+    }
+
     fn entity_tick(&self, delta: f64, _focused: bool, _dead: bool) {
         let mut entities = self.entities.write();
         {
@@ -1178,21 +1655,83 @@ impl Server {
         self.target_info.clone().write().clear(renderer);
     }*/
 
-    fn update_time(&self, renderer: &Arc<render::Renderer>) {
-        renderer.light_data.lock().sky_offset = self.calculate_sky_offset();
+    fn update_time(&self, renderer: &Arc<render::Renderer>, game: &Game) {
+        let sky_offset = self.calculate_sky_offset();
+        // Night vision lights everything up as if it was day
+        let night_vision = self.hud_context.read().night_vision_level();
+        let mut light_data = renderer.light_data.lock();
+        light_data.sky_offset = sky_offset + (1.0 - sky_offset) * night_vision;
+        light_data.light_level = 0.8 + 0.2 * night_vision;
+        light_data.linear_lighting = game.settings.get_bool(BoolSetting::LinearLighting);
+    }
+
+    fn update_weather(&self, renderer: &Arc<render::Renderer>, delta: f64) {
+        // Vanilla changes the levels by at most 0.01 per tick
+        let step = (0.01 * delta / 3.0) as f32;
+        let rain_level = {
+            let mut entities = self.entities.write();
+            let mut world_data = entities.world.resource_mut::<WorldData>();
+            world_data.rain_level +=
+                (world_data.rain_level_target - world_data.rain_level).clamp(-step, step);
+            world_data.thunder_level +=
+                (world_data.thunder_level_target - world_data.thunder_level).clamp(-step, step);
+            world_data.rain_level
+        };
+        let camera = renderer.camera.lock().pos.to_vec();
+        self.weather_effect
+            .write()
+            .tick(renderer.clone(), &self.world, camera, rain_level, delta);
+        self.lightning_effect.write().tick(renderer.clone(), delta);
+    }
+
+    /// Shows the hurt animation straight away when landing from a fall the
+    /// server is going to deal damage for.
+    fn predict_fall_damage(&self, gamemode: Option<GameMode>, feet: Position, fall_distance: f64) {
+        if !matches!(gamemode, Some(GameMode::Survival | GameMode::Adventure)) {
+            return;
+        }
+        // Liquids and climbing break the fall
+        let block = self.world.get_block(feet);
+        if block.is_climbable()
+            || matches!(
+                block,
+                world::block::Block::Water { .. } | world::block::Block::Lava { .. }
+            )
+        {
+            return;
+        }
+        let mut hud_context = self.hud_context.write();
+        // Slow falling isn't simulated, so the fall distance can't be trusted
+        if hud_context.has_effect(Effect::SlowFalling) {
+            return;
+        }
+        let damage = fall_distance - 3.0 - hud_context.effect_level(Effect::JumpBoost) as f64;
+        if damage.ceil() > 0.0 {
+            hud_context.predict_damage();
+        }
+    }
+
+    fn update_void_fog(&self, renderer: &Arc<render::Renderer>, delta: f64) {
+        let dimension = self.world.dimension.load();
+        let mut void_effect = self.void_effect.write();
+        // Like vanilla, only worlds with a sky fade into the void
+        if dimension.has_sky_light() {
+            let camera = renderer.camera.lock().pos.to_vec();
+            void_effect.tick(renderer.clone(), camera, dimension.min_y(), delta);
+        } else {
+            void_effect.clear();
+        }
+        let darkness = void_effect.darkness();
+        if self.hud_context.read().void_darkness() != darkness {
+            self.hud_context.write().update_void_darkness(darkness);
+        }
     }
 
     fn calculate_sky_offset(&self) -> f32 {
         use std::f32::consts::PI;
-        let mut offset = ((1.0
-            + self
-                .entities
-                .read()
-                .world
-                .resource::<WorldData>()
-                .world_time as f32)
-            / 24000.0)
-            - 0.25;
+        let entities = self.entities.read();
+        let world_data = entities.world.resource::<WorldData>();
+        let mut offset = ((1.0 + world_data.world_time as f32) / 24000.0) - 0.25;
         if offset < 0.0 {
             offset += 1.0;
         } else if offset > 1.0 {
@@ -1206,6 +1745,13 @@ impl Server {
         offset = 1.0 - ((offset * PI * 2.0).cos() * 2.0 + 0.2);
         offset = offset.clamp(0.0, 1.0);
         offset = 1.0 - offset;
+        // Rain and thunder storms darken the sky
+        offset *= 1.0 - world_data.rain_level * 5.0 / 16.0;
+        offset *= 1.0 - world_data.thunder_level * 5.0 / 16.0;
+        offset *= 1.0 - self.void_effect.read().darkness();
+        // Lightning briefly lights the sky back up
+        let flash = self.lightning_effect.read().flash_level();
+        offset += (1.0 - offset) * flash;
         offset * 0.8 + 0.2
     }
 
@@ -1239,7 +1785,9 @@ impl Server {
             let position = player.get::<TargetPosition>().unwrap().clone();
             let rotation = player.get::<crate::entity::Rotation>().unwrap().clone();
             let mut delta = player.get_mut::<MovementDelta>().unwrap();
-            let pos_delta = delta.prev_pos != position.position;
+            delta.ticks_since_position += 1;
+            let pos_delta = (position.position - delta.prev_pos).magnitude2() > MOVE_THRESHOLD_SQ
+                || delta.ticks_since_position >= POSITION_REMINDER_TICKS;
             let rot_delta = delta.prev_rot != rotation;
 
             // Sync our position to the server
@@ -1277,7 +1825,12 @@ impl Server {
                 }
             }
 
-            delta.prev_pos = position.position;
+            // Only remember what was actually sent so slow drift still
+            // adds up to a reported move eventually
+            if pos_delta {
+                delta.prev_pos = position.position;
+                delta.ticks_since_position = 0;
+            }
             delta.prev_rot = rotation;
 
             if !game.is_focused() {
@@ -1285,6 +1838,26 @@ impl Server {
                 mouse_buttons.left = false;
                 mouse_buttons.right = false;
             }
+
+            // Drop chunks far behind the player so servers with huge view
+            // distances can't fill up our memory
+            let center = CPos(
+                (position.position.x.floor() as i32) >> 4,
+                (position.position.z.floor() as i32) >> 4,
+            );
+            let max_distance = game.settings.get_int(IntSetting::ChunkRetainDistance);
+            let view_distance = self.view_distance.load(Ordering::Relaxed) as i32;
+            let max_chunks = game.settings.get_int(IntSetting::MaxLoadedChunks).max(0) as usize;
+            let unloaded = self.world.unload_distant_chunks(
+                center,
+                max_distance,
+                view_distance,
+                max_chunks,
+                &mut entities,
+            );
+            if unloaded > 0 {
+                debug!("Unloaded {} distant chunks", unloaded);
+            }
         }
     }
 
@@ -1308,7 +1881,24 @@ impl Server {
             }
             match key {
                 Actionkey::OpenInv => {
-                    let player_inv = self.inventory_context.read().player_inventory.clone();
+                    let creative = self
+                        .player
+                        .load()
+                        .as_ref()
+                        .and_then(|player| {
+                            self.entities
+                                .read()
+                                .world
+                                .get::<GameMode>(player.1)
+                                .copied()
+                        })
+                        .map(|gamemode| gamemode == GameMode::Creative)
+                        .unwrap_or(false);
+                    let player_inv: Arc<RwLock<dyn Inventory + Sync + Send>> = if creative {
+                        self.inventory_context.read().creative_inventory.clone()
+                    } else {
+                        self.inventory_context.read().player_inventory.clone()
+                    };
                     self.inventory_context.write().open_inventory(
                         player_inv,
                         &self.screen_sys,
@@ -1328,6 +1918,9 @@ impl Server {
                         self.hud_context.write().enabled = !curr;
                     }
                 }
+                Actionkey::Screenshot if state_changed => {
+                    self.hud_context.write().request_screenshot();
+                }
                 Actionkey::ToggleDebug => {
                     if state_changed {
                         let curr = self.hud_context.read().debug;
@@ -1360,6 +1953,25 @@ impl Server {
                         }
                     }
                 }
+                // The off hand was added in 1.9
+                Actionkey::SwapHands
+                    if state_changed && self.mapped_protocol_version > Version::V1_8 =>
+                {
+                    let inv = self.inventory_context.read();
+                    let mut slots = inv.player_inventory.write();
+                    let slot_id = 36 + inv.hotbar_index as u16;
+                    let main_hand = slots.get_item(slot_id);
+                    let off_hand = slots.get_item(OFF_HAND_SLOT);
+                    if main_hand.is_some() || off_hand.is_some() {
+                        slots.set_item(slot_id, off_hand);
+                        slots.set_item(OFF_HAND_SLOT, main_hand);
+                        self.hud_context
+                            .read()
+                            .dirty_slots
+                            .store(true, Ordering::Relaxed);
+                        send_swap_item_in_hand(self.conn.write().as_mut().unwrap()).unwrap();
+                    }
+                }
                 Actionkey::Hotbar1 => {
                     self.inventory_context.write().hotbar_index = 0;
                     self.hud_context.write().update_slot_index(0);
@@ -1407,10 +2019,45 @@ impl Server {
             let mut entities = self.entities.write();
             // check if the player exists, as it might not be initialized very early on server join
             if let Some(player) = self.player.load().as_ref() {
+                let spectator =
+                    entities.world.get::<GameMode>(player.1) == Some(&GameMode::Spectator);
+                if let Some(target_id) = self.attack_target(&entities, player.1) {
+                    // Since 1.9 attacks need time to recharge, so clicks landing
+                    // before that are dropped instead of sending weak attacks
+                    if !spectator
+                        && self.mapped_protocol_version >= Version::V1_9
+                        && self.hud_context.read().attack_strength() < 1.0
+                    {
+                        return;
+                    }
+                    let sneaking = entities
+                        .world
+                        .get::<PlayerMovement>(player.1)
+                        .map(|movement| movement.is_key_pressed(Actionkey::Sneak))
+                        .unwrap_or(false);
+                    // Spectators attacking an entity asks the server to spectate it,
+                    // which it confirms with a camera packet
+                    packet::send_attack_entity(
+                        self.conn.write().as_mut().unwrap(),
+                        target_id,
+                        sneaking,
+                    )
+                    .unwrap();
+                    if !spectator {
+                        packet::send_arm_swing(self.conn.write().as_mut().unwrap(), Hand::MainHand)
+                            .unwrap();
+                        self.hud_context.write().reset_attack_cooldown();
+                    }
+                    return;
+                }
+                if spectator {
+                    return;
+                }
                 let mut player = entities.world.entity_mut(player.1);
                 let mut mouse_buttons = player.get_mut::<MouseButtons>().unwrap();
                 packet::send_arm_swing(self.conn.write().as_mut().unwrap(), Hand::MainHand)
                     .unwrap();
+                self.hud_context.write().reset_attack_cooldown();
                 mouse_buttons.left = true;
             }
         } else {
@@ -1418,6 +2065,31 @@ impl Server {
         }
     }
 
+    /// Returns the server id of the entity the player is looking at, unless a
+    /// block is in the way.
+    fn attack_target(&self, entities: &ecs::Manager, player: Entity) -> Option<i32> {
+        let reach = entities
+            .world
+            .get::<GameMode>(player)
+            .map_or(GameMode::Survival, |mode| *mode)
+            .attack_reach_distance();
+        let camera = self.renderer.camera.lock().pos.to_vec();
+        let view: Vector3<f64> = self.renderer.view_vector.lock().cast().unwrap();
+        let index = entities.world.get_resource::<EntityIndex>()?;
+        let (target, hit) = target::trace_entity(index, reach, camera, view, Some(player))?;
+        let entity_distance = (hit - camera).magnitude();
+        let blocked = target::trace_ray(&self.world, reach, camera, view, target::test_block)
+            .map(|(pos, _, _, at)| {
+                let hit = Vector3::new(pos.x as f64, pos.y as f64, pos.z as f64) + at;
+                (hit - camera).magnitude() < entity_distance
+            })
+            .unwrap_or(false);
+        if blocked {
+            return None;
+        }
+        entities.world.get::<ServerId>(target).map(|id| id.0)
+    }
+
     pub fn on_release_left_click(&self, focused: bool) {
         if focused {
             let mut entities = self.entities.write();
@@ -1427,8 +2099,9 @@ impl Server {
                 let mut mouse_buttons = player.get_mut::<MouseButtons>().unwrap();
                 mouse_buttons.left = false;
             }
+        } else {
+            self.inventory_context.write().on_release(true);
         }
-        // TODO: Pass events into inventory context when not focused
     }
 
     #[allow(unused_must_use)]
@@ -1451,20 +2124,35 @@ impl Server {
                 .entity(self.player.load().as_ref().unwrap().1)
                 .get::<GameMode>()
                 .unwrap();
-            let item = self
-                .hud_context
-                .read()
-                .slots
-                .as_ref()
-                .unwrap()
-                .clone()
-                .read()
-                .get_item((27 + self.hud_context.read().get_slot_index()) as u16)
-                .as_ref()
-                .cloned();
+            // Spectators can't use items or blocks
+            if gamemode == GameMode::Spectator {
+                return;
+            }
+            let (slots, slot) = {
+                let hud_context = self.hud_context.read();
+                let slot = 27 + hud_context.get_slot_index() as u16;
+                (hud_context.slots.clone().unwrap(), slot)
+            };
+            let item = slots.read().get_item(slot);
+            // An empty main hand lets the off hand item be used instead
+            let (hand, item) = if item.is_none() && self.mapped_protocol_version > Version::V1_8 {
+                let off_hand = self
+                    .inventory_context
+                    .read()
+                    .player_inventory
+                    .read()
+                    .get_item(OFF_HAND_SLOT);
+                if off_hand.is_some() {
+                    (Hand::OffHand, off_hand)
+                } else {
+                    (Hand::MainHand, None)
+                }
+            } else {
+                (Hand::MainHand, item)
+            };
             if let Some((pos, _, face, at)) = target::trace_ray(
                 &self.world,
-                4.0,
+                gamemode.reach_distance(),
                 self.renderer.camera.lock().pos.to_vec(),
                 self.renderer.view_vector.lock().cast().unwrap(),
                 target::test_block,
@@ -1474,7 +2162,7 @@ impl Server {
                     pos,
                     face.index() as u8 as i8,
                     at,
-                    Hand::MainHand,
+                    hand,
                     item.clone().map(|item| item.stack),
                 )
                 .map_err(|_| self.disconnect_closed(None));
@@ -1487,11 +2175,8 @@ impl Server {
                             .material
                             .is_placable_block(self.mapped_protocol_version, item.stack.id)
                         {
-                            packet::send_arm_swing(
-                                self.conn.write().as_mut().unwrap(),
-                                Hand::MainHand,
-                            )
-                            .map_err(|_| self.disconnect_closed(None));
+                            packet::send_arm_swing(self.conn.write().as_mut().unwrap(), hand)
+                                .map_err(|_| self.disconnect_closed(None));
                         }
                     }
                 }
@@ -1501,7 +2186,7 @@ impl Server {
                 // we don't look it a block while rightclicking
                 packet::send_use_item(
                     self.conn.clone().write().as_mut().unwrap(),
-                    Hand::MainHand,
+                    hand,
                     None,
                     Some(item.stack),
                 );
@@ -1520,8 +2205,9 @@ impl Server {
                 let mut mouse_buttons = player.get_mut::<MouseButtons>().unwrap();
                 mouse_buttons.right = false;
             }
+        } else {
+            self.inventory_context.write().on_release(false);
         }
-        // TODO: Pass events into inventory context when not focused
     }
 
     pub fn on_cursor_moved(&self, x: f64, y: f64) {
@@ -1555,8 +2241,20 @@ impl Server {
         }
 
         match &*msg.channel {
-            "REGISTER" => {}   // TODO
-            "UNREGISTER" => {} // TODO
+            "REGISTER" | "minecraft:register" => self.on_register_channels(&msg.channel, &msg.data),
+            "UNREGISTER" | "minecraft:unregister" => {
+                let mut server_channels = self.server_channels.write();
+                for channel in split_channels(&msg.data) {
+                    server_channels.remove(channel);
+                }
+            }
+            "MC|Brand" | "minecraft:brand" => {
+                let brand: Result<String, _> =
+                    protocol::Serializable::read_from(&mut std::io::Cursor::new(msg.data));
+                if let Ok(brand) = brand {
+                    info!("Server brand: {}", brand);
+                }
+            }
             "FML|HS" => {
                 let msg =
                     crate::protocol::Serializable::read_from(&mut std::io::Cursor::new(msg.data))
@@ -1650,6 +2348,26 @@ impl Server {
         }
     }
 
+    /// Remembers the channels the server registered, and tells it which of
+    /// them Leafish understands too, like mod loaders do. Servers such as
+    /// Fabric ones only send on channels the client registered.
+    fn on_register_channels(&self, register_channel: &str, data: &[u8]) {
+        let mut shared = vec![];
+        {
+            let mut server_channels = self.server_channels.write();
+            for channel in split_channels(data) {
+                debug!("Server registered plugin channel {}", channel);
+                if server_channels.insert(channel.to_owned()) && HANDLED_CHANNELS.contains(&channel)
+                {
+                    shared.push(channel);
+                }
+            }
+        }
+        if !shared.is_empty() {
+            self.write_plugin_message(register_channel, shared.join("\0").as_bytes());
+        }
+    }
+
     fn write_fmlhs_plugin_message(&self, msg: &forge::FmlHs) {
         let _ = self
             .conn
@@ -1660,6 +2378,10 @@ impl Server {
     }
 
     fn write_plugin_message(&self, channel: &str, data: &[u8]) {
+        if !BUILTIN_CHANNELS.contains(&channel) && !self.server_channels.read().contains(channel) {
+            debug!("Not sending on unregistered plugin channel {}", channel);
+            return;
+        }
         let _ = self
             .conn
             .write()
@@ -1726,6 +2448,125 @@ impl Server {
                 .read()
                 .dirty_slots
                 .store(true, Ordering::Relaxed);
+            if (inventory_id == -1 || inventory_id == 0) && (5..=8).contains(&slot) {
+                let armor = top_inventory.read().player_inventory.read().armor_points();
+                self.hud_context.write().update_armor(armor);
+            }
+        }
+    }
+
+    fn on_entity_properties(
+        &self,
+        entity_properties: mapped_packet::play::clientbound::EntityProperties,
+    ) {
+        let Some(entity) = self
+            .entity_map
+            .read()
+            .get(&entity_properties.entity_id)
+            .copied()
+        else {
+            return;
+        };
+        let is_local = self.is_local_player(entity_properties.entity_id);
+        let mut entities = self.entities.write();
+        let mut entity = entities.world.entity_mut(entity);
+        let mut attributes = entity.take::<Attributes>().unwrap_or_default();
+        attributes.apply_properties(&entity_properties.properties);
+        if let Some(mut scale) = entity.get_mut::<Scale>() {
+            scale.apply_attributes(&attributes);
+        }
+        if is_local {
+            let mut hud_context = self.hud_context.write();
+            if let Some(attack_speed) = attributes.value("attack_speed") {
+                hud_context.update_attack_speed(attack_speed);
+            }
+            if let Some(max_health) = attributes.value("max_health") {
+                hud_context.update_max_health(max_health as f32);
+            }
+        }
+        entity.insert(attributes);
+    }
+
+    fn on_entity_effect(&self, effect: mapped_packet::play::clientbound::EntityEffect) {
+        if !self.is_local_player(effect.entity_id) {
+            return;
+        }
+        if let Ok(kind) = Effect::try_from(effect.effect_id as i16) {
+            self.hud_context
+                .write()
+                .add_effect(kind, effect.amplifier, effect.duration);
+        }
+    }
+
+    fn on_entity_remove_effect(
+        &self,
+        remove_effect: mapped_packet::play::clientbound::EntityRemoveEffect,
+    ) {
+        if !self.is_local_player(remove_effect.entity_id) {
+            return;
+        }
+        if let Ok(kind) = Effect::try_from(remove_effect.effect_id as i16) {
+            self.hud_context.write().remove_effect(kind);
+        }
+    }
+
+    fn is_local_player(&self, entity_id: i32) -> bool {
+        self.player
+            .load()
+            .as_ref()
+            .is_some_and(|player| player.0 == entity_id)
+    }
+
+    fn on_entity_metadata(
+        &self,
+        entity_metadata: mapped_packet::play::clientbound::EntityMetadata,
+    ) {
+        let is_local = self
+            .player
+            .load()
+            .as_ref()
+            .map(|player| player.0 == entity_metadata.entity_id)
+            .unwrap_or(false);
+        if !is_local {
+            if let Some(entity) = self.entity_map.read().get(&entity_metadata.entity_id) {
+                let mut entities = self.entities.write();
+                let mut entity = entities.world.entity_mut(*entity);
+                let version = self.mapped_protocol_version;
+                if let Some(mut frame) = entity.get_mut::<ItemFrameModel>() {
+                    frame.apply_metadata(&entity_metadata.metadata, version);
+                }
+                if let Some(mut stand) = entity.get_mut::<ArmorStandModel>() {
+                    stand.apply_metadata(&entity_metadata.metadata, version);
+                }
+                if let Some(mut item) = entity.get_mut::<DroppedItemModel>() {
+                    item.apply_metadata(&entity_metadata.metadata, version);
+                }
+                if let Some(kind) = entity.get::<EntityType>().copied() {
+                    if let Some(mut scale) = entity.get_mut::<Scale>() {
+                        scale.apply_metadata(&entity_metadata.metadata, version, kind);
+                    }
+                }
+            }
+            return;
+        }
+        // The index of the player's additional hearts moves whenever new
+        // fields are added to its parent entities.
+        let absorption_index = match self.mapped_protocol_version {
+            Version::V1_7 | Version::V1_8 => 17,
+            Version::V1_9 => 10,
+            Version::V1_10
+            | Version::V1_11
+            | Version::V1_12
+            | Version::V1_13
+            | Version::V1_13_2 => 11,
+            Version::V1_14 => 13,
+            Version::V1_15 | Version::V1_16 | Version::V1_16_2 => 14,
+            _ => 15,
+        };
+        if let Some(types::Value::Float(absorption)) =
+            entity_metadata.metadata.get_raw(absorption_index)
+        {
+            self.hud_context.write().update_absorbtion(*absorption);
         }
     }
 
@@ -1761,19 +2602,11 @@ impl Server {
             .unwrap()
             .flying = gamemode.can_fly();
 
-        self.entity_map.write().insert(entity_id, player);
+        self.map_entity(&mut self.entities.write(), entity_id, player);
         self.player.store(Some(Arc::new((entity_id, player))));
+        self.camera_entity.store(None);
 
-        packet::send_client_settings(
-            self.conn.write().as_mut().unwrap(),
-            "en_us".to_string(),
-            8,
-            0,
-            true,
-            127,
-            Hand::MainHand,
-        )
-        .map_err(|_| self.disconnect_closed(None)); // TODO: Make these configurable!
+        self.send_client_settings();
 
         // Let the server know who we are
         let brand = plugin_messages::Brand {
@@ -1805,10 +2638,26 @@ impl Server {
                 self.entities.write().world.despawn(*entity.1);
             }
         }
+        // The new player starts without effects, the server resends any it keeps
+        self.hud_context.write().clear_effects();
+
+        {
+            // The server sends the weather again after respawning
+            let mut entities = self.entities.write();
+            let mut world_data = entities.world.resource_mut::<WorldData>();
+            world_data.rain_level = 0.0;
+            world_data.rain_level_target = 0.0;
+            world_data.thunder_level = 0.0;
+            world_data.thunder_level_target = 0.0;
+        }
+        self.weather_effect.write().clear();
+        self.lightning_effect.write().clear();
+        self.void_effect.write().clear();
 
         let entity_id = self.player.load().as_ref().unwrap().0;
         let local_player = create_local(&mut self.entities.write());
         self.player.store(Some(Arc::new((entity_id, local_player))));
+        self.camera_entity.store(None);
         let gamemode = GameMode::from_int((gamemode & 0x7) as i32);
 
         if let Some(player) = self.player.load().as_ref() {
@@ -1841,7 +2690,7 @@ impl Server {
             drop(hud_context);
             self.screen_sys.pop_screen();
         }
-        self.entity_map.write().insert(entity_id, local_player);
+        self.map_entity(&mut self.entities.write(), entity_id, local_player);
 
         let dimension = dimension
             .map(world::Dimension::from_index)
@@ -1875,25 +2724,51 @@ impl Server {
     }
 
     fn on_game_state_change(&self, game_state: mapped_packet::play::clientbound::ChangeGameState) {
-        if game_state.reason == 3 {
-            if let Some(player) = self.player.load().as_ref() {
-                let gamemode = GameMode::from_int(game_state.value as i32);
-                self.hud_context.clone().write().update_game_mode(gamemode);
-                *self
-                    .entities
-                    .write()
-                    .world
-                    .entity_mut(player.1)
-                    .get_mut::<GameMode>()
-                    .unwrap() = gamemode;
-                self.entities
-                    .write()
-                    .world
-                    .entity_mut(player.1)
-                    .get_mut::<PlayerMovement>()
-                    .unwrap()
-                    .flying = gamemode.can_fly();
+        match game_state.reason {
+            // Begin raining, the levels are eased towards their targets
+            1 => {
+                let mut entities = self.entities.write();
+                let mut world_data = entities.world.resource_mut::<WorldData>();
+                world_data.rain_level_target = 1.0;
+            }
+            // End raining
+            2 => {
+                let mut entities = self.entities.write();
+                let mut world_data = entities.world.resource_mut::<WorldData>();
+                world_data.rain_level_target = 0.0;
+                world_data.thunder_level_target = 0.0;
+            }
+            3 => {
+                if let Some(player) = self.player.load().as_ref() {
+                    let gamemode = GameMode::from_int(game_state.value as i32);
+                    self.hud_context.clone().write().update_game_mode(gamemode);
+                    *self
+                        .entities
+                        .write()
+                        .world
+                        .entity_mut(player.1)
+                        .get_mut::<GameMode>()
+                        .unwrap() = gamemode;
+                    self.entities
+                        .write()
+                        .world
+                        .entity_mut(player.1)
+                        .get_mut::<PlayerMovement>()
+                        .unwrap()
+                        .flying = gamemode.can_fly();
+                }
+            }
+            7 => {
+                let mut entities = self.entities.write();
+                let mut world_data = entities.world.resource_mut::<WorldData>();
+                world_data.rain_level_target = game_state.value.clamp(0.0, 1.0);
+            }
+            8 => {
+                let mut entities = self.entities.write();
+                let mut world_data = entities.world.resource_mut::<WorldData>();
+                world_data.thunder_level_target = game_state.value.clamp(0.0, 1.0);
             }
+            _ => {}
         }
     }
 
@@ -1908,15 +2783,148 @@ impl Server {
         pitch: f64,
     ) {
         let entity_type = entity::versions::to_entity_type(ty, self.mapped_protocol_version);
+        self.spawn_entity(entity_type, entity_id, x, y, z, yaw, pitch);
+    }
+
+    fn on_object_spawn(&self, spawn: mapped_packet::play::clientbound::SpawnObject) {
+        use std::f64::consts::PI;
+        let version = self.mapped_protocol_version;
+        let entity_type = entity::versions::object_to_entity_type(spawn.ty as i16, version);
+        let yaw = -(spawn.yaw as f64 / 256.0) * PI * 2.0;
+        let pitch = -(spawn.pitch as f64 / 256.0) * PI * 2.0;
+        if entity_type == EntityType::Lightning {
+            // Newer versions spawn lightning like any other entity
+            self.on_lightning(spawn.x, spawn.y, spawn.z);
+            return;
+        }
+        if entity_type == EntityType::ItemFrame {
+            // Frames are spawned at the block they hang in, facing the way
+            // given in the object data
+            let facing = ItemFrameModel::facing_from_data(spawn.data, version);
+            let (x, y, z) = ItemFrameModel::center(spawn.x, spawn.y, spawn.z, facing);
+            let mut entities = self.entities.write();
+            if let Some(entity) =
+                entity_type.create_entity_custom_model(&mut entities, x, y, z, yaw, pitch)
+            {
+                entities
+                    .world
+                    .entity_mut(entity)
+                    .insert(ItemFrameModel::new(facing));
+                self.map_entity(&mut entities, spawn.entity_id, entity);
+            }
+            return;
+        }
+        self.spawn_entity(
+            entity_type,
+            spawn.entity_id,
+            spawn.x,
+            spawn.y,
+            spawn.z,
+            yaw,
+            pitch,
+        );
+    }
+
+    fn on_global_entity_spawn(&self, spawn: mapped_packet::play::clientbound::SpawnGlobalEntity) {
+        // Lightning is the only global entity
+        if spawn.ty == 1 {
+            self.on_lightning(spawn.x, spawn.y, spawn.z);
+        }
+    }
+
+    fn on_lightning(&self, x: f64, y: f64, z: f64) {
+        self.lightning_effect.write().strike(Vector3::new(x, y, z));
+    }
+
+    fn spawn_entity(
+        &self,
+        entity_type: EntityType,
+        entity_id: i32,
+        x: f64,
+        y: f64,
+        z: f64,
+        yaw: f64,
+        pitch: f64,
+    ) {
         if entity_type != EntityType::Unknown {
             let entity = entity_type.create_entity(&mut self.entities.write(), x, y, z, yaw, pitch);
             if let Some(entity) = entity {
-                self.entity_map.write().insert(entity_id, entity);
-                println!("spawned {} {:?}", ty, entity_type);
+                self.map_entity(&mut self.entities.write(), entity_id, entity);
+                debug!("Spawned {:?}", entity_type);
+            }
+        }
+    }
+
+    fn on_entity_equipment(&self, entity_id: i32, slot: i32, item: Option<&Stack>) {
+        if let Some(entity) = self.entity_map.read().get(&entity_id) {
+            let mut entities = self.entities.write();
+            if let Some(mut stand) = entities
+                .world
+                .entity_mut(*entity)
+                .get_mut::<ArmorStandModel>()
+            {
+                stand.set_equipment(slot, item, self.mapped_protocol_version);
+            } else if let Some(mut player) =
+                entities.world.entity_mut(*entity).get_mut::<PlayerModel>()
+            {
+                player.set_equipment(slot, item, self.mapped_protocol_version);
             }
         }
     }
 
+    /// Moves the camera to the eyes of the spectated entity, returning false
+    /// if the camera isn't following one.
+    fn copy_spectated_to_camera(&self, entities: &ecs::Manager) -> bool {
+        use std::f64::consts::PI;
+        let entity = match self.camera_entity.load().as_ref() {
+            Some(entity) => match entities.world.get_entity(**entity) {
+                Some(entity) => entity,
+                None => {
+                    // The entity was removed, so look through our own eyes again
+                    self.camera_entity.store(None);
+                    return false;
+                }
+            },
+            None => return false,
+        };
+        let (position, rotation) = match (
+            entity.get::<crate::entity::Position>(),
+            entity.get::<crate::entity::Rotation>(),
+        ) {
+            (Some(position), Some(rotation)) => (position, rotation),
+            _ => return false,
+        };
+        // Most entities have their eyes at 85% of their height
+        let eye_height = entity
+            .get::<crate::entity::Bounds>()
+            .map_or(1.62, |bounds| bounds.bounds.max.y * 0.85);
+        let yaw = entity
+            .get::<HeadRotation>()
+            .map_or(rotation.yaw, |head| head.yaw);
+        let mut camera = self.renderer.camera.lock();
+        camera.pos = cgmath::Point3::from_vec(
+            position.position + cgmath::Vector3::new(0.0, eye_height, 0.0),
+        );
+        camera.yaw = yaw;
+        // Other entities store their pitch the opposite way around to the player
+        camera.pitch = PI + rotation.pitch;
+        true
+    }
+
+    fn on_camera(&self, camera: mapped_packet::play::clientbound::Camera) {
+        let is_player = self
+            .player
+            .load()
+            .as_ref()
+            .is_some_and(|player| player.0 == camera.target_id);
+        let target = if is_player {
+            None
+        } else {
+            self.entity_map.read().get(&camera.target_id).copied()
+        };
+        self.camera_entity.store(target.map(Arc::new));
+    }
+
     fn on_entity_destroy(&self, entity_destroy: mapped_packet::play::clientbound::EntityDestroy) {
         for id in entity_destroy.entity_ids {
             if let Some(entity) = self.entity_map.write().remove(&id) {
@@ -2046,11 +3054,22 @@ impl Server {
             target_rotation.yaw = yaw;
             target_rotation.pitch = pitch;
         }
+        entity.insert(HeadRotation::new((PI * 2.0 + yaw) % (PI * 2.0)));
         if let Some(info) = self.players.read().get(&uuid) {
             let mut model = entity.get_mut::<PlayerModel>().unwrap();
             model.set_skin(info.skin_url.clone());
         }
-        self.entity_map.write().insert(entity_id, world_entity);
+        self.map_entity(&mut entities, entity_id, world_entity);
+    }
+
+    /// Remembers the entity the server refers to by `entity_id`, and the id
+    /// on the entity so it can be looked up the other way too.
+    fn map_entity(&self, entities: &mut ecs::Manager, entity_id: i32, entity: Entity) {
+        entities
+            .world
+            .entity_mut(entity)
+            .insert(ServerId(entity_id));
+        self.entity_map.write().insert(entity_id, entity);
     }
 
     fn on_teleport_player(&self, teleport: mapped_packet::play::clientbound::TeleportPlayer) {
@@ -2132,7 +3151,16 @@ impl Server {
                     // TODO: support more block update actions
                     //1 => // Mob spawner
                     //2 => // Command block text
-                    //3 => // Beacon
+                    3 => {
+                        let get = |name| nbt.1.get(name).and_then(|v| v.as_int()).unwrap_or(0);
+                        self.world
+                            .add_block_entity_action(world::BlockEntityAction::UpdateBeacon(
+                                block_update.location,
+                                get("Levels"),
+                                get("Primary"),
+                                get("Secondary"),
+                            ));
+                    }
                     //4 => // Mob head
                     //5 => // Conduit
                     //6 => // Banner
@@ -2315,10 +3343,115 @@ impl Server {
         }
     }
 
+    #[allow(unused_must_use)]
+    fn send_client_settings(&self) {
+        let chat_mode = self.hud_context.read().chat_visibility() as u8;
+        packet::send_client_settings(
+            self.conn.write().as_mut().unwrap(),
+            "en_us".to_string(),
+            self.view_distance.load(Ordering::Relaxed),
+            chat_mode,
+            true,
+            127,
+            Hand::MainHand,
+        )
+        .map_err(|_| self.disconnect_closed(None)); // TODO: Make these configurable!
+    }
+
+    fn on_entity_status(&self, status: mapped_packet::play::clientbound::EntityStatus) {
+        if self
+            .player
+            .load()
+            .as_ref()
+            .is_none_or(|player| player.0 != status.entity_id)
+        {
+            return;
+        }
+        match status.entity_status {
+            ENTITY_STATUS_REDUCED_DEBUG_INFO => {
+                self.hud_context.write().update_reduced_debug_info(true);
+            }
+            ENTITY_STATUS_FULL_DEBUG_INFO => {
+                self.hud_context.write().update_reduced_debug_info(false);
+            }
+            _ => {}
+        }
+    }
+
+    fn on_resource_pack_send(&self, pack: mapped_packet::play::clientbound::ResourcePackSend) {
+        *self.pending_resource_pack.lock() = Some(pack);
+    }
+
+    /// Downloads the server's resource pack in the background and puts it on
+    /// top of the other packs, reusing a cached copy with the same hash. The
+    /// pack is declined unless `allowed`, which the per-server settings can
+    /// turn on for trusted servers.
+    fn load_resource_pack(
+        &self,
+        pack: mapped_packet::play::clientbound::ResourcePackSend,
+        allowed: bool,
+    ) {
+        let conn = self.conn.clone();
+        let resources = self.resources.clone();
+        let hash = pack.hash.clone();
+        let send_status = move |result| {
+            if let Some(conn) = conn.write().as_mut() {
+                let _ = packet::send_resource_pack_status(conn, hash.clone(), result);
+            }
+        };
+        if !allowed {
+            info!("Declined server resource pack {}", pack.url);
+            send_status(ResourcePackResult::Declined);
+            return;
+        }
+        send_status(ResourcePackResult::Accepted);
+        let conn = self.conn.clone();
+        thread::spawn(move || {
+            let dir = paths::get_cache_dir().join("server-resource-packs");
+            let cached = pack.hash.len() == 40 && pack.hash.chars().all(|c| c.is_ascii_hexdigit());
+            let path = if cached {
+                dir.join(format!("{}.zip", pack.hash.to_lowercase()))
+            } else {
+                dir.join("unknown.zip")
+            };
+            let hash = cached.then_some(pack.hash.as_str());
+            let result = if cached && check_resource_pack_hash(&path, &pack.hash).is_ok() {
+                Ok(())
+            } else {
+                download_resource_pack(&pack.url, &path, hash)
+            };
+            let result = result.and_then(|_| {
+                // The player might have left while the pack was downloading
+                if conn.read().is_none() {
+                    return Ok(());
+                }
+                let mut resources = resources.write();
+                resources.remove_layer(resources::PackLayer::Server);
+                resources.add_pack_path(resources::PackLayer::Server, &path)
+            });
+            match result {
+                Ok(()) => send_status(ResourcePackResult::Loaded),
+                Err(err) => {
+                    warn!("Failed to load server resource pack {}: {}", pack.url, err);
+                    send_status(ResourcePackResult::FailedDownload);
+                }
+            }
+        });
+    }
+
     fn on_servermessage(&self, message: mapped_packet::play::clientbound::ServerMessage) {
         debug!("Received chat message: {}", message.message);
         match message.position {
             None | Some(0) | Some(1) => {
+                // Servers don't always respect the chat mode, so player chat
+                // is filtered here too. Older versions don't tell them apart.
+                let system = message.position != Some(0);
+                if !self.hud_context.read().chat_visibility().shows(system) {
+                    return;
+                }
+                if chat::is_from_ignored(&message.message) {
+                    return;
+                }
                 self.hud_context
                     .write()
                     .display_message_in_chat(message.message);
@@ -2346,6 +3479,7 @@ impl Server {
                 let action = match tile_id {
                     // Fake a sign update
                     "Sign" => 9,
+                    "Beacon" | "minecraft:beacon" => 3,
                     // Not something we care about, so break the loop
                     _ => continue,
                 };
@@ -2381,6 +3515,10 @@ impl Server {
                 chunk_data.data,
             )
             .unwrap();
+        if chunk_data.new {
+            self.world
+                .set_biomes_3d(chunk_data.chunk_x, chunk_data.chunk_z, &chunk_data.biomes);
+        }
         self.load_block_entities(chunk_data.block_entities);
     }
 
@@ -2399,6 +3537,13 @@ impl Server {
                 chunk_data.data,
             )
             .unwrap();
+        if chunk_data.new {
+            self.world.set_biomes_3d(
+                chunk_data.chunk_x,
+                chunk_data.chunk_z,
+                &chunk_data.biomes.data,
+            );
+        }
         self.load_block_entities(chunk_data.block_entities);
     }
 
@@ -2417,6 +3562,13 @@ impl Server {
                 chunk_data.data,
             )
             .unwrap();
+        if chunk_data.new {
+            self.world.set_biomes_3d(
+                chunk_data.chunk_x,
+                chunk_data.chunk_z,
+                &chunk_data.biomes.data,
+            );
+        }
         self.load_block_entities(chunk_data.block_entities);
     }
 
@@ -2575,6 +3727,17 @@ impl Server {
         self.hud_context
             .write()
             .update_health_and_food(health, food, saturation);
+        if let Some(player) = self.player.load().as_ref() {
+            if let Some(mut player_food) = self
+                .entities
+                .write()
+                .world
+                .get_mut::<entity::Food>(player.1)
+            {
+                player_food.level = food;
+                player_food.saturation = saturation;
+            }
+        }
         if health <= 0.0 && !self.dead.load(Ordering::Acquire) {
             self.dead.store(true, Ordering::Release);
             self.screen_sys.close_closable_screens();
@@ -2601,6 +3764,81 @@ fn calculate_relative_teleport(flag: TeleportFlag, flags: u8, base: f64, val: f6
     }
 }
 
+/// Splits the payload of a channel (un)registration into channel names.
+fn split_channels(data: &[u8]) -> impl Iterator<Item = &str> {
+    data.split(|&b| b == 0)
+        .filter_map(|channel| std::str::from_utf8(channel).ok())
+        .filter(|channel| !channel.is_empty())
+}
+
+/// Streams the resource pack at `url` to `path`, giving up once it grows
+/// past `MAX_RESOURCE_PACK_SIZE` or if it doesn't match the SHA-1 `hash`.
+fn download_resource_pack(url: &str, path: &Path, hash: Option<&str>) -> io::Result<()> {
+    let mut res = reqwest::blocking::get(url)
+        .and_then(|res| res.error_for_status())
+        .map_err(io::Error::other)?;
+    if res
+        .content_length()
+        .is_some_and(|len| len > MAX_RESOURCE_PACK_SIZE)
+    {
+        return Err(io::Error::other("resource pack is too large"));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        let mut hasher = Sha1::new();
+        let mut size = 0;
+        let mut buf = [0; 8192];
+        loop {
+            let read = res.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            size += read as u64;
+            if size > MAX_RESOURCE_PACK_SIZE {
+                return Err(io::Error::other("resource pack is too large"));
+            }
+            hasher.update(&buf[..read]);
+            file.write_all(&buf[..read])?;
+        }
+        file.sync_all()?;
+        if let Some(hash) = hash {
+            let actual = format!("{:x}", hasher.finalize());
+            if !actual.eq_ignore_ascii_case(hash) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("resource pack hash {} doesn't match {}", actual, hash),
+                ));
+            }
+        }
+        fs::rename(&tmp_path, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Checks that the resource pack at `path` has the SHA-1 `hash`.
+fn check_resource_pack_hash(path: &Path, hash: &str) -> io::Result<()> {
+    let mut hasher = Sha1::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    let actual = format!("{:x}", hasher.finalize());
+    if actual.eq_ignore_ascii_case(hash) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("resource pack hash {} doesn't match {}", actual, hash),
+        ))
+    }
+}
+
 #[derive(Resource)]
 pub struct WorldResource(pub Arc<World>);
 
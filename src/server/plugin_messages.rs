@@ -1,7 +1,9 @@
+use crate::protocol::packet::play::serverbound::NameItem;
 use crate::protocol::packet::play::serverbound::PluginMessageServerbound;
 use crate::protocol::packet::play::serverbound::PluginMessageServerbound_i16;
-use crate::protocol::{Serializable, VarShort};
+use crate::protocol::{Error, Serializable, VarShort};
 use leafish_protocol::protocol::Conn;
+use shared::Version;
 
 pub struct Brand {
     pub brand: String,
@@ -33,3 +35,32 @@ impl Brand {
         }
     }
 }
+
+/// The name typed into an anvil, sent whenever it changes.
+pub struct ItemName {
+    pub name: String,
+}
+
+impl ItemName {
+    pub fn write_to(self, conn: &mut Conn) -> Result<(), Error> {
+        let version = conn.get_version();
+        if version >= Version::V1_13 {
+            conn.write_packet(NameItem {
+                item_name: self.name,
+            })
+        } else if version >= Version::V1_8 {
+            let mut data = vec![];
+            Serializable::write_to(&self.name, &mut data)?;
+            conn.write_packet(PluginMessageServerbound {
+                channel: "MC|ItemName".into(),
+                data,
+            })
+        } else {
+            // 1.7 takes the name as it is, without a length
+            conn.write_packet(PluginMessageServerbound_i16 {
+                channel: "MC|ItemName".into(),
+                data: crate::protocol::LenPrefixedBytes::<VarShort>::new(self.name.into_bytes()),
+            })
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use crate::entity::spatial_index::EntityIndex;
 use crate::render;
 use crate::render::model;
 use crate::shared::{Direction, Position};
 use crate::world;
 use crate::world::block;
+use bevy_ecs::entity::Entity;
+use cgmath::InnerSpace;
 use collision::Aabb;
 use std::sync::Arc;
 
@@ -10,6 +13,10 @@ pub struct Info {
     model: Option<model::ModelHandle>,
     last_block: block::Block,
     last_pos: Position,
+    last_bounds: Vec<collision::Aabb3<f64>>,
+    /// Whether the outline is left out while the HUD is hidden. The target
+    /// is still tracked so the player can keep interacting with it.
+    hidden: bool,
 }
 
 impl Default for Info {
@@ -24,27 +31,68 @@ impl Info {
             model: None,
             last_block: block::Air {},
             last_pos: Position::new(0, 0, 0),
+            last_bounds: vec![],
+            hidden: false,
+        }
+    }
+
+    pub fn set_hidden(&mut self, hidden: bool) {
+        if self.hidden != hidden {
+            self.hidden = hidden;
+            // Forces the outline to be rebuilt on the next update
+            self.last_bounds.clear();
+            self.model.take();
+        }
+    }
+
+    /// Returns the block the player is currently looking at.
+    pub fn target(&self) -> Option<(Position, block::Block)> {
+        match self.last_block {
+            block::Air {} => None,
+            block => Some((self.last_pos, block)),
         }
     }
 
     pub fn clear(&mut self) {
         self.last_block = block::Air {};
+        self.last_bounds.clear();
         self.model.take();
     }
 
-    pub fn update(&mut self, renderer: Arc<render::Renderer>, pos: Position, bl: block::Block) {
-        if self.last_block == bl && self.last_pos == pos {
+    /// Outlines the part of `bl` containing `cursor`, the point the ray hit
+    /// relative to the block.
+    pub fn update(
+        &mut self,
+        renderer: Arc<render::Renderer>,
+        pos: Position,
+        bl: block::Block,
+        cursor: cgmath::Vector3<f64>,
+    ) {
+        const EPSILON: f64 = 0.001;
+        let mut bounds = bl.get_collision_boxes();
+        // Only outline the box that was hit, e.g. the step of a stair
+        if let Some(hit) = bounds.iter().copied().find(|bound| {
+            (0..3)
+                .all(|i| cursor[i] >= bound.min[i] - EPSILON && cursor[i] <= bound.max[i] + EPSILON)
+        }) {
+            bounds = vec![hit];
+        }
+        if self.last_block == bl && self.last_pos == pos && self.last_bounds == bounds {
             return;
         }
         self.last_block = bl;
         self.last_pos = pos;
         self.model.take();
+        if self.hidden {
+            self.last_bounds = bounds;
+            return;
+        }
         let mut parts = vec![];
 
         const LINE_SIZE: f64 = 1.0 / 128.0;
         let tex = render::Renderer::get_texture(renderer.get_textures_ref(), "leafish:solid");
 
-        for bound in bl.get_collision_boxes() {
+        for bound in &bounds {
             let bound = bound.add_v(cgmath::Vector3::new(
                 pos.x as f64,
                 pos.y as f64,
@@ -127,6 +175,76 @@ impl Info {
             part.g = 0;
             part.b = 0;
         }
+        self.last_bounds = bounds;
+
+        self.model = Some(renderer.models.lock().create_model(
+            model::DEFAULT,
+            vec![parts],
+            renderer.clone(),
+        ));
+    }
+}
+
+/// A faint ghost of the block that would be placed where the player is
+/// looking.
+pub struct PlacementPreview {
+    model: Option<model::ModelHandle>,
+    last: Option<(Position, String)>,
+}
+
+impl Default for PlacementPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlacementPreview {
+    pub fn new() -> PlacementPreview {
+        PlacementPreview {
+            model: None,
+            last: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.last = None;
+        self.model.take();
+    }
+
+    pub fn update(&mut self, renderer: Arc<render::Renderer>, pos: Position, tex: render::Texture) {
+        if self
+            .last
+            .as_ref()
+            .is_some_and(|(last_pos, last_tex)| *last_pos == pos && *last_tex == tex.name)
+        {
+            return;
+        }
+        self.last = Some((pos, tex.name.clone()));
+        self.model.take();
+
+        // Slightly larger than the block to avoid fighting with its neighbours
+        const GROW: f64 = 1.0 / 256.0;
+        let mut parts = vec![];
+        model::append_box(
+            &mut parts,
+            (pos.x as f64 - GROW) as f32,
+            (pos.y as f64 - GROW) as f32,
+            (pos.z as f64 - GROW) as f32,
+            (1.0 + GROW * 2.0) as f32,
+            (1.0 + GROW * 2.0) as f32,
+            (1.0 + GROW * 2.0) as f32,
+            [
+                Some(tex.clone()),
+                Some(tex.clone()),
+                Some(tex.clone()),
+                Some(tex.clone()),
+                Some(tex.clone()),
+                Some(tex),
+            ],
+        );
+        for part in &mut parts {
+            part.a = 100;
+        }
 
         self.model = Some(renderer.models.lock().create_model(
             model::DEFAULT,
@@ -319,3 +437,28 @@ where
 
     None
 }
+
+/// Finds the closest entity hit by a ray within `max` blocks. Only entities
+/// bucketed near the ray are tested.
+pub fn trace_entity(
+    index: &EntityIndex,
+    max: f64,
+    s: cgmath::Vector3<f64>,
+    d: cgmath::Vector3<f64>,
+    exclude: Option<Entity>,
+) -> Option<(Entity, cgmath::Vector3<f64>)> {
+    let e = s + d * max;
+    let mut closest: Option<(Entity, cgmath::Vector3<f64>, f64)> = None;
+    for (entity, bounds) in index.query(s.x.min(e.x), s.z.min(e.z), s.x.max(e.x), s.z.max(e.z)) {
+        if Some(entity) == exclude {
+            continue;
+        }
+        if let Some(hit) = intersects_line(*bounds, s, d) {
+            let distance = (hit - s).magnitude();
+            if distance <= max && closest.map(|c| distance < c.2).unwrap_or(true) {
+                closest = Some((entity, hit, distance));
+            }
+        }
+    }
+    closest.map(|(entity, hit, _)| (entity, hit))
+}
@@ -3,10 +3,46 @@ use log::{info, warn};
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, Write};
 
 use super::default_config::default_vars;
 
+/// The layout version of the config file, bumped whenever a setting is
+/// renamed or its values change meaning, along with a migration below.
+const CONFIG_VERSION: u32 = 1;
+const CONFIG_VERSION_NAME: &str = "config_version";
+
+/// Upgrades the settings read from the config file by one version each,
+/// the first one from unversioned files. Settings the current version
+/// doesn't know are dropped when loading, and new ones keep their
+/// defaults, so migrations only need to handle renames and changed values.
+const MIGRATIONS: [Migration; CONFIG_VERSION as usize] = [migrate_console_names];
+
+/// Changes the names and values of the settings read from a config file.
+type Migration = fn(&mut [(String, String)]);
+
+/// Settings used to be console variables with their prefixes.
+fn migrate_console_names(entries: &mut [(String, String)]) {
+    const RENAMED: [(&str, &str); 11] = [
+        ("r_max_fps", "max_fps"),
+        ("r_fov", "fov"),
+        ("r_vsync", "vsync"),
+        ("cl_mouse_sensitivity", "mouse_sens"),
+        ("cl_master_volume", "master_volume"),
+        ("cl_cape", "cape"),
+        ("cl_jacket", "jacket"),
+        ("cl_left_sleeve", "left_sleeve"),
+        ("cl_right_sleeve", "right_sleeve"),
+        ("cl_left_pants", "left_pants"),
+        ("cl_right_pants", "right_pants"),
+    ];
+    for (name, _) in entries.iter_mut() {
+        if let Some((_, new)) = RENAMED.iter().find(|(old, _)| old == name) {
+            *name = new.to_string();
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum SettingValue {
     String(String),
@@ -37,6 +73,12 @@ pub enum IntSetting {
     FOV,
     MasterVolume,
     DefaultProtocolVersion,
+    ChunkRetainDistance,
+    MaxLoadedChunks,
+    CrosshairStyle,
+    ChatVisibility,
+    BiomeBlendRadius,
+    ViewDistance,
 }
 
 #[derive(PartialEq, PartialOrd, Hash, Eq, Ord, Clone, Copy)]
@@ -49,6 +91,19 @@ pub enum BoolSetting {
     RightPantsVisible,
     LeftPantsVisible,
     HatVisible,
+    CenterCursor,
+    PlacementPreview,
+    ChunkMeshCache,
+    BlockModelCache,
+    ServerResourcePacks,
+    GreedyMeshing,
+    AttackIndicator,
+    ViewBobbing,
+    LinearLighting,
+    BiomeBlend,
+    HideCoords,
+    AutoJump,
+    ScreenshotHideHud,
 }
 
 #[derive(PartialEq, PartialOrd, Hash, Eq, Ord, Clone, Copy)]
@@ -60,6 +115,8 @@ pub enum FloatSetting {
 pub enum StringSetting {
     AuthClientToken, // TODO: get rid of this as this is a dead, unused setting
     BackgroundImage,
+    Locale,
+    ResourcePacks,
     LogLevelFile,
     LogLevelTerm,
 }
@@ -81,11 +138,16 @@ impl SettingValue {
 }
 
 // stores all game settings, except keybinds
-pub struct SettingStore(Mutex<HashMap<SettingType, ConfigVar>>);
+pub struct SettingStore(
+    Mutex<HashMap<SettingType, ConfigVar>>,
+    /// Values layered on top of the user's settings while connected to a
+    /// server that has its own, which are never saved.
+    Mutex<HashMap<SettingType, SettingValue>>,
+);
 
 impl SettingStore {
     pub fn new() -> Self {
-        let mut store = Self(Mutex::new(HashMap::new()));
+        let mut store = Self(Mutex::new(HashMap::new()), Mutex::new(HashMap::new()));
         store.load_defaults();
         store.load_config();
         store.save_config();
@@ -93,6 +155,8 @@ impl SettingStore {
     }
 
     fn set(&self, s_type: SettingType, val: SettingValue) {
+        // Changing a setting by hand wins over the server's override
+        self.1.lock().remove(&s_type);
         self.0.lock().get_mut(&s_type).unwrap().value = val;
         self.save_config();
     }
@@ -118,6 +182,9 @@ impl SettingStore {
     }
 
     fn get_value(&self, input: SettingType) -> SettingValue {
+        if let Some(value) = self.1.lock().get(&input) {
+            return value.clone();
+        }
         self.0.lock().get(&input).unwrap().value.clone()
     }
 
@@ -145,42 +212,131 @@ impl SettingStore {
             .unwrap()
     }
 
-    fn load_config(&mut self) {
-        if let Ok(file) = fs::File::open(paths::get_config_dir().join("conf.cfg")) {
-            let reader = BufReader::new(file);
-            for line in reader.lines() {
-                let line = line.unwrap();
-                if line.starts_with('#') || line.is_empty() {
-                    continue;
+    /// Returns the value of the setting with the given config file name,
+    /// written the way it is in the config file.
+    pub fn get_by_name(&self, name: &str) -> Option<String> {
+        let s_type = self.type_by_name(name)?;
+        Some(match &self.get_value(s_type) {
+            SettingValue::Float(f) => f.to_string(),
+            SettingValue::Num(n) => n.to_string(),
+            SettingValue::Bool(b) => b.to_string(),
+            SettingValue::String(s) => s.clone(),
+        })
+    }
+
+    /// Changes the setting with the given config file name, parsing the
+    /// value like the config file does. Returns false if there is no such
+    /// setting or the value doesn't suit it.
+    pub fn set_by_name(&self, name: &str, value: &str) -> bool {
+        let Some((s_type, val)) = self.parse_by_name(name, value) else {
+            return false;
+        };
+        self.set(s_type, val);
+        true
+    }
+
+    /// Whether `value` suits the setting with the given config file name,
+    /// the way `set_by_name` and `set_overrides` would parse it.
+    pub fn is_valid_value(&self, name: &str, value: &str) -> bool {
+        self.parse_by_name(name, value).is_some()
+    }
+
+    /// Overrides settings by their config file names until
+    /// `clear_overrides`, without touching the saved values. Returns the
+    /// names of settings that don't exist or whose values don't suit them.
+    pub fn set_overrides(&self, overrides: &[(String, String)]) -> Vec<String> {
+        let mut invalid = vec![];
+        let mut values = HashMap::new();
+        for (name, value) in overrides {
+            match self.parse_by_name(name, value) {
+                Some((s_type, val)) => {
+                    values.insert(s_type, val);
                 }
-                let parts = line
-                    .splitn(2, ' ')
-                    .map(|v| v.to_owned())
-                    .collect::<Vec<String>>();
-                let (name, arg) = (&parts[0], &parts[1]);
-                if name.starts_with("keybind_") {
+                None => invalid.push(name.clone()),
+            }
+        }
+        *self.1.lock() = values;
+        invalid
+    }
+
+    /// Goes back to the user's own settings.
+    pub fn clear_overrides(&self) {
+        self.1.lock().clear();
+    }
+
+    fn type_by_name(&self, name: &str) -> Option<SettingType> {
+        self.0
+            .lock()
+            .iter()
+            .find(|(_, var)| var.name == name)
+            .map(|(s_type, _)| *s_type)
+    }
+
+    fn parse_by_name(&self, name: &str, value: &str) -> Option<(SettingType, SettingValue)> {
+        let s_type = self.type_by_name(name)?;
+        let old = self.0.lock().get(&s_type)?.value.clone();
+        Some((s_type, deserialize_value(value, old)?))
+    }
+
+    fn load_config(&mut self) {
+        let Ok(file) = fs::File::open(paths::get_config_dir().join("conf.cfg")) else {
+            return;
+        };
+        let mut version = 0;
+        let mut entries = vec![];
+        for line in BufReader::new(file).lines() {
+            let line = line.unwrap();
+            if line.starts_with('#') || line.is_empty() {
+                continue;
+            }
+            let (name, arg) = line.split_once(' ').unwrap_or((&line, ""));
+            if name.starts_with("keybind_") {
+                continue;
+            }
+            if name == CONFIG_VERSION_NAME {
+                version = arg.parse().unwrap_or_else(|_| {
+                    warn!("the config file has an invalid version: {arg}");
+                    0
+                });
+                continue;
+            }
+            entries.push((name.to_owned(), arg.to_owned()));
+        }
+
+        if version > CONFIG_VERSION {
+            warn!("the config file is from a newer version of Leafish, settings may be lost");
+        }
+        for migrate in MIGRATIONS.iter().skip(version as usize) {
+            migrate(&mut entries);
+        }
+        if version < CONFIG_VERSION {
+            info!("upgraded the config file from version {version} to {CONFIG_VERSION}");
+        }
+
+        let mut store = self.0.lock();
+        for (name, arg) in entries {
+            if let Some((s_type, setting)) = store.clone().iter().find(|(_, e)| e.name == name) {
+                let Some(val) = deserialize_value(&arg, setting.value.clone()) else {
+                    warn!("a config value couldnt be loaded from file: {name}");
                     continue;
+                };
+                if setting.serializable {
+                    store.get_mut(s_type).unwrap().value = val;
                 }
-                let mut store = self.0.lock();
-                if let Some((s_type, setting)) = store.clone().iter().find(|(_, e)| e.name == name)
-                {
-                    let Some(val) = deserialize_value(arg, setting.value.clone()) else {
-                        warn!("a config value couldnt be loaded from file: {name}");
-                        continue;
-                    };
-                    if setting.serializable {
-                        store.get_mut(s_type).unwrap().value = val;
-                    }
-                } else {
-                    info!("a unknwon config option was specified: {name}");
-                }
+            } else {
+                info!("a unknwon config option was specified: {name}");
             }
         }
     }
 
     fn save_config(&self) {
-        let mut file =
-            BufWriter::new(fs::File::create(paths::get_config_dir().join("conf.cfg")).unwrap());
+        let mut file = vec![];
+        if let Err(err) = write!(
+            file,
+            "# The layout of this file, used to upgrade settings from older versions\n{CONFIG_VERSION_NAME} {CONFIG_VERSION}\n\n"
+        ) {
+            warn!("couldnt write the config version to config file: {err}");
+        }
         for var in self.0.lock().values() {
             if !var.serializable {
                 continue;
@@ -201,6 +357,9 @@ impl SettingStore {
                 warn!("couldnt write a setting to config file: {err}, {name}");
             }
         }
+        if let Err(err) = paths::write_atomic(&paths::get_config_dir().join("conf.cfg"), &file) {
+            warn!("couldnt save the config file: {err}");
+        }
     }
 
     fn load_defaults(&self) {
@@ -20,6 +20,24 @@ pub fn default_vars() -> Vec<(SettingType, ConfigVar)> {
                 value: SettingValue::Num(90),
             },
         ),
+        (
+            SettingType::Int(IntSetting::ChunkRetainDistance),
+            ConfigVar {
+                name: "chunk_retain_distance",
+                description: "Chunks further away than this from the player are unloaded",
+                serializable: true,
+                value: SettingValue::Num(32),
+            },
+        ),
+        (
+            SettingType::Int(IntSetting::MaxLoadedChunks),
+            ConfigVar {
+                name: "max_loaded_chunks",
+                description: "The most chunks kept loaded, dropping the least recently seen ones outside the view distance first",
+                serializable: true,
+                value: SettingValue::Num(4225),
+            },
+        ),
         (
             SettingType::Bool(BoolSetting::Vsync),
             ConfigVar {
@@ -110,6 +128,160 @@ pub fn default_vars() -> Vec<(SettingType, ConfigVar)> {
                 value: SettingValue::Bool(false),
             },
         ),
+        (
+            SettingType::Bool(BoolSetting::CenterCursor),
+            ConfigVar {
+                name: "center_cursor",
+                description: "Move the cursor to the middle of the window when a menu opens",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::PlacementPreview),
+            ConfigVar {
+                name: "placement_preview",
+                description: "Show a faint ghost of the block that would be placed",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::ServerResourcePacks),
+            ConfigVar {
+                name: "server_resource_packs",
+                description: "Download and use the resource packs servers ask for",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::BlockModelCache),
+            ConfigVar {
+                name: "block_model_cache",
+                description: "Remember the model each block state resolves to while building chunks",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::ChunkMeshCache),
+            ConfigVar {
+                name: "chunk_mesh_cache",
+                description: "Keep built chunk meshes on disk to speed up rejoining servers",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::GreedyMeshing),
+            ConfigVar {
+                name: "greedy_meshing",
+                description: "Merge matching block faces into larger ones to draw fewer triangles",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::BiomeBlend),
+            ConfigVar {
+                name: "biome_blend",
+                description: "Blend grass, foliage and water colors across biome borders",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Int(IntSetting::BiomeBlendRadius),
+            ConfigVar {
+                name: "biome_blend_radius",
+                description: "How many blocks biome colors are blended over, from 1 to 7",
+                serializable: true,
+                value: SettingValue::Num(2),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::HideCoords),
+            ConfigVar {
+                name: "hide_coords",
+                description: "Leave coordinates out of the debug overlay, like servers can ask for",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::AutoJump),
+            ConfigVar {
+                name: "auto_jump",
+                description: "Jump automatically when walking into a block",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::ScreenshotHideHud),
+            ConfigVar {
+                name: "screenshot_hide_hud",
+                description: "Hide the HUD while taking a screenshot",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
+        (
+            SettingType::Int(IntSetting::ViewDistance),
+            ConfigVar {
+                name: "view_distance",
+                description: "How many chunks around the player the server is asked to send, from 2 to 32",
+                serializable: true,
+                value: SettingValue::Num(8),
+            },
+        ),
+        (
+            SettingType::Int(IntSetting::CrosshairStyle),
+            ConfigVar {
+                name: "crosshair_style",
+                description: "The crosshair to draw: 0 = default, 1 = dot, 2 = cross, 3 = none",
+                serializable: true,
+                value: SettingValue::Num(0),
+            },
+        ),
+        (
+            SettingType::Int(IntSetting::ChatVisibility),
+            ConfigVar {
+                name: "chat_visibility",
+                description: "The chat to show: 0 = all, 1 = commands only, 2 = hidden",
+                serializable: true,
+                value: SettingValue::Num(0),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::AttackIndicator),
+            ConfigVar {
+                name: "attack_indicator",
+                description:
+                    "Show how far the attack cooldown has recharged below the crosshair (1.9+)",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::ViewBobbing),
+            ConfigVar {
+                name: "view_bobbing",
+                description: "Sway the view while walking",
+                serializable: true,
+                value: SettingValue::Bool(true),
+            },
+        ),
+        (
+            SettingType::Bool(BoolSetting::LinearLighting),
+            ConfigVar {
+                name: "linear_lighting",
+                description: "Blend block lighting in linear color space for smoother gradients",
+                serializable: true,
+                value: SettingValue::Bool(false),
+            },
+        ),
         (
             SettingType::String(StringSetting::LogLevelTerm),
             ConfigVar {
@@ -137,6 +309,24 @@ pub fn default_vars() -> Vec<(SettingType, ConfigVar)> {
                 value: SettingValue::String("leafish:gui/background".to_owned()),
             },
         ),
+        (
+            SettingType::String(StringSetting::Locale),
+            ConfigVar {
+                name: "locale",
+                description: "The language of the menus and chat, like en_us or de_de",
+                serializable: true,
+                value: SettingValue::String(crate::lang::DEFAULT_LOCALE.to_owned()),
+            },
+        ),
+        (
+            SettingType::String(StringSetting::ResourcePacks),
+            ConfigVar {
+                name: "resource_packs",
+                description: "Resource packs from the resourcepacks folder to use, separated by commas. Later packs override earlier ones",
+                serializable: true,
+                value: SettingValue::String(String::new()),
+            },
+        ),
         (
             SettingType::String(StringSetting::AuthClientToken),
             ConfigVar {
@@ -76,6 +76,14 @@ pub fn create_keybinds() -> Vec<(Key, Keybind)> {
                 action: Actionkey::DropItem,
             },
         ),
+        (
+            Key::Character(SmolStr::new_inline("f")),
+            Keybind {
+                name: "keybind_swap_hands",
+                description: "Keybinding for swapping the items in both hands",
+                action: Actionkey::SwapHands,
+            },
+        ),
         (
             Key::Named(NamedKey::F1),
             Keybind {
@@ -84,6 +92,14 @@ pub fn create_keybinds() -> Vec<(Key, Keybind)> {
                 action: Actionkey::ToggleHud,
             },
         ),
+        (
+            Key::Named(NamedKey::F2),
+            Keybind {
+                name: "keybind_screenshot",
+                description: "Keybinding for taking a screenshot",
+                action: Actionkey::Screenshot,
+            },
+        ),
         (
             Key::Named(NamedKey::F3),
             Keybind {
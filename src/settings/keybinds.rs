@@ -2,7 +2,7 @@ use arc_swap::ArcSwap;
 use log::{info, warn};
 use std::collections::HashMap;
 use std::fs;
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, Write};
 use std::ops::Deref;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -127,8 +127,7 @@ impl KeybindStore {
     }
 
     fn save_config(&self) {
-        let mut file =
-            BufWriter::new(fs::File::create(paths::get_config_dir().join("keybinds.cfg")).unwrap());
+        let mut file = vec![];
         for (key, keybind) in self.key_cache.load().iter() {
             for line in keybind.description.lines() {
                 if let Err(err) = writeln!(file, "# {}", line) {
@@ -150,6 +149,10 @@ impl KeybindStore {
                 );
             };
         }
+        if let Err(err) = paths::write_atomic(&paths::get_config_dir().join("keybinds.cfg"), &file)
+        {
+            warn!("couldnt save the keybinds config file: {err}");
+        }
     }
 
     fn load_defaults(&self) {
@@ -182,7 +185,9 @@ pub enum Actionkey {
     Sprint,
     Jump,
     DropItem,
+    SwapHands,
     ToggleHud,
+    Screenshot,
     ToggleDebug,
     ToggleChat,
     Hotbar1,
@@ -209,7 +214,9 @@ impl FromStr for Actionkey {
             "keybind_sprint" => Ok(Actionkey::Sprint),
             "keybind_jump" => Ok(Actionkey::Jump),
             "keybind_drop_item" => Ok(Actionkey::DropItem),
+            "keybind_swap_hands" => Ok(Actionkey::SwapHands),
             "keybind_toggle_hud" => Ok(Actionkey::ToggleHud),
+            "keybind_screenshot" => Ok(Actionkey::Screenshot),
             "keybind_toggle_debug_info" => Ok(Actionkey::ToggleDebug),
             "keybind_toggle_chat" => Ok(Actionkey::ToggleChat),
             "keybind_hotbar_1" => Ok(Actionkey::Hotbar1),
@@ -227,7 +234,7 @@ impl FromStr for Actionkey {
 }
 
 impl Actionkey {
-    const VALUES: [Actionkey; 21] = [
+    const VALUES: [Actionkey; 23] = [
         Actionkey::Forward,
         Actionkey::Backward,
         Actionkey::Left,
@@ -237,7 +244,9 @@ impl Actionkey {
         Actionkey::Sprint,
         Actionkey::Jump,
         Actionkey::DropItem,
+        Actionkey::SwapHands,
         Actionkey::ToggleHud,
+        Actionkey::Screenshot,
         Actionkey::ToggleDebug,
         Actionkey::ToggleChat,
         Actionkey::Hotbar1,
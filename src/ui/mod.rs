@@ -18,6 +18,7 @@ use crate::format;
 use crate::render;
 use crate::Game;
 use crate::KeyCmp;
+use leafish_protocol::translate::ClickEvent;
 use std::cell::{RefCell, RefMut};
 use std::rc::{Rc, Weak};
 use std::sync::Arc;
@@ -515,6 +516,19 @@ trait UIElement {
         true
     }
     fn key_type(&mut self, _game: &Game, _c: char) {}
+    /// Handles clicks on parts of the element that aren't children,
+    /// returning whether the click was consumed.
+    fn click_inner(
+        &mut self,
+        _r: &Region,
+        _game: &Game,
+        _mx: f64,
+        _my: f64,
+        _sw: f64,
+        _sh: f64,
+    ) -> bool {
+        false
+    }
     fn tick(&mut self, renderer: Arc<render::Renderer>);
 }
 
@@ -661,7 +675,7 @@ macro_rules! element {
 
             fn click_at(&mut self, super_region: &Region, game: &Game, mx: f64, my: f64, sw: f64, sh: f64) -> bool {
                 use std::mem;
-                let mut handle_self = true;
+                let mut handle_self = !self.click_inner(super_region, game, mx, my, sw, sh);
                 for e in &self.elements {
                     let r = Container::compute_draw_region(&e.1, sw, sh, &super_region);
                     if mx >= r.x && mx <= r.x + r.w && my >= r.y && my <= r.y + r.h {
@@ -1072,6 +1086,8 @@ impl UIElement for Text {
     }
 }
 
+type ClickHandler = Rc<dyn Fn(&ClickEvent, &Game)>;
+
 element! {
     ref FormattedRef
     pub struct Formatted {
@@ -1088,6 +1104,7 @@ element! {
         priv last_scale_y: f64,
         priv last_max_width: f64,
         priv dirty: bool,
+        priv click_handler: Option<ClickHandler>,
     }
     builder FormattedBuilder {
         hardcode width = 0.0,
@@ -1098,6 +1115,7 @@ element! {
         hardcode last_scale_y = 0.0,
         hardcode last_max_width = -1.0,
         hardcode dirty = true,
+        hardcode click_handler = None,
         simple text: format::Component,
         optional scale_x: f64 = 1.0,
         optional scale_y: f64 = 1.0,
@@ -1132,6 +1150,7 @@ impl UIElement for Formatted {
                     transparency: self.transparency,
                     scale_x: self.scale_x,
                     scale_y: self.scale_y,
+                    click_handler: self.click_handler.clone(),
                 };
                 state.build(&self.text, Some(format::Color::White));
                 self.text_elements = state.text;
@@ -1185,6 +1204,17 @@ impl UIElement for Formatted {
             self.height = h;
         }
     }
+
+    fn click_inner(&mut self, r: &Region, game: &Game, mx: f64, my: f64, sw: f64, sh: f64) -> bool {
+        let mut consumed = false;
+        for e in &self.text_elements {
+            let r = Container::compute_draw_region(e, sw, sh, r);
+            if mx >= r.x && mx <= r.x + r.w && my >= r.y && my <= r.y + r.h {
+                consumed |= e.click_at(&r, game, mx, my, sw, sh);
+            }
+        }
+        consumed
+    }
 }
 
 impl Formatted {
@@ -1193,6 +1223,18 @@ impl Formatted {
         self.dirty = true;
     }
 
+    pub fn set_transparency(&mut self, val: f64) {
+        self.transparency = val;
+        self.dirty = true;
+    }
+
+    /// Sets the function called when a part of the text with a click
+    /// event is clicked.
+    pub fn set_click_handler<F: Fn(&ClickEvent, &Game) + 'static>(&mut self, handler: F) {
+        self.click_handler = Some(Rc::new(handler));
+        self.dirty = true;
+    }
+
     pub fn compute_size(
         renderer: Arc<render::Renderer>,
         text: &format::Component,
@@ -1211,6 +1253,7 @@ impl Formatted {
             transparency,
             scale_x,
             scale_y,
+            click_handler: None,
         };
         state.build(text, Some(format::Color::White));
         (state.width + 2.0, (state.lines + 1) as f64 * 18.0)
@@ -1227,6 +1270,7 @@ struct FormatState {
     scale_y: f64,
     text: Vec<Element>,
     renderer: Arc<render::Renderer>,
+    click_handler: Option<ClickHandler>,
 }
 
 impl ElementHolder for FormatState {
@@ -1238,6 +1282,7 @@ impl ElementHolder for FormatState {
 impl FormatState {
     fn build(&mut self, components: &format::Component, color: Option<format::Color>) {
         for component in components.list.iter() {
+            let start = self.text.len();
             self.append_text(
                 self.scale_x,
                 self.scale_y,
@@ -1247,33 +1292,62 @@ impl FormatState {
                 } else {
                     component.get_modifier().color
                 },
-            )
+            );
+            let click_event = match component {
+                format::ComponentType::Click { click_event, .. }
+                | format::ComponentType::ClickAndHover { click_event, .. } => click_event,
+                _ => continue,
+            };
+            if let Some(handler) = &self.click_handler {
+                for e in &self.text[start..] {
+                    if let Element::Text(text) = e {
+                        let handler = handler.clone();
+                        let click_event = click_event.clone();
+                        text.borrow_mut().add_click_func(move |_, game| {
+                            handler(&click_event, game);
+                            true
+                        });
+                    }
+                }
+            }
         }
     }
 
     fn append_text(&mut self, scale_x: f64, scale_y: f64, txt: &str, color: format::Color) {
         let mut width = 0.0;
         let mut last = 0;
+        // The last space on the current line and the width of the text after it,
+        // so long lines are wrapped between words where possible
+        let mut space: Option<(usize, f64)> = None;
         for (i, c) in txt.char_indices() {
             let size = self.renderer.ui.lock().size_of_char(c) + 2.0;
             if (self.max_width > 0.0 && self.offset + width + size > self.max_width) || c == '\n' {
+                let (end, next, rest) = match space {
+                    Some((space, rest)) if c != '\n' && space > last => (space, space + 1, rest),
+                    _ if c == '\n' => (i, i + 1, 0.0),
+                    _ => (i, i, 0.0),
+                };
                 let (rr, gg, bb) = color.to_rgb();
                 TextBuilder::new()
-                    .text(&txt[last..i])
+                    .text(&txt[last..end])
                     .position(self.offset, (self.lines * 18 + 1) as f64)
                     .colour((rr, gg, bb, (self.transparency * 255_f64) as u8))
                     .scale_x(scale_x)
                     .scale_y(scale_y)
                     .create(self);
-                last = i;
-                if c == '\n' {
-                    last += 1;
-                }
+                last = next;
                 self.offset = 0.0;
                 self.lines += 1;
-                width = 0.0;
+                width = rest;
+                space = None;
             }
             width += size;
+            if let Some((_, rest)) = space.as_mut() {
+                *rest += size;
+            }
+            if c == ' ' {
+                space = Some((i, 0.0));
+            }
             if self.offset + width > self.width {
                 self.width = self.offset + width;
             }
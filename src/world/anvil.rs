@@ -0,0 +1,201 @@
+//! Writes loaded chunks out as Anvil region files, laid out like a 1.16.5
+//! world, so what the server sent can be looked at in other tools.
+
+use super::{CPos, ChunkSnapshot, World};
+use byteorder::{BigEndian, WriteBytesExt};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use leafish_blocks as block;
+use leafish_protocol::nbt::{NamedTag, Tag};
+use leafish_protocol::protocol::Serializable;
+use log::warn;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The data version of 1.16.5, the last release using this chunk layout.
+const DATA_VERSION: i32 = 2586;
+const SECTOR_SIZE: usize = 4096;
+/// Chunks are stored zlib compressed.
+const COMPRESSION_ZLIB: u8 = 2;
+
+/// Writes the loaded chunks within `radius` chunks of `center` into region
+/// files in `dir`, returning how many chunks were written.
+pub fn export_region_files(
+    world: &World,
+    center: CPos,
+    radius: i32,
+    dir: &Path,
+) -> io::Result<usize> {
+    // Snapshot first so the world isn't locked while compressing
+    let snapshots = world
+        .chunks
+        .read()
+        .range(
+            CPos(center.0 - radius, center.1 - radius)..=CPos(center.0 + radius, center.1 + radius),
+        )
+        .filter(|(pos, _)| (pos.1 - center.1).abs() <= radius)
+        .map(|(_, chunk)| chunk.capture_snapshot())
+        .collect::<Vec<_>>();
+
+    let mut regions: BTreeMap<(i32, i32), Vec<ChunkSnapshot>> = BTreeMap::new();
+    for snapshot in snapshots {
+        let pos = snapshot.position;
+        regions
+            .entry((pos.0 >> 5, pos.1 >> 5))
+            .or_default()
+            .push(snapshot);
+    }
+
+    fs::create_dir_all(dir)?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as u32);
+    let mut count = 0;
+    for ((x, z), chunks) in regions {
+        let mut locations = vec![0u8; SECTOR_SIZE];
+        let mut timestamps = vec![0u8; SECTOR_SIZE];
+        let mut data = vec![];
+        for chunk in &chunks {
+            let mut nbt = vec![];
+            Some(NamedTag("".to_owned(), chunk_tag(chunk)))
+                .write_to(&mut nbt)
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            let mut encoder = ZlibEncoder::new(vec![], Compression::default());
+            encoder.write_all(&nbt)?;
+            let compressed = encoder.finish()?;
+
+            let offset = 2 + data.len() / SECTOR_SIZE;
+            data.write_u32::<BigEndian>(compressed.len() as u32 + 1)?;
+            data.write_u8(COMPRESSION_ZLIB)?;
+            data.extend_from_slice(&compressed);
+            data.resize(data.len().next_multiple_of(SECTOR_SIZE), 0);
+            let sectors = 2 + data.len() / SECTOR_SIZE - offset;
+            if sectors > u8::MAX as usize {
+                warn!("Skipping chunk too large for a region file");
+                data.truncate((offset - 2) * SECTOR_SIZE);
+                continue;
+            }
+
+            let index = 4 * ((chunk.position.0 & 31) + (chunk.position.1 & 31) * 32) as usize;
+            let location = ((offset as u32) << 8) | sectors as u32;
+            (&mut locations[index..index + 4]).write_u32::<BigEndian>(location)?;
+            (&mut timestamps[index..index + 4]).write_u32::<BigEndian>(timestamp)?;
+            count += 1;
+        }
+
+        let mut file = fs::File::create(dir.join(format!("r.{}.{}.mca", x, z)))?;
+        file.write_all(&locations)?;
+        file.write_all(&timestamps)?;
+        file.write_all(&data)?;
+    }
+    Ok(count)
+}
+
+fn chunk_tag(chunk: &ChunkSnapshot) -> Tag {
+    let mut sections = vec![];
+    for (i, section) in chunk.sections.iter().enumerate() {
+        let section = match section {
+            Some(section) => section,
+            None => continue,
+        };
+        let mut palette: Vec<block::Block> = vec![];
+        let mut ids = HashMap::new();
+        let mut indices = Vec::with_capacity(16 * 16 * 16);
+        for idx in 0..16 * 16 * 16 {
+            let block = section.blocks.get(idx);
+            let id = *ids.entry(block).or_insert_with(|| {
+                palette.push(block);
+                palette.len() - 1
+            });
+            indices.push(id as u64);
+        }
+
+        let mut tag = Tag::new_compound();
+        tag.put("Y", Tag::Byte((chunk.min_section + i as i32) as i8));
+        tag.put(
+            "Palette",
+            Tag::List(
+                palette
+                    .iter()
+                    .map(|block| block_state_tag(*block))
+                    .collect(),
+            ),
+        );
+        tag.put(
+            "BlockStates",
+            Tag::LongArray(pack_indices(&indices, palette.len())),
+        );
+        tag.put(
+            "BlockLight",
+            Tag::ByteArray(section.block_light.data.clone()),
+        );
+        tag.put("SkyLight", Tag::ByteArray(section.sky_light.data.clone()));
+        sections.push(tag);
+    }
+
+    // Biomes are stored for every 4x4x4 cell, the columns are repeated
+    // for each layer of cells
+    let mut biomes = Vec::with_capacity(1024);
+    for _ in 0..64 {
+        for z in 0..4 {
+            for x in 0..4 {
+                biomes.push(chunk.biomes[(z * 4 * 16 + x * 4) as usize] as i32);
+            }
+        }
+    }
+
+    let mut level = Tag::new_compound();
+    level.put("xPos", Tag::Int(chunk.position.0));
+    level.put("zPos", Tag::Int(chunk.position.1));
+    level.put("LastUpdate", Tag::Long(0));
+    level.put("InhabitedTime", Tag::Long(0));
+    level.put("Status", Tag::String("full".to_owned()));
+    level.put("isLightOn", Tag::Byte(1));
+    level.put("Biomes", Tag::IntArray(biomes));
+    level.put("Sections", Tag::List(sections));
+    level.put("Entities", Tag::new_list());
+    level.put("TileEntities", Tag::new_list());
+
+    let mut root = Tag::new_compound();
+    root.put("DataVersion", Tag::Int(DATA_VERSION));
+    root.put("Level", level);
+    root
+}
+
+/// Describes the block by the name and properties of its model, which
+/// leaves out properties that don't change how the block looks.
+fn block_state_tag(block: block::Block) -> Tag {
+    let mut tag = Tag::new_compound();
+    let (namespace, name) = match block.get_model() {
+        ("leafish", _) => ("minecraft", "air"),
+        model => model,
+    };
+    tag.put("Name", Tag::String(format!("{}:{}", namespace, name)));
+
+    let variant = block.get_model_variant();
+    let mut properties = Tag::new_compound();
+    let mut has_properties = false;
+    for (key, value) in variant.split(',').filter_map(|pair| pair.split_once('=')) {
+        properties.put(key, Tag::String(value.to_owned()));
+        has_properties = true;
+    }
+    if has_properties {
+        tag.put("Properties", properties);
+    }
+    tag
+}
+
+/// Packs palette indices into longs the way 1.16 does, where no index is
+/// split between two longs.
+fn pack_indices(indices: &[u64], palette_len: usize) -> Vec<i64> {
+    let bits = (usize::BITS - (palette_len - 1).leading_zeros()).max(4) as usize;
+    let per_long = 64 / bits;
+    let mut longs = vec![0u64; indices.len().div_ceil(per_long)];
+    for (i, index) in indices.iter().enumerate() {
+        longs[i / per_long] |= index << ((i % per_long) * bits);
+    }
+    longs.into_iter().map(|long| long as i64).collect()
+}
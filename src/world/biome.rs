@@ -39,6 +39,25 @@ impl Biome {
             col
         }
     }
+
+    pub fn water_color(self) -> (u8, u8, u8) {
+        if self.id == SWAMPLAND.id || self.id == SWAMPLAND_MOUNTAINS.id {
+            (0x61, 0x7B, 0x64)
+        } else if self.id == WARM_OCEAN.id || self.id == DEEP_WARM_OCEAN.id {
+            (0x43, 0xD5, 0xEE)
+        } else if self.id == LUKEWARM_OCEAN.id || self.id == DEEP_LUKEWARM_OCEAN.id {
+            (0x45, 0xAD, 0xF2)
+        } else if self.id == COLD_OCEAN.id || self.id == DEEP_COLD_OCEAN.id {
+            (0x3D, 0x57, 0xD6)
+        } else if self.id == FROZEN_OCEAN.id
+            || self.id == FROZEN_RIVER.id
+            || self.id == DEEP_FROZEN_OCEAN.id
+        {
+            (0x39, 0x38, 0xC9)
+        } else {
+            (0x3F, 0x76, 0xE4)
+        }
+    }
 }
 
 macro_rules! define_biomes {
@@ -102,6 +121,19 @@ pub const SAVANNA_PLATEAU: Biome = Biome::new(36, 100, 0);
 pub const MESA: Biome = Biome::new(37, 200, 0);
 pub const MESA_PLATEAU_FOREST: Biome = Biome::new(38, 200, 0);
 pub const MESA_PLATEAU: Biome = Biome::new(39, 200, 0);
+pub const SMALL_END_ISLANDS: Biome = Biome::new(40, 50, 50);
+pub const END_MIDLANDS: Biome = Biome::new(41, 50, 50);
+pub const END_HIGHLANDS: Biome = Biome::new(42, 50, 50);
+pub const END_BARRENS: Biome = Biome::new(43, 50, 50);
+pub const WARM_OCEAN: Biome = Biome::new(44, 50, 50);
+pub const LUKEWARM_OCEAN: Biome = Biome::new(45, 50, 50);
+pub const COLD_OCEAN: Biome = Biome::new(46, 50, 50);
+pub const DEEP_WARM_OCEAN: Biome = Biome::new(47, 50, 50);
+pub const DEEP_LUKEWARM_OCEAN: Biome = Biome::new(48, 50, 50);
+pub const DEEP_COLD_OCEAN: Biome = Biome::new(49, 50, 50);
+pub const DEEP_FROZEN_OCEAN: Biome = Biome::new(50, 50, 50);
+
+pub const THE_VOID: Biome = Biome::new(127, 50, 50);
 
 pub const SUNFLOWER_PLAINS: Biome = Biome::new(129, 80, 40);
 pub const DESERT_MOUNTAIN: Biome = Biome::new(130, 200, 0);
@@ -124,6 +156,12 @@ pub const SAVANNA_PLATEAU_MOUNTAINS: Biome = Biome::new(164, 100, 0);
 pub const MESA_BRYCE: Biome = Biome::new(165, 200, 0);
 pub const MESA_PLATEAU_FOREST_MOUNTAINS: Biome = Biome::new(166, 200, 0);
 pub const MESA_PLATEAU_MOUNTAINS: Biome = Biome::new(167, 200, 0);
+pub const BAMBOO_JUNGLE: Biome = Biome::new(168, 95, 90);
+pub const BAMBOO_JUNGLE_HILLS: Biome = Biome::new(169, 95, 90);
+pub const SOUL_SAND_VALLEY: Biome = Biome::new(170, 200, 0);
+pub const CRIMSON_FOREST: Biome = Biome::new(171, 200, 0);
+pub const WARPED_FOREST: Biome = Biome::new(172, 200, 0);
+pub const BASALT_DELTAS: Biome = Biome::new(173, 200, 0);
 
 pub const INVALID: Biome = Biome::new(255, 0, 0);
 }
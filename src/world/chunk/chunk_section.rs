@@ -13,7 +13,7 @@ pub struct ChunkSection {
     pub cull_info: chunk_builder::CullInfo,
     pub render_buffer: Arc<RwLock<render::ChunkBuffer>>,
 
-    pub(crate) y: u8,
+    pub(crate) y: i32,
 
     pub(crate) blocks: storage::BlockStorage,
 
@@ -25,7 +25,7 @@ pub struct ChunkSection {
 }
 
 impl ChunkSection {
-    pub(crate) fn new(y: u8, fill_sky: bool) -> Self {
+    pub(crate) fn new(y: i32, fill_sky: bool) -> Self {
         let sky_light = if fill_sky {
             nibble::Array::new_def(16 * 16 * 16, 0xF)
         } else {
@@ -94,7 +94,7 @@ impl ChunkSection {
 
 #[derive(Clone)]
 pub struct ChunkSectionSnapshot {
-    pub y: u8,
+    pub y: i32,
     pub blocks: storage::BlockStorage,
     pub block_light: nibble::Array,
     pub sky_light: nibble::Array,
@@ -106,6 +106,10 @@ impl ChunkSectionSnapshot {
         self.blocks.get(((y << 8) | (z << 4) | x) as usize)
     }
 
+    pub fn get_state(&self, x: i32, y: i32, z: i32) -> block::StateId {
+        self.blocks.get_state(((y << 8) | (z << 4) | x) as usize)
+    }
+
     pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
         self.block_light.get(((y << 8) | (z << 4) | x) as usize)
     }
@@ -152,15 +156,14 @@ impl ChunkSectionSnapshotGroup {
                 let chunk = chunks.get(&CPos(x + xo, z + zo));
                 for yo in -1..2 {
                     let section = if let Some(chunk) = chunk {
-                        if y + yo != (y + yo) & 15 {
-                            None
-                        } else {
-                            let section = &chunk.sections[(y + yo) as usize].as_ref();
-                            if let Some(section) = section {
+                        if let Some(idx) = chunk.section_index(y + yo) {
+                            if let Some(section) = chunk.sections[idx].as_ref() {
                                 Some(section.capture_snapshot(chunk.biomes))
                             } else {
                                 Some(EMPTY_SECTION.clone())
                             }
+                        } else {
+                            None
                         }
                     } else {
                         None
@@ -190,6 +193,22 @@ impl ChunkSectionSnapshotGroup {
         section.map_or(block::Air {}, |s| s.get_block(x, y, z))
     }
 
+    pub fn get_state(&self, x: i32, y: i32, z: i32) -> block::StateId {
+        let chunk_x = ChunkSectionSnapshotGroup::cmp(x & !15, 0);
+        let chunk_z = ChunkSectionSnapshotGroup::cmp(z & !15, 0);
+        let chunk_y = ChunkSectionSnapshotGroup::cmp(y & !15, 0);
+        let section = self.sections
+            [((chunk_x + 1) + (chunk_z + 1) * 3 + (chunk_y + 1) * 3 * 3) as usize]
+            .as_ref();
+        let x = if x < 0 { 16 + x } else { x & 15 };
+        let y = if y < 0 { 16 + y } else { y & 15 };
+        let z = if z < 0 { 16 + z } else { z & 15 };
+        section.map_or_else(
+            || block::StateId::of(block::Air {}),
+            |s| s.get_state(x, y, z),
+        )
+    }
+
     pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> u8 {
         let chunk_x = ChunkSectionSnapshotGroup::cmp(x & !15, 0);
         let chunk_z = ChunkSectionSnapshotGroup::cmp(z & !15, 0);
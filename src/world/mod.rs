@@ -40,10 +40,12 @@ use std::io::{BufRead, Cursor, Read};
 use std::sync::Arc;
 
 pub use self::{chunk::*, lighting::*};
+use crate::entity::block_entity::beacon::BeaconInfo;
 use crate::entity::block_entity::sign::SignInfo;
 use leafish_protocol::protocol::{Serializable, VarInt};
 use std::sync::atomic::Ordering;
 
+pub mod anvil;
 pub mod biome;
 mod chunk;
 mod lighting;
@@ -62,6 +64,8 @@ pub enum BlockEntityAction {
             format::Component,
         )>,
     ),
+    /// Position, levels and the primary and secondary effects
+    UpdateBeacon(Position, i32, i32, i32),
 }
 
 pub struct World {
@@ -97,6 +101,10 @@ impl World {
         }
     }
 
+    pub fn protocol_version(&self) -> i32 {
+        self.protocol_version
+    }
+
     pub fn reset(&self, protocol_version: i32) {
         if self.protocol_version != protocol_version {
             warn!("Can't switch protocol version, when resetting the world :(");
@@ -117,7 +125,7 @@ impl World {
     fn set_block_raw(&self, pos: Position, b: block::Block) -> bool {
         let cpos = CPos(pos.x >> 4, pos.z >> 4);
         let mut chunks = self.chunks.write();
-        let chunk = chunks.entry(cpos).or_insert_with(|| Chunk::new(cpos));
+        let chunk = chunks.entry(cpos).or_insert_with(|| self.new_chunk(cpos));
         if chunk.set_block(pos.x & 0xF, pos.y, pos.z & 0xF, b) {
             if chunk.block_entities.contains_key(&pos) {
                 self.block_entity_actions
@@ -203,10 +211,18 @@ impl World {
         }
     }
 
+    /// Returns the biome of the column at `pos`, if its chunk is loaded.
+    pub fn get_biome(&self, pos: Position) -> Option<biome::Biome> {
+        self.chunks
+            .read()
+            .get(&CPos(pos.x >> 4, pos.z >> 4))
+            .map(|chunk| chunk.get_biome(pos.x & 0xF, pos.z & 0xF))
+    }
+
     pub(crate) fn set_block_light(&self, pos: Position, light: u8) {
         let cpos = CPos(pos.x >> 4, pos.z >> 4);
         let mut chunks = self.chunks.write();
-        let chunk = chunks.entry(cpos).or_insert_with(|| Chunk::new(cpos));
+        let chunk = chunks.entry(cpos).or_insert_with(|| self.new_chunk(cpos));
         chunk.set_block_light(pos.x & 0xF, pos.y, pos.z & 0xF, light);
     }
 
@@ -220,7 +236,7 @@ impl World {
     pub(crate) fn set_sky_light(&self, pos: Position, light: u8) {
         let cpos = CPos(pos.x >> 4, pos.z >> 4);
         let mut chunks = self.chunks.write();
-        let chunk = chunks.entry(cpos).or_insert_with(|| Chunk::new(cpos));
+        let chunk = chunks.entry(cpos).or_insert_with(|| self.new_chunk(cpos));
         chunk.set_sky_light(pos.x & 0xF, pos.y, pos.z & 0xF, light);
     }
 
@@ -277,6 +293,13 @@ impl World {
                         }
                     }
                 }
+                BlockEntityAction::UpdateBeacon(pos, levels, primary, secondary) => {
+                    if let Some(chunk) = self.chunks.write().get(&CPos(pos.x >> 4, pos.z >> 4)) {
+                        if let Some(entity) = chunk.block_entities.get(&pos) {
+                            cmds.add(UpdateBeaconInfoCmd(levels, primary, secondary, *entity));
+                        }
+                    }
+                }
             }
         }
     }
@@ -285,8 +308,9 @@ impl World {
     #[allow(dead_code)]
     pub(crate) fn do_light_update(&self, update: LightUpdate) {
         use std::cmp;
-        if update.pos.y < 0
-            || update.pos.y > 255
+        let dimension = self.dimension.load();
+        if update.pos.y < dimension.min_y()
+            || update.pos.y >= dimension.min_y() + dimension.height()
             || !self.is_chunk_loaded(update.pos.x >> 4, update.pos.z >> 4)
         {
             return;
@@ -348,7 +372,7 @@ impl World {
                     for zz in 0..16 {
                         data[(((c.position.0 << 4) as usize + xx) & 0x1FF)
                             + ((((c.position.1 << 4) as usize + zz) & 0x1FF) << 9)] =
-                            c.heightmap[(zz << 4) | xx];
+                            c.heightmap[(zz << 4) | xx].clamp(0, 255) as u8;
                     }
                 }
             }
@@ -526,7 +550,9 @@ impl World {
                     return;
                 }
                 if let Some(chunk) = self.chunks.write().get_mut(&CPos(pos.0, pos.2)) {
-                    chunk.sections_rendered_on[pos.1 as usize] = frame_id;
+                    if let Some(idx) = chunk.section_index(pos.1) {
+                        chunk.sections_rendered_on[idx] = frame_id;
+                    }
                 }
 
                 let min = cgmath::Point3::new(
@@ -585,7 +611,7 @@ impl World {
                 let chunks = self.chunks.read();
                 let chunk = chunks.get(&CPos(v.0, v.2));
                 if let Some(chunk) = chunk {
-                    if let Some(sec) = chunk.sections[v.1 as usize].as_ref() {
+                    if let Some(sec) = chunk.section(v.1) {
                         return Some((*v, sec.render_buffer.clone()));
                     }
                 }
@@ -723,12 +749,10 @@ impl World {
 
     // TODO: Improve the perf of this method as it is the MAIN bottleneck slowing down the program!
     fn get_render_section_mut(&self, x: i32, y: i32, z: i32) -> Option<(Option<CullInfo>, u32)> {
-        if !(0..=15).contains(&y) {
-            return None;
-        }
         if let Some(chunk) = self.chunks.read().get(&CPos(x, z)) {
-            let rendered = &chunk.sections_rendered_on[y as usize];
-            if let Some(sec) = chunk.sections[y as usize].as_ref() {
+            let idx = chunk.section_index(y)?;
+            let rendered = &chunk.sections_rendered_on[idx];
+            if let Some(sec) = chunk.sections[idx].as_ref() {
                 return Some((Some(sec.cull_info), *rendered));
             }
             return Some((None, *rendered));
@@ -742,7 +766,7 @@ impl World {
             for sec in &chunk.sections {
                 if let Some(sec) = sec.as_ref() {
                     if !sec.building && sec.dirty {
-                        out.push((chunk.position.0, sec.y as i32, chunk.position.1));
+                        out.push((chunk.position.0, sec.y, chunk.position.1));
                     }
                 }
             }
@@ -752,7 +776,7 @@ impl World {
 
     fn set_dirty(&self, x: i32, y: i32, z: i32) {
         if let Some(chunk) = self.chunks.write().get_mut(&CPos(x, z)) {
-            if let Some(sec) = chunk.sections.get_mut(y as usize).and_then(|v| v.as_mut()) {
+            if let Some(sec) = chunk.section_mut(y) {
                 sec.dirty = true;
             }
         }
@@ -760,7 +784,7 @@ impl World {
 
     pub fn is_section_dirty(&self, pos: (i32, i32, i32)) -> bool {
         if let Some(chunk) = self.chunks.read().get(&CPos(pos.0, pos.2)) {
-            if let Some(sec) = chunk.sections[pos.1 as usize].as_ref() {
+            if let Some(sec) = chunk.section(pos.1) {
                 return sec.dirty && !sec.building;
             }
         }
@@ -769,7 +793,7 @@ impl World {
 
     pub fn set_building_flag(&self, pos: (i32, i32, i32)) {
         if let Some(chunk) = self.chunks.write().get_mut(&CPos(pos.0, pos.2)) {
-            if let Some(sec) = chunk.sections[pos.1 as usize].as_mut() {
+            if let Some(sec) = chunk.section_mut(pos.1) {
                 sec.building = true;
                 sec.dirty = false;
             }
@@ -778,7 +802,7 @@ impl World {
 
     pub fn reset_building_flag(&self, pos: (i32, i32, i32)) {
         if let Some(chunk) = self.chunks.write().get_mut(&CPos(pos.0, pos.2)) {
-            if let Some(section) = chunk.sections[pos.1 as usize].as_mut() {
+            if let Some(section) = chunk.section_mut(pos.1) {
                 section.building = false;
             }
         }
@@ -806,11 +830,9 @@ impl World {
                 return None;
             }
         };
-        let sec = &chunk.sections[cy as usize];
-        if sec.is_none() {
-            return None;
-        }
-        return Some(sec.as_ref().unwrap().capture_snapshot(chunk.biomes));
+        chunk
+            .section(cy)
+            .map(|sec| sec.capture_snapshot(chunk.biomes))
     }
 
     pub fn unload_chunk(&self, x: i32, z: i32, m: &mut ecs::Manager) {
@@ -821,6 +843,68 @@ impl World {
         }
     }
 
+    /// Unloads every chunk further than `max_distance` chunks away from
+    /// `center`, and then the least recently rendered chunks outside the
+    /// view distance until at most `max_chunks` are left, returning how many
+    /// were dropped.
+    ///
+    /// Chunks can't be requested again, servers only resend them once they
+    /// have left and re-entered their view distance. So chunks within the
+    /// view distance are always kept, however low `max_chunks` is, and the
+    /// ones dropped are rebuilt along with their neighbours once they're
+    /// sent again.
+    pub fn unload_distant_chunks(
+        &self,
+        center: CPos,
+        max_distance: i32,
+        view_distance: i32,
+        max_chunks: usize,
+        m: &mut ecs::Manager,
+    ) -> usize {
+        let distance = |pos: &CPos| (pos.0 - center.0).abs().max((pos.1 - center.1).abs());
+        let max_chunks = max_chunks.max(((2 * view_distance + 1) as usize).pow(2));
+        let distant = {
+            let chunks = self.chunks.read();
+            let (mut distant, near): (Vec<&Chunk>, Vec<&Chunk>) = chunks
+                .values()
+                .partition(|chunk| distance(&chunk.position) > max_distance);
+            let over_cap = near.len().saturating_sub(max_chunks);
+            if over_cap > 0 {
+                let mut outside_view = near
+                    .into_iter()
+                    .filter(|chunk| distance(&chunk.position) > view_distance)
+                    .collect::<Vec<_>>();
+                outside_view.sort_unstable_by_key(|chunk| {
+                    (
+                        chunk.last_rendered(),
+                        std::cmp::Reverse(distance(&chunk.position)),
+                    )
+                });
+                distant.extend(outside_view.into_iter().take(over_cap));
+            }
+            distant
+                .into_iter()
+                .map(|chunk| chunk.position)
+                .collect::<Vec<_>>()
+        };
+        for pos in &distant {
+            self.unload_chunk(pos.0, pos.1, m);
+        }
+        // The neighbours' faces towards the dropped chunks were hidden by
+        // their blocks
+        let mut chunks = self.chunks.write();
+        for pos in &distant {
+            for (dx, dz) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                if let Some(chunk) = chunks.get_mut(&CPos(pos.0 + dx, pos.1 + dz)) {
+                    for sec in chunk.sections.iter_mut().flatten() {
+                        sec.dirty = true;
+                    }
+                }
+            }
+        }
+        distant.len()
+    }
+
     pub fn load_chunk(
         &self,
         x: i32,
@@ -839,7 +923,7 @@ impl World {
         {
             let mut chunk = if new {
                 // TODO: Improve lighting with something similar to bixilon's light accessor!
-                Chunk::new(cpos)
+                self.new_chunk(cpos)
             } else {
                 match self.chunks.read().get(&cpos) {
                     Some(chunk) => chunk.clone(),
@@ -849,13 +933,15 @@ impl World {
 
             // Block type array - whole byte per block  // 17
             let mut block_types: [[u8; 4096]; 16] = [[0u8; 4096]; 16]; // 17
-            for (i, block_type) in block_types.iter_mut().enumerate() {
+            let section_count = chunk.sections.len();
+            for (i, block_type) in block_types.iter_mut().enumerate().take(section_count) {
                 if chunk.sections[i].is_none() {
                     let mut fill_sky = chunk.sections.iter().skip(i).all(|v| v.is_none());
                     fill_sky &= (mask & !((1 << i) | ((1 << i) - 1))) == 0;
                     fill_sky &= self.dimension.load().has_sky_light();
                     if !fill_sky || mask & (1 << i) != 0 {
-                        chunk.sections[i] = Some(ChunkSection::new(i as u8, fill_sky));
+                        chunk.sections[i] =
+                            Some(ChunkSection::new(chunk.min_section + i as i32, fill_sky));
                     }
                 }
                 if mask & (1 << i) == 0 {
@@ -889,7 +975,14 @@ impl World {
 
             if new && read_biomes {
                 // read biomes is always true (as param) except for load_chunk_19
-                data.read_exact(&mut chunk.biomes)?;
+                if self.protocol_version >= 393 {
+                    // Since 1.13 every biome id is sent as an int
+                    for biome in chunk.biomes.iter_mut() {
+                        *biome = data.read_i32::<byteorder::BigEndian>()? as u8;
+                    }
+                } else {
+                    data.read_exact(&mut chunk.biomes)?;
+                }
             }
 
             chunk.calculate_heightmap();
@@ -917,7 +1010,7 @@ impl World {
         let section = chunk.sections[section_id].as_mut().unwrap();
 
         let mut bit_size = data.read_u8().unwrap();
-        let mut mappings: BTreeMap<usize, block::Block> = BTreeMap::new();
+        let mut mappings: BTreeMap<usize, block::StateId> = BTreeMap::new();
 
         if bit_size == 0 {
             bit_size = 13;
@@ -931,7 +1024,7 @@ impl World {
                 let id = VarInt::read_from(data).unwrap().0;
                 let bl = self
                     .id_map
-                    .by_vanilla_id(id as usize, self.modded_block_ids.load().as_ref());
+                    .state_by_vanilla_id(id as usize, self.modded_block_ids.load().as_ref());
                 mappings.insert(i as usize, bl);
             }
         }
@@ -942,7 +1035,7 @@ impl World {
 
         for block_index in 0..4096 {
             let id = m.get(block_index);
-            section.blocks_mut().set(
+            section.blocks_mut().set_state(
                 block_index,
                 mappings
                     .get(&id)
@@ -950,7 +1043,7 @@ impl World {
                     // TODO: fix or_fun_call, but do not re-borrow self
                     .unwrap_or_else(|| {
                         self.id_map
-                            .by_vanilla_id(id, self.modded_block_ids.load().as_ref())
+                            .state_by_vanilla_id(id, self.modded_block_ids.load().as_ref())
                     }),
             );
             // Spawn block entities
@@ -962,7 +1055,7 @@ impl World {
                     ((block_index >> 4) & 0xF) as i32,
                 ) + (
                     chunk.position.0 << 4,
-                    (section_id << 4) as i32,
+                    (chunk.min_section + section_id as i32) << 4,
                     chunk.position.1 << 4,
                 );
                 if chunk.block_entities.contains_key(&pos) {
@@ -991,10 +1084,10 @@ impl World {
         let section = chunk.sections[section_id].as_mut().unwrap();
         for bi in 0..4096 {
             let id = data.read_u16::<byteorder::LittleEndian>().unwrap();
-            section.blocks.set(
+            section.blocks.set_state(
                 bi,
                 self.id_map
-                    .by_vanilla_id(id as usize, &self.modded_block_ids.load()),
+                    .state_by_vanilla_id(id as usize, &self.modded_block_ids.load()),
             );
 
             // Spawn block entities
@@ -1006,7 +1099,7 @@ impl World {
                     ((bi >> 4) & 0xF) as i32,
                 ) + (
                     chunk.position.0 << 4,
-                    (section_id << 4) as i32,
+                    (chunk.min_section + section_id as i32) << 4,
                     chunk.position.1 << 4,
                 );
                 if chunk.block_entities.contains_key(&pos) {
@@ -1127,10 +1220,10 @@ impl World {
                 let id = ((block_add[i].get(bi) as u16) << 12)
                     | ((block_types[i][bi] as u16) << 4)
                     | (block_meta[i].get(bi) as u16);
-                section.blocks.set(
+                section.blocks.set_state(
                     bi,
                     self.id_map
-                        .by_vanilla_id(id as usize, &self.modded_block_ids.load()),
+                        .state_by_vanilla_id(id as usize, &self.modded_block_ids.load()),
                 );
 
                 // Spawn block entities
@@ -1142,7 +1235,7 @@ impl World {
                         ((bi >> 4) & 0xF) as i32,
                     ) + (
                         chunk.position.0 << 4,
-                        (i << 4) as i32,
+                        (chunk.min_section + i as i32) << 4,
                         chunk.position.1 << 4,
                     );
                     if chunk.block_entities.contains_key(&pos) {
@@ -1193,10 +1286,12 @@ impl World {
     }
 
     fn dirty_chunks_by_bitmask(&self, x: i32, z: i32, mask: u16) {
-        for i in 0..16 {
-            if mask & (1 << i) == 0 {
+        let min_section = self.dimension.load().min_y() >> 4;
+        for bit in 0..16 {
+            if mask & (1 << bit) == 0 {
                 continue;
             }
+            let i = min_section + bit;
             for pos in [
                 (-1, 0, 0),
                 (1, 0, 0),
@@ -1325,7 +1420,8 @@ impl World {
                 }
                 let i = i - 1;
                 if chunk.sections[i as usize].as_ref().is_none() {
-                    chunk.sections[i as usize].replace(ChunkSection::new(i, false));
+                    chunk.sections[i as usize]
+                        .replace(ChunkSection::new(chunk.min_section + i, false));
                 }
                 let section = chunk.sections[i as usize].as_mut().unwrap();
                 let _size = VarInt::read_from(data);
@@ -1350,7 +1446,7 @@ impl World {
             }
             let i = i - 1;
             if chunk.sections[i as usize].as_ref().is_none() {
-                chunk.sections[i as usize].replace(ChunkSection::new(i, false));
+                chunk.sections[i as usize].replace(ChunkSection::new(chunk.min_section + i, false));
             }
             let section = chunk.sections[i as usize].as_mut().unwrap();
             let _size = VarInt::read_from(data);
@@ -1407,18 +1503,39 @@ impl World {
         )
     }
 
-    fn flag_section_dirty(&self, x: i32, y: i32, z: i32) {
-        if !(0..=15).contains(&y) {
-            return;
+    /// Stores the biomes of a 1.15+ chunk. These are sent per 4x4x4 cell, the
+    /// layer at sea level is used for the chunk's biome map.
+    pub fn set_biomes_3d(&self, x: i32, z: i32, biomes: &[i32]) {
+        const SEA_LEVEL_CELL: usize = 64 >> 2;
+        if let Some(chunk) = self.chunks.write().get_mut(&CPos(x, z)) {
+            for bz in 0..16 {
+                for bx in 0..16 {
+                    let idx = (SEA_LEVEL_CELL << 4) | ((bz >> 2) << 2) | (bx >> 2);
+                    if let Some(biome) = biomes.get(idx) {
+                        chunk.biomes[(bz << 4) | bx] = *biome as u8;
+                    }
+                }
+            }
+            for sec in chunk.sections.iter_mut().flatten() {
+                sec.dirty = true;
+            }
         }
+    }
+
+    fn flag_section_dirty(&self, x: i32, y: i32, z: i32) {
         let cpos = CPos(x, z);
         if let Some(chunk) = self.chunks.write().get_mut(&cpos) {
-            if let Some(sec) = chunk.sections[y as usize].as_mut() {
+            if let Some(sec) = chunk.section_mut(y) {
                 sec.dirty = true;
             }
         }
     }
 
+    fn new_chunk(&self, pos: CPos) -> Chunk {
+        let dimension = self.dimension.load();
+        Chunk::new(pos, dimension.min_y(), dimension.height())
+    }
+
     pub fn set_dimension(&self, new_dimension: Dimension) {
         self.dimension.store(Arc::new(new_dimension));
     }
@@ -1472,6 +1589,26 @@ impl Dimension {
     pub fn has_sky_light(&self) -> bool {
         matches!(*self, Dimension::Overworld)
     }
+
+    /// The lowest block y of the dimension. 1.17+ dimension types can extend
+    /// below 0, older ones always start at 0.
+    pub fn min_y(&self) -> i32 {
+        self.tag_int("min_y").unwrap_or(0)
+    }
+
+    /// The number of blocks between the lowest and highest y of the dimension.
+    pub fn height(&self) -> i32 {
+        self.tag_int("height").unwrap_or(256)
+    }
+
+    fn tag_int(&self, name: &str) -> Option<i32> {
+        match self {
+            Self::Other(DimensionID::Tag(tag)) if tag.1.is_compound() => {
+                tag.1.get(name).and_then(|v| v.as_int())
+            }
+            _ => None,
+        }
+    }
 }
 
 struct UpdateSignInfoCmd([Component; 4], Entity);
@@ -1489,6 +1626,23 @@ impl Command for UpdateSignInfoCmd {
     }
 }
 
+struct UpdateBeaconInfoCmd(i32, i32, i32, Entity);
+
+impl Command for UpdateBeaconInfoCmd {
+    fn apply(self, world: &mut bevy_ecs::world::World) {
+        let mut entity = world.get_entity_mut(self.3);
+        if let Some(mut info) = entity
+            .as_mut()
+            .and_then(|entity| entity.get_mut::<BeaconInfo>())
+        {
+            info.levels = self.0;
+            info.primary = self.1;
+            info.secondary = self.2;
+            info.dirty = true;
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1731,4 +1885,36 @@ mod tests {
         let data = include_bytes!("testdata/chunk_1.16.4_nether.bin");
         load_chunk(&world, 0, 0, true, false, false, 247, 0, data, 19);
     }
+
+    #[test]
+    fn unload_distant_chunks_keeps_view_distance() {
+        let world = build_world(754);
+        let mut manager = ecs::Manager {
+            world: Default::default(),
+            schedule: Default::default(),
+            render_schedule: Default::default(),
+        };
+        {
+            let mut chunks = world.chunks.write();
+            for z in -5..=5 {
+                for x in -5..=5 {
+                    chunks.insert(CPos(x, z), world.new_chunk(CPos(x, z)));
+                }
+            }
+            chunks.get_mut(&CPos(3, 0)).unwrap().sections_rendered_on[0] = 10;
+        }
+
+        // The outer ring is too far, 51 more chunks outside the view
+        // distance go to get down to 30
+        let unloaded = world.unload_distant_chunks(CPos(0, 0), 4, 2, 30, &mut manager);
+        assert_eq!(unloaded, 40 + 51);
+        assert!(world.is_chunk_loaded(2, -2));
+        assert!(world.is_chunk_loaded(3, 0));
+        assert!(!world.is_chunk_loaded(5, 0));
+
+        // The chunks within the view distance are kept regardless
+        let unloaded = world.unload_distant_chunks(CPos(0, 0), 4, 2, 0, &mut manager);
+        assert_eq!(unloaded, 5);
+        assert_eq!(world.chunks.read().len(), 25);
+    }
 }
@@ -1,8 +1,9 @@
 use crate::world::block;
+use crate::world::block::StateId;
 
 #[derive(Clone)]
 pub struct BlockStorage {
-    blocks: Vec<block::Block>,
+    blocks: Vec<StateId>,
 }
 
 impl BlockStorage {
@@ -12,19 +13,27 @@ impl BlockStorage {
 
     pub fn new_default(size: usize, def: block::Block) -> Self {
         Self {
-            blocks: vec![def; size],
+            blocks: vec![StateId::of(def); size],
         }
     }
 
     pub fn get(&self, idx: usize) -> block::Block {
+        self.blocks[idx].block()
+    }
+
+    pub fn get_state(&self, idx: usize) -> StateId {
         self.blocks[idx]
     }
 
     pub fn set(&mut self, idx: usize, b: block::Block) -> bool {
-        if self.blocks[idx] == b {
+        self.set_state(idx, StateId::of(b))
+    }
+
+    pub fn set_state(&mut self, idx: usize, state: StateId) -> bool {
+        if self.blocks[idx] == state {
             false
         } else {
-            self.blocks[idx] = b;
+            self.blocks[idx] = state;
             true
         }
     }